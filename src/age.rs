@@ -0,0 +1,514 @@
+//! The age file format (age-encryption.org/v1) with the post-quantum
+//! `mlkem768x25519` recipient type of age v1.3.
+//!
+//! A recipient is an X-Wing public key and an identity the 32-byte X-Wing
+//! seed, both in Bech32 (BIP 173, without the 90-character limit):
+//!
+//! ```text
+//! age1pq1...                  recipient, lowercase
+//! AGE-SECRET-KEY-PQ-1...      identity, uppercase
+//! ```
+//!
+//! The 16-byte file key is sealed to each recipient with HPKE (X-Wing,
+//! HKDF-SHA256, ChaCha20-Poly1305) in Base mode, with info
+//! `"age-encryption.org/mlkem768x25519"` and no associated data. The stanza
+//! carries the encapsulated key as its one argument and the sealed file key
+//! as its body:
+//!
+//! ```text
+//! -> mlkem768x25519 <base64(enc)>
+//! <base64(sealed file key)>
+//! ```
+//!
+//! These recipients have the label [`LABEL`]: age refuses to mix them with
+//! recipients that lack it, so a file is never weaker than post-quantum.
+//!
+//! [`encrypt`] and [`decrypt`] handle whole binary (not armored) files in
+//! memory: the header with its HMAC-SHA256, then the payload as 64 KiB
+//! ChaCha20-Poly1305 chunks in the STREAM construction, under a key derived
+//! from the file key and a 16-byte nonce. [`Header`] and [`Stanza`] are
+//! there for files that also carry other recipient types. The module needs
+//! only `alloc`.
+
+use crate::error::Error;
+use crate::hpke::{ChaCha20Poly1305, HkdfSha256, Hpke};
+use crate::xwing::{self, XWing};
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use base64ct::{Base64Unpadded, Encoding};
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use core::fmt;
+use core::str::FromStr;
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+pub const VERSION_LINE: &str = "age-encryption.org/v1";
+pub const STANZA_TAG: &str = "mlkem768x25519";
+pub const LABEL: &str = "postquantum";
+pub const RECIPIENT_HRP: &str = "age1pq";
+pub const IDENTITY_HRP: &str = "AGE-SECRET-KEY-PQ-";
+
+pub const FILE_KEY_BYTES: usize = 16;
+pub const MAC_BYTES: usize = 32;
+pub const NONCE_BYTES: usize = 16;
+/// Plaintext bytes per payload chunk.
+pub const CHUNK_BYTES: usize = 64 * 1024;
+pub const TAG_BYTES: usize = 16;
+
+const INFO: &[u8] = b"age-encryption.org/mlkem768x25519";
+const SEALED_CHUNK_BYTES: usize = CHUNK_BYTES + TAG_BYTES;
+// Stanza bodies are wrapped at 64 base64 characters
+const BODY_COLUMNS: usize = 64;
+
+type Suite = Hpke<XWing, HkdfSha256, ChaCha20Poly1305>;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+// The HRP expansion of BIP 173, over the lowercase HRP
+fn hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    let lower = || hrp.bytes().map(|c| c.to_ascii_lowercase());
+    lower().map(|c| c >> 5).chain([0]).chain(lower().map(|c| c & 31))
+}
+
+// Lowercase Bech32 of `data`, without the BIP 173 length limit
+fn bech32_encode(hrp: &str, data: &[u8]) -> String {
+    let mut words = Vec::with_capacity((data.len() * 8).div_ceil(5) + 6);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in data {
+        acc = (acc << 8 | b as u32) & 0xfff;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            words.push((acc >> bits) as u8 & 31);
+        }
+    }
+    if bits > 0 {
+        words.push((acc << (5 - bits)) as u8 & 31);
+    }
+    let chk = bech32_polymod(hrp_expand(hrp).chain(words.iter().copied()).chain([0; 6])) ^ 1;
+    words.extend((0..6).map(|i| (chk >> (5 * (5 - i))) as u8 & 31));
+
+    let mut out = String::with_capacity(hrp.len() + 1 + words.len());
+    out.extend(hrp.chars().map(|c| c.to_ascii_lowercase()));
+    out.push('1');
+    out.extend(words.iter().map(|&w| CHARSET[w as usize] as char));
+    words.zeroize();
+    out
+}
+
+// Decodes Bech32 with the given HRP (either case) into exactly N bytes
+fn bech32_decode<const N: usize>(hrp: &str, s: &str) -> Result<Zeroizing<[u8; N]>, Error> {
+    let has_lower = s.bytes().any(|c| c.is_ascii_lowercase());
+    let has_upper = s.bytes().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper || !s.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err(Error::InvalidEncoding);
+    }
+    let sep = s.rfind('1').ok_or(Error::InvalidEncoding)?;
+    let (prefix, rest) = (&s[..sep], &s[sep + 1..]);
+    if !prefix.eq_ignore_ascii_case(hrp) {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    if rest.len() < 6 {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut words = Zeroizing::new(Vec::with_capacity(rest.len()));
+    for c in rest.bytes() {
+        let c = c.to_ascii_lowercase();
+        let w = CHARSET.iter().position(|&x| x == c).ok_or(Error::InvalidEncoding)?;
+        words.push(w as u8);
+    }
+    if bech32_polymod(hrp_expand(prefix).chain(words.iter().copied())) != 1 {
+        return Err(Error::InvalidEncoding);
+    }
+
+    let data = &words[..words.len() - 6];
+    if data.len() * 5 / 8 != N {
+        return Err(Error::InvalidLength);
+    }
+    let mut out = Zeroizing::new([0u8; N]);
+    let (mut acc, mut bits, mut i) = (0u32, 0, 0);
+    for &w in data {
+        acc = (acc << 5 | w as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out[i] = (acc >> bits) as u8;
+            i += 1;
+        }
+    }
+    // Padding must be under a byte and all zero
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return Err(Error::InvalidEncoding);
+    }
+    acc.zeroize();
+    Ok(out)
+}
+
+/// An `mlkem768x25519` recipient: an X-Wing public key.
+#[derive(Clone, Copy)]
+pub struct Recipient(xwing::PublicKey);
+
+impl From<xwing::PublicKey> for Recipient {
+    fn from(pk: xwing::PublicKey) -> Self {
+        Recipient(pk)
+    }
+}
+
+impl Recipient {
+    pub fn public_key(&self) -> &xwing::PublicKey {
+        &self.0
+    }
+
+    /// Reads an `age1pq1...` string. Fails with
+    /// [`Error::UnsupportedAlgorithm`] for any other kind of age recipient.
+    pub fn from_bech32(s: &str) -> Result<Self, Error> {
+        let bytes = bech32_decode::<{ xwing::PUBLIC_KEY_BYTES }>(RECIPIENT_HRP, s)?;
+        Ok(Recipient(xwing::PublicKey::from(*bytes)))
+    }
+
+    pub fn to_bech32(&self) -> String {
+        bech32_encode(RECIPIENT_HRP, self.0.as_bytes())
+    }
+
+    /// Seals `file_key` to this recipient.
+    pub fn wrap_file_key<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        file_key: &[u8; FILE_KEY_BYTES],
+    ) -> Result<Stanza, Error> {
+        let (enc, body) = Suite::seal_base(rng, &self.0, INFO, b"", file_key)?;
+        Ok(Stanza {
+            tag: String::from(STANZA_TAG),
+            args: vec![Base64Unpadded::encode_string(enc.as_bytes())],
+            body,
+        })
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_bech32())
+    }
+}
+
+impl FromStr for Recipient {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        Self::from_bech32(s)
+    }
+}
+
+/// An `mlkem768x25519` identity: an X-Wing secret key.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct Identity(xwing::SecretKey);
+
+impl From<xwing::SecretKey> for Identity {
+    fn from(sk: xwing::SecretKey) -> Self {
+        Identity(sk)
+    }
+}
+
+impl Identity {
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Identity(xwing::keypair(rng).1)
+    }
+
+    pub fn secret_key(&self) -> &xwing::SecretKey {
+        &self.0
+    }
+
+    pub fn to_recipient(&self) -> Recipient {
+        Recipient(self.0.public_key())
+    }
+
+    /// Reads an `AGE-SECRET-KEY-PQ-1...` string.
+    pub fn from_bech32(s: &str) -> Result<Self, Error> {
+        let bytes = bech32_decode::<{ xwing::SECRET_KEY_BYTES }>(IDENTITY_HRP, s)?;
+        Ok(Identity(xwing::SecretKey::from(*bytes)))
+    }
+
+    /// The identity in the uppercase form age writes to key files.
+    pub fn to_bech32(&self) -> Zeroizing<String> {
+        let mut s = bech32_encode(IDENTITY_HRP, self.0.as_bytes());
+        s.make_ascii_uppercase();
+        Zeroizing::new(s)
+    }
+
+    /// Opens the file key in an `mlkem768x25519` stanza. Fails with
+    /// [`Error::NotRecipient`] for other stanza types and
+    /// [`Error::AuthenticationFailed`] if the stanza is for another identity.
+    pub fn unwrap_file_key(&self, stanza: &Stanza) -> Result<Zeroizing<[u8; FILE_KEY_BYTES]>, Error> {
+        if stanza.tag != STANZA_TAG {
+            return Err(Error::NotRecipient);
+        }
+        let [arg] = stanza.args.as_slice() else {
+            return Err(Error::InvalidEncoding);
+        };
+        if stanza.body.len() != FILE_KEY_BYTES + TAG_BYTES {
+            return Err(Error::InvalidLength);
+        }
+        let enc: [u8; xwing::CIPHERTEXT_BYTES] = decode_base64(arg)?;
+        let pt = Zeroizing::new(Suite::open_base(&enc.into(), &self.0, INFO, b"", &stanza.body)?);
+        let mut file_key = Zeroizing::new([0u8; FILE_KEY_BYTES]);
+        file_key.copy_from_slice(&pt);
+        Ok(file_key)
+    }
+
+    /// Finds and opens this identity's stanza among a file's recipients.
+    pub fn unwrap_any(&self, stanzas: &[Stanza]) -> Result<Zeroizing<[u8; FILE_KEY_BYTES]>, Error> {
+        for stanza in stanzas {
+            match self.unwrap_file_key(stanza) {
+                Err(Error::NotRecipient | Error::AuthenticationFailed) => continue,
+                result => return result,
+            }
+        }
+        Err(Error::NotRecipient)
+    }
+}
+
+// Canonical unpadded base64 of exactly N bytes
+fn decode_base64<const N: usize>(s: &str) -> Result<[u8; N], Error> {
+    let mut out = [0u8; N];
+    match Base64Unpadded::decode(s, &mut out) {
+        Ok(bytes) if bytes.len() == N => Ok(out),
+        Ok(_) | Err(base64ct::Error::InvalidLength) => Err(Error::InvalidLength),
+        Err(base64ct::Error::InvalidEncoding) => Err(Error::InvalidEncoding),
+    }
+}
+
+/// One recipient stanza of an age header, of any type.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stanza {
+    pub tag: String,
+    pub args: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+impl Stanza {
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(b"->");
+        for arg in core::iter::once(&self.tag).chain(&self.args) {
+            out.push(b' ');
+            out.extend_from_slice(arg.as_bytes());
+        }
+        out.push(b'\n');
+        let body = Base64Unpadded::encode_string(&self.body);
+        // A full last line is followed by an empty one
+        for line in body
+            .as_bytes()
+            .chunks(BODY_COLUMNS)
+            .chain((body.len() % BODY_COLUMNS == 0).then_some(&[][..]))
+        {
+            out.extend_from_slice(line);
+            out.push(b'\n');
+        }
+    }
+}
+
+/// A parsed age header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub recipients: Vec<Stanza>,
+    pub mac: [u8; MAC_BYTES],
+}
+
+// The next `\n`-terminated line of the header
+fn read_line<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a str, Error> {
+    let rest = &data[*pos..];
+    let len = rest.iter().position(|&c| c == b'\n').ok_or(Error::Truncated)?;
+    *pos += len + 1;
+    core::str::from_utf8(&rest[..len]).map_err(|_| Error::InvalidEncoding)
+}
+
+fn is_arg(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|c| (33..=126).contains(&c))
+}
+
+impl Header {
+    /// Parses the header at the start of `file`, returning it with the length
+    /// of its MACed part (everything up to and including `---`) and the
+    /// offset of the payload.
+    pub fn parse(file: &[u8]) -> Result<(Self, usize, usize), Error> {
+        let mut pos = 0;
+        if read_line(file, &mut pos)? != VERSION_LINE {
+            return Err(Error::InvalidHeader);
+        }
+        let mut recipients = Vec::new();
+        loop {
+            let start = pos;
+            let line = read_line(file, &mut pos)?;
+            if let Some(mac) = line.strip_prefix("--- ") {
+                if recipients.is_empty() {
+                    return Err(Error::InvalidHeader);
+                }
+                let mac = decode_base64(mac)?;
+                return Ok((Header { recipients, mac }, start + 3, pos));
+            }
+            let mut args = line.strip_prefix("-> ").ok_or(Error::InvalidEncoding)?.split(' ');
+            let tag = args.next().filter(|t| is_arg(t)).ok_or(Error::InvalidEncoding)?;
+            let args: Vec<String> = args.map(String::from).collect();
+            if !args.iter().all(|a| is_arg(a)) {
+                return Err(Error::InvalidEncoding);
+            }
+            let mut body = String::new();
+            loop {
+                let line = read_line(file, &mut pos)?;
+                if line.len() > BODY_COLUMNS {
+                    return Err(Error::InvalidEncoding);
+                }
+                body.push_str(line);
+                if line.len() < BODY_COLUMNS {
+                    break;
+                }
+            }
+            let body = Base64Unpadded::decode_vec(&body).map_err(|_| Error::InvalidEncoding)?;
+            recipients.push(Stanza {
+                tag: String::from(tag),
+                args,
+                body,
+            });
+        }
+    }
+
+    /// The header text up to and including `---`, which the MAC covers.
+    pub fn mac_input(recipients: &[Stanza]) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(VERSION_LINE.as_bytes());
+        out.push(b'\n');
+        for stanza in recipients {
+            stanza.write(&mut out);
+        }
+        out.extend_from_slice(b"---");
+        out
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Self::mac_input(&self.recipients);
+        out.push(b' ');
+        out.extend_from_slice(Base64Unpadded::encode_string(&self.mac).as_bytes());
+        out.push(b'\n');
+        out
+    }
+}
+
+fn header_mac(file_key: &[u8; FILE_KEY_BYTES]) -> Hmac<Sha256> {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(None, file_key)
+        .expand(b"header", key.as_mut())
+        .expect("32 bytes is a valid HKDF length");
+    <Hmac<Sha256> as Mac>::new_from_slice(key.as_ref()).expect("HMAC takes any key length")
+}
+
+fn payload_cipher(file_key: &[u8; FILE_KEY_BYTES], nonce: &[u8; NONCE_BYTES]) -> chacha20poly1305::ChaCha20Poly1305 {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(nonce), file_key)
+        .expand(b"payload", key.as_mut())
+        .expect("32 bytes is a valid HKDF length");
+    chacha20poly1305::ChaCha20Poly1305::new(key.as_ref().into())
+}
+
+// 11-byte big-endian chunk counter, then the last-chunk flag
+fn chunk_nonce(counter: u64, last: bool) -> chacha20poly1305::Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce.into()
+}
+
+/// Encrypts `plaintext` to every recipient, as a binary age file.
+pub fn encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    recipients: &[Recipient],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if recipients.is_empty() {
+        return Err(Error::NotRecipient);
+    }
+    let mut file_key = Zeroizing::new([0u8; FILE_KEY_BYTES]);
+    rng.fill_bytes(file_key.as_mut());
+    let stanzas = recipients
+        .iter()
+        .map(|r| r.wrap_file_key(rng, &file_key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut mac = header_mac(&file_key);
+    mac.update(&Header::mac_input(&stanzas));
+    let header = Header {
+        recipients: stanzas,
+        mac: mac.finalize().into_bytes().into(),
+    };
+
+    let mut nonce = [0u8; NONCE_BYTES];
+    rng.fill_bytes(&mut nonce);
+    let cipher = payload_cipher(&file_key, &nonce);
+
+    let chunks = plaintext.len().div_ceil(CHUNK_BYTES).max(1);
+    let mut out = header.to_bytes();
+    out.reserve(NONCE_BYTES + plaintext.len() + chunks * TAG_BYTES);
+    out.extend_from_slice(&nonce);
+    for i in 0..chunks {
+        let chunk = &plaintext[i * CHUNK_BYTES..plaintext.len().min((i + 1) * CHUNK_BYTES)];
+        let start = out.len();
+        out.extend_from_slice(chunk);
+        let tag = cipher
+            .encrypt_in_place_detached(&chunk_nonce(i as u64, i + 1 == chunks), b"", &mut out[start..])
+            .map_err(|_| Error::MessageLimitReached)?;
+        out.extend_from_slice(&tag);
+    }
+    Ok(out)
+}
+
+/// Decrypts a binary age file with `identity`. Fails with
+/// [`Error::NotRecipient`] if no stanza opens for it.
+pub fn decrypt(identity: &Identity, file: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let (header, mac_len, payload) = Header::parse(file)?;
+    let file_key = identity.unwrap_any(&header.recipients)?;
+
+    let mut mac = header_mac(&file_key);
+    mac.update(&file[..mac_len]);
+    mac.verify_slice(&header.mac).map_err(|_| Error::AuthenticationFailed)?;
+
+    let rest = &file[payload..];
+    if rest.len() < NONCE_BYTES + TAG_BYTES {
+        return Err(Error::Truncated);
+    }
+    let (nonce, body) = rest.split_at(NONCE_BYTES);
+    let cipher = payload_cipher(&file_key, nonce.try_into().expect("split at NONCE_BYTES"));
+
+    let chunks = body.len().div_ceil(SEALED_CHUNK_BYTES);
+    let mut out = Zeroizing::new(Vec::with_capacity(body.len()));
+    for (i, sealed) in body.chunks(SEALED_CHUNK_BYTES).enumerate() {
+        let last = i + 1 == chunks;
+        // Only the first chunk may be empty, and only when it is the last
+        if sealed.len() < TAG_BYTES || sealed.len() == TAG_BYTES && i > 0 {
+            return Err(Error::Truncated);
+        }
+        let (ct, tag) = sealed.split_at(sealed.len() - TAG_BYTES);
+        let start = out.len();
+        out.extend_from_slice(ct);
+        cipher
+            .decrypt_in_place_detached(&chunk_nonce(i as u64, last), b"", &mut out[start..], tag.into())
+            .map_err(|_| Error::AuthenticationFailed)?;
+    }
+    Ok(out)
+}
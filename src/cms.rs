@@ -0,0 +1,732 @@
+//! CMS (RFC 5652) `EnvelopedData` with ML-KEM recipients, using the
+//! `KEMRecipientInfo` of RFC 9629 as profiled for ML-KEM by RFC 9936.
+//!
+//! Each recipient is an `OtherRecipientInfo` of type `id-ori-kem`:
+//!
+//! ```text
+//! KEMRecipientInfo ::= SEQUENCE {
+//!     version CMSVersion,  -- always 0
+//!     rid RecipientIdentifier,
+//!     kem KEMAlgorithmIdentifier,
+//!     kemct OCTET STRING,
+//!     kdf KeyDerivationAlgorithmIdentifier,
+//!     kekLength INTEGER (1..65535),
+//!     ukm [0] EXPLICIT UserKeyingMaterial OPTIONAL,
+//!     wrap KeyEncryptionAlgorithmIdentifier,
+//!     encryptedKey EncryptedKey }
+//! ```
+//!
+//! The key-encryption key is HKDF (RFC 8619) of the ML-KEM shared secret,
+//! with no salt and the DER of
+//!
+//! ```text
+//! CMSORIforKEMOtherInfo ::= SEQUENCE {
+//!     wrap KeyEncryptionAlgorithmIdentifier,
+//!     kekLength INTEGER (1..65535),
+//!     ukm [0] EXPLICIT UserKeyingMaterial OPTIONAL }
+//! ```
+//!
+//! as the info, and it wraps the content-encryption key with AES Key Wrap
+//! (RFC 3394). The content is `id-data` under AES-CBC.
+//!
+//! [`encrypt`] does what OpenSSL 3.6's `cms -encrypt` does for an ML-KEM
+//! certificate: HKDF-SHA256 and the AES Key Wrap of the content cipher's
+//! key size. Decryption reads the DER OpenSSL writes by
+//! default as well as the indefinite-length BER of `-stream`, and skips
+//! recipients of other types as well as `KEMRecipientInfo`s with a KEM, KDF
+//! or key wrap it does not support.
+
+use crate::der::{
+    algorithm, integer, parse, read_algorithm, read_integer, tlv, Reader, TAG_INTEGER, TAG_OCTET_STRING, TAG_OID,
+    TAG_SEQUENCE,
+};
+use crate::error::Error;
+use aes_kw::Kek;
+use alloc::vec;
+use alloc::vec::Vec;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use hkdf::Hkdf;
+use zeroize::Zeroizing;
+
+pub const IV_BYTES: usize = 16;
+
+const TAG_NULL: u8 = 0x05;
+const TAG_SET: u8 = 0x31;
+// [0] EXPLICIT of ContentInfo and of ukm, [0] IMPLICIT originatorInfo
+const TAG_EXPLICIT_0: u8 = 0xa0;
+// [0] IMPLICIT subjectKeyIdentifier and encryptedContent
+const TAG_IMPLICIT_0: u8 = 0x80;
+// [1] IMPLICIT unprotectedAttrs
+const TAG_UNPROTECTED_ATTRS: u8 = 0xa1;
+// [4] IMPLICIT OtherRecipientInfo
+const TAG_ORI: u8 = 0xa4;
+const TAG_CONSTRUCTED: u8 = 0x20;
+
+// Nesting allowed in the BER layers
+const MAX_DEPTH: usize = 8;
+
+// OIDs without tag and length
+const OID_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+const OID_ENVELOPED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x03];
+const OID_ORI_KEM: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x0d, 0x03];
+const OID_HKDF_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x03, 0x1c];
+const OID_HKDF_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x03, 0x1d];
+const OID_HKDF_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x03, 0x1e];
+const OID_AES128_WRAP: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x05];
+const OID_AES192_WRAP: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x19];
+const OID_AES256_WRAP: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2d];
+const OID_AES128_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x02];
+const OID_AES192_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x16];
+const OID_AES256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+/// The key derivation of a `KEMRecipientInfo`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    HkdfSha256,
+    HkdfSha384,
+    HkdfSha512,
+}
+
+impl Kdf {
+    fn oid(self) -> &'static [u8] {
+        match self {
+            Kdf::HkdfSha256 => OID_HKDF_SHA256,
+            Kdf::HkdfSha384 => OID_HKDF_SHA384,
+            Kdf::HkdfSha512 => OID_HKDF_SHA512,
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Result<Self, Error> {
+        match oid {
+            OID_HKDF_SHA256 => Ok(Kdf::HkdfSha256),
+            OID_HKDF_SHA384 => Ok(Kdf::HkdfSha384),
+            OID_HKDF_SHA512 => Ok(Kdf::HkdfSha512),
+            _ => Err(Error::UnsupportedAlgorithm),
+        }
+    }
+}
+
+/// AES Key Wrap of the content-encryption key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyWrap {
+    Aes128,
+    Aes192,
+    Aes256,
+}
+
+impl KeyWrap {
+    /// The key-encryption key length, `kekLength`.
+    pub fn key_bytes(self) -> usize {
+        match self {
+            KeyWrap::Aes128 => 16,
+            KeyWrap::Aes192 => 24,
+            KeyWrap::Aes256 => 32,
+        }
+    }
+
+    fn oid(self) -> &'static [u8] {
+        match self {
+            KeyWrap::Aes128 => OID_AES128_WRAP,
+            KeyWrap::Aes192 => OID_AES192_WRAP,
+            KeyWrap::Aes256 => OID_AES256_WRAP,
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Result<Self, Error> {
+        match oid {
+            OID_AES128_WRAP => Ok(KeyWrap::Aes128),
+            OID_AES192_WRAP => Ok(KeyWrap::Aes192),
+            OID_AES256_WRAP => Ok(KeyWrap::Aes256),
+            _ => Err(Error::UnsupportedAlgorithm),
+        }
+    }
+
+    fn wrap(self, kek: &[u8], cek: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut out = vec![0u8; cek.len() + 8];
+        match self {
+            KeyWrap::Aes128 => Kek::<aes::Aes128>::try_from(kek).unwrap().wrap(cek, &mut out),
+            KeyWrap::Aes192 => Kek::<aes::Aes192>::try_from(kek).unwrap().wrap(cek, &mut out),
+            KeyWrap::Aes256 => Kek::<aes::Aes256>::try_from(kek).unwrap().wrap(cek, &mut out),
+        }
+        .map_err(|_| Error::InvalidLength)?;
+        Ok(out)
+    }
+
+    fn unwrap(self, kek: &[u8], wrapped: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+            return Err(Error::InvalidLength);
+        }
+        let mut cek = Zeroizing::new(vec![0u8; wrapped.len() - 8]);
+        match self {
+            KeyWrap::Aes128 => Kek::<aes::Aes128>::try_from(kek).unwrap().unwrap(wrapped, &mut cek),
+            KeyWrap::Aes192 => Kek::<aes::Aes192>::try_from(kek).unwrap().unwrap(wrapped, &mut cek),
+            KeyWrap::Aes256 => Kek::<aes::Aes256>::try_from(kek).unwrap().unwrap(wrapped, &mut cek),
+        }
+        .map_err(|_| Error::AuthenticationFailed)?;
+        Ok(cek)
+    }
+}
+
+/// The content-encryption algorithm, with a 16-byte IV and PKCS#7 padding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentCipher {
+    Aes128Cbc,
+    Aes192Cbc,
+    Aes256Cbc,
+}
+
+impl ContentCipher {
+    pub fn key_bytes(self) -> usize {
+        match self {
+            ContentCipher::Aes128Cbc => 16,
+            ContentCipher::Aes192Cbc => 24,
+            ContentCipher::Aes256Cbc => 32,
+        }
+    }
+
+    /// The key wrap OpenSSL pairs with the cipher: the one of the same key
+    /// size.
+    pub fn key_wrap(self) -> KeyWrap {
+        match self {
+            ContentCipher::Aes128Cbc => KeyWrap::Aes128,
+            ContentCipher::Aes192Cbc => KeyWrap::Aes192,
+            ContentCipher::Aes256Cbc => KeyWrap::Aes256,
+        }
+    }
+
+    fn oid(self) -> &'static [u8] {
+        match self {
+            ContentCipher::Aes128Cbc => OID_AES128_CBC,
+            ContentCipher::Aes192Cbc => OID_AES192_CBC,
+            ContentCipher::Aes256Cbc => OID_AES256_CBC,
+        }
+    }
+
+    fn from_oid(oid: &[u8]) -> Result<Self, Error> {
+        match oid {
+            OID_AES128_CBC => Ok(ContentCipher::Aes128Cbc),
+            OID_AES192_CBC => Ok(ContentCipher::Aes192Cbc),
+            OID_AES256_CBC => Ok(ContentCipher::Aes256Cbc),
+            _ => Err(Error::UnsupportedAlgorithm),
+        }
+    }
+}
+
+/// How a recipient's certificate is named.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecipientId {
+    /// The DER of `IssuerAndSerialNumber`, the SEQUENCE of the
+    /// certificate's issuer `Name` and serial number.
+    IssuerAndSerialNumber(Vec<u8>),
+    /// The certificate's subject key identifier.
+    SubjectKeyIdentifier(Vec<u8>),
+}
+
+/// One `KEMRecipientInfo`. `kekLength` is implied by `wrap`, and a
+/// recipient info whose `kekLength` disagrees with it is rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KemRecipientInfo {
+    pub rid: RecipientId,
+    /// The KEM OID without tag and length, such as
+    /// [`crate::der::OID`].
+    pub kem: Vec<u8>,
+    pub kemct: Vec<u8>,
+    pub kdf: Kdf,
+    pub ukm: Option<Vec<u8>>,
+    pub wrap: KeyWrap,
+    pub encrypted_key: Vec<u8>,
+}
+
+impl KemRecipientInfo {
+    /// The DER of the `KEMRecipientInfo` SEQUENCE.
+    pub fn to_der(&self) -> Vec<u8> {
+        let rid = match &self.rid {
+            RecipientId::IssuerAndSerialNumber(der) => der.clone(),
+            RecipientId::SubjectKeyIdentifier(id) => tlv(TAG_IMPLICIT_0, &[id]),
+        };
+        let ukm = match &self.ukm {
+            Some(ukm) => tlv(TAG_EXPLICIT_0, &[&tlv(TAG_OCTET_STRING, &[ukm])]),
+            None => Vec::new(),
+        };
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                &integer(0),
+                &rid,
+                &algorithm(&self.kem, &[]),
+                &tlv(TAG_OCTET_STRING, &[&self.kemct]),
+                &algorithm(self.kdf.oid(), &[]),
+                &integer(self.wrap.key_bytes() as u64),
+                &ukm,
+                &algorithm(self.wrap.oid(), &[]),
+                &tlv(TAG_OCTET_STRING, &[&self.encrypted_key]),
+            ],
+        )
+    }
+
+    /// Reads a `KEMRecipientInfo` of any KEM; the level functions check
+    /// `kem`. KDF parameters may be absent or NULL, key wrap parameters
+    /// must be absent.
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        let mut info = Reader(parse(der, TAG_SEQUENCE)?);
+        if read_integer(&mut info)? != 0 {
+            return Err(Error::InvalidParameters);
+        }
+        let rid = match info.peek() {
+            Some(TAG_SEQUENCE) => {
+                let start = info.0;
+                info.read(TAG_SEQUENCE)?;
+                RecipientId::IssuerAndSerialNumber(start[..start.len() - info.0.len()].to_vec())
+            }
+            _ => RecipientId::SubjectKeyIdentifier(info.read(TAG_IMPLICIT_0)?.to_vec()),
+        };
+        let (kem, kem_params) = read_algorithm(&mut info)?;
+        let kemct = info.read(TAG_OCTET_STRING)?;
+        let (kdf, kdf_params) = read_algorithm(&mut info)?;
+        let kek_length = read_integer(&mut info)?;
+        let ukm = match info.peek() {
+            Some(TAG_EXPLICIT_0) => Some(parse(info.read(TAG_EXPLICIT_0)?, TAG_OCTET_STRING)?.to_vec()),
+            _ => None,
+        };
+        let (wrap, wrap_params) = read_algorithm(&mut info)?;
+        let encrypted_key = info.read(TAG_OCTET_STRING)?;
+        info.finish()?;
+
+        if !kem_params.is_empty() || !matches!(kdf_params, [] | [TAG_NULL, 0]) || !wrap_params.is_empty() {
+            return Err(Error::InvalidParameters);
+        }
+        let wrap = KeyWrap::from_oid(wrap)?;
+        if kek_length != wrap.key_bytes() as u64 {
+            return Err(Error::InvalidParameters);
+        }
+        Ok(KemRecipientInfo {
+            rid,
+            kem: kem.to_vec(),
+            kemct: kemct.to_vec(),
+            kdf: Kdf::from_oid(kdf)?,
+            ukm,
+            wrap,
+            encrypted_key: encrypted_key.to_vec(),
+        })
+    }
+}
+
+/// The DER of `CMSORIforKEMOtherInfo`, the KDF info.
+pub fn cms_ori_for_kem_other_info(wrap: KeyWrap, ukm: Option<&[u8]>) -> Vec<u8> {
+    let ukm = match ukm {
+        Some(ukm) => tlv(TAG_EXPLICIT_0, &[&tlv(TAG_OCTET_STRING, &[ukm])]),
+        None => Vec::new(),
+    };
+    tlv(
+        TAG_SEQUENCE,
+        &[&algorithm(wrap.oid(), &[]), &integer(wrap.key_bytes() as u64), &ukm],
+    )
+}
+
+// The key-encryption key from an ML-KEM shared secret
+fn derive_kek(kdf: Kdf, ss: &[u8], wrap: KeyWrap, ukm: Option<&[u8]>) -> Zeroizing<Vec<u8>> {
+    let info = cms_ori_for_kem_other_info(wrap, ukm);
+    let mut kek = Zeroizing::new(vec![0u8; wrap.key_bytes()]);
+    // At most 32 bytes, well within what HKDF can expand to
+    match kdf {
+        Kdf::HkdfSha256 => Hkdf::<sha2::Sha256>::new(None, ss).expand(&info, &mut kek).unwrap(),
+        Kdf::HkdfSha384 => Hkdf::<sha2::Sha384>::new(None, ss).expand(&info, &mut kek).unwrap(),
+        Kdf::HkdfSha512 => Hkdf::<sha2::Sha512>::new(None, ss).expand(&info, &mut kek).unwrap(),
+    }
+    kek
+}
+
+// Reads BER elements, allowing the indefinite lengths and constructed
+// strings of a streamed message. Definite lengths need not be minimal.
+struct BerReader<'a>(&'a [u8]);
+
+impl<'a> BerReader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    // The tag and content of the next element; for an indefinite length
+    // the content excludes the end-of-contents octets
+    fn read_any(&mut self, depth: usize) -> Result<(u8, &'a [u8]), Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::InvalidEncoding);
+        }
+        let [tag, first, rest @ ..] = self.0 else {
+            return Err(Error::InvalidEncoding);
+        };
+        // High tag numbers and end-of-contents out of place
+        if *tag & 0x1f == 0x1f || *tag == 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        if *first == 0x80 {
+            if *tag & TAG_CONSTRUCTED == 0 {
+                return Err(Error::InvalidEncoding);
+            }
+            let mut inner = BerReader(rest);
+            while !inner.0.starts_with(&[0, 0]) {
+                inner.read_any(depth + 1)?;
+            }
+            let content = &rest[..rest.len() - inner.0.len()];
+            self.0 = &inner.0[2..];
+            return Ok((*tag, content));
+        }
+        let (len, rest) = match *first {
+            len @ 0..=0x7f => (len as usize, rest),
+            0x81..=0x84 => {
+                let n = (*first & 0x7f) as usize;
+                if rest.len() < n {
+                    return Err(Error::InvalidEncoding);
+                }
+                let len = rest[..n].iter().fold(0usize, |len, &b| len << 8 | b as usize);
+                (len, &rest[n..])
+            }
+            _ => return Err(Error::InvalidEncoding),
+        };
+        if rest.len() < len {
+            return Err(Error::InvalidEncoding);
+        }
+        let (content, rest) = rest.split_at(len);
+        self.0 = rest;
+        Ok((*tag, content))
+    }
+
+    fn read(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        match self.read_any(0)? {
+            (t, content) if t == tag => Ok(content),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidEncoding)
+        }
+    }
+}
+
+// Appends the octets of a primitive or constructed string
+fn read_octets(tag: u8, content: &[u8], out: &mut Vec<u8>, depth: usize) -> Result<(), Error> {
+    if tag & TAG_CONSTRUCTED == 0 {
+        out.extend_from_slice(content);
+        return Ok(());
+    }
+    if depth > MAX_DEPTH {
+        return Err(Error::InvalidEncoding);
+    }
+    let mut chunks = BerReader(content);
+    while chunks.peek().is_some() {
+        let (tag, chunk) = chunks.read_any(depth + 1)?;
+        if tag & !TAG_CONSTRUCTED != TAG_OCTET_STRING {
+            return Err(Error::InvalidEncoding);
+        }
+        read_octets(tag, chunk, out, depth + 1)?;
+    }
+    Ok(())
+}
+
+/// An `EnvelopedData` of `id-data` content, with only its ML-KEM
+/// recipients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvelopedData {
+    pub recipients: Vec<KemRecipientInfo>,
+    pub cipher: ContentCipher,
+    pub iv: [u8; IV_BYTES],
+    pub encrypted_content: Vec<u8>,
+}
+
+impl EnvelopedData {
+    /// Encrypts `content` under `cek`, which must suit `cipher`, with no
+    /// recipients yet.
+    pub fn encrypt_content(
+        cipher: ContentCipher,
+        cek: &[u8],
+        iv: [u8; IV_BYTES],
+        content: &[u8],
+    ) -> Result<Self, Error> {
+        if cek.len() != cipher.key_bytes() {
+            return Err(Error::InvalidLength);
+        }
+        let len = content.len();
+        let mut buf = content.to_vec();
+        buf.resize(len + IV_BYTES - len % IV_BYTES, 0);
+        match cipher {
+            ContentCipher::Aes128Cbc => cbc::Encryptor::<aes::Aes128>::new_from_slices(cek, &iv)
+                .unwrap()
+                .encrypt_padded_mut::<Pkcs7>(&mut buf, len),
+            ContentCipher::Aes192Cbc => cbc::Encryptor::<aes::Aes192>::new_from_slices(cek, &iv)
+                .unwrap()
+                .encrypt_padded_mut::<Pkcs7>(&mut buf, len),
+            ContentCipher::Aes256Cbc => cbc::Encryptor::<aes::Aes256>::new_from_slices(cek, &iv)
+                .unwrap()
+                .encrypt_padded_mut::<Pkcs7>(&mut buf, len),
+        }
+        .unwrap();
+        Ok(EnvelopedData {
+            recipients: Vec::new(),
+            cipher,
+            iv,
+            encrypted_content: buf,
+        })
+    }
+
+    /// Decrypts the content with a content-encryption key one of the
+    /// recipients unwrapped. A wrong key fails with
+    /// [`Error::AuthenticationFailed`] when the padding is wrong, which is
+    /// not guaranteed.
+    pub fn decrypt_content(&self, cek: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+        if cek.len() != self.cipher.key_bytes() {
+            return Err(Error::InvalidLength);
+        }
+        let mut buf = Zeroizing::new(self.encrypted_content.clone());
+        let len = match self.cipher {
+            ContentCipher::Aes128Cbc => cbc::Decryptor::<aes::Aes128>::new_from_slices(cek, &self.iv)
+                .unwrap()
+                .decrypt_padded_mut::<Pkcs7>(&mut buf),
+            ContentCipher::Aes192Cbc => cbc::Decryptor::<aes::Aes192>::new_from_slices(cek, &self.iv)
+                .unwrap()
+                .decrypt_padded_mut::<Pkcs7>(&mut buf),
+            ContentCipher::Aes256Cbc => cbc::Decryptor::<aes::Aes256>::new_from_slices(cek, &self.iv)
+                .unwrap()
+                .decrypt_padded_mut::<Pkcs7>(&mut buf),
+        }
+        .map_err(|_| Error::AuthenticationFailed)?
+        .len();
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    /// The DER of the `ContentInfo` holding the `EnvelopedData`, version 3
+    /// with the recipient infos sorted as a DER SET OF.
+    pub fn to_der(&self) -> Vec<u8> {
+        let mut recipients: Vec<Vec<u8>> = self
+            .recipients
+            .iter()
+            .map(|ri| tlv(TAG_ORI, &[&tlv(TAG_OID, &[OID_ORI_KEM]), &ri.to_der()]))
+            .collect();
+        recipients.sort();
+        let recipients: Vec<&[u8]> = recipients.iter().map(|ri| &ri[..]).collect();
+
+        let cipher = algorithm(self.cipher.oid(), &tlv(TAG_OCTET_STRING, &[&self.iv]));
+        let content_info = tlv(
+            TAG_SEQUENCE,
+            &[
+                &tlv(TAG_OID, &[OID_DATA]),
+                &cipher,
+                &tlv(TAG_IMPLICIT_0, &[&self.encrypted_content]),
+            ],
+        );
+        let enveloped = tlv(TAG_SEQUENCE, &[&integer(3), &tlv(TAG_SET, &recipients), &content_info]);
+        tlv(
+            TAG_SEQUENCE,
+            &[
+                &tlv(TAG_OID, &[OID_ENVELOPED_DATA]),
+                &tlv(TAG_EXPLICIT_0, &[&enveloped]),
+            ],
+        )
+    }
+
+    /// Reads a `ContentInfo` holding an `EnvelopedData`, in DER or BER.
+    /// Recipients other than `KEMRecipientInfo` are skipped, and so are
+    /// `KEMRecipientInfo`s that fail with [`Error::UnsupportedAlgorithm`] or
+    /// [`Error::InvalidParameters`], as they may be for other software. So
+    /// are `originatorInfo` and `unprotectedAttrs`. Content other than
+    /// `id-data` fails with [`Error::UnsupportedAlgorithm`].
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        let mut outer = BerReader(der);
+        let mut content_info = BerReader(outer.read(TAG_SEQUENCE)?);
+        outer.finish()?;
+        if content_info.read(TAG_OID)? != OID_ENVELOPED_DATA {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let mut explicit = BerReader(content_info.read(TAG_EXPLICIT_0)?);
+        content_info.finish()?;
+        let mut enveloped = BerReader(explicit.read(TAG_SEQUENCE)?);
+        explicit.finish()?;
+
+        enveloped.read(TAG_INTEGER)?;
+        if enveloped.peek() == Some(TAG_EXPLICIT_0) {
+            enveloped.read(TAG_EXPLICIT_0)?;
+        }
+        let mut set = BerReader(enveloped.read(TAG_SET)?);
+        let mut recipients = Vec::new();
+        while set.peek().is_some() {
+            let (tag, ri) = set.read_any(0)?;
+            if tag != TAG_ORI {
+                continue;
+            }
+            let mut ori = Reader(ri);
+            if ori.read(TAG_OID)? != OID_ORI_KEM {
+                continue;
+            }
+            match KemRecipientInfo::from_der(ori.0) {
+                Ok(ri) => recipients.push(ri),
+                Err(Error::UnsupportedAlgorithm | Error::InvalidParameters) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut encrypted = BerReader(enveloped.read(TAG_SEQUENCE)?);
+        if encrypted.read(TAG_OID)? != OID_DATA {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let mut alg = BerReader(encrypted.read(TAG_SEQUENCE)?);
+        let cipher = ContentCipher::from_oid(alg.read(TAG_OID)?)?;
+        let iv = alg
+            .read(TAG_OCTET_STRING)?
+            .try_into()
+            .map_err(|_| Error::InvalidLength)?;
+        alg.finish()?;
+        // Detached content is not supported
+        let (tag, content) = encrypted.read_any(0)?;
+        if tag & !TAG_CONSTRUCTED != TAG_IMPLICIT_0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut encrypted_content = Vec::with_capacity(content.len());
+        read_octets(tag, content, &mut encrypted_content, 0)?;
+        encrypted.finish()?;
+
+        if enveloped.peek() == Some(TAG_UNPROTECTED_ATTRS) {
+            enveloped.read(TAG_UNPROTECTED_ATTRS)?;
+        }
+        enveloped.finish()?;
+        Ok(EnvelopedData {
+            recipients,
+            cipher,
+            iv,
+            encrypted_content,
+        })
+    }
+}
+
+macro_rules! cms_level {
+    ($level:ident, $kem:ident, $name:expr) => {
+        #[doc = concat!("CMS `KEMRecipientInfo` recipients for ", $name, ".")]
+        pub mod $level {
+            use super::{
+                derive_kek, ContentCipher, EnvelopedData, Kdf, KemRecipientInfo, KeyWrap, RecipientId, IV_BYTES,
+            };
+            use crate::der::$level::OID;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{$kem, Ciphertext, PublicKey, SecretKey};
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, Zeroizing};
+
+            /// Encapsulates to `pk` and wraps `cek` under the derived
+            /// key-encryption key.
+            pub fn wrap_content_key<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                rid: RecipientId,
+                kdf: Kdf,
+                wrap: KeyWrap,
+                ukm: Option<&[u8]>,
+                cek: &[u8],
+            ) -> Result<KemRecipientInfo, Error> {
+                let (ct, mut ss) = $kem::encaps(rng, pk)?;
+                let kek = derive_kek(kdf, ss.as_bytes(), wrap, ukm);
+                ss.zeroize();
+                Ok(KemRecipientInfo {
+                    rid,
+                    kem: OID.to_vec(),
+                    kemct: ct.as_bytes().to_vec(),
+                    kdf,
+                    ukm: ukm.map(|ukm| ukm.to_vec()),
+                    wrap,
+                    encrypted_key: wrap.wrap(&kek, cek)?,
+                })
+            }
+
+            /// The content-encryption key of a recipient info for this
+            /// level. Fails with [`Error::UnsupportedAlgorithm`] for another
+            /// KEM, and with [`Error::AuthenticationFailed`] when the key
+            /// does not unwrap, which includes a recipient info for another
+            /// key.
+            pub fn unwrap_content_key(sk: &SecretKey, ri: &KemRecipientInfo) -> Result<Zeroizing<Vec<u8>>, Error> {
+                if ri.kem != OID {
+                    return Err(Error::UnsupportedAlgorithm);
+                }
+                let ct: [u8; kyber_params::CIPHERTEXT_BYTES] =
+                    ri.kemct[..].try_into().map_err(|_| Error::InvalidLength)?;
+                let mut ss = $kem::decaps(sk, &Ciphertext::from(ct))?;
+                let kek = derive_kek(ri.kdf, ss.as_bytes(), ri.wrap, ri.ukm.as_deref());
+                ss.zeroize();
+                ri.wrap.unwrap(&kek, &ri.encrypted_key)
+            }
+
+            /// Encrypts `content` to every `(public key, recipient
+            /// identifier)` as OpenSSL does: HKDF-SHA256 and the key wrap
+            /// of [`ContentCipher::key_wrap`]. Returns the DER of the
+            /// `ContentInfo`; fails with [`Error::InvalidLength`] for no
+            /// recipients.
+            pub fn encrypt<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                recipients: &[(&PublicKey, RecipientId)],
+                cipher: ContentCipher,
+                content: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                if recipients.is_empty() {
+                    return Err(Error::InvalidLength);
+                }
+                let mut cek = Zeroizing::new(vec![0u8; cipher.key_bytes()]);
+                rng.fill_bytes(&mut cek);
+                let mut iv = [0u8; IV_BYTES];
+                rng.fill_bytes(&mut iv);
+                let mut enveloped = EnvelopedData::encrypt_content(cipher, &cek, iv, content)?;
+                for (pk, rid) in recipients {
+                    let ri = wrap_content_key(
+                        rng,
+                        pk,
+                        rid.clone(),
+                        Kdf::HkdfSha256,
+                        cipher.key_wrap(),
+                        None,
+                        &cek,
+                    )?;
+                    enveloped.recipients.push(ri);
+                }
+                Ok(enveloped.to_der())
+            }
+
+            /// Decrypts a `ContentInfo` holding an `EnvelopedData`, trying
+            /// every recipient info of this level whose identifier, if
+            /// given, is `rid`. Fails with [`Error::NotRecipient`] if there
+            /// is none, and otherwise with the error of the last one tried.
+            pub fn decrypt(sk: &SecretKey, rid: Option<&RecipientId>, der: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+                let enveloped = EnvelopedData::from_der(der)?;
+                let mut result = Err(Error::NotRecipient);
+                let candidates = enveloped
+                    .recipients
+                    .iter()
+                    .filter(|ri| ri.kem == OID && rid.map_or(true, |rid| ri.rid == *rid));
+                for ri in candidates {
+                    result = unwrap_content_key(sk, ri).and_then(|cek| enveloped.decrypt_content(&cek));
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+cms_level!(kyber512, MlKem512, "ML-KEM-512");
+#[cfg(feature = "kyber768")]
+cms_level!(kyber768, MlKem768, "ML-KEM-768");
+#[cfg(feature = "kyber1024")]
+cms_level!(kyber1024, MlKem1024, "ML-KEM-1024");
+
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{decrypt, encrypt, unwrap_content_key, wrap_content_key};
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{decrypt, encrypt, unwrap_content_key, wrap_content_key};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{decrypt, encrypt, unwrap_content_key, wrap_content_key};
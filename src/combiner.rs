@@ -0,0 +1,221 @@
+//! Generic two-KEM combiner for composite and hybrid KEMs.
+//!
+//! [`Combiner<K1, K2>`] runs both KEMs side by side and derives one 32-byte
+//! shared secret from the two secrets, the ciphertexts, the public keys and a
+//! label. The combining functions of the drafts concatenate fixed-width fields:
+//!
+//! ```text
+//! Composite, Qsf:  SHA3-256(ss1 || ss2 || ct2 || pk2 || label)
+//! KitchenSink:     SHA3-256(ss1 || ss2 || ct1 || ct2 || pk1 || pk2 || label)
+//! ```
+//!
+//! [`Kdf::Composite`] is the combiner of the LAMPS composite ML-KEM draft
+//! (draft-ietf-lamps-pq-composite-kem), where the label is its `Domain`, the
+//! DER encoding of the composite algorithm's OID. [`Kdf::KitchenSink`] and
+//! [`Kdf::Qsf`] are the combiners of the same names in the CFRG hybrid-KEM
+//! draft (draft-irtf-cfrg-hybrid-kems). In all three the first KEM must be
+//! ML-KEM. Composite and QSF leave out the ML-KEM ciphertext and public key,
+//! which ML-KEM already binds into its own shared secret. X-Wing is QSF over
+//! ML-KEM-768 and X25519 with the label `\.//^\`.
+//!
+//! The `LengthPrefixed*` functions are specific to this crate and do not
+//! interoperate with anything else. They prefix every field with its length
+//! as a 4-byte big-endian integer and bind all six, so they stay unambiguous
+//! for KEMs whose encodings vary in length and need no assumption about how
+//! either KEM behaves under ciphertext or key substitution.
+//!
+//! The component shared secrets are wiped once they are combined.
+
+use crate::error::Error;
+use crate::traits::Kem;
+use crate::utils::kmac256;
+use core::marker::PhantomData;
+use rand_core::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake256};
+use zeroize::Zeroize;
+
+pub const SHARED_SECRET_BYTES: usize = 32;
+
+/// The combining function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kdf {
+    /// LAMPS composite ML-KEM: `SHA3-256(mlkemSS || tradSS || tradCT || tradPK || Domain)`.
+    /// The same function as [`Kdf::Qsf`]; the drafts only differ in the label.
+    Composite,
+    /// CFRG KitchenSink: SHA3-256 over both secrets, both ciphertexts, both
+    /// public keys and the label.
+    KitchenSink,
+    /// CFRG QSF: `SHA3-256(ss_PQ || ss_T || ct_T || ek_T || label)`.
+    Qsf,
+    /// Non-standard: SHA3-256 over the length-prefixed fields and the label.
+    LengthPrefixedSha3_256,
+    /// Non-standard: SHAKE256 over the length-prefixed fields and the label,
+    /// 32 bytes of output.
+    LengthPrefixedShake256,
+    /// Non-standard: KMAC256 with the length-prefixed secrets as key `K`, the
+    /// length-prefixed ciphertexts and public keys as input `X` and the label
+    /// as customization string `S`.
+    LengthPrefixedKmac256,
+}
+
+pub struct PublicKey<K1: Kem, K2: Kem> {
+    pub first: K1::PublicKey,
+    pub second: K2::PublicKey,
+}
+
+pub struct SecretKey<K1: Kem, K2: Kem> {
+    pub first: K1::SecretKey,
+    pub second: K2::SecretKey,
+}
+
+pub struct Ciphertext<K1: Kem, K2: Kem> {
+    pub first: K1::Ciphertext,
+    pub second: K2::Ciphertext,
+}
+
+#[derive(Clone, Copy, Zeroize)]
+pub struct SharedSecret([u8; SHARED_SECRET_BYTES]);
+
+impl From<SharedSecret> for [u8; SHARED_SECRET_BYTES] {
+    fn from(ss: SharedSecret) -> Self {
+        ss.0
+    }
+}
+
+impl SharedSecret {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Derives application keys from this secret, see [`crate::kdf`].
+    #[cfg(feature = "kdf")]
+    pub fn derive_keys(&self, algorithm: crate::kdf::Algorithm) -> crate::kdf::KeyDerivation<'_> {
+        crate::kdf::KeyDerivation::new(algorithm, &self.0)
+    }
+}
+
+impl AsRef<[u8]> for SharedSecret {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Composes `K1` and `K2` with a fixed KDF and label.
+pub struct Combiner<K1: Kem, K2: Kem> {
+    kdf: Kdf,
+    label: &'static [u8],
+    _kems: PhantomData<fn() -> (K1, K2)>,
+}
+
+impl<K1: Kem, K2: Kem> Clone for Combiner<K1, K2> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K1: Kem, K2: Kem> Copy for Combiner<K1, K2> {}
+
+impl<K1: Kem, K2: Kem> Combiner<K1, K2> {
+    pub const fn new(kdf: Kdf, label: &'static [u8]) -> Self {
+        Combiner { kdf, label, _kems: PhantomData }
+    }
+
+    pub fn kdf(&self) -> Kdf {
+        self.kdf
+    }
+
+    /// The algorithm identifier bound into every shared secret.
+    pub fn label(&self) -> &'static [u8] {
+        self.label
+    }
+
+    /// Names of the component algorithms, in order.
+    pub fn algorithms(&self) -> [&'static str; 2] {
+        [K1::NAME, K2::NAME]
+    }
+
+    pub fn keypair<R: RngCore + CryptoRng>(&self, rng: &mut R) -> (PublicKey<K1, K2>, SecretKey<K1, K2>) {
+        let (pk1, sk1) = K1::keypair(rng);
+        let (pk2, sk2) = K2::keypair(rng);
+        (
+            PublicKey { first: pk1, second: pk2 },
+            SecretKey { first: sk1, second: sk2 },
+        )
+    }
+
+    pub fn encaps<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pk: &PublicKey<K1, K2>,
+    ) -> Result<(Ciphertext<K1, K2>, SharedSecret), Error> {
+        let (ct1, mut ss1) = K1::encaps(rng, &pk.first)?;
+        let (ct2, mut ss2) = match K2::encaps(rng, &pk.second) {
+            Ok(result) => result,
+            Err(e) => {
+                ss1.zeroize();
+                return Err(e);
+            }
+        };
+        let ss = self.combine(ss1.as_ref(), ss2.as_ref(), ct1.as_ref(), ct2.as_ref(), pk);
+        ss1.zeroize();
+        ss2.zeroize();
+        Ok((Ciphertext { first: ct1, second: ct2 }, ss))
+    }
+
+    /// Needs the public key as well, since it is bound into the shared secret.
+    pub fn decaps(
+        &self,
+        sk: &SecretKey<K1, K2>,
+        pk: &PublicKey<K1, K2>,
+        ct: &Ciphertext<K1, K2>,
+    ) -> Result<SharedSecret, Error> {
+        let mut ss1 = K1::decaps(&sk.first, &ct.first)?;
+        let mut ss2 = match K2::decaps(&sk.second, &ct.second) {
+            Ok(ss2) => ss2,
+            Err(e) => {
+                ss1.zeroize();
+                return Err(e);
+            }
+        };
+        let ss = self.combine(ss1.as_ref(), ss2.as_ref(), ct.first.as_ref(), ct.second.as_ref(), pk);
+        ss1.zeroize();
+        ss2.zeroize();
+        Ok(ss)
+    }
+
+    fn combine(&self, ss1: &[u8], ss2: &[u8], ct1: &[u8], ct2: &[u8], pk: &PublicKey<K1, K2>) -> SharedSecret {
+        let (pk1, pk2) = (pk.first.as_ref(), pk.second.as_ref());
+        let [l1, l2, l3, l4, l5, l6] = [ss1, ss2, ct1, ct2, pk1, pk2].map(|field| length_prefix(field.len()));
+        let secrets: [&[u8]; 4] = [&l1, ss1, &l2, ss2];
+        let public: [&[u8]; 8] = [&l3, ct1, &l4, ct2, &l5, pk1, &l6, pk2];
+        let mut out = [0u8; SHARED_SECRET_BYTES];
+        match self.kdf {
+            Kdf::Composite | Kdf::Qsf => sha3_256([ss1, ss2, ct2, pk2, self.label], &mut out),
+            Kdf::KitchenSink => sha3_256([ss1, ss2, ct1, ct2, pk1, pk2, self.label], &mut out),
+            Kdf::LengthPrefixedSha3_256 => sha3_256(secrets.into_iter().chain(public).chain([self.label]), &mut out),
+            Kdf::LengthPrefixedShake256 => {
+                let mut hasher = Shake256::default();
+                for part in secrets.into_iter().chain(public).chain([self.label]) {
+                    hasher.update(part);
+                }
+                hasher.finalize_xof().read(&mut out);
+            }
+            Kdf::LengthPrefixedKmac256 => kmac256(&secrets, &public, self.label, &mut out),
+        }
+        SharedSecret(out)
+    }
+}
+
+fn sha3_256<'a>(parts: impl IntoIterator<Item = &'a [u8]>, out: &mut [u8; SHARED_SECRET_BYTES]) {
+    let mut hasher = Sha3_256::new();
+    for part in parts {
+        Digest::update(&mut hasher, part);
+    }
+    out.copy_from_slice(&hasher.finalize());
+}
+
+// Every encoding in this crate is far below 4 GiB
+fn length_prefix(len: usize) -> [u8; 4] {
+    (len as u32).to_be_bytes()
+}
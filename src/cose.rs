@@ -0,0 +1,891 @@
+//! COSE (RFC 9052) for ML-KEM: keys as COSE_Key and ML-KEM recipients for
+//! COSE_Encrypt and COSE_Encrypt0, following draft-ietf-cose-hpke and the
+//! COSE part of draft-ietf-jose-pqc-kem. The module needs only `alloc`.
+//!
+//! Keys use the `AKP` (algorithm key pair) key type, `7`, with the
+//! encapsulation key in `pub` (`-1`) and the 64-byte seed `d || z` in `priv`
+//! (`-2`), so a private COSE_Key reads into a level's `SecretSeed`.
+//!
+//! Each level has four algorithms:
+//!
+//! | Algorithm            | ML-KEM-512 | ML-KEM-768 | ML-KEM-1024 |
+//! |----------------------|------------|------------|-------------|
+//! | KEM direct           | `-70010`   | `-70011`   | `-70012`    |
+//! | KEM + AES-KW         | `-70020`   | `-70021`   | `-70022`    |
+//! | HPKE integrated      | `-70030`   | `-70031`   | `-70032`    |
+//! | HPKE key encryption  | `-70040`   | `-70041`   | `-70042`    |
+//!
+//! The drafts leave the algorithm identifiers to IANA, so these sit in the
+//! private-use range (below `-65536`) and will change once values are
+//! assigned. Only messages made with this crate interoperate for now.
+//!
+//! HPKE integrated encryption seals the payload of a COSE_Encrypt0 to one
+//! recipient, with the `Enc_structure` as associated data. In a COSE_Encrypt
+//! the payload is under a random content key (AES-GCM), and every recipient
+//! structure carries that key: sealed with HPKE, with `Recipient_structure`
+//! as the info, or wrapped with AES Key Wrap under a key derived from the
+//! ML-KEM shared secret. In direct mode the content key is itself derived
+//! from the shared secret, so there is exactly one recipient. The derivation
+//! is HKDF-SHA256 with the `COSE_KDF_Context` of RFC 9053 section 5.2, as
+//! for ECDH-ES. The HPKE ciphersuites are ML-KEM-512 and ML-KEM-768 with
+//! HKDF-SHA256, and ML-KEM-1024 with HKDF-SHA384, under AES-128-GCM for
+//! ML-KEM-512 and AES-256-GCM otherwise.
+//!
+//! The ML-KEM ciphertext, or HPKE encapsulated key, travels in the `ek`
+//! header parameter (`-4`) of the recipient's unprotected header.
+
+use crate::error::Error;
+use crate::hpke::{Aead, AesGcm128, AesGcm256, NONCE_BYTES};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use zeroize::Zeroizing;
+
+pub const KEY_TYPE: i64 = 7;
+
+/// COSE_Key labels.
+pub const KEY_KTY: i64 = 1;
+pub const KEY_KID: i64 = 2;
+pub const KEY_ALG: i64 = 3;
+pub const KEY_PUB: i64 = -1;
+pub const KEY_PRIV: i64 = -2;
+
+/// Header parameter labels.
+pub const HEADER_ALG: i64 = 1;
+pub const HEADER_KID: i64 = 4;
+pub const HEADER_IV: i64 = 5;
+pub const HEADER_EK: i64 = -4;
+
+/// CBOR tags of the two message types.
+pub const TAG_ENCRYPT0: u64 = 16;
+pub const TAG_ENCRYPT: u64 = 96;
+
+/// Content encryption algorithms for COSE_Encrypt.
+pub const A128GCM: i64 = 1;
+pub const A256GCM: i64 = 3;
+
+// Nesting allowed when decoding, enough for any COSE message
+const MAX_DEPTH: usize = 16;
+
+// The CBOR data model, less indefinite lengths
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+    // Simple values and floats, kept by their raw argument
+    Simple(u8, u64),
+}
+
+const NULL: Value = Value::Simple(22, 22);
+
+fn write_head(major: u8, n: u64, out: &mut Vec<u8>) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= 0xff {
+        out.extend_from_slice(&[major | 24, n as u8]);
+    } else if n <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn encode(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Int(n) if *n >= 0 => write_head(0, *n as u64, out),
+        // -1 - n, which is !n in two's complement
+        Value::Int(n) => write_head(1, !*n as u64, out),
+        Value::Bytes(bytes) => {
+            write_head(2, bytes.len() as u64, out);
+            out.extend_from_slice(bytes);
+        }
+        Value::Text(text) => {
+            write_head(3, text.len() as u64, out);
+            out.extend_from_slice(text.as_bytes());
+        }
+        Value::Array(items) => {
+            write_head(4, items.len() as u64, out);
+            for item in items {
+                encode(item, out);
+            }
+        }
+        Value::Map(entries) => {
+            write_head(5, entries.len() as u64, out);
+            for (key, value) in entries {
+                encode(key, out);
+                encode(value, out);
+            }
+        }
+        Value::Tag(tag, inner) => {
+            write_head(6, *tag, out);
+            encode(inner, out);
+        }
+        Value::Simple(info, raw) => {
+            out.push(0xe0 | info);
+            let len = match info {
+                24 => 1,
+                25 => 2,
+                26 => 4,
+                27 => 8,
+                _ => 0,
+            };
+            out.extend_from_slice(&raw.to_be_bytes()[8 - len..]);
+        }
+    }
+}
+
+fn to_vec(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode(value, &mut out);
+    out
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len > self.data.len() {
+            return Err(Error::InvalidEncoding);
+        }
+        let (head, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(head)
+    }
+
+    // Major type, additional information and argument
+    fn head(&mut self) -> Result<(u8, u8, u64), Error> {
+        let first = self.take(1)?[0];
+        let info = first & 0x1f;
+        let len = match info {
+            0..=23 => return Ok((first >> 5, info, info as u64)),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            // Reserved, or an indefinite length
+            _ => return Err(Error::InvalidEncoding),
+        };
+        let mut arg = [0u8; 8];
+        arg[8 - len..].copy_from_slice(self.take(len)?);
+        Ok((first >> 5, info, u64::from_be_bytes(arg)))
+    }
+
+    // A count of items, each at least one byte, so a bogus length fails here
+    // instead of in an allocation
+    fn count(&self, n: u64) -> Result<usize, Error> {
+        match usize::try_from(n) {
+            Ok(n) if n <= self.data.len() => Ok(n),
+            _ => Err(Error::InvalidEncoding),
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, Error> {
+        if depth == MAX_DEPTH {
+            return Err(Error::InvalidEncoding);
+        }
+        let (major, info, arg) = self.head()?;
+        Ok(match major {
+            0 => Value::Int(i64::try_from(arg).map_err(|_| Error::InvalidEncoding)?),
+            1 => Value::Int(!i64::try_from(arg).map_err(|_| Error::InvalidEncoding)?),
+            2 => Value::Bytes(self.take(self.count(arg)?)?.to_vec()),
+            3 => {
+                let bytes = self.take(self.count(arg)?)?;
+                Value::Text(String::from(
+                    core::str::from_utf8(bytes).map_err(|_| Error::InvalidEncoding)?,
+                ))
+            }
+            4 => {
+                let n = self.count(arg)?;
+                Value::Array(
+                    (0..n)
+                        .map(|_| self.value(depth + 1))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            5 => {
+                let n = self.count(arg)?;
+                let mut entries: Vec<(Value, Value)> = Vec::new();
+                for _ in 0..n {
+                    let key = self.value(depth + 1)?;
+                    // RFC 9052 section 3: duplicate labels are an error
+                    if entries.iter().any(|(k, _)| *k == key) {
+                        return Err(Error::InvalidEncoding);
+                    }
+                    let value = self.value(depth + 1)?;
+                    entries.push((key, value));
+                }
+                Value::Map(entries)
+            }
+            6 => Value::Tag(arg, Box::new(self.value(depth + 1)?)),
+            _ => Value::Simple(info, arg),
+        })
+    }
+}
+
+// Exactly one CBOR item
+fn decode(data: &[u8]) -> Result<Value, Error> {
+    let mut decoder = Decoder { data };
+    let value = decoder.value(0)?;
+    if !decoder.data.is_empty() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(value)
+}
+
+fn lookup(map: &[(Value, Value)], label: i64) -> Option<&Value> {
+    map.iter()
+        .find(|(key, _)| *key == Value::Int(label))
+        .map(|(_, value)| value)
+}
+
+/// A COSE_Key of key type `AKP`. Unknown parameters are ignored on input.
+#[derive(Clone)]
+pub struct CoseKey {
+    pub kty: i64,
+    pub alg: i64,
+    pub public: Vec<u8>,
+    pub private: Option<Zeroizing<Vec<u8>>>,
+    pub kid: Option<Vec<u8>>,
+}
+
+impl CoseKey {
+    /// Fails with [`Error::UnsupportedAlgorithm`] if `kty` or `alg` is a
+    /// text string rather than an integer.
+    pub fn from_cbor(cbor: &[u8]) -> Result<Self, Error> {
+        let Value::Map(map) = decode(cbor)? else {
+            return Err(Error::InvalidEncoding);
+        };
+        let int = |label| match lookup(&map, label) {
+            Some(Value::Int(n)) => Ok(*n),
+            Some(Value::Text(_)) => Err(Error::UnsupportedAlgorithm),
+            _ => Err(Error::InvalidEncoding),
+        };
+        let bytes = |label| match lookup(&map, label) {
+            Some(Value::Bytes(bytes)) => Ok(Some(bytes.clone())),
+            None => Ok(None),
+            _ => Err(Error::InvalidEncoding),
+        };
+        Ok(CoseKey {
+            kty: int(KEY_KTY)?,
+            alg: int(KEY_ALG)?,
+            public: bytes(KEY_PUB)?.ok_or(Error::InvalidEncoding)?,
+            private: bytes(KEY_PRIV)?.map(Zeroizing::new),
+            kid: bytes(KEY_KID)?,
+        })
+    }
+
+    /// The deterministic encoding, zeroized on drop as a private key holds
+    /// the seed.
+    pub fn to_cbor(&self) -> Zeroizing<Vec<u8>> {
+        let mut map = Vec::from([(Value::Int(KEY_KTY), Value::Int(self.kty))]);
+        if let Some(kid) = &self.kid {
+            map.push((Value::Int(KEY_KID), Value::Bytes(kid.clone())));
+        }
+        map.push((Value::Int(KEY_ALG), Value::Int(self.alg)));
+        map.push((Value::Int(KEY_PUB), Value::Bytes(self.public.clone())));
+
+        // `priv` goes straight into the zeroized buffer, sized up front so it
+        // never reallocates
+        let private = self
+            .private
+            .as_deref()
+            .map_or(&[][..], |private| &private[..]);
+        let mut out = Zeroizing::new(Vec::with_capacity(self.public.len() + private.len() + 64));
+        write_head(
+            5,
+            map.len() as u64 + self.private.is_some() as u64,
+            &mut out,
+        );
+        for (key, value) in &map {
+            encode(key, &mut out);
+            encode(value, &mut out);
+        }
+        if self.private.is_some() {
+            encode(&Value::Int(KEY_PRIV), &mut out);
+            write_head(2, private.len() as u64, &mut out);
+            out.extend_from_slice(private);
+        }
+        out
+    }
+}
+
+/// How a COSE_Encrypt recipient carries the content key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecipientMode {
+    /// HPKE key encryption of a random content key.
+    Hpke,
+    /// The content key is derived from the ML-KEM shared secret. Allows one
+    /// recipient only.
+    Direct,
+    /// A random content key wrapped with AES Key Wrap.
+    KeyWrap,
+}
+
+// One layer of a message: a COSE_Encrypt0, the content of a COSE_Encrypt or
+// a COSE_recipient
+struct Layer {
+    protected: Vec<u8>,
+    protected_map: Vec<(Value, Value)>,
+    unprotected: Vec<(Value, Value)>,
+    ciphertext: Vec<u8>,
+}
+
+impl Layer {
+    // [protected, unprotected, ciphertext, rest...]
+    fn from_items(items: &mut Vec<Value>) -> Result<Self, Error> {
+        let mut items = items.drain(..3);
+        let (
+            Some(Value::Bytes(protected)),
+            Some(Value::Map(unprotected)),
+            Some(Value::Bytes(ciphertext)),
+        ) = (items.next(), items.next(), items.next())
+        else {
+            return Err(Error::InvalidEncoding);
+        };
+        let protected_map = match protected.is_empty() {
+            true => Vec::new(),
+            false => match decode(&protected)? {
+                Value::Map(map) => map,
+                _ => return Err(Error::InvalidEncoding),
+            },
+        };
+        Ok(Layer {
+            protected,
+            protected_map,
+            unprotected,
+            ciphertext,
+        })
+    }
+
+    fn header(&self, label: i64) -> Option<&Value> {
+        lookup(&self.protected_map, label).or_else(|| lookup(&self.unprotected, label))
+    }
+
+    fn alg(&self) -> Option<i64> {
+        match self.header(HEADER_ALG) {
+            Some(Value::Int(alg)) => Some(*alg),
+            _ => None,
+        }
+    }
+
+    fn bytes(&self, label: i64) -> Option<&[u8]> {
+        match self.header(label) {
+            Some(Value::Bytes(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
+// The array of a message, tagged with `tag` or untagged
+fn message_items(message: &[u8], tag: u64, len: usize) -> Result<Vec<Value>, Error> {
+    let items = match decode(message)? {
+        Value::Tag(t, inner) if t == tag => *inner,
+        Value::Tag(..) => return Err(Error::InvalidHeader),
+        value => value,
+    };
+    match items {
+        Value::Array(items) if items.len() == len => Ok(items),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+fn parse_encrypt0(message: &[u8]) -> Result<Layer, Error> {
+    Layer::from_items(&mut message_items(message, TAG_ENCRYPT0, 3)?)
+}
+
+// The content layer and the recipients; nested recipients are not supported
+fn parse_encrypt(message: &[u8]) -> Result<(Layer, Vec<Layer>), Error> {
+    let mut items = message_items(message, TAG_ENCRYPT, 4)?;
+    let content = Layer::from_items(&mut items)?;
+    let Some(Value::Array(recipients)) = items.pop() else {
+        return Err(Error::InvalidEncoding);
+    };
+    let recipients = recipients
+        .into_iter()
+        .map(|recipient| match recipient {
+            Value::Array(mut items) if items.len() == 3 => Layer::from_items(&mut items),
+            _ => Err(Error::InvalidEncoding),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if recipients.is_empty() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok((content, recipients))
+}
+
+fn layer(protected: Vec<u8>, unprotected: Vec<(Value, Value)>, ciphertext: Vec<u8>) -> Vec<Value> {
+    Vec::from([
+        Value::Bytes(protected),
+        Value::Map(unprotected),
+        Value::Bytes(ciphertext),
+    ])
+}
+
+// The protected header {1: alg}
+fn protected_header(alg: i64) -> Vec<u8> {
+    to_vec(&Value::Map(Vec::from([(
+        Value::Int(HEADER_ALG),
+        Value::Int(alg),
+    )])))
+}
+
+// The unprotected header of a recipient, {4: kid, -4: ek}
+fn recipient_header(kid: Option<&[u8]>, ek: &[u8]) -> Vec<(Value, Value)> {
+    let mut map = Vec::new();
+    if let Some(kid) = kid {
+        map.push((Value::Int(HEADER_KID), Value::Bytes(kid.to_vec())));
+    }
+    map.push((Value::Int(HEADER_EK), Value::Bytes(ek.to_vec())));
+    map
+}
+
+// Whether a recipient may be for the holder of `kid`: one without a kid is
+fn kid_matches(layer: &Layer, kid: Option<&[u8]>) -> bool {
+    match (kid, layer.bytes(HEADER_KID)) {
+        (Some(kid), Some(other)) => kid == other,
+        _ => true,
+    }
+}
+
+// Enc_structure, the associated data of the content layer
+fn enc_structure(context: &str, protected: &[u8], external_aad: &[u8]) -> Vec<u8> {
+    to_vec(&Value::Array(Vec::from([
+        Value::Text(String::from(context)),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(external_aad.to_vec()),
+    ])))
+}
+
+// Recipient_structure of draft-ietf-cose-hpke, the HPKE info of key encryption
+fn recipient_structure(content_alg: i64, protected: &[u8]) -> Vec<u8> {
+    to_vec(&Value::Array(Vec::from([
+        Value::Text(String::from("HPKE Recipient")),
+        Value::Int(content_alg),
+        Value::Bytes(protected.to_vec()),
+        Value::Bytes(Vec::new()),
+    ])))
+}
+
+// HKDF-SHA256 of the shared secret, with COSE_KDF_Context as the info and
+// no PartyU or PartyV identities
+fn derive_key(shared_secret: &[u8], alg: i64, protected: &[u8], out: &mut [u8]) {
+    let party = Value::Array(Vec::from([NULL, NULL, NULL]));
+    let context = to_vec(&Value::Array(Vec::from([
+        Value::Int(alg),
+        party.clone(),
+        party,
+        Value::Array(Vec::from([
+            Value::Int(out.len() as i64 * 8),
+            Value::Bytes(protected.to_vec()),
+        ])),
+    ])));
+    hkdf::Hkdf::<sha2::Sha256>::new(None, shared_secret)
+        .expand(&context, out)
+        .unwrap();
+}
+
+fn content_key_bytes(alg: i64) -> Result<usize, Error> {
+    match alg {
+        A128GCM => Ok(AesGcm128::NK),
+        A256GCM => Ok(AesGcm256::NK),
+        _ => Err(Error::UnsupportedAlgorithm),
+    }
+}
+
+fn seal_content(alg: i64, key: &[u8], iv: &[u8; NONCE_BYTES], aad: &[u8], pt: &[u8]) -> Vec<u8> {
+    match alg {
+        A128GCM => AesGcm128::seal(key, iv, aad, pt),
+        _ => AesGcm256::seal(key, iv, aad, pt),
+    }
+}
+
+fn open_content(
+    alg: i64,
+    key: &[u8],
+    iv: &[u8; NONCE_BYTES],
+    aad: &[u8],
+    ct: &[u8],
+) -> Result<Vec<u8>, Error> {
+    match alg {
+        A128GCM => AesGcm128::open(key, iv, aad, ct),
+        _ => AesGcm256::open(key, iv, aad, ct),
+    }
+}
+
+// The largest content key, 32 bytes, plus the AES-KW integrity block
+const MAX_WRAPPED_BYTES: usize = 32 + 8;
+
+macro_rules! cose_level {
+    (
+        $level:ident, $kem:ident, $name:expr, $alg:expr, $alg_kw:expr, $alg_hpke:expr, $alg_hpke_ke:expr,
+        $kek:ty, $kek_bytes:expr, $kdf:ty, $aead:ty
+    ) => {
+        #[doc = concat!("COSE_Key and COSE recipients for ", $name, ".")]
+        pub mod $level {
+            use super::{
+                content_key_bytes, derive_key, enc_structure, kid_matches, layer, open_content,
+                parse_encrypt, parse_encrypt0, protected_header, recipient_header,
+                recipient_structure, seal_content, to_vec, CoseKey, Layer, RecipientMode, Value,
+                HEADER_EK, HEADER_IV, KEY_TYPE, MAX_WRAPPED_BYTES, TAG_ENCRYPT, TAG_ENCRYPT0,
+            };
+            use crate::error::Error;
+            use crate::hpke::{Hpke, NONCE_BYTES};
+            use crate::kem::check_public_key;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{$kem, Ciphertext, PublicKey, SecretKey, SecretSeed};
+            use alloc::boxed::Box;
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, Zeroizing};
+
+            /// KEM direct key agreement, also the `alg` of a COSE_Key.
+            pub const ALG: i64 = $alg;
+            /// KEM with the content key wrapped by AES Key Wrap.
+            pub const ALG_KW: i64 = $alg_kw;
+            /// HPKE integrated encryption, for COSE_Encrypt0.
+            pub const ALG_HPKE: i64 = $alg_hpke;
+            /// HPKE key encryption, for COSE_Encrypt recipients.
+            pub const ALG_HPKE_KE: i64 = $alg_hpke_ke;
+
+            const KEK_BYTES: usize = $kek_bytes;
+
+            type Suite = Hpke<$kem, $kdf, $aead>;
+
+            fn check_key(key: &CoseKey) -> Result<(), Error> {
+                if key.kty != KEY_TYPE || ![ALG, ALG_KW, ALG_HPKE, ALG_HPKE_KE].contains(&key.alg) {
+                    return Err(Error::UnsupportedAlgorithm);
+                }
+                Ok(())
+            }
+
+            fn ciphertext(ek: Option<&[u8]>) -> Result<Ciphertext, Error> {
+                let ek = ek.ok_or(Error::InvalidEncoding)?;
+                let ct: [u8; kyber_params::CIPHERTEXT_BYTES] =
+                    ek.try_into().map_err(|_| Error::InvalidLength)?;
+                Ok(Ciphertext::from(ct))
+            }
+
+            impl PublicKey {
+                pub fn to_cose_key(&self) -> CoseKey {
+                    CoseKey {
+                        kty: KEY_TYPE,
+                        alg: ALG,
+                        public: self.as_bytes().to_vec(),
+                        private: None,
+                        kid: None,
+                    }
+                }
+
+                /// Reads `pub` from a public or private COSE_Key. Fails with
+                /// [`Error::UnsupportedAlgorithm`] for a key of another key
+                /// type or level, and with [`Error::InvalidPublicKey`] if the
+                /// key does not pass the FIPS 203 modulus check.
+                pub fn from_cose_key(key: &CoseKey) -> Result<Self, Error> {
+                    check_key(key)?;
+                    let pk: [u8; kyber_params::PUBLIC_KEY_BYTES] = key.public[..]
+                        .try_into()
+                        .map_err(|_| Error::InvalidLength)?;
+                    if !check_public_key::<{ kyber_params::K }>(&pk) {
+                        return Err(Error::InvalidPublicKey);
+                    }
+                    Ok(PublicKey::from(pk))
+                }
+            }
+
+            impl SecretSeed {
+                /// A private COSE_Key with both `pub` and `priv`.
+                pub fn to_cose_key(&self) -> CoseKey {
+                    let mut key = self.public_key().to_cose_key();
+                    key.private = Some(Zeroizing::new(self.to_bytes().to_vec()));
+                    key
+                }
+
+                /// Reads a private COSE_Key. Fails with
+                /// [`Error::InconsistentKey`] if `pub` is not the seed's
+                /// encapsulation key.
+                pub fn from_cose_key(key: &CoseKey) -> Result<Self, Error> {
+                    check_key(key)?;
+                    let private = key.private.as_ref().ok_or(Error::InvalidEncoding)?;
+                    let mut seed: [u8; kyber_params::SEED_BYTES] =
+                        private[..].try_into().map_err(|_| Error::InvalidLength)?;
+                    let result = SecretSeed::from(seed);
+                    seed.zeroize();
+                    if result.public_key().as_bytes()[..] != key.public[..] {
+                        return Err(Error::InconsistentKey);
+                    }
+                    Ok(result)
+                }
+            }
+
+            impl SecretKey {
+                /// Expands the seed of a private COSE_Key, as
+                /// [`SecretSeed::from_cose_key`]. An expanded key has no
+                /// COSE_Key form, as `priv` holds the seed only.
+                pub fn from_cose_key(key: &CoseKey) -> Result<Self, Error> {
+                    Ok(SecretSeed::from_cose_key(key)?.expand().1)
+                }
+            }
+
+            /// A tagged COSE_Encrypt0 of `payload` to `pk` with HPKE
+            /// integrated encryption. `kid` goes into the unprotected header.
+            /// Fails if `pk` does not pass the FIPS 203 modulus check.
+            pub fn encrypt0<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                kid: Option<&[u8]>,
+                payload: &[u8],
+                external_aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let protected = protected_header(ALG_HPKE);
+                let aad = enc_structure("Encrypt0", &protected, external_aad);
+                let (enc, ct) = Suite::seal_base(rng, pk, b"", &aad, payload)?;
+                let items = layer(protected, recipient_header(kid, enc.as_bytes()), ct);
+                Ok(to_vec(&Value::Tag(
+                    TAG_ENCRYPT0,
+                    Box::new(Value::Array(items)),
+                )))
+            }
+
+            /// Opens a COSE_Encrypt0, tagged or not. Fails with
+            /// [`Error::UnsupportedAlgorithm`] for another algorithm or level,
+            /// and with [`Error::AuthenticationFailed`] if the message was
+            /// modified or is for another key.
+            pub fn decrypt0(
+                sk: &SecretKey,
+                message: &[u8],
+                external_aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let message = parse_encrypt0(message)?;
+                if message.alg() != Some(ALG_HPKE) {
+                    return Err(Error::UnsupportedAlgorithm);
+                }
+                let enc = ciphertext(message.bytes(HEADER_EK))?;
+                let aad = enc_structure("Encrypt0", &message.protected, external_aad);
+                Suite::open_base(&enc, sk, b"", &aad, &message.ciphertext)
+            }
+
+            /// A tagged COSE_Encrypt of `payload` under `content_alg`
+            /// ([`super::A128GCM`] or [`super::A256GCM`]), with one recipient
+            /// structure per `(public key, kid)`. Fails with
+            /// [`Error::InvalidLength`] for no recipients, or more than one in
+            /// [`RecipientMode::Direct`].
+            pub fn encrypt<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                recipients: &[(&PublicKey, Option<&[u8]>)],
+                mode: RecipientMode,
+                content_alg: i64,
+                payload: &[u8],
+                external_aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let mut cek = Zeroizing::new(vec![0u8; content_key_bytes(content_alg)?]);
+                if recipients.is_empty() || (mode == RecipientMode::Direct && recipients.len() != 1)
+                {
+                    return Err(Error::InvalidLength);
+                }
+                rng.fill_bytes(&mut cek);
+
+                let mut layers = Vec::with_capacity(recipients.len());
+                for (pk, kid) in recipients {
+                    let alg = match mode {
+                        RecipientMode::Hpke => ALG_HPKE_KE,
+                        RecipientMode::Direct => ALG,
+                        RecipientMode::KeyWrap => ALG_KW,
+                    };
+                    let protected = protected_header(alg);
+                    let (ek, encrypted_key) = match mode {
+                        RecipientMode::Hpke => {
+                            let info = recipient_structure(content_alg, &protected);
+                            let (enc, ct) = Suite::seal_base(rng, pk, &info, b"", &cek)?;
+                            (enc.as_bytes().to_vec(), ct)
+                        }
+                        RecipientMode::Direct => {
+                            let (ct, mut ss) = $kem::encaps(rng, pk)?;
+                            derive_key(ss.as_bytes(), content_alg, &protected, &mut cek);
+                            ss.zeroize();
+                            (ct.as_bytes().to_vec(), Vec::new())
+                        }
+                        RecipientMode::KeyWrap => {
+                            let (ct, mut ss) = $kem::encaps(rng, pk)?;
+                            let mut kek = Zeroizing::new([0u8; KEK_BYTES]);
+                            derive_key(ss.as_bytes(), ALG_KW, &protected, &mut kek[..]);
+                            ss.zeroize();
+                            let mut wrapped = [0u8; MAX_WRAPPED_BYTES];
+                            let wrapped = &mut wrapped[..cek.len() + 8];
+                            <$kek>::from(*kek).wrap(&cek, wrapped).unwrap();
+                            (ct.as_bytes().to_vec(), wrapped.to_vec())
+                        }
+                    };
+                    layers.push(Value::Array(layer(
+                        protected,
+                        recipient_header(*kid, &ek),
+                        encrypted_key,
+                    )));
+                }
+
+                let mut iv = [0u8; NONCE_BYTES];
+                rng.fill_bytes(&mut iv);
+                let protected = protected_header(content_alg);
+                let aad = enc_structure("Encrypt", &protected, external_aad);
+                let ct = seal_content(content_alg, &cek, &iv, &aad, payload);
+                let mut items = layer(
+                    protected,
+                    vec![(Value::Int(HEADER_IV), Value::Bytes(iv.to_vec()))],
+                    ct,
+                );
+                items.push(Value::Array(layers));
+                Ok(to_vec(&Value::Tag(
+                    TAG_ENCRYPT,
+                    Box::new(Value::Array(items)),
+                )))
+            }
+
+            // The content key from one recipient structure
+            fn content_key(
+                sk: &SecretKey,
+                recipient: &Layer,
+                content_alg: i64,
+                len: usize,
+            ) -> Result<Zeroizing<Vec<u8>>, Error> {
+                let ct = ciphertext(recipient.bytes(HEADER_EK))?;
+                let alg = recipient.alg();
+                if alg == Some(ALG_HPKE_KE) {
+                    let info = recipient_structure(content_alg, &recipient.protected);
+                    let cek = Zeroizing::new(Suite::open_base(
+                        &ct,
+                        sk,
+                        &info,
+                        b"",
+                        &recipient.ciphertext,
+                    )?);
+                    return match cek.len() == len {
+                        true => Ok(cek),
+                        false => Err(Error::InvalidLength),
+                    };
+                }
+
+                let expected = if alg == Some(ALG_KW) { len + 8 } else { 0 };
+                if recipient.ciphertext.len() != expected {
+                    return Err(Error::InvalidLength);
+                }
+                let mut cek = Zeroizing::new(vec![0u8; len]);
+                let mut ss = $kem::decaps(sk, &ct)?;
+                if alg == Some(ALG_KW) {
+                    let mut kek = Zeroizing::new([0u8; KEK_BYTES]);
+                    derive_key(ss.as_bytes(), ALG_KW, &recipient.protected, &mut kek[..]);
+                    ss.zeroize();
+                    <$kek>::from(*kek)
+                        .unwrap(&recipient.ciphertext, &mut cek)
+                        .map_err(|_| Error::AuthenticationFailed)?;
+                } else {
+                    derive_key(ss.as_bytes(), content_alg, &recipient.protected, &mut cek);
+                    ss.zeroize();
+                }
+                Ok(cek)
+            }
+
+            /// Opens a COSE_Encrypt, tagged or not, trying every recipient
+            /// structure of this level whose `kid`, if any, matches `kid`.
+            /// Fails with [`Error::NotRecipient`] if there is none, and with
+            /// [`Error::AuthenticationFailed`] if none of them opens the
+            /// message.
+            pub fn decrypt(
+                sk: &SecretKey,
+                kid: Option<&[u8]>,
+                message: &[u8],
+                external_aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let (content, recipients) = parse_encrypt(message)?;
+                let content_alg = content.alg().ok_or(Error::InvalidEncoding)?;
+                let len = content_key_bytes(content_alg)?;
+                let iv: &[u8; NONCE_BYTES] = content
+                    .bytes(HEADER_IV)
+                    .ok_or(Error::InvalidEncoding)?
+                    .try_into()
+                    .map_err(|_| Error::InvalidLength)?;
+                let aad = enc_structure("Encrypt", &content.protected, external_aad);
+
+                let mut result = Err(Error::NotRecipient);
+                for recipient in recipients.iter().filter(|recipient| {
+                    matches!(recipient.alg(), Some(ALG | ALG_KW | ALG_HPKE_KE))
+                        && kid_matches(recipient, kid)
+                }) {
+                    result = content_key(sk, recipient, content_alg, len).and_then(|cek| {
+                        open_content(content_alg, &cek, iv, &aad, &content.ciphertext)
+                    });
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+cose_level!(
+    kyber512,
+    MlKem512,
+    "ML-KEM-512",
+    -70010,
+    -70020,
+    -70030,
+    -70040,
+    aes_kw::KekAes128,
+    16,
+    crate::hpke::HkdfSha256,
+    crate::hpke::AesGcm128
+);
+#[cfg(feature = "kyber768")]
+cose_level!(
+    kyber768,
+    MlKem768,
+    "ML-KEM-768",
+    -70011,
+    -70021,
+    -70031,
+    -70041,
+    aes_kw::KekAes192,
+    24,
+    crate::hpke::HkdfSha256,
+    crate::hpke::AesGcm256
+);
+#[cfg(feature = "kyber1024")]
+cose_level!(
+    kyber1024,
+    MlKem1024,
+    "ML-KEM-1024",
+    -70012,
+    -70022,
+    -70032,
+    -70042,
+    aes_kw::KekAes256,
+    32,
+    crate::hpke::HkdfSha384,
+    crate::hpke::AesGcm256
+);
+
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{decrypt, decrypt0, encrypt, encrypt0, ALG, ALG_HPKE, ALG_HPKE_KE, ALG_KW};
+#[cfg(all(
+    feature = "kyber512",
+    not(feature = "kyber768"),
+    not(feature = "kyber1024")
+))]
+pub use kyber512::{decrypt, decrypt0, encrypt, encrypt0, ALG, ALG_HPKE, ALG_HPKE_KE, ALG_KW};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{decrypt, decrypt0, encrypt, encrypt0, ALG, ALG_HPKE, ALG_HPKE_KE, ALG_KW};
@@ -1,28 +1,32 @@
-use crate::params::{K, SECRET_KEY_BYTES, CIPHERTEXT_BYTES, POLY_VEC_BYTES, POLY_BYTES};
+use crate::params::{du, dv, indcpa_secret_key_bytes, ciphertext_bytes, poly_vec_compressed_bytes, POLY_BYTES};
 use crate::poly::Poly;
-use crate::utils::{poly_to_bytes, poly_from_bytes};
+use crate::utils::{poly_from_bytes, poly_decompress, poly_to_msg};
 
-pub fn indcpa_dec(
-    sk: &[u8; SECRET_KEY_BYTES],
-    ct: &[u8; CIPHERTEXT_BYTES],
+pub fn indcpa_dec<const K: usize>(
+    sk: &[u8],
+    ct: &[u8],
     msg: &mut [u8; 32],
 ) {
+    debug_assert_eq!(sk.len(), indcpa_secret_key_bytes(K));
+    debug_assert_eq!(ct.len(), ciphertext_bytes(K));
+    let (du, dv) = (du(K), dv(K));
+
     let mut bp = [Poly::new(); K];
     let mut sk_poly = [Poly::new(); K];
     let mut mp = Poly::new();
     let mut v = Poly::new();
 
     for i in 0..K {
-        poly_from_bytes(&ct[i * POLY_BYTES..], &mut bp[i]);
+        poly_decompress(&ct[i * 32 * du..], du, &mut bp[i]);
         poly_from_bytes(&sk[i * POLY_BYTES..], &mut sk_poly[i]);
     }
-    poly_from_bytes(&ct[POLY_VEC_BYTES..], &mut v);
+    poly_decompress(&ct[poly_vec_compressed_bytes(K)..], dv, &mut v);
 
-    for i in 0..K {
-        bp[i].ntt();
+    for bp_i in bp.iter_mut() {
+        bp_i.ntt();
     }
 
-    // Matrix-vector multiplication
+    // Inner product of the secret vector with u
     for i in 0..K {
         let mut tmp = sk_poly[i];
         tmp.pointwise_mul(&bp[i]);
@@ -30,7 +34,7 @@ pub fn indcpa_dec(
     }
 
     mp.inv_ntt();
-    mp.sub(&v);
+    v.sub(&mp);
 
-    poly_to_bytes(&mp, msg);
+    poly_to_msg(&v, msg);
 }
@@ -0,0 +1,71 @@
+//! Secret tainting for constant-time checks under Valgrind memcheck.
+//!
+//! This is the Rust counterpart of PQClean's `crypto_declassify.h`. With the
+//! `ctgrind` feature, [`poison`] marks memory as undefined and [`declassify`]
+//! marks it defined again, using memcheck client requests. Run a binary under
+//! `valgrind` and every branch or memory index that depends on poisoned bytes
+//! is reported as a use of uninitialised data. Without the feature, or when
+//! not running under Valgrind, both calls do nothing.
+
+// VG_USERREQ_TOOL_BASE('M', 'C') + 1 and + 2 from memcheck.h
+#[cfg(feature = "ctgrind")]
+const MAKE_MEM_UNDEFINED: usize = 0x4d43_0001;
+#[cfg(feature = "ctgrind")]
+const MAKE_MEM_DEFINED: usize = 0x4d43_0002;
+
+/// Marks `buf` as secret: memcheck reports any branch or index that depends on it.
+#[inline(always)]
+pub fn poison(buf: &[u8]) {
+    #[cfg(feature = "ctgrind")]
+    client_request(MAKE_MEM_UNDEFINED, buf);
+    #[cfg(not(feature = "ctgrind"))]
+    let _ = buf;
+}
+
+/// Marks `buf` as public, e.g. a value derived from secrets that is published anyway.
+#[inline(always)]
+pub fn declassify(buf: &[u8]) {
+    #[cfg(feature = "ctgrind")]
+    client_request(MAKE_MEM_DEFINED, buf);
+    #[cfg(not(feature = "ctgrind"))]
+    let _ = buf;
+}
+
+// The magic instruction sequences from valgrind.h; natively they leave every
+// register unchanged, under Valgrind they trap into the tool.
+#[cfg(all(feature = "ctgrind", target_arch = "x86_64"))]
+fn client_request(request: usize, buf: &[u8]) {
+    let args: [usize; 6] = [request, buf.as_ptr() as usize, buf.len(), 0, 0, 0];
+    unsafe {
+        core::arch::asm!(
+            "rol rdi, 3",
+            "rol rdi, 13",
+            "rol rdi, 61",
+            "rol rdi, 51",
+            "xchg rbx, rbx",
+            in("rax") args.as_ptr(),
+            inout("rdx") 0usize => _,
+            options(nostack),
+        );
+    }
+}
+
+#[cfg(all(feature = "ctgrind", target_arch = "aarch64"))]
+fn client_request(request: usize, buf: &[u8]) {
+    let args: [usize; 6] = [request, buf.as_ptr() as usize, buf.len(), 0, 0, 0];
+    unsafe {
+        core::arch::asm!(
+            "ror x12, x12, #3",
+            "ror x12, x12, #13",
+            "ror x12, x12, #51",
+            "ror x12, x12, #61",
+            "orr x10, x10, x10",
+            in("x4") args.as_ptr(),
+            inout("x3") 0usize => _,
+            options(nostack),
+        );
+    }
+}
+
+#[cfg(all(feature = "ctgrind", not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn client_request(_request: usize, _buf: &[u8]) {}
@@ -0,0 +1,435 @@
+//! ASN.1 DER encodings of ML-KEM keys, as in RFC 9935 (formerly
+//! draft-ietf-lamps-kyber-certificates).
+//!
+//! Public keys are `SubjectPublicKeyInfo` with the `id-alg-ml-kem-512`,
+//! `-768` or `-1024` OID, no parameters and the encapsulation key as the bit
+//! string. Private keys are PKCS#8 `OneAsymmetricKey` whose private key is
+//!
+//! ```text
+//! ML-KEM-PrivateKey ::= CHOICE {
+//!     seed        [0] IMPLICIT OCTET STRING (SIZE (64)),
+//!     expandedKey OCTET STRING,
+//!     both        SEQUENCE { seed OCTET STRING (SIZE (64)), expandedKey OCTET STRING } }
+//! ```
+//!
+//! where the seed is `d || z` from FIPS 203 ML-KEM.KeyGen_internal. A
+//! `SecretKey` only holds the expanded key, so its own `to_der` writes
+//! `expandedKey`; a `SecretSeed` can write any of the three forms.
+//! Decoding accepts all three forms. An expanded key must pass the FIPS 203
+//! input checks, and in `both` it must be the expansion of the seed.
+//!
+//! These are the formats OpenSSL 3.5 reads and writes (its
+//! `ml-kem.output_formats` `seed-only`, `priv-only` and `seed-priv`).
+
+use alloc::vec::Vec;
+use crate::error::Error;
+use zeroize::Zeroizing;
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+// [0] IMPLICIT OCTET STRING, for the seed
+const TAG_SEED: u8 = 0x80;
+// [0] attributes and [1] IMPLICIT BIT STRING publicKey of OneAsymmetricKey
+const TAG_ATTRIBUTES: u8 = 0xa0;
+const TAG_PUBLIC_KEY: u8 = 0x81;
+
+/// Which `ML-KEM-PrivateKey` form to write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PrivateKeyForm {
+    Seed,
+    ExpandedKey,
+    Both,
+}
+
+// ML-KEM-PrivateKey, borrowing its parts
+enum PrivateKey<'a> {
+    Seed(&'a [u8]),
+    ExpandedKey(&'a [u8]),
+    Both(&'a [u8], &'a [u8]),
+}
+
+// Reads DER TLVs off the front of its input, accepting only definite,
+// minimally encoded lengths
+pub(crate) struct Reader<'a>(pub(crate) &'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn peek(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    pub(crate) fn read(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        let [t, first, rest @ ..] = self.0 else {
+            return Err(Error::InvalidEncoding);
+        };
+        if *t != tag {
+            return Err(Error::InvalidEncoding);
+        }
+        let (len, rest) = match *first {
+            len @ 0..=0x7f => (len as usize, rest),
+            0x81..=0x84 => {
+                let n = (*first & 0x7f) as usize;
+                if rest.len() < n || rest[0] == 0 {
+                    return Err(Error::InvalidEncoding);
+                }
+                let len = rest[..n].iter().fold(0usize, |len, &b| len << 8 | b as usize);
+                if len < 0x80 {
+                    return Err(Error::InvalidEncoding);
+                }
+                (len, &rest[n..])
+            }
+            _ => return Err(Error::InvalidEncoding),
+        };
+        if rest.len() < len {
+            return Err(Error::InvalidEncoding);
+        }
+        let (content, rest) = rest.split_at(len);
+        self.0 = rest;
+        Ok(content)
+    }
+
+    pub(crate) fn finish(self) -> Result<(), Error> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidEncoding)
+        }
+    }
+}
+
+// The content of a single TLV that makes up all of `der`
+pub(crate) fn parse(der: &[u8], tag: u8) -> Result<&[u8], Error> {
+    let mut reader = Reader(der);
+    let content = reader.read(tag)?;
+    reader.finish()?;
+    Ok(content)
+}
+
+// Tag and length bytes; keys stay below 64 KiB, CMS content may not
+pub(crate) fn header_bytes(len: usize) -> usize {
+    match len {
+        0..=0x7f => 2,
+        0x80..=0xff => 3,
+        0x100..=0xffff => 4,
+        0x1_0000..=0xff_ffff => 5,
+        _ => 6,
+    }
+}
+
+pub(crate) fn write_header(out: &mut Vec<u8>, tag: u8, len: usize) {
+    out.push(tag);
+    match header_bytes(len) {
+        2 => out.push(len as u8),
+        n => {
+            out.push(0x80 | (n - 2) as u8);
+            out.extend_from_slice(&(len as u32).to_be_bytes()[6 - n..]);
+        }
+    }
+}
+
+pub(crate) fn write(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    write_header(out, tag, content.len());
+    out.extend_from_slice(content);
+}
+
+// A TLV around the concatenation of `parts`
+pub(crate) fn tlv(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+    let len = parts.iter().map(|part| part.len()).sum();
+    let mut out = Vec::with_capacity(header_bytes(len) + len);
+    write_header(&mut out, tag, len);
+    for part in parts {
+        out.extend_from_slice(part);
+    }
+    out
+}
+
+pub(crate) fn algorithm(oid: &[u8], params: &[u8]) -> Vec<u8> {
+    tlv(TAG_SEQUENCE, &[&tlv(TAG_OID, &[oid]), params])
+}
+
+#[cfg(any(feature = "password", feature = "cms"))]
+pub(crate) fn integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let bytes = &bytes[(value.leading_zeros() as usize / 8).min(7)..];
+    if bytes[0] & 0x80 != 0 {
+        tlv(TAG_INTEGER, &[&[0], bytes])
+    } else {
+        tlv(TAG_INTEGER, &[bytes])
+    }
+}
+
+// A minimally encoded, non-negative INTEGER
+#[cfg(any(feature = "password", feature = "cms"))]
+pub(crate) fn read_integer(reader: &mut Reader) -> Result<u64, Error> {
+    let content = reader.read(TAG_INTEGER)?;
+    let minimal = match content {
+        [] => false,
+        [0, next, ..] => next & 0x80 != 0,
+        [first, ..] => first & 0x80 == 0,
+    };
+    if !minimal {
+        return Err(Error::InvalidEncoding);
+    }
+    let bytes = content.strip_prefix(&[0]).filter(|rest| !rest.is_empty()).unwrap_or(content);
+    if bytes.len() > 8 {
+        return Err(Error::InvalidParameters);
+    }
+    Ok(bytes.iter().fold(0, |value, &b| value << 8 | b as u64))
+}
+
+// The OID and encoded parameters of an AlgorithmIdentifier
+#[cfg(any(feature = "password", feature = "cms"))]
+pub(crate) fn read_algorithm<'a>(reader: &mut Reader<'a>) -> Result<(&'a [u8], &'a [u8]), Error> {
+    let mut alg = Reader(reader.read(TAG_SEQUENCE)?);
+    let oid = alg.read(TAG_OID)?;
+    Ok((oid, alg.0))
+}
+
+// AlgorithmIdentifier with no parameters, which RFC 9935 requires
+fn check_algorithm(alg: &[u8], oid: &[u8]) -> Result<(), Error> {
+    if parse(alg, TAG_OID)? == oid {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedAlgorithm)
+    }
+}
+
+fn encode_spki(oid: &[u8], key: &[u8]) -> Vec<u8> {
+    let alg = algorithm(oid, &[]);
+    let bits_len = 1 + key.len();
+    let len = alg.len() + header_bytes(bits_len) + bits_len;
+    let mut der = Vec::with_capacity(header_bytes(len) + len);
+    write_header(&mut der, TAG_SEQUENCE, len);
+    der.extend_from_slice(&alg);
+    write_header(&mut der, TAG_BIT_STRING, bits_len);
+    der.push(0);
+    der.extend_from_slice(key);
+    der
+}
+
+// A BIT STRING with no unused bits, as bytes
+fn bit_string_bytes(bits: &[u8]) -> Result<&[u8], Error> {
+    match bits {
+        [0, bytes @ ..] => Ok(bytes),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+// The subjectPublicKey of a SubjectPublicKeyInfo for `oid`
+fn decode_spki<'a>(der: &'a [u8], oid: &[u8]) -> Result<&'a [u8], Error> {
+    let mut spki = Reader(parse(der, TAG_SEQUENCE)?);
+    let alg = spki.read(TAG_SEQUENCE)?;
+    let bits = spki.read(TAG_BIT_STRING)?;
+    spki.finish()?;
+    check_algorithm(alg, oid)?;
+    bit_string_bytes(bits)
+}
+
+fn encode_pkcs8(oid: &[u8], private_key: &PrivateKey) -> Zeroizing<Vec<u8>> {
+    let choice_len = match private_key {
+        PrivateKey::Seed(seed) => header_bytes(seed.len()) + seed.len(),
+        PrivateKey::ExpandedKey(expanded) => header_bytes(expanded.len()) + expanded.len(),
+        PrivateKey::Both(seed, expanded) => {
+            let inner = header_bytes(seed.len()) + seed.len() + header_bytes(expanded.len()) + expanded.len();
+            header_bytes(inner) + inner
+        }
+    };
+    let alg = algorithm(oid, &[]);
+    let len = 3 + alg.len() + header_bytes(choice_len) + choice_len;
+
+    // Sized up front so the secret is never left behind in a reallocation
+    let mut der = Zeroizing::new(Vec::with_capacity(header_bytes(len) + len));
+    write_header(&mut der, TAG_SEQUENCE, len);
+    write(&mut der, TAG_INTEGER, &[0]);
+    der.extend_from_slice(&alg);
+    write_header(&mut der, TAG_OCTET_STRING, choice_len);
+    match private_key {
+        PrivateKey::Seed(seed) => write(&mut der, TAG_SEED, seed),
+        PrivateKey::ExpandedKey(expanded) => write(&mut der, TAG_OCTET_STRING, expanded),
+        PrivateKey::Both(seed, expanded) => {
+            write_header(&mut der, TAG_SEQUENCE, choice_len - header_bytes(choice_len));
+            write(&mut der, TAG_OCTET_STRING, seed);
+            write(&mut der, TAG_OCTET_STRING, expanded);
+        }
+    }
+    der
+}
+
+// The private key of a OneAsymmetricKey for `oid`, and its public key if the
+// encoding carries one (version 2 only). Attributes are skipped.
+fn decode_pkcs8<'a>(der: &'a [u8], oid: &[u8]) -> Result<(PrivateKey<'a>, Option<&'a [u8]>), Error> {
+    let mut key = Reader(parse(der, TAG_SEQUENCE)?);
+    let version = key.read(TAG_INTEGER)?;
+    let alg = key.read(TAG_SEQUENCE)?;
+    let choice = key.read(TAG_OCTET_STRING)?;
+    if key.peek() == Some(TAG_ATTRIBUTES) {
+        key.read(TAG_ATTRIBUTES)?;
+    }
+    let public_key = match (version, key.peek()) {
+        ([0], _) => None,
+        ([1], Some(TAG_PUBLIC_KEY)) => Some(bit_string_bytes(key.read(TAG_PUBLIC_KEY)?)?),
+        ([1], _) => None,
+        _ => return Err(Error::InvalidEncoding),
+    };
+    key.finish()?;
+    check_algorithm(alg, oid)?;
+
+    let private_key = match choice.first() {
+        Some(&TAG_SEED) => PrivateKey::Seed(parse(choice, TAG_SEED)?),
+        Some(&TAG_OCTET_STRING) => PrivateKey::ExpandedKey(parse(choice, TAG_OCTET_STRING)?),
+        Some(&TAG_SEQUENCE) => {
+            let mut both = Reader(parse(choice, TAG_SEQUENCE)?);
+            let seed = both.read(TAG_OCTET_STRING)?;
+            let expanded = both.read(TAG_OCTET_STRING)?;
+            both.finish()?;
+            PrivateKey::Both(seed, expanded)
+        }
+        _ => return Err(Error::InvalidEncoding),
+    };
+    Ok((private_key, public_key))
+}
+
+macro_rules! der_level {
+    ($level:ident, $name:expr, $oid:expr) => {
+        #[doc = concat!("DER encodings of ", $name, " keys.")]
+        pub mod $level {
+            use super::{decode_pkcs8, decode_spki, encode_pkcs8, encode_spki, PrivateKey, PrivateKeyForm};
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::kem::check_public_key;
+            use crate::params::$level as kyber_params;
+            use crate::utils::h;
+            use crate::$level::{PublicKey, SecretKey, SecretSeed};
+            use zeroize::{Zeroize, Zeroizing};
+
+            const K: usize = kyber_params::K;
+            // Where ek and H(ek) sit in the decapsulation key
+            const EK_OFFSET: usize = kyber_params::INDCPA_SECRET_KEY_BYTES;
+            const H_OFFSET: usize = EK_OFFSET + kyber_params::PUBLIC_KEY_BYTES;
+
+            #[doc = concat!("The `id-alg-", $name, "` OID, without tag and length.")]
+            pub const OID: &[u8] = &$oid;
+
+            impl PublicKey {
+                /// The `SubjectPublicKeyInfo` encoding.
+                pub fn to_der(&self) -> Vec<u8> {
+                    encode_spki(OID, self.as_bytes())
+                }
+
+                /// Reads a `SubjectPublicKeyInfo`. Fails with
+                /// [`Error::UnsupportedAlgorithm`] for a key of another
+                /// algorithm or level, and with [`Error::InvalidPublicKey`] if
+                /// the key does not pass the FIPS 203 modulus check.
+                pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+                    let key = decode_spki(der, OID)?;
+                    let key: [u8; kyber_params::PUBLIC_KEY_BYTES] =
+                        key.try_into().map_err(|_| Error::InvalidLength)?;
+                    if !check_public_key::<K>(&key) {
+                        return Err(Error::InvalidPublicKey);
+                    }
+                    Ok(PublicKey::from(key))
+                }
+            }
+
+            impl SecretKey {
+                /// The PKCS#8 encoding in the `expandedKey` form.
+                pub fn to_der(&self) -> Zeroizing<Vec<u8>> {
+                    encode_pkcs8(OID, &PrivateKey::ExpandedKey(self.as_bytes()))
+                }
+
+                /// Reads a PKCS#8 key in any of the three forms, see
+                /// [`private_key_from_der`].
+                pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+                    private_key_from_der(der).map(|(sk, _)| sk)
+                }
+            }
+
+            // The input checks of FIPS 203, section 7.3: the embedded
+            // encapsulation key is reduced and matches the stored hash
+            pub(crate) fn check_expanded(expanded: &[u8]) -> Result<SecretKey, Error> {
+                let sk: [u8; kyber_params::SECRET_KEY_BYTES] =
+                    expanded.try_into().map_err(|_| Error::InvalidLength)?;
+                let ek = &sk[EK_OFFSET..H_OFFSET];
+                if !check_public_key::<K>(ek) {
+                    return Err(Error::InvalidPublicKey);
+                }
+                let mut h_ek = [0u8; 32];
+                h(ek, &mut h_ek);
+                if h_ek[..] != sk[H_OFFSET..H_OFFSET + 32] {
+                    return Err(Error::InconsistentKey);
+                }
+                Ok(SecretKey::from(sk))
+            }
+
+            impl SecretSeed {
+                /// The PKCS#8 encoding of this seed's key pair in the given form.
+                pub fn to_der(&self, form: PrivateKeyForm) -> Zeroizing<Vec<u8>> {
+                    let seed = self.to_bytes();
+                    let (_, mut sk) = self.expand();
+                    let der = match form {
+                        PrivateKeyForm::Seed => encode_pkcs8(OID, &PrivateKey::Seed(&seed[..])),
+                        PrivateKeyForm::ExpandedKey => encode_pkcs8(OID, &PrivateKey::ExpandedKey(sk.as_bytes())),
+                        PrivateKeyForm::Both => encode_pkcs8(OID, &PrivateKey::Both(&seed[..], sk.as_bytes())),
+                    };
+                    sk.zeroize();
+                    der
+                }
+
+                /// Reads a PKCS#8 key in the `seed` or `both` form; an
+                /// `expandedKey` alone fails with [`Error::InvalidEncoding`]
+                /// as the seed cannot be recovered from it.
+                pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+                    let (mut sk, seed) = private_key_from_der(der)?;
+                    sk.zeroize();
+                    seed.ok_or(Error::InvalidEncoding)
+                }
+            }
+
+            /// Reads a PKCS#8 key in any of the three forms, returning the
+            /// seed as well when the encoding has one.
+            ///
+            /// Fails with [`Error::UnsupportedAlgorithm`] for a key of another
+            /// algorithm or level, with [`Error::InvalidPublicKey`] or
+            /// [`Error::InconsistentKey`] if an expanded key fails the FIPS 203
+            /// checks, and with [`Error::InconsistentKey`] if the expanded key
+            /// or public key does not belong to the seed.
+            pub fn private_key_from_der(der: &[u8]) -> Result<(SecretKey, Option<SecretSeed>), Error> {
+                let (private_key, public_key) = decode_pkcs8(der, OID)?;
+                let (sk, seed) = match private_key {
+                    PrivateKey::Seed(seed) => {
+                        let seed = SecretSeed::from(<[u8; kyber_params::SEED_BYTES]>::try_from(seed).map_err(|_| Error::InvalidLength)?);
+                        (seed.expand().1, Some(seed))
+                    }
+                    PrivateKey::ExpandedKey(expanded) => (check_expanded(expanded)?, None),
+                    PrivateKey::Both(seed, expanded) => {
+                        let seed = <[u8; kyber_params::SEED_BYTES]>::try_from(seed).map_err(|_| Error::InvalidLength)?;
+                        let expanded: [u8; kyber_params::SECRET_KEY_BYTES] =
+                            expanded.try_into().map_err(|_| Error::InconsistentKey)?;
+                        let sk = SecretKey::from(expanded);
+                        (sk, Some(SecretSeed::from_expanded_with_check(seed, &sk)?))
+                    }
+                };
+                if public_key.is_some_and(|pk| pk != &sk.as_bytes()[EK_OFFSET..H_OFFSET]) {
+                    return Err(Error::InconsistentKey);
+                }
+                Ok((sk, seed))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+der_level!(kyber512, "ML-KEM-512", [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x04, 0x01]);
+#[cfg(feature = "kyber768")]
+der_level!(kyber768, "ML-KEM-768", [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x04, 0x02]);
+#[cfg(feature = "kyber1024")]
+der_level!(kyber1024, "ML-KEM-1024", [0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x04, 0x03]);
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{private_key_from_der, OID};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{private_key_from_der, OID};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{private_key_from_der, OID};
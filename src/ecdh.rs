@@ -0,0 +1,178 @@
+//! Plain ephemeral-static ECDH as a [`Kem`], the classical side of a combiner.
+//!
+//! The ciphertext is the ephemeral public key and the shared secret is the raw
+//! ECDH output (the x-coordinate for the NIST curves). There is no KDF here;
+//! [`crate::combiner::Combiner`] binds the keys and ciphertexts itself.
+
+use crate::error::Error;
+use crate::traits::Kem;
+#[cfg(feature = "x448")]
+use ed448_goldilocks_plus::{MontgomeryPoint, Scalar};
+use rand_core::{CryptoRng, RngCore};
+use x25519_dalek::{x25519, X25519_BASEPOINT_BYTES};
+use zeroize::Zeroizing;
+
+/// X25519 (RFC 7748). Rejects an all-zero result from a low-order point.
+pub struct X25519;
+
+impl X25519 {
+    fn dh(sk: &[u8; 32], pk: &[u8; 32]) -> Result<Zeroizing<[u8; 32]>, Error> {
+        let ss = Zeroizing::new(x25519(*sk, *pk));
+        let nonzero = ss.iter().fold(0u8, |acc, &b| acc | b);
+        if nonzero == 0 {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(ss)
+    }
+
+    /// The public key for a 32-byte secret scalar.
+    pub fn public_key(sk: &[u8; 32]) -> [u8; 32] {
+        x25519(*sk, X25519_BASEPOINT_BYTES)
+    }
+}
+
+impl Kem for X25519 {
+    const NAME: &'static str = "X25519";
+
+    type PublicKey = [u8; 32];
+    type SecretKey = Zeroizing<[u8; 32]>;
+    type Ciphertext = [u8; 32];
+    type SharedSecret = Zeroizing<[u8; 32]>;
+
+    fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (Self::PublicKey, Self::SecretKey) {
+        let mut sk = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(&mut sk[..]);
+        (Self::public_key(&sk), sk)
+    }
+
+    fn encaps<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk: &Self::PublicKey,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), Error> {
+        let (ct, esk) = Self::keypair(rng);
+        Ok((ct, Self::dh(&esk, pk)?))
+    }
+
+    fn decaps(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, Error> {
+        Self::dh(sk, ct)
+    }
+}
+
+#[cfg(feature = "hybrid-nist")]
+macro_rules! nist_dh_kem {
+    ($name:ident, $curve:ident, $alg:expr, $point_bytes:expr, $secret_bytes:expr) => {
+        #[doc = concat!($alg, " ECDH with uncompressed SEC1 points, validated on receipt.")]
+        pub struct $name;
+
+        impl $name {
+            fn parse_point(bytes: &[u8; $point_bytes]) -> Result<$curve::PublicKey, Error> {
+                if bytes[0] != 0x04 {
+                    return Err(Error::InvalidPublicKey);
+                }
+                $curve::PublicKey::from_sec1_bytes(bytes).map_err(|_| Error::InvalidPublicKey)
+            }
+
+            fn encode_point(pk: &$curve::PublicKey) -> [u8; $point_bytes] {
+                use $curve::elliptic_curve::sec1::ToEncodedPoint;
+                pk.to_encoded_point(false).as_bytes().try_into().unwrap()
+            }
+        }
+
+        impl Kem for $name {
+            const NAME: &'static str = $alg;
+
+            type PublicKey = [u8; $point_bytes];
+            type SecretKey = $curve::SecretKey;
+            type Ciphertext = [u8; $point_bytes];
+            type SharedSecret = Zeroizing<[u8; $secret_bytes]>;
+
+            fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (Self::PublicKey, Self::SecretKey) {
+                let sk = $curve::SecretKey::random(rng);
+                (Self::encode_point(&sk.public_key()), sk)
+            }
+
+            fn encaps<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &Self::PublicKey,
+            ) -> Result<(Self::Ciphertext, Self::SharedSecret), Error> {
+                let peer = Self::parse_point(pk)?;
+                let esk = $curve::ecdh::EphemeralSecret::random(rng);
+                let ss = esk.diffie_hellman(&peer);
+                let ct = Self::encode_point(&esk.public_key());
+                Ok((ct, Zeroizing::new((*ss.raw_secret_bytes()).into())))
+            }
+
+            fn decaps(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, Error> {
+                let peer = Self::parse_point(ct)?;
+                let ss = $curve::ecdh::diffie_hellman(sk.to_nonzero_scalar(), peer.as_affine());
+                Ok(Zeroizing::new((*ss.raw_secret_bytes()).into()))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "hybrid-nist")]
+nist_dh_kem!(P256, p256, "P-256", 65, 32);
+#[cfg(feature = "hybrid-nist")]
+nist_dh_kem!(P384, p384, "P-384", 97, 48);
+
+/// X448 (RFC 7748). Rejects an all-zero result from a low-order point.
+#[cfg(feature = "x448")]
+pub struct X448;
+
+#[cfg(feature = "x448")]
+impl X448 {
+    /// The u-coordinate of the base point.
+    pub const BASEPOINT: [u8; 56] = MontgomeryPoint::GENERATOR.0;
+
+    // The RFC 7748 function: the clamped scalar, unreduced, times the
+    // u-coordinate
+    fn x448(sk: &[u8; 56], u: &[u8; 56]) -> [u8; 56] {
+        let mut k = Zeroizing::new(*sk);
+        k[0] &= 252;
+        k[55] |= 128;
+        (&MontgomeryPoint(*u) * &Scalar::from_bytes(&k)).0
+    }
+
+    fn dh(sk: &[u8; 56], pk: &[u8; 56]) -> Result<Zeroizing<[u8; 56]>, Error> {
+        let ss = Zeroizing::new(Self::x448(sk, pk));
+        let nonzero = ss.iter().fold(0u8, |acc, &b| acc | b);
+        if nonzero == 0 {
+            return Err(Error::InvalidPublicKey);
+        }
+        Ok(ss)
+    }
+
+    /// The public key for a 56-byte secret scalar.
+    pub fn public_key(sk: &[u8; 56]) -> [u8; 56] {
+        Self::x448(sk, &Self::BASEPOINT)
+    }
+}
+
+#[cfg(feature = "x448")]
+impl Kem for X448 {
+    const NAME: &'static str = "X448";
+
+    type PublicKey = [u8; 56];
+    type SecretKey = Zeroizing<[u8; 56]>;
+    type Ciphertext = [u8; 56];
+    type SharedSecret = Zeroizing<[u8; 56]>;
+
+    fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (Self::PublicKey, Self::SecretKey) {
+        let mut sk = Zeroizing::new([0u8; 56]);
+        rng.fill_bytes(&mut sk[..]);
+        (Self::public_key(&sk), sk)
+    }
+
+    fn encaps<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk: &Self::PublicKey,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), Error> {
+        let (ct, esk) = Self::keypair(rng);
+        Ok((ct, Self::dh(&esk, pk)?))
+    }
+
+    fn decaps(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, Error> {
+        Self::dh(sk, ct)
+    }
+}
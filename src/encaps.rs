@@ -1,59 +1,75 @@
-use crate::params::{K, PUBLIC_KEY_BYTES, CIPHERTEXT_BYTES, POLY_VEC_BYTES, POLY_BYTES, POLY_COMPRESSED_BYTES};
+use crate::params::{eta1, eta2, du, dv, public_key_bytes, ciphertext_bytes, poly_vec_bytes, poly_vec_compressed_bytes, MAX_ETA, POLY_BYTES};
 use crate::poly::Poly;
-use crate::utils::{xof, prf, cbd, poly_to_bytes, poly_from_bytes, sample_ntt, poly_compress, poly_decompress};
+use crate::utils::{prf, cbd, poly_from_bytes, poly_from_msg, sample_ntt, poly_compress};
+use crate::declassify::declassify;
 
-pub fn indcpa_enc(
-    pk: &[u8; PUBLIC_KEY_BYTES],
+pub fn indcpa_enc<const K: usize>(
+    pk: &[u8],
     msg: &[u8; 32],
     coins: &[u8; 32],
-    ct: &mut [u8; CIPHERTEXT_BYTES],
+    ct: &mut [u8],
 ) {
-    let mut pk_poly = [Poly::new(); K];
+    debug_assert_eq!(pk.len(), public_key_bytes(K));
+    debug_assert_eq!(ct.len(), ciphertext_bytes(K));
+    let eta2 = eta2(K);
+
     let mut r = [Poly::new(); K];
-    let mut e1 = [Poly::new(); K];
+    let (ct_u, ct_v) = ct.split_at_mut(poly_vec_compressed_bytes(K));
+    indcpa_enc_u::<K>(&pk[poly_vec_bytes(K)..], coins, &mut r, ct_u);
+
     let mut e2 = Poly::new();
-    let mut t = [Poly::new(); K];
+    let mut prf_out = [0u8; 64 * MAX_ETA];
+    prf(coins, 2 * K as u8, &mut prf_out[..64 * eta2]);
+    cbd(&prf_out[..64 * eta2], eta2, &mut e2);
+
+    indcpa_enc_v::<K>(pk, &r, &e2, msg, ct_v);
+}
+
+// The first half of the ciphertext, u = A^T r + e1 compressed, which depends
+// only on the matrix seed and the coins. Leaves r in the NTT domain for
+// indcpa_enc_v. Uses PRF nonces 0..2K.
+pub fn indcpa_enc_u<const K: usize>(
+    rho: &[u8],
+    coins: &[u8; 32],
+    r: &mut [Poly; K],
+    ct_u: &mut [u8],
+) {
+    debug_assert_eq!(rho.len(), 32);
+    debug_assert_eq!(ct_u.len(), poly_vec_compressed_bytes(K));
+    let (eta1, eta2, du) = (eta1(K), eta2(K), du(K));
+
+    let mut e1 = [Poly::new(); K];
     let mut u = [Poly::new(); K];
-    let mut v = Poly::new();
 
-    let rho = &pk[POLY_VEC_BYTES..];
-    for i in 0..K {
-        poly_from_bytes(&pk[i * POLY_BYTES..], &mut pk_poly[i]);
-        t[i] = pk_poly[i];
-        t[i].ntt();
-    }
+    // The matrix seed is public even when pk is read out of a secret key
+    declassify(rho);
 
     let mut nonce = 0;
-    for i in 0..K {
-        let mut prf_out = [0u8; 128];
-        prf(coins, nonce, &mut prf_out);
-        cbd(&prf_out, &mut r[i]);
+    for r_i in r.iter_mut() {
+        let mut prf_out = [0u8; 64 * MAX_ETA];
+        prf(coins, nonce, &mut prf_out[..64 * eta1]);
+        cbd(&prf_out[..64 * eta1], eta1, r_i);
         nonce += 1;
     }
-    for i in 0..K {
-        let mut prf_out = [0u8; 128];
-        prf(coins, nonce, &mut prf_out);
-        cbd(&prf_out, &mut e1[i]);
+    for e1_i in e1.iter_mut() {
+        let mut prf_out = [0u8; 64 * MAX_ETA];
+        prf(coins, nonce, &mut prf_out[..64 * eta2]);
+        cbd(&prf_out[..64 * eta2], eta2, e1_i);
         nonce += 1;
     }
-    let mut prf_out = [0u8; 128];
-    prf(coins, nonce, &mut prf_out);
-    cbd(&prf_out, &mut e2);
 
-    for i in 0..K {
-        r[i].ntt();
+    for r_i in r.iter_mut() {
+        r_i.ntt();
     }
 
     let mut at = [[Poly::new(); K]; K];
-    for i in 0..K {
-        for j in 0..K {
-            let mut xof_out = [0u8; 672];
+    for (i, row) in at.iter_mut().enumerate() {
+        for (j, at_ij) in row.iter_mut().enumerate() {
             let mut seed = [0u8; 34];
             seed[..32].copy_from_slice(rho);
-            seed[32] = j as u8;
-            seed[33] = i as u8;
-            xof(&seed, xof_out.len(), &mut xof_out);
-            sample_ntt(&xof_out, &mut at[i][j]);
+            seed[32] = i as u8;
+            seed[33] = j as u8;
+            sample_ntt(&seed, at_ij);
         }
     }
 
@@ -67,25 +83,40 @@ pub fn indcpa_enc(
         u[i].add(&e1[i]);
     }
 
+    for i in 0..K {
+        poly_compress(&u[i], du, &mut ct_u[i * 32 * du..]);
+    }
+}
+
+// The second half, v = t^T r + e2 + Decompress_1(msg) compressed, for the
+// recipient key pk and the r left by indcpa_enc_u
+pub fn indcpa_enc_v<const K: usize>(
+    pk: &[u8],
+    r: &[Poly; K],
+    e2: &Poly,
+    msg: &[u8; 32],
+    ct_v: &mut [u8],
+) {
+    debug_assert_eq!(pk.len(), public_key_bytes(K));
+    debug_assert_eq!(ct_v.len(), 32 * dv(K));
+
+    let mut t = [Poly::new(); K];
+    let mut v = Poly::new();
+    for (i, t_i) in t.iter_mut().enumerate() {
+        poly_from_bytes(&pk[i * POLY_BYTES..], t_i);
+    }
+
     for i in 0..K {
         let mut tmp = t[i];
         tmp.pointwise_mul(&r[i]);
         v.add(&tmp);
     }
     v.inv_ntt();
-    v.add(&e2);
+    v.add(e2);
 
     let mut msg_poly = Poly::new();
-    poly_from_bytes(msg, &mut msg_poly);
+    poly_from_msg(msg, &mut msg_poly);
     v.add(&msg_poly);
 
-    let mut u_bytes = [0u8; K * POLY_COMPRESSED_BYTES];
-    for i in 0..K {
-        poly_compress(&u[i], &mut u_bytes[i * POLY_COMPRESSED_BYTES..]);
-    }
-    let mut v_bytes = [0u8; POLY_COMPRESSED_BYTES];
-    poly_compress(&v, &mut v_bytes);
-
-    ct[..u_bytes.len()].copy_from_slice(&u_bytes);
-    ct[u_bytes.len()..].copy_from_slice(&v_bytes);
+    poly_compress(&v, dv(K), ct_v);
 }
@@ -0,0 +1,220 @@
+//! Multi-recipient envelopes: one payload encrypted once and readable by any
+//! of a list of ML-KEM public keys.
+//!
+//! `seal` draws a random 32-byte content key and encrypts the payload under
+//! it with ChaCha20-Poly1305. For every recipient it then encapsulates to the
+//! recipient's key, derives a key-encryption key from the shared secret with
+//! HKDF-SHA256 and wraps the content key with AES-256-KW (RFC 3394). The
+//! stanza for each recipient starts with a key ID, `H(ek)` = SHA3-256 of the
+//! encapsulation key, so `open` goes straight to its own stanza instead of
+//! trying every one. The same hash is stored in every FIPS 203 decapsulation
+//! key, so the recipient side does not hash anything.
+//!
+//! ```text
+//! version (1) || KEM ID (2) || count (2, big-endian) || stanza * count
+//!     || payload ciphertext || tag (16)
+//! stanza = key ID (32) || ML-KEM ciphertext || wrapped content key (40)
+//! ```
+//!
+//! All recipients use the same ML-KEM level, named by the KEM ID as in
+//! [`crate::sealed`]. The payload's associated data is everything before the
+//! payload followed by the caller's `aad`, so no stanza can be added, removed
+//! or changed without failing authentication. Recipients share the content key,
+//! so any of them could make a new envelope for the same list. The envelope
+//! does not say who sent it.
+
+use alloc::vec::Vec;
+use crate::error::Error;
+use aes_kw::KekAes256;
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::ChaCha20Poly1305;
+use zeroize::Zeroizing;
+
+pub const VERSION: u8 = 1;
+/// Version, KEM ID and recipient count.
+pub const PREFIX_BYTES: usize = 5;
+pub const KEY_ID_BYTES: usize = 32;
+pub const CONTENT_KEY_BYTES: usize = 32;
+/// AES-KW adds an 8-byte integrity block.
+pub const WRAPPED_KEY_BYTES: usize = CONTENT_KEY_BYTES + 8;
+pub const TAG_BYTES: usize = 16;
+
+const INFO_LABEL: &[u8] = b"rusty-kyber envelope";
+
+/// `H(ek)`, naming the recipient of a stanza.
+pub type KeyId = [u8; KEY_ID_BYTES];
+
+/// Reads the KEM ID from the header of an envelope, to pick the right key.
+pub fn kem_id(envelope: &[u8]) -> Result<u16, Error> {
+    match envelope {
+        [VERSION, hi, lo, ..] => Ok(u16::from_be_bytes([*hi, *lo])),
+        [_, _, _, ..] => Err(Error::InvalidHeader),
+        _ => Err(Error::InvalidLength),
+    }
+}
+
+// Splits an envelope for `kem_id` into its prefix and stanzas, and the payload
+fn split(envelope: &[u8], kem_id: u16, stanza_bytes: usize) -> Result<(&[u8], &[u8]), Error> {
+    if self::kem_id(envelope)? != kem_id {
+        return Err(Error::InvalidHeader);
+    }
+    if envelope.len() < PREFIX_BYTES {
+        return Err(Error::InvalidLength);
+    }
+    let count = u16::from_be_bytes([envelope[3], envelope[4]]) as usize;
+    let header_bytes = PREFIX_BYTES + count * stanza_bytes;
+    if count == 0 || envelope.len() < header_bytes + TAG_BYTES {
+        return Err(Error::InvalidLength);
+    }
+    Ok(envelope.split_at(header_bytes))
+}
+
+fn kek(shared_secret: &[u8], kem_id: u16, key_id: &[u8]) -> KekAes256 {
+    let mut kek = Zeroizing::new([0u8; 32]);
+    hkdf::Hkdf::<sha2::Sha256>::new(None, shared_secret)
+        .expand_multi_info(&[INFO_LABEL, &[VERSION], &kem_id.to_be_bytes(), key_id], &mut kek[..])
+        .unwrap();
+    KekAes256::from(*kek)
+}
+
+fn payload_aad(header: &[u8], aad: &[u8]) -> Vec<u8> {
+    let mut full = Vec::with_capacity(header.len() + aad.len());
+    full.extend_from_slice(header);
+    full.extend_from_slice(aad);
+    full
+}
+
+// The content key is fresh for every envelope, so a fixed nonce is safe
+fn payload_cipher(content_key: &[u8; CONTENT_KEY_BYTES]) -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new_from_slice(content_key).unwrap()
+}
+
+macro_rules! envelope_level {
+    ($level:ident, $kem:ident, $name:expr, $kem_id:expr) => {
+        #[doc = concat!("Envelopes to ", $name, " keys.")]
+        pub mod $level {
+            use super::{
+                kek, payload_aad, payload_cipher, split, KeyId, CONTENT_KEY_BYTES, KEY_ID_BYTES, PREFIX_BYTES,
+                TAG_BYTES, VERSION, WRAPPED_KEY_BYTES,
+            };
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::utils::h;
+            use crate::$level::{Ciphertext, PublicKey, SecretKey, $kem};
+            use chacha20poly1305::aead::{Aead, Payload};
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, Zeroizing};
+
+            pub const KEM_ID: u16 = $kem_id;
+            pub const STANZA_BYTES: usize = KEY_ID_BYTES + kyber_params::CIPHERTEXT_BYTES + WRAPPED_KEY_BYTES;
+
+            // H(ek) sits between the encapsulation key and z in the decapsulation key
+            const SK_KEY_ID_OFFSET: usize = kyber_params::INDCPA_SECRET_KEY_BYTES + kyber_params::PUBLIC_KEY_BYTES;
+
+            pub fn key_id(pk: &PublicKey) -> KeyId {
+                let mut id = [0u8; KEY_ID_BYTES];
+                h(pk.as_bytes(), &mut id);
+                id
+            }
+
+            /// The key ID of the matching public key, read from `sk`.
+            pub fn secret_key_id(sk: &SecretKey) -> KeyId {
+                sk.as_bytes()[SK_KEY_ID_OFFSET..SK_KEY_ID_OFFSET + KEY_ID_BYTES].try_into().unwrap()
+            }
+
+            /// Fails with [`Error::InvalidLength`] for no recipients or more
+            /// than 65535, and with [`Error::InvalidPublicKey`] if any key does
+            /// not pass the FIPS 203 modulus check.
+            pub fn seal<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                recipients: &[PublicKey],
+                plaintext: &[u8],
+                aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let count = u16::try_from(recipients.len()).map_err(|_| Error::InvalidLength)?;
+                if count == 0 {
+                    return Err(Error::InvalidLength);
+                }
+
+                let mut content_key = Zeroizing::new([0u8; CONTENT_KEY_BYTES]);
+                rng.fill_bytes(&mut content_key[..]);
+
+                let header_bytes = PREFIX_BYTES + recipients.len() * STANZA_BYTES;
+                let mut envelope = Vec::with_capacity(header_bytes + plaintext.len() + TAG_BYTES);
+                envelope.push(VERSION);
+                envelope.extend_from_slice(&KEM_ID.to_be_bytes());
+                envelope.extend_from_slice(&count.to_be_bytes());
+
+                for pk in recipients {
+                    let id = key_id(pk);
+                    let (ct, mut ss) = $kem::encaps(rng, pk)?;
+                    let mut wrapped = [0u8; WRAPPED_KEY_BYTES];
+                    kek(ss.as_bytes(), KEM_ID, &id).wrap(&content_key[..], &mut wrapped).unwrap();
+                    ss.zeroize();
+
+                    envelope.extend_from_slice(&id);
+                    envelope.extend_from_slice(ct.as_bytes());
+                    envelope.extend_from_slice(&wrapped);
+                }
+
+                let full_aad = payload_aad(&envelope, aad);
+                let body = payload_cipher(&content_key)
+                    .encrypt(&Default::default(), Payload { msg: plaintext, aad: &full_aad })
+                    .unwrap();
+                envelope.extend(body);
+                Ok(envelope)
+            }
+
+            /// Key IDs of the recipients, in stanza order.
+            pub fn recipients(envelope: &[u8]) -> Result<Vec<KeyId>, Error> {
+                let (header, _) = split(envelope, KEM_ID, STANZA_BYTES)?;
+                Ok(header[PREFIX_BYTES..]
+                    .chunks_exact(STANZA_BYTES)
+                    .map(|stanza| stanza[..KEY_ID_BYTES].try_into().unwrap())
+                    .collect())
+            }
+
+            /// Returns [`Error::NotRecipient`] if no stanza carries the key ID
+            /// of `sk`, and [`Error::AuthenticationFailed`] if the stanza or
+            /// payload was modified.
+            pub fn open(sk: &SecretKey, envelope: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+                let (header, body) = split(envelope, KEM_ID, STANZA_BYTES)?;
+                let id = secret_key_id(sk);
+                let stanza = header[PREFIX_BYTES..]
+                    .chunks_exact(STANZA_BYTES)
+                    .find(|stanza| stanza[..KEY_ID_BYTES] == id)
+                    .ok_or(Error::NotRecipient)?;
+
+                let (ct, wrapped) = stanza[KEY_ID_BYTES..].split_at(kyber_params::CIPHERTEXT_BYTES);
+                let ct = Ciphertext::from(<[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(ct).unwrap());
+                let mut ss = $kem::decaps(sk, &ct)?;
+                let kek = kek(ss.as_bytes(), KEM_ID, &id);
+                ss.zeroize();
+
+                let mut content_key = Zeroizing::new([0u8; CONTENT_KEY_BYTES]);
+                kek.unwrap(wrapped, &mut content_key[..]).map_err(|_| Error::AuthenticationFailed)?;
+
+                let full_aad = payload_aad(header, aad);
+                payload_cipher(&content_key)
+                    .decrypt(&Default::default(), Payload { msg: body, aad: &full_aad })
+                    .map_err(|_| Error::AuthenticationFailed)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+envelope_level!(kyber512, MlKem512, "ML-KEM-512", 0x0040);
+#[cfg(feature = "kyber768")]
+envelope_level!(kyber768, MlKem768, "ML-KEM-768", 0x0041);
+#[cfg(feature = "kyber1024")]
+envelope_level!(kyber1024, MlKem1024, "ML-KEM-1024", 0x0042);
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{key_id, open, recipients, seal, secret_key_id, KEM_ID, STANZA_BYTES};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{key_id, open, recipients, seal, secret_key_id, KEM_ID, STANZA_BYTES};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{key_id, open, recipients, seal, secret_key_id, KEM_ID, STANZA_BYTES};
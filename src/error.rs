@@ -0,0 +1,62 @@
+//! Errors for the fallible APIs (protocol encodings, key validation and
+//! authenticated encryption).
+
+use core::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Error {
+    /// An encoded key share, key or ciphertext had the wrong length.
+    InvalidLength,
+    /// A received public key or key share failed validation.
+    InvalidPublicKey,
+    /// A ciphertext failed AEAD authentication.
+    AuthenticationFailed,
+    /// An encryption context ran out of nonces.
+    MessageLimitReached,
+    /// A pre-shared key was given without its ID or the other way round, or
+    /// for a mode that does not use one.
+    InvalidPsk,
+    /// An encrypted message has an unknown format version or was made for
+    /// another ML-KEM level.
+    InvalidHeader,
+    /// An encrypted stream ended before its last chunk.
+    Truncated,
+    /// A multi-recipient message has nothing for the given key.
+    NotRecipient,
+    /// Multi-recipient encapsulation keys were not generated from the same
+    /// matrix seed.
+    MatrixSeedMismatch,
+    /// A DER or PEM encoding was malformed.
+    InvalidEncoding,
+    /// An encoded key names an algorithm other than the one expected.
+    UnsupportedAlgorithm,
+    /// The parts of an encoded private key do not belong together, e.g. a
+    /// seed and an expanded key from different key pairs.
+    InconsistentKey,
+    /// Password-based key derivation costs are out of range.
+    InvalidParameters,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidLength => f.write_str("invalid length"),
+            Error::InvalidPublicKey => f.write_str("invalid public key"),
+            Error::AuthenticationFailed => f.write_str("authentication failed"),
+            Error::MessageLimitReached => f.write_str("message limit reached"),
+            Error::InvalidPsk => f.write_str("inconsistent pre-shared key inputs"),
+            Error::InvalidHeader => f.write_str("invalid header"),
+            Error::Truncated => f.write_str("truncated stream"),
+            Error::NotRecipient => f.write_str("not a recipient"),
+            Error::MatrixSeedMismatch => f.write_str("public keys use different matrix seeds"),
+            Error::InvalidEncoding => f.write_str("invalid encoding"),
+            Error::UnsupportedAlgorithm => f.write_str("unsupported algorithm"),
+            Error::InconsistentKey => f.write_str("inconsistent private key"),
+            Error::InvalidParameters => f.write_str("invalid key derivation parameters"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
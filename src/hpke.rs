@@ -0,0 +1,448 @@
+//! Hybrid Public Key Encryption (RFC 9180) with the post-quantum KEMs of
+//! draft-ietf-hpke-pq.
+//!
+//! [`Hpke<K, H, A>`] fixes a ciphersuite: a KEM implementing [`HpkeKem`], an
+//! HKDF ([`HkdfSha256`], [`HkdfSha384`], [`HkdfSha512`]) and an AEAD
+//! ([`AesGcm128`], [`AesGcm256`], [`ChaCha20Poly1305`]). It provides the Base
+//! and PSK modes, both as a context set up once for a stream of messages and
+//! as single-shot `seal`/`open`. The Auth modes need an authenticated KEM and
+//! are not available with ML-KEM.
+//!
+//! | KEM                 | KEM ID   | `Npk` | `Nenc` |
+//! |---------------------|----------|-------|--------|
+//! | ML-KEM-512          | `0x0040` | 800   | 768    |
+//! | ML-KEM-768          | `0x0041` | 1184  | 1088   |
+//! | ML-KEM-1024         | `0x0042` | 1568  | 1568   |
+//! | X-Wing (`xwing`)    | `0x647A` | 1216  | 1120   |
+//!
+//! The KEM shared secret goes straight into the key schedule, and the
+//! encapsulated key `enc` is the KEM ciphertext. `DeriveKeyPair` expands the
+//! input keying material with SHAKE256 into the 64-byte ML-KEM seed `d || z`,
+//! or the 32-byte X-Wing seed.
+//!
+//! ```ignore
+//! use rusty_kyber::hpke::{Hpke, HkdfSha256, AesGcm128};
+//! use rusty_kyber::kyber768::MlKem768;
+//!
+//! type Suite = Hpke<MlKem768, HkdfSha256, AesGcm128>;
+//! let (enc, ct) = Suite::seal_base(&mut rng, &pk, b"info", b"aad", b"hello")?;
+//! let pt = Suite::open_base(&enc, &sk, b"info", b"aad", &ct)?;
+//! ```
+
+use alloc::vec::Vec;
+use crate::error::Error;
+use crate::traits::Kem;
+use aes_gcm::aead::{Aead as _, KeyInit, Payload};
+use core::marker::PhantomData;
+use rand_core::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+use zeroize::Zeroizing;
+
+const VERSION_LABEL: &[u8] = b"HPKE-v1";
+
+const MODE_BASE: u8 = 0x00;
+const MODE_PSK: u8 = 0x01;
+
+/// Length of every AEAD nonce in this module.
+pub const NONCE_BYTES: usize = 12;
+/// Length of every AEAD tag in this module.
+pub const TAG_BYTES: usize = 16;
+
+// Largest Nk and Nh of the supported AEADs and KDFs
+const MAX_KEY_BYTES: usize = 32;
+const MAX_HASH_BYTES: usize = 64;
+
+/// A KEM with an HPKE KEM ID and the deterministic `DeriveKeyPair`.
+pub trait HpkeKem: Kem {
+    const KEM_ID: u16;
+    /// Length of an encoded public key.
+    const NPK: usize;
+    /// Length of an encapsulated key.
+    const NENC: usize;
+
+    /// Derives a key pair from input keying material, as in the
+    /// draft-ietf-hpke-pq test vectors.
+    fn derive_keypair(ikm: &[u8]) -> (Self::PublicKey, Self::SecretKey);
+}
+
+/// A key derivation function in the two-stage Extract/Expand form of RFC 9180.
+pub trait Kdf {
+    const KDF_ID: u16;
+    /// Output length of the hash, `Nh`.
+    const NH: usize;
+
+    /// Writes `NH` bytes of PRK, with `ikm` given in parts.
+    fn extract(salt: &[u8], ikm: &[&[u8]], prk: &mut [u8]);
+
+    /// Fails if `okm` is longer than `255 * NH`.
+    fn expand(prk: &[u8], info: &[&[u8]], okm: &mut [u8]) -> Result<(), Error>;
+}
+
+/// An AEAD with a 12-byte nonce and a 16-byte tag.
+pub trait Aead {
+    const AEAD_ID: u16;
+    /// Key length, `Nk`.
+    const NK: usize;
+
+    /// Returns the ciphertext with the tag appended.
+    fn seal(key: &[u8], nonce: &[u8; NONCE_BYTES], aad: &[u8], pt: &[u8]) -> Vec<u8>;
+
+    fn open(key: &[u8], nonce: &[u8; NONCE_BYTES], aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+macro_rules! hkdf_kdf {
+    ($name:ident, $hash:ty, $id:expr, $nh:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name;
+
+        impl Kdf for $name {
+            const KDF_ID: u16 = $id;
+            const NH: usize = $nh;
+
+            fn extract(salt: &[u8], ikm: &[&[u8]], prk: &mut [u8]) {
+                let mut extract = hkdf::HkdfExtract::<$hash>::new(Some(salt));
+                for part in ikm {
+                    extract.input_ikm(part);
+                }
+                prk.copy_from_slice(&extract.finalize().0);
+            }
+
+            fn expand(prk: &[u8], info: &[&[u8]], okm: &mut [u8]) -> Result<(), Error> {
+                let hkdf = hkdf::Hkdf::<$hash>::from_prk(prk).map_err(|_| Error::InvalidLength)?;
+                hkdf.expand_multi_info(info, okm).map_err(|_| Error::InvalidLength)
+            }
+        }
+    };
+}
+
+hkdf_kdf!(HkdfSha256, sha2::Sha256, 0x0001, 32, "HKDF-SHA256 (KDF ID `0x0001`).");
+hkdf_kdf!(HkdfSha384, sha2::Sha384, 0x0002, 48, "HKDF-SHA384 (KDF ID `0x0002`).");
+hkdf_kdf!(HkdfSha512, sha2::Sha512, 0x0003, 64, "HKDF-SHA512 (KDF ID `0x0003`).");
+
+macro_rules! aead {
+    ($name:ident, $cipher:ty, $id:expr, $nk:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name;
+
+        impl Aead for $name {
+            const AEAD_ID: u16 = $id;
+            const NK: usize = $nk;
+
+            fn seal(key: &[u8], nonce: &[u8; NONCE_BYTES], aad: &[u8], pt: &[u8]) -> Vec<u8> {
+                let cipher = <$cipher>::new_from_slice(key).unwrap();
+                cipher.encrypt(nonce.into(), Payload { msg: pt, aad }).unwrap()
+            }
+
+            fn open(key: &[u8], nonce: &[u8; NONCE_BYTES], aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+                let cipher = <$cipher>::new_from_slice(key).unwrap();
+                cipher
+                    .decrypt(nonce.into(), Payload { msg: ct, aad })
+                    .map_err(|_| Error::AuthenticationFailed)
+            }
+        }
+    };
+}
+
+aead!(AesGcm128, aes_gcm::Aes128Gcm, 0x0001, 16, "AES-128-GCM (AEAD ID `0x0001`).");
+aead!(AesGcm256, aes_gcm::Aes256Gcm, 0x0002, 32, "AES-256-GCM (AEAD ID `0x0002`).");
+aead!(
+    ChaCha20Poly1305,
+    chacha20poly1305::ChaCha20Poly1305,
+    0x0003,
+    32,
+    "ChaCha20-Poly1305 (AEAD ID `0x0003`)."
+);
+
+// LabeledDerive with SHAKE256 over the KEM suite ID "KEM" || I2OSP(kem_id, 2)
+fn derive_kem_seed(kem_id: u16, ikm: &[u8], out: &mut [u8]) {
+    let label = b"DeriveKeyPair";
+    let mut shake = Shake256::default();
+    shake.update(ikm);
+    shake.update(VERSION_LABEL);
+    shake.update(b"KEM");
+    shake.update(&kem_id.to_be_bytes());
+    shake.update(&(label.len() as u16).to_be_bytes());
+    shake.update(label);
+    shake.update(&(out.len() as u16).to_be_bytes());
+    shake.finalize_xof().read(out);
+}
+
+macro_rules! mlkem_hpke {
+    ($level:ident, $kem:ident, $id:expr) => {
+        impl HpkeKem for crate::$level::$kem {
+            const KEM_ID: u16 = $id;
+            const NPK: usize = crate::params::$level::PUBLIC_KEY_BYTES;
+            const NENC: usize = crate::params::$level::CIPHERTEXT_BYTES;
+
+            fn derive_keypair(ikm: &[u8]) -> (crate::$level::PublicKey, crate::$level::SecretKey) {
+                use crate::params::$level as kyber_params;
+
+                let mut seed = Zeroizing::new([0u8; 64]);
+                derive_kem_seed(Self::KEM_ID, ikm, &mut seed[..]);
+                let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
+                let mut sk = Zeroizing::new([0u8; kyber_params::SECRET_KEY_BYTES]);
+                crate::kem::keygen_internal::<{ kyber_params::K }>(&seed, &mut pk, &mut sk[..]);
+                (pk.into(), (*sk).into())
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+mlkem_hpke!(kyber512, MlKem512, 0x0040);
+#[cfg(feature = "kyber768")]
+mlkem_hpke!(kyber768, MlKem768, 0x0041);
+#[cfg(feature = "kyber1024")]
+mlkem_hpke!(kyber1024, MlKem1024, 0x0042);
+
+#[cfg(feature = "xwing")]
+impl HpkeKem for crate::xwing::XWing {
+    const KEM_ID: u16 = 0x647a;
+    const NPK: usize = crate::xwing::PUBLIC_KEY_BYTES;
+    const NENC: usize = crate::xwing::CIPHERTEXT_BYTES;
+
+    fn derive_keypair(ikm: &[u8]) -> (crate::xwing::PublicKey, crate::xwing::SecretKey) {
+        let mut seed = [0u8; crate::xwing::SECRET_KEY_BYTES];
+        derive_kem_seed(Self::KEM_ID, ikm, &mut seed);
+        let sk = crate::xwing::SecretKey::from(seed);
+        (sk.public_key(), sk)
+    }
+}
+
+// The encryption and export state shared by both roles
+struct Context<H: Kdf, A: Aead> {
+    suite_id: [u8; 10],
+    key: Zeroizing<[u8; MAX_KEY_BYTES]>,
+    base_nonce: [u8; NONCE_BYTES],
+    seq: u64,
+    exporter_secret: Zeroizing<[u8; MAX_HASH_BYTES]>,
+    _suite: PhantomData<fn() -> (H, A)>,
+}
+
+impl<H: Kdf, A: Aead> Context<H, A> {
+    fn labeled_extract(&self, salt: &[u8], label: &[u8], ikm: &[u8], prk: &mut [u8]) {
+        H::extract(salt, &[VERSION_LABEL, &self.suite_id, label, ikm], prk);
+    }
+
+    fn labeled_expand(&self, prk: &[u8], label: &[u8], info: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        let len = u16::try_from(out.len()).map_err(|_| Error::InvalidLength)?;
+        H::expand(prk, &[&len.to_be_bytes(), VERSION_LABEL, &self.suite_id, label, info], out)
+    }
+
+    // KeySchedule<ROLE>() from RFC 9180, section 5.1
+    fn new(suite_id: [u8; 10], mode: u8, shared_secret: &[u8], info: &[u8], psk: &[u8], psk_id: &[u8]) -> Result<Self, Error> {
+        // VerifyPSKInputs
+        let (got_psk, got_psk_id) = (!psk.is_empty(), !psk_id.is_empty());
+        if got_psk != got_psk_id || got_psk != (mode == MODE_PSK) {
+            return Err(Error::InvalidPsk);
+        }
+
+        let mut ctx = Context {
+            suite_id,
+            key: Zeroizing::new([0u8; MAX_KEY_BYTES]),
+            base_nonce: [0u8; NONCE_BYTES],
+            seq: 0,
+            exporter_secret: Zeroizing::new([0u8; MAX_HASH_BYTES]),
+            _suite: PhantomData,
+        };
+
+        let mut schedule = [0u8; 1 + 2 * MAX_HASH_BYTES];
+        schedule[0] = mode;
+        ctx.labeled_extract(b"", b"psk_id_hash", psk_id, &mut schedule[1..1 + H::NH]);
+        ctx.labeled_extract(b"", b"info_hash", info, &mut schedule[1 + H::NH..1 + 2 * H::NH]);
+        let schedule = &schedule[..1 + 2 * H::NH];
+
+        let mut secret = Zeroizing::new([0u8; MAX_HASH_BYTES]);
+        ctx.labeled_extract(shared_secret, b"secret", psk, &mut secret[..H::NH]);
+        let secret = &secret[..H::NH];
+
+        let mut key = Zeroizing::new([0u8; MAX_KEY_BYTES]);
+        let mut base_nonce = [0u8; NONCE_BYTES];
+        let mut exporter_secret = Zeroizing::new([0u8; MAX_HASH_BYTES]);
+        ctx.labeled_expand(secret, b"key", schedule, &mut key[..A::NK])?;
+        ctx.labeled_expand(secret, b"base_nonce", schedule, &mut base_nonce)?;
+        ctx.labeled_expand(secret, b"exp", schedule, &mut exporter_secret[..H::NH])?;
+        ctx.key = key;
+        ctx.base_nonce = base_nonce;
+        ctx.exporter_secret = exporter_secret;
+        Ok(ctx)
+    }
+
+    fn nonce(&self) -> [u8; NONCE_BYTES] {
+        let mut nonce = self.base_nonce;
+        for (n, s) in nonce[NONCE_BYTES - 8..].iter_mut().zip(self.seq.to_be_bytes()) {
+            *n ^= s;
+        }
+        nonce
+    }
+
+    // The sequence number is only advanced once a message went through, and
+    // never wraps around to reuse a nonce
+    fn next_seq(&self) -> Result<u64, Error> {
+        self.seq.checked_add(1).ok_or(Error::MessageLimitReached)
+    }
+
+    fn seal(&mut self, aad: &[u8], pt: &[u8]) -> Result<Vec<u8>, Error> {
+        let next = self.next_seq()?;
+        let ct = A::seal(&self.key[..A::NK], &self.nonce(), aad, pt);
+        self.seq = next;
+        Ok(ct)
+    }
+
+    fn open(&mut self, aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+        let next = self.next_seq()?;
+        let pt = A::open(&self.key[..A::NK], &self.nonce(), aad, ct)?;
+        self.seq = next;
+        Ok(pt)
+    }
+
+    fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.labeled_expand(&self.exporter_secret[..H::NH], b"sec", exporter_context, out)
+    }
+}
+
+/// The sender's side of an HPKE context.
+pub struct SenderContext<H: Kdf, A: Aead>(Context<H, A>);
+
+impl<H: Kdf, A: Aead> SenderContext<H, A> {
+    /// Encrypts the next message in sequence.
+    pub fn seal(&mut self, aad: &[u8], pt: &[u8]) -> Result<Vec<u8>, Error> {
+        self.0.seal(aad, pt)
+    }
+
+    /// Fills `out` with a secret bound to `exporter_context`. Fails if `out`
+    /// is longer than `255 * Nh`.
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.0.export(exporter_context, out)
+    }
+}
+
+/// The recipient's side of an HPKE context.
+pub struct RecipientContext<H: Kdf, A: Aead>(Context<H, A>);
+
+impl<H: Kdf, A: Aead> RecipientContext<H, A> {
+    /// Decrypts the next message in sequence. A failed message does not
+    /// advance the sequence.
+    pub fn open(&mut self, aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+        self.0.open(aad, ct)
+    }
+
+    /// Fills `out` with a secret bound to `exporter_context`. Fails if `out`
+    /// is longer than `255 * Nh`.
+    pub fn export(&self, exporter_context: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        self.0.export(exporter_context, out)
+    }
+}
+
+/// An HPKE ciphersuite.
+pub struct Hpke<K: HpkeKem, H: Kdf, A: Aead>(PhantomData<(K, H, A)>);
+
+impl<K: HpkeKem, H: Kdf, A: Aead> Hpke<K, H, A> {
+    /// `"HPKE" || I2OSP(kem_id, 2) || I2OSP(kdf_id, 2) || I2OSP(aead_id, 2)`
+    pub const SUITE_ID: [u8; 10] = {
+        let (k, h, a) = (K::KEM_ID.to_be_bytes(), H::KDF_ID.to_be_bytes(), A::AEAD_ID.to_be_bytes());
+        [b'H', b'P', b'K', b'E', k[0], k[1], h[0], h[1], a[0], a[1]]
+    };
+
+    fn setup_s<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk_r: &K::PublicKey,
+        mode: u8,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<(K::Ciphertext, SenderContext<H, A>), Error> {
+        let (enc, ss) = K::encaps(rng, pk_r)?;
+        let ctx = Context::new(Self::SUITE_ID, mode, ss.as_ref(), info, psk, psk_id)?;
+        Ok((enc, SenderContext(ctx)))
+    }
+
+    fn setup_r(
+        enc: &K::Ciphertext,
+        sk_r: &K::SecretKey,
+        mode: u8,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<RecipientContext<H, A>, Error> {
+        let ss = K::decaps(sk_r, enc)?;
+        Ok(RecipientContext(Context::new(Self::SUITE_ID, mode, ss.as_ref(), info, psk, psk_id)?))
+    }
+
+    /// Fails if `pk_r` does not pass the KEM's public key validation.
+    pub fn setup_base_s<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk_r: &K::PublicKey,
+        info: &[u8],
+    ) -> Result<(K::Ciphertext, SenderContext<H, A>), Error> {
+        Self::setup_s(rng, pk_r, MODE_BASE, info, b"", b"")
+    }
+
+    pub fn setup_base_r(enc: &K::Ciphertext, sk_r: &K::SecretKey, info: &[u8]) -> Result<RecipientContext<H, A>, Error> {
+        Self::setup_r(enc, sk_r, MODE_BASE, info, b"", b"")
+    }
+
+    /// Like [`Self::setup_base_s`], also authenticating the sender as a holder
+    /// of the pre-shared key. Both `psk` and `psk_id` must be non-empty.
+    pub fn setup_psk_s<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk_r: &K::PublicKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<(K::Ciphertext, SenderContext<H, A>), Error> {
+        Self::setup_s(rng, pk_r, MODE_PSK, info, psk, psk_id)
+    }
+
+    pub fn setup_psk_r(
+        enc: &K::Ciphertext,
+        sk_r: &K::SecretKey,
+        info: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<RecipientContext<H, A>, Error> {
+        Self::setup_r(enc, sk_r, MODE_PSK, info, psk, psk_id)
+    }
+
+    /// Single-shot encryption of one message.
+    pub fn seal_base<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk_r: &K::PublicKey,
+        info: &[u8],
+        aad: &[u8],
+        pt: &[u8],
+    ) -> Result<(K::Ciphertext, Vec<u8>), Error> {
+        let (enc, mut ctx) = Self::setup_base_s(rng, pk_r, info)?;
+        Ok((enc, ctx.seal(aad, pt)?))
+    }
+
+    pub fn open_base(enc: &K::Ciphertext, sk_r: &K::SecretKey, info: &[u8], aad: &[u8], ct: &[u8]) -> Result<Vec<u8>, Error> {
+        Self::setup_base_r(enc, sk_r, info)?.open(aad, ct)
+    }
+
+    pub fn seal_psk<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk_r: &K::PublicKey,
+        info: &[u8],
+        aad: &[u8],
+        pt: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<(K::Ciphertext, Vec<u8>), Error> {
+        let (enc, mut ctx) = Self::setup_psk_s(rng, pk_r, info, psk, psk_id)?;
+        Ok((enc, ctx.seal(aad, pt)?))
+    }
+
+    pub fn open_psk(
+        enc: &K::Ciphertext,
+        sk_r: &K::SecretKey,
+        info: &[u8],
+        aad: &[u8],
+        ct: &[u8],
+        psk: &[u8],
+        psk_id: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Self::setup_psk_r(enc, sk_r, info, psk, psk_id)?.open(aad, ct)
+    }
+}
@@ -0,0 +1,192 @@
+//! Hybrid ECDHE + ML-KEM key exchange for TLS 1.3 (draft-ietf-tls-ecdhe-mlkem).
+//!
+//! Each named group has its own module with the same shape as the ML-KEM
+//! levels: `keypair` produces the client key share, `encaps` is the server's
+//! response and `decaps` finishes on the client. The X25519MLKEM768 names are
+//! also re-exported here, since it is the group most peers offer.
+//!
+//! With the `hybrid-nist` feature, SecP256r1MLKEM768 and SecP384r1MLKEM1024
+//! combine the ML-KEM levels with NIST P-256 and P-384 ECDH for deployments
+//! that need FIPS-approved classical components. Unlike X25519MLKEM768, these
+//! put the ECDH part first in every value, and points use the uncompressed
+//! SEC1 encoding.
+
+pub mod x25519mlkem768;
+
+pub use x25519mlkem768::{
+    keypair, encaps, decaps, ClientShare, ServerShare, SharedSecret, ClientSecret,
+    CODEPOINT, CLIENT_SHARE_BYTES, SERVER_SHARE_BYTES, SHARED_SECRET_BYTES, X25519_BYTES,
+};
+
+#[cfg(feature = "hybrid-nist")]
+macro_rules! ecdh_hybrid {
+    ($name:ident, $title:expr, $curve:ident, $level:ident, $codepoint:expr, $point_bytes:expr, $ecdh_bytes:expr) => {
+        #[doc = concat!($title, ": ECDH share first, then ML-KEM, in every value.")]
+        pub mod $name {
+            use crate::error::Error;
+            use crate::kem;
+            use crate::$level;
+            use crate::params::$level as kyber_params;
+            use $curve::ecdh::EphemeralSecret;
+            use $curve::elliptic_curve::sec1::ToEncodedPoint;
+            use $curve::PublicKey as EcPublicKey;
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, Zeroizing};
+
+            /// IANA TLS Supported Groups codepoint.
+            pub const CODEPOINT: u16 = $codepoint;
+
+            /// Uncompressed SEC1 point: 0x04 || x || y.
+            pub const POINT_BYTES: usize = $point_bytes;
+            pub const ECDH_SECRET_BYTES: usize = $ecdh_bytes;
+            pub const CLIENT_SHARE_BYTES: usize = POINT_BYTES + kyber_params::PUBLIC_KEY_BYTES;
+            pub const SERVER_SHARE_BYTES: usize = POINT_BYTES + kyber_params::CIPHERTEXT_BYTES;
+            pub const SHARED_SECRET_BYTES: usize = ECDH_SECRET_BYTES + kyber_params::SHARED_SECRET_BYTES;
+
+            /// The `key_exchange` field of the client's KeyShareEntry.
+            #[derive(Clone, Copy)]
+            pub struct ClientShare([u8; CLIENT_SHARE_BYTES]);
+
+            /// The `key_exchange` field of the server's KeyShareEntry.
+            #[derive(Clone, Copy)]
+            pub struct ServerShare([u8; SERVER_SHARE_BYTES]);
+
+            #[derive(Clone, Copy, Zeroize)]
+            pub struct SharedSecret([u8; SHARED_SECRET_BYTES]);
+
+            /// The client's ephemeral secrets, wiped when dropped.
+            pub struct ClientSecret {
+                ecdh: EphemeralSecret,
+                mlkem: Zeroizing<$level::SecretKey>,
+            }
+
+            impl From<[u8; CLIENT_SHARE_BYTES]> for ClientShare {
+                fn from(bytes: [u8; CLIENT_SHARE_BYTES]) -> Self {
+                    ClientShare(bytes)
+                }
+            }
+
+            impl TryFrom<&[u8]> for ClientShare {
+                type Error = Error;
+
+                fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+                    Ok(ClientShare(bytes.try_into().map_err(|_| Error::InvalidLength)?))
+                }
+            }
+
+            impl ClientShare {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            impl From<[u8; SERVER_SHARE_BYTES]> for ServerShare {
+                fn from(bytes: [u8; SERVER_SHARE_BYTES]) -> Self {
+                    ServerShare(bytes)
+                }
+            }
+
+            impl TryFrom<&[u8]> for ServerShare {
+                type Error = Error;
+
+                fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+                    Ok(ServerShare(bytes.try_into().map_err(|_| Error::InvalidLength)?))
+                }
+            }
+
+            impl ServerShare {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            impl From<SharedSecret> for [u8; SHARED_SECRET_BYTES] {
+                fn from(ss: SharedSecret) -> Self {
+                    ss.0
+                }
+            }
+
+            impl SharedSecret {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            // Only the uncompressed form is allowed on the wire; from_sec1_bytes
+            // rejects points that are off the curve or the identity
+            fn parse_point(bytes: &[u8]) -> Result<EcPublicKey, Error> {
+                if bytes.len() != POINT_BYTES || bytes[0] != 0x04 {
+                    return Err(Error::InvalidPublicKey);
+                }
+                EcPublicKey::from_sec1_bytes(bytes).map_err(|_| Error::InvalidPublicKey)
+            }
+
+            fn encode_point(ecdh: &EphemeralSecret) -> [u8; POINT_BYTES] {
+                let point = ecdh.public_key().to_encoded_point(false);
+                point.as_bytes().try_into().unwrap()
+            }
+
+            /// Generates the client key share: an ECDH key, then an ML-KEM key pair.
+            pub fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (ClientShare, ClientSecret) {
+                let ecdh = EphemeralSecret::random(&mut *rng);
+                let (ek, dk) = $level::keypair(rng);
+
+                let mut share = [0u8; CLIENT_SHARE_BYTES];
+                share[..POINT_BYTES].copy_from_slice(&encode_point(&ecdh));
+                share[POINT_BYTES..].copy_from_slice(ek.as_bytes());
+
+                (ClientShare(share), ClientSecret { ecdh, mlkem: Zeroizing::new(dk) })
+            }
+
+            /// Server side: validates the client's point and encapsulation key,
+            /// then returns the server key share with the shared secret.
+            pub fn encaps<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                client: &ClientShare,
+            ) -> Result<(ServerShare, SharedSecret), Error> {
+                let (point, ek) = client.0.split_at(POINT_BYTES);
+                let peer = parse_point(point)?;
+                if !kem::check_public_key::<{ kyber_params::K }>(ek) {
+                    return Err(Error::InvalidPublicKey);
+                }
+                let ek = $level::PublicKey::from(<[u8; kyber_params::PUBLIC_KEY_BYTES]>::try_from(ek).unwrap());
+
+                let ecdh = EphemeralSecret::random(&mut *rng);
+                let ecdh_ss = ecdh.diffie_hellman(&peer);
+                let (ct, mlkem_ss) = $level::encaps(rng, &ek);
+
+                let mut share = [0u8; SERVER_SHARE_BYTES];
+                share[..POINT_BYTES].copy_from_slice(&encode_point(&ecdh));
+                share[POINT_BYTES..].copy_from_slice(ct.as_bytes());
+
+                Ok((ServerShare(share), combine(ecdh_ss.raw_secret_bytes(), mlkem_ss)))
+            }
+
+            /// Client side: validates the server's point and recovers the
+            /// shared secret.
+            pub fn decaps(sk: ClientSecret, server: &ServerShare) -> Result<SharedSecret, Error> {
+                let (point, ct) = server.0.split_at(POINT_BYTES);
+                let peer = parse_point(point)?;
+                let ct = $level::Ciphertext::from(<[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(ct).unwrap());
+
+                let ecdh_ss = sk.ecdh.diffie_hellman(&peer);
+                let mlkem_ss = $level::decaps(&sk.mlkem, &ct);
+
+                Ok(combine(ecdh_ss.raw_secret_bytes(), mlkem_ss))
+            }
+
+            fn combine(ecdh_ss: &[u8], mut mlkem_ss: $level::SharedSecret) -> SharedSecret {
+                let mut ss = [0u8; SHARED_SECRET_BYTES];
+                ss[..ECDH_SECRET_BYTES].copy_from_slice(ecdh_ss);
+                ss[ECDH_SECRET_BYTES..].copy_from_slice(mlkem_ss.as_bytes());
+                mlkem_ss.zeroize();
+                SharedSecret(ss)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "hybrid-nist")]
+ecdh_hybrid!(secp256r1mlkem768, "SecP256r1MLKEM768 (codepoint 0x11EB)", p256, kyber768, 0x11EB, 65, 32);
+#[cfg(feature = "hybrid-nist")]
+ecdh_hybrid!(secp384r1mlkem1024, "SecP384r1MLKEM1024 (codepoint 0x11ED)", p384, kyber1024, 0x11ED, 97, 48);
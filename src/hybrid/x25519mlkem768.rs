@@ -0,0 +1,167 @@
+//! X25519MLKEM768 (codepoint 0x11EC).
+//!
+//! The named group combines ML-KEM-768 with X25519. Every value puts the
+//! ML-KEM part first:
+//!
+//! - client key share: `ek || x25519_pk` (1216 bytes)
+//! - server key share: `ct || x25519_pk` (1120 bytes)
+//! - shared secret: `mlkem_ss || x25519_ss` (64 bytes), fed to the TLS key
+//!   schedule as the (EC)DHE input
+//!
+//! The server rejects an encapsulation key that fails the FIPS 203 modulus
+//! check, and both sides reject an all-zero X25519 result (RFC 8446, 7.4.2).
+
+use crate::error::Error;
+use crate::kem;
+use crate::kyber768;
+use crate::params::kyber768 as kyber_params;
+use rand_core::{CryptoRng, RngCore};
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey};
+use zeroize::{Zeroize, Zeroizing};
+
+/// IANA TLS Supported Groups codepoint for X25519MLKEM768.
+pub const CODEPOINT: u16 = 0x11EC;
+
+pub const X25519_BYTES: usize = 32;
+pub const CLIENT_SHARE_BYTES: usize = kyber_params::PUBLIC_KEY_BYTES + X25519_BYTES;
+pub const SERVER_SHARE_BYTES: usize = kyber_params::CIPHERTEXT_BYTES + X25519_BYTES;
+pub const SHARED_SECRET_BYTES: usize = kyber_params::SHARED_SECRET_BYTES + X25519_BYTES;
+
+/// The `key_exchange` field of the client's KeyShareEntry.
+#[derive(Clone, Copy)]
+pub struct ClientShare([u8; CLIENT_SHARE_BYTES]);
+
+/// The `key_exchange` field of the server's KeyShareEntry.
+#[derive(Clone, Copy)]
+pub struct ServerShare([u8; SERVER_SHARE_BYTES]);
+
+/// The 64-byte hybrid shared secret.
+#[derive(Clone, Copy, Zeroize)]
+pub struct SharedSecret([u8; SHARED_SECRET_BYTES]);
+
+/// The client's ephemeral secrets, kept until the ServerHello arrives.
+/// Both halves are wiped when it is dropped.
+pub struct ClientSecret {
+    mlkem: Zeroizing<kyber768::SecretKey>,
+    x25519: EphemeralSecret,
+}
+
+impl From<[u8; CLIENT_SHARE_BYTES]> for ClientShare {
+    fn from(bytes: [u8; CLIENT_SHARE_BYTES]) -> Self {
+        ClientShare(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ClientShare {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(ClientShare(bytes.try_into().map_err(|_| Error::InvalidLength)?))
+    }
+}
+
+impl ClientShare {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; SERVER_SHARE_BYTES]> for ServerShare {
+    fn from(bytes: [u8; SERVER_SHARE_BYTES]) -> Self {
+        ServerShare(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for ServerShare {
+    type Error = Error;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(ServerShare(bytes.try_into().map_err(|_| Error::InvalidLength)?))
+    }
+}
+
+impl ServerShare {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<SharedSecret> for [u8; SHARED_SECRET_BYTES] {
+    fn from(ss: SharedSecret) -> Self {
+        ss.0
+    }
+}
+
+impl SharedSecret {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Generates the client key share: an ML-KEM-768 key pair, then an X25519 key.
+pub fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (ClientShare, ClientSecret) {
+    let (ek, dk) = kyber768::keypair(rng);
+    let x25519 = EphemeralSecret::random_from_rng(&mut *rng);
+    let x25519_pk = X25519PublicKey::from(&x25519);
+
+    let mut share = [0u8; CLIENT_SHARE_BYTES];
+    share[..kyber_params::PUBLIC_KEY_BYTES].copy_from_slice(ek.as_bytes());
+    share[kyber_params::PUBLIC_KEY_BYTES..].copy_from_slice(x25519_pk.as_bytes());
+
+    (ClientShare(share), ClientSecret { mlkem: Zeroizing::new(dk), x25519 })
+}
+
+/// Server side: encapsulates to the client's share and returns the server key
+/// share with the shared secret.
+pub fn encaps<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    client: &ClientShare,
+) -> Result<(ServerShare, SharedSecret), Error> {
+    let (ek, peer) = client.0.split_at(kyber_params::PUBLIC_KEY_BYTES);
+    if !kem::check_public_key::<{ kyber_params::K }>(ek) {
+        return Err(Error::InvalidPublicKey);
+    }
+    let ek = kyber768::PublicKey::from(<[u8; kyber_params::PUBLIC_KEY_BYTES]>::try_from(ek).unwrap());
+    let peer = X25519PublicKey::from(<[u8; X25519_BYTES]>::try_from(peer).unwrap());
+
+    let (ct, mut mlkem_ss) = kyber768::encaps(rng, &ek);
+    let x25519 = EphemeralSecret::random_from_rng(&mut *rng);
+    let x25519_pk = X25519PublicKey::from(&x25519);
+    let x25519_ss = x25519.diffie_hellman(&peer);
+    if !x25519_ss.was_contributory() {
+        mlkem_ss.zeroize();
+        return Err(Error::InvalidPublicKey);
+    }
+
+    let mut share = [0u8; SERVER_SHARE_BYTES];
+    share[..kyber_params::CIPHERTEXT_BYTES].copy_from_slice(ct.as_bytes());
+    share[kyber_params::CIPHERTEXT_BYTES..].copy_from_slice(x25519_pk.as_bytes());
+
+    Ok((ServerShare(share), combine(mlkem_ss, x25519_ss.as_bytes())))
+}
+
+/// Client side: consumes the ephemeral secrets and recovers the shared secret
+/// from the server key share.
+pub fn decaps(sk: ClientSecret, server: &ServerShare) -> Result<SharedSecret, Error> {
+    let (ct, peer) = server.0.split_at(kyber_params::CIPHERTEXT_BYTES);
+    let ct = kyber768::Ciphertext::from(<[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(ct).unwrap());
+    let peer = X25519PublicKey::from(<[u8; X25519_BYTES]>::try_from(peer).unwrap());
+
+    let mut mlkem_ss = kyber768::decaps(&sk.mlkem, &ct);
+    let ClientSecret { x25519, .. } = sk;
+    let x25519_ss = x25519.diffie_hellman(&peer);
+    if !x25519_ss.was_contributory() {
+        mlkem_ss.zeroize();
+        return Err(Error::InvalidPublicKey);
+    }
+
+    Ok(combine(mlkem_ss, x25519_ss.as_bytes()))
+}
+
+fn combine(mut mlkem_ss: kyber768::SharedSecret, x25519_ss: &[u8; X25519_BYTES]) -> SharedSecret {
+    let mut ss = [0u8; SHARED_SECRET_BYTES];
+    ss[..kyber_params::SHARED_SECRET_BYTES].copy_from_slice(mlkem_ss.as_bytes());
+    ss[kyber_params::SHARED_SECRET_BYTES..].copy_from_slice(x25519_ss);
+    mlkem_ss.zeroize();
+    SharedSecret(ss)
+}
@@ -0,0 +1,342 @@
+//! JOSE for ML-KEM: keys as JSON Web Keys and JWE key management, following
+//! draft-ietf-jose-pqc-kem.
+//!
+//! Keys use the `AKP` (algorithm key pair) key type with `alg` set to
+//! `ML-KEM-512`, `ML-KEM-768` or `ML-KEM-1024`. `pub` is the base64url
+//! encapsulation key and `priv` the base64url 64-byte seed `d || z`, so a
+//! private JWK reads into a level's `SecretSeed`.
+//!
+//! Each level has two JWE key management algorithms. Direct key agreement,
+//! named like the level, derives the content encryption key (CEK) from the
+//! ML-KEM shared secret. Key wrapping (`ML-KEM-512+A128KW`,
+//! `ML-KEM-768+A192KW`, `ML-KEM-1024+A256KW`) derives a key encryption key
+//! instead and wraps a random CEK with AES Key Wrap. Either way the ML-KEM
+//! ciphertext travels in the `ek` header parameter.
+//!
+//! The derivation is the Concat KDF of RFC 7518 section 4.6.2 with SHA-256,
+//! fed as for ECDH-ES: the `enc` value (direct) or `alg` value (key wrap) as
+//! AlgorithmID, then `apu`, `apv` and the key length in bits. The draft is
+//! not final, and this module will track it if that changes.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::error::Error;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+pub const KEY_TYPE: &str = "AKP";
+
+/// A JSON Web Key of key type `AKP`. Unknown members are ignored on input.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub alg: String,
+    #[serde(rename = "pub")]
+    pub public: String,
+    #[serde(rename = "priv", default, skip_serializing_if = "Option::is_none")]
+    pub private: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+}
+
+impl Drop for Jwk {
+    fn drop(&mut self) {
+        self.private.zeroize();
+    }
+}
+
+impl Jwk {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(|_| Error::InvalidEncoding)
+    }
+
+    /// The JSON text, zeroized on drop as a private JWK holds the seed.
+    pub fn to_json(&self) -> Zeroizing<String> {
+        Zeroizing::new(serde_json::to_string(self).expect("a JWK serializes"))
+    }
+}
+
+/// What JWE key management adds to a message: the ML-KEM ciphertext for the
+/// `ek` header parameter, and the JWE Encrypted Key, which is empty for
+/// direct key agreement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEncapsulation {
+    pub ek: Vec<u8>,
+    pub encrypted_key: Vec<u8>,
+}
+
+impl KeyEncapsulation {
+    /// Reads the base64url `ek` header parameter and JWE Encrypted Key.
+    pub fn from_base64url(ek: &str, encrypted_key: &str) -> Result<Self, Error> {
+        Ok(KeyEncapsulation {
+            ek: Base64UrlUnpadded::decode_vec(ek).map_err(|_| Error::InvalidEncoding)?,
+            encrypted_key: Base64UrlUnpadded::decode_vec(encrypted_key)
+                .map_err(|_| Error::InvalidEncoding)?,
+        })
+    }
+
+    /// The base64url `ek` header parameter and JWE Encrypted Key.
+    pub fn to_base64url(&self) -> (String, String) {
+        (
+            Base64UrlUnpadded::encode_string(&self.ek),
+            Base64UrlUnpadded::encode_string(&self.encrypted_key),
+        )
+    }
+}
+
+/// The Concat KDF of RFC 7518 section 4.6.2 (NIST SP 800-56A) with SHA-256
+/// and no SuppPrivInfo; `out` sets the key length.
+pub fn concat_kdf(z: &[u8], algorithm_id: &[u8], apu: &[u8], apv: &[u8], out: &mut [u8]) {
+    let bits = (out.len() as u32 * 8).to_be_bytes();
+    for (i, chunk) in out.chunks_mut(32).enumerate() {
+        let mut hash = Sha256::new();
+        hash.update((i as u32 + 1).to_be_bytes());
+        hash.update(z);
+        for part in [algorithm_id, apu, apv] {
+            hash.update((part.len() as u32).to_be_bytes());
+            hash.update(part);
+        }
+        hash.update(bits);
+        let digest = Zeroizing::new(<[u8; 32]>::from(hash.finalize()));
+        chunk.copy_from_slice(&digest[..chunk.len()]);
+    }
+}
+
+// CEK length for a JWE `enc` value
+fn cek_bytes(enc: &str) -> Result<usize, Error> {
+    match enc {
+        "A128GCM" => Ok(16),
+        "A192GCM" => Ok(24),
+        "A256GCM" | "A128CBC-HS256" => Ok(32),
+        "A192CBC-HS384" => Ok(48),
+        "A256CBC-HS512" => Ok(64),
+        _ => Err(Error::UnsupportedAlgorithm),
+    }
+}
+
+// A base64url JWK member of exactly N bytes
+fn decode_member<const N: usize>(value: &str) -> Result<Zeroizing<[u8; N]>, Error> {
+    let mut out = Zeroizing::new([0u8; N]);
+    let len = match Base64UrlUnpadded::decode(value, &mut out[..]) {
+        Ok(decoded) => decoded.len(),
+        Err(base64ct::Error::InvalidLength) => return Err(Error::InvalidLength),
+        Err(base64ct::Error::InvalidEncoding) => return Err(Error::InvalidEncoding),
+    };
+    if len != N {
+        return Err(Error::InvalidLength);
+    }
+    Ok(out)
+}
+
+// The largest CEK, 64 bytes, plus the AES-KW integrity block
+const MAX_WRAPPED_BYTES: usize = 64 + 8;
+
+macro_rules! jose_level {
+    ($level:ident, $kem:ident, $name:expr, $kw:literal, $kek:ty, $kek_bytes:expr) => {
+        #[doc = concat!("JWKs and JWE key management for ", $name, ".")]
+        pub mod $level {
+            use super::{
+                cek_bytes, concat_kdf, decode_member, Jwk, KeyEncapsulation, KEY_TYPE,
+                MAX_WRAPPED_BYTES,
+            };
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::kem::check_public_key;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{$kem, Ciphertext, PublicKey, SecretKey, SecretSeed};
+            use base64ct::{Base64UrlUnpadded, Encoding};
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, Zeroizing};
+
+            /// The JWK `alg` and the JWE direct key agreement algorithm.
+            pub const ALG: &str = $name;
+            /// The JWE key wrapping algorithm.
+            pub const ALG_KW: &str = concat!($name, "+", $kw);
+
+            const KEK_BYTES: usize = $kek_bytes;
+
+            fn check_jwk(jwk: &Jwk) -> Result<(), Error> {
+                if jwk.kty != KEY_TYPE || (jwk.alg != ALG && jwk.alg != ALG_KW) {
+                    return Err(Error::UnsupportedAlgorithm);
+                }
+                Ok(())
+            }
+
+            impl PublicKey {
+                pub fn to_jwk(&self) -> Jwk {
+                    Jwk {
+                        kty: KEY_TYPE.into(),
+                        alg: ALG.into(),
+                        public: Base64UrlUnpadded::encode_string(self.as_bytes()),
+                        private: None,
+                        kid: None,
+                    }
+                }
+
+                /// Reads the `pub` member of a public or private JWK. Fails
+                /// with [`Error::UnsupportedAlgorithm`] for a JWK of another
+                /// key type or level, and with [`Error::InvalidPublicKey`] if
+                /// the key does not pass the FIPS 203 modulus check.
+                pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+                    check_jwk(jwk)?;
+                    let key = decode_member::<{ kyber_params::PUBLIC_KEY_BYTES }>(&jwk.public)?;
+                    if !check_public_key::<{ kyber_params::K }>(&key[..]) {
+                        return Err(Error::InvalidPublicKey);
+                    }
+                    Ok(PublicKey::from(*key))
+                }
+            }
+
+            impl SecretSeed {
+                /// A private JWK with both `pub` and `priv`.
+                pub fn to_jwk(&self) -> Jwk {
+                    let mut jwk = self.public_key().to_jwk();
+                    jwk.private = Some(Base64UrlUnpadded::encode_string(&self.to_bytes()[..]));
+                    jwk
+                }
+
+                /// Reads a private JWK. Fails with
+                /// [`Error::InconsistentKey`] if `pub` is not the seed's
+                /// encapsulation key.
+                pub fn from_jwk(jwk: &Jwk) -> Result<Self, Error> {
+                    check_jwk(jwk)?;
+                    let private = jwk.private.as_deref().ok_or(Error::InvalidEncoding)?;
+                    let seed =
+                        SecretSeed::from(*decode_member::<{ kyber_params::SEED_BYTES }>(private)?);
+                    let public = decode_member::<{ kyber_params::PUBLIC_KEY_BYTES }>(&jwk.public)?;
+                    if seed.public_key().as_bytes() != &public[..] {
+                        return Err(Error::InconsistentKey);
+                    }
+                    Ok(seed)
+                }
+            }
+
+            /// JWE key management on the sender's side: returns the CEK for
+            /// `enc` and what goes into the message. `alg` is [`ALG`] or
+            /// [`ALG_KW`]; `apu` and `apv` are the decoded header parameters,
+            /// empty if absent. Fails if `pk` does not pass the FIPS 203
+            /// modulus check.
+            pub fn encapsulate<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                alg: &str,
+                enc: &str,
+                apu: &[u8],
+                apv: &[u8],
+            ) -> Result<(Zeroizing<Vec<u8>>, KeyEncapsulation), Error> {
+                let wrap = match alg {
+                    ALG => false,
+                    ALG_KW => true,
+                    _ => return Err(Error::UnsupportedAlgorithm),
+                };
+                let mut cek = Zeroizing::new(vec![0u8; cek_bytes(enc)?]);
+                let (ct, mut ss) = $kem::encaps(rng, pk)?;
+
+                let encrypted_key = if wrap {
+                    rng.fill_bytes(&mut cek);
+                    let mut kek = Zeroizing::new([0u8; KEK_BYTES]);
+                    concat_kdf(ss.as_bytes(), alg.as_bytes(), apu, apv, &mut kek[..]);
+                    let mut wrapped = [0u8; MAX_WRAPPED_BYTES];
+                    let wrapped = &mut wrapped[..cek.len() + 8];
+                    <$kek>::from(*kek).wrap(&cek, wrapped).unwrap();
+                    wrapped.to_vec()
+                } else {
+                    concat_kdf(ss.as_bytes(), enc.as_bytes(), apu, apv, &mut cek);
+                    Vec::new()
+                };
+                ss.zeroize();
+                Ok((
+                    cek,
+                    KeyEncapsulation {
+                        ek: ct.as_bytes().to_vec(),
+                        encrypted_key,
+                    },
+                ))
+            }
+
+            /// JWE key management on the recipient's side: recovers the CEK
+            /// for `enc`. Fails with [`Error::AuthenticationFailed`] if a
+            /// wrapped CEK does not unwrap; in direct mode a wrong key or
+            /// ciphertext instead gives a CEK that fails content decryption.
+            pub fn decapsulate(
+                sk: &SecretKey,
+                alg: &str,
+                enc: &str,
+                encapsulation: &KeyEncapsulation,
+                apu: &[u8],
+                apv: &[u8],
+            ) -> Result<Zeroizing<Vec<u8>>, Error> {
+                let wrap = match alg {
+                    ALG => false,
+                    ALG_KW => true,
+                    _ => return Err(Error::UnsupportedAlgorithm),
+                };
+                let mut cek = Zeroizing::new(vec![0u8; cek_bytes(enc)?]);
+                let ct: [u8; kyber_params::CIPHERTEXT_BYTES] = encapsulation.ek[..]
+                    .try_into()
+                    .map_err(|_| Error::InvalidLength)?;
+                let expected_encrypted_key = if wrap { cek.len() + 8 } else { 0 };
+                if encapsulation.encrypted_key.len() != expected_encrypted_key {
+                    return Err(Error::InvalidLength);
+                }
+                let mut ss = $kem::decaps(sk, &Ciphertext::from(ct))?;
+
+                if wrap {
+                    let mut kek = Zeroizing::new([0u8; KEK_BYTES]);
+                    concat_kdf(ss.as_bytes(), alg.as_bytes(), apu, apv, &mut kek[..]);
+                    ss.zeroize();
+                    <$kek>::from(*kek)
+                        .unwrap(&encapsulation.encrypted_key, &mut cek)
+                        .map_err(|_| Error::AuthenticationFailed)?;
+                } else {
+                    concat_kdf(ss.as_bytes(), enc.as_bytes(), apu, apv, &mut cek);
+                    ss.zeroize();
+                }
+                Ok(cek)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+jose_level!(
+    kyber512,
+    MlKem512,
+    "ML-KEM-512",
+    "A128KW",
+    aes_kw::KekAes128,
+    16
+);
+#[cfg(feature = "kyber768")]
+jose_level!(
+    kyber768,
+    MlKem768,
+    "ML-KEM-768",
+    "A192KW",
+    aes_kw::KekAes192,
+    24
+);
+#[cfg(feature = "kyber1024")]
+jose_level!(
+    kyber1024,
+    MlKem1024,
+    "ML-KEM-1024",
+    "A256KW",
+    aes_kw::KekAes256,
+    32
+);
+
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{decapsulate, encapsulate, ALG, ALG_KW};
+#[cfg(all(
+    feature = "kyber512",
+    not(feature = "kyber768"),
+    not(feature = "kyber1024")
+))]
+pub use kyber512::{decapsulate, encapsulate, ALG, ALG_KW};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{decapsulate, encapsulate, ALG, ALG_KW};
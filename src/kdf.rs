@@ -0,0 +1,169 @@
+//! Key derivation from KEM shared secrets.
+//!
+//! A shared secret should not be used as a key directly: applications need
+//! several keys of their own lengths, separated by purpose and bound to the
+//! session. [`KeyDerivation`] covers that with one of four standard KDFs:
+//!
+//! - [`Algorithm::HkdfSha256`] and [`Algorithm::HkdfSha3_256`]: HKDF (RFC
+//!   5869) with an all-zero salt, i.e. the two-step KDF of NIST SP 800-56C.
+//! - [`Algorithm::Kmac128`] and [`Algorithm::Kmac256`]: the one-step KDF of
+//!   SP 800-56C with `H(x) = KMAC#(salt, x, L, "KDF")`, the default all-zero
+//!   salt and `x = counter || Z || FixedInfo`. With the output length set to
+//!   `L` this is a single KMAC call, which is also the KMAC-based KDF of
+//!   SP 800-108.
+//!
+//! Every key is derived from the same fixed info, which is HKDF's `info` and
+//! the one-step KDF's `FixedInfo`:
+//!
+//! ```text
+//! lp(label) || lp(context) || lp(ciphertext) || lp(sender_pk) || lp(recipient_pk) || L
+//! ```
+//!
+//! where `lp(x)` is the 4-byte big-endian length of `x` followed by `x`, an
+//! unbound field is empty and `L` is the output length in bits as 4 bytes.
+//! Different labels, contexts, bindings or lengths therefore give unrelated
+//! keys. Binding the ciphertext and public keys ties the keys to one
+//! exchange, which the KEM alone does not promise for every use.
+//!
+//! ```
+//! # use rand_core::OsRng;
+//! use rusty_kyber::kdf::{Algorithm, DerivedKey};
+//!
+//! let (pk, sk) = rusty_kyber::keypair(&mut OsRng);
+//! let (ct, ss) = rusty_kyber::encaps(&mut OsRng, &pk);
+//!
+//! let keys = ss.derive_keys(Algorithm::HkdfSha256).context(b"chat v1").bind_ciphertext(ct.as_bytes());
+//! let enc: DerivedKey<32> = keys.key(b"encryption").unwrap();
+//! let mac: DerivedKey<64> = keys.key(b"authentication").unwrap();
+//! # let _ = (sk, enc, mac);
+//! ```
+
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::error::Error;
+use crate::utils::{kmac128, kmac256};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use sha3::Sha3_256;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// The key derivation function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    HkdfSha256,
+    HkdfSha3_256,
+    Kmac128,
+    Kmac256,
+}
+
+impl Algorithm {
+    /// The longest output of one derivation, in bytes.
+    pub const fn max_output_bytes(self) -> usize {
+        match self {
+            // 255 blocks of the hash output
+            Algorithm::HkdfSha256 | Algorithm::HkdfSha3_256 => 255 * 32,
+            // L goes into FixedInfo as 32 bits
+            Algorithm::Kmac128 | Algorithm::Kmac256 => (u32::MAX / 8) as usize,
+        }
+    }
+}
+
+/// A derived key of `N` bytes, wiped on drop.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct DerivedKey<const N: usize>([u8; N]);
+
+impl<const N: usize> DerivedKey<N> {
+    pub fn as_bytes(&self) -> &[u8; N] {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for DerivedKey<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Derives keys from one shared secret, usually made with
+/// `SharedSecret::derive_keys`.
+#[derive(Clone, Copy)]
+pub struct KeyDerivation<'a> {
+    algorithm: Algorithm,
+    secret: &'a [u8],
+    context: &'a [u8],
+    ciphertext: &'a [u8],
+    public_keys: (&'a [u8], &'a [u8]),
+}
+
+impl<'a> KeyDerivation<'a> {
+    pub fn new(algorithm: Algorithm, secret: &'a [u8]) -> Self {
+        KeyDerivation { algorithm, secret, context: &[], ciphertext: &[], public_keys: (&[], &[]) }
+    }
+
+    /// Application context shared by every key, such as a protocol name and version.
+    pub fn context(self, context: &'a [u8]) -> Self {
+        KeyDerivation { context, ..self }
+    }
+
+    /// Binds the KEM ciphertext the secret came from.
+    pub fn bind_ciphertext(self, ciphertext: &'a [u8]) -> Self {
+        KeyDerivation { ciphertext, ..self }
+    }
+
+    /// Binds the public keys of both parties. With a plain KEM exchange the
+    /// sender has no key of its own; pass whatever key authenticates it, or
+    /// an empty slice.
+    pub fn bind_public_keys(self, sender: &'a [u8], recipient: &'a [u8]) -> Self {
+        KeyDerivation { public_keys: (sender, recipient), ..self }
+    }
+
+    /// Derives an `N`-byte key for `label`.
+    pub fn key<const N: usize>(&self, label: &[u8]) -> Result<DerivedKey<N>, Error> {
+        let mut key = DerivedKey([0u8; N]);
+        self.fill(label, &mut key.0)?;
+        Ok(key)
+    }
+
+    /// Derives a key of `len` bytes for `label`, for lengths known only at run time.
+    pub fn key_vec(&self, label: &[u8], len: usize) -> Result<Zeroizing<Vec<u8>>, Error> {
+        let mut key = Zeroizing::new(vec![0u8; len]);
+        self.fill(label, &mut key[..])?;
+        Ok(key)
+    }
+
+    /// Fills `out` with the key for `label`. Fails with
+    /// [`Error::InvalidLength`] if `out` is longer than
+    /// [`Algorithm::max_output_bytes`].
+    pub fn fill(&self, label: &[u8], out: &mut [u8]) -> Result<(), Error> {
+        if out.len() > self.algorithm.max_output_bytes() {
+            return Err(Error::InvalidLength);
+        }
+        let info = self.fixed_info(label, out.len());
+        match self.algorithm {
+            Algorithm::HkdfSha256 => {
+                Hkdf::<Sha256>::new(None, self.secret).expand(&info, out).map_err(|_| Error::InvalidLength)?
+            }
+            Algorithm::HkdfSha3_256 => {
+                Hkdf::<Sha3_256>::new(None, self.secret).expand(&info, out).map_err(|_| Error::InvalidLength)?
+            }
+            // The default salt is a zero block of the KMAC rate minus 4 bytes
+            Algorithm::Kmac128 => kmac128(&[&[0u8; 164]], &[&COUNTER, self.secret, &info], b"KDF", out),
+            Algorithm::Kmac256 => kmac256(&[&[0u8; 132]], &[&COUNTER, self.secret, &info], b"KDF", out),
+        }
+        Ok(())
+    }
+
+    fn fixed_info(&self, label: &[u8], len: usize) -> Vec<u8> {
+        let fields = [label, self.context, self.ciphertext, self.public_keys.0, self.public_keys.1];
+        let mut info = Vec::with_capacity(fields.iter().map(|f| 4 + f.len()).sum::<usize>() + 4);
+        for field in fields {
+            info.extend_from_slice(&(field.len() as u32).to_be_bytes());
+            info.extend_from_slice(field);
+        }
+        info.extend_from_slice(&((len * 8) as u32).to_be_bytes());
+        info
+    }
+}
+
+// One KMAC call covers any output length, so the counter is always 1
+const COUNTER: [u8; 4] = [0, 0, 0, 1];
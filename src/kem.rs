@@ -1,59 +1,114 @@
-use crate::params::{PUBLIC_KEY_BYTES, SECRET_KEY_BYTES, CIPHERTEXT_BYTES, SHARED_SECRET_BYTES};
+use crate::params::{Q, POLY_BYTES, public_key_bytes, secret_key_bytes, indcpa_secret_key_bytes, ciphertext_bytes, MAX_CIPHERTEXT_BYTES};
 use crate::keygen::indcpa_keypair;
 use crate::encaps::indcpa_enc;
 use crate::decaps::indcpa_dec;
-use crate::utils::{h, g, kdf};
+use crate::poly::Poly;
+use crate::utils::{h, g, kdf, verify, cmov, poly_from_bytes};
 use rand_core::{RngCore, CryptoRng};
+use zeroize::Zeroize;
 
-pub fn keygen<R: RngCore + CryptoRng>(
+// All three functions are generic over the module rank K; the per-level
+// modules in lib.rs call them with the right K and correctly sized buffers.
+
+pub fn keygen<const K: usize, R: RngCore + CryptoRng>(
     rng: &mut R,
-    pk: &mut [u8; PUBLIC_KEY_BYTES],
-    sk: &mut [u8; SECRET_KEY_BYTES],
+    pk: &mut [u8],
+    sk: &mut [u8],
 ) {
-    indcpa_keypair(rng, pk, sk);
+    // d || z: d seeds the IND-CPA key pair, z the implicit rejection key
+    let mut seed = [0u8; 64];
+    rng.fill_bytes(&mut seed);
+    keygen_internal::<K>(&seed, pk, sk);
+    seed.zeroize();
+}
+
+// ML-KEM.KeyGen_internal (FIPS 203, Algorithm 16), for callers that derive
+// d || z themselves, e.g. X-Wing's seed expansion
+pub fn keygen_internal<const K: usize>(
+    seed: &[u8; 64],
+    pk: &mut [u8],
+    sk: &mut [u8],
+) {
+    debug_assert_eq!(sk.len(), secret_key_bytes(K));
+
+    let pk_len = public_key_bytes(K);
+    let (sk_pke, rest) = sk.split_at_mut(indcpa_secret_key_bytes(K));
+    indcpa_keypair::<K>(seed[..32].try_into().unwrap(), pk, sk_pke);
+
     let mut h_pk = [0u8; 32];
     h(pk, &mut h_pk);
-    sk[SECRET_KEY_BYTES - 64..SECRET_KEY_BYTES - 32].copy_from_slice(pk);
-    sk[SECRET_KEY_BYTES - 32..].copy_from_slice(&h_pk);
+    rest[..pk_len].copy_from_slice(pk);
+    rest[pk_len..pk_len + 32].copy_from_slice(&h_pk);
+    rest[pk_len + 32..].copy_from_slice(&seed[32..]);
+}
+
+// Encapsulation key check (FIPS 203, section 7.2): every 12-bit coefficient
+// of t must already be reduced mod q. Public input, so no need to be constant time.
+pub fn check_public_key<const K: usize>(pk: &[u8]) -> bool {
+    if pk.len() != public_key_bytes(K) {
+        return false;
+    }
+    let mut p = Poly::new();
+    (0..K).all(|i| {
+        poly_from_bytes(&pk[i * POLY_BYTES..], &mut p);
+        p.coeffs.iter().all(|&c| (c as i32) < Q)
+    })
 }
 
-pub fn encaps<R: RngCore + CryptoRng>(
+pub fn encaps<const K: usize, R: RngCore + CryptoRng>(
     rng: &mut R,
-    pk: &[u8; PUBLIC_KEY_BYTES],
-    ss: &mut [u8; SHARED_SECRET_BYTES],
-    ct: &mut [u8; CIPHERTEXT_BYTES],
+    pk: &[u8],
+    ss: &mut [u8; 32],
+    ct: &mut [u8],
 ) {
     let mut m = [0u8; 32];
     rng.fill_bytes(&mut m);
+    encaps_internal::<K>(&m, pk, ss, ct);
+    m.zeroize();
+}
 
+// ML-KEM.Encaps_internal (FIPS 203, Algorithm 17) with caller-supplied m
+pub fn encaps_internal<const K: usize>(
+    m: &[u8; 32],
+    pk: &[u8],
+    ss: &mut [u8; 32],
+    ct: &mut [u8],
+) {
     let mut h_pk = [0u8; 32];
     h(pk, &mut h_pk);
 
     let mut g_in = [0u8; 64];
-    g_in[..32].copy_from_slice(&m);
+    g_in[..32].copy_from_slice(m);
     g_in[32..].copy_from_slice(&h_pk);
     let mut g_out = [0u8; 64];
     g(&g_in, &mut g_out);
     let (k, coins) = g_out.split_at(32);
 
-    indcpa_enc(pk, &m, coins.try_into().unwrap(), ct);
+    indcpa_enc::<K>(pk, m, coins.try_into().unwrap(), ct);
 
-    let mut kdf_in = [0u8; 64];
-    kdf_in[..32].copy_from_slice(k);
-    kdf_in[32..].copy_from_slice(ct);
-    kdf(&kdf_in, ss);
+    ss.copy_from_slice(k);
+    g_in.zeroize();
+    g_out.zeroize();
 }
 
-pub fn decaps(
-    sk: &[u8; SECRET_KEY_BYTES],
-    ct: &[u8; CIPHERTEXT_BYTES],
-    ss: &mut [u8; SHARED_SECRET_BYTES],
+pub fn decaps<const K: usize>(
+    sk: &[u8],
+    ct: &[u8],
+    ss: &mut [u8; 32],
 ) {
-    let mut m = [0u8; 32];
-    indcpa_dec(sk, ct, &mut m);
+    let sk_len = secret_key_bytes(K);
+    let ct_len = ciphertext_bytes(K);
+    debug_assert_eq!(sk.len(), sk_len);
+    debug_assert_eq!(ct.len(), ct_len);
 
-    let pk = &sk[SECRET_KEY_BYTES - 64..SECRET_KEY_BYTES - 32];
-    let h_pk = &sk[SECRET_KEY_BYTES - 32..];
+    let sk_pke_len = indcpa_secret_key_bytes(K);
+    let sk_pke = &sk[..sk_pke_len];
+    let pk = &sk[sk_pke_len..sk_pke_len + public_key_bytes(K)];
+    let h_pk = &sk[sk_len - 64..sk_len - 32];
+    let z = &sk[sk_len - 32..];
+
+    let mut m = [0u8; 32];
+    indcpa_dec::<K>(sk_pke, ct, &mut m);
 
     let mut g_in = [0u8; 64];
     g_in[..32].copy_from_slice(&m);
@@ -62,16 +117,23 @@ pub fn decaps(
     g(&g_in, &mut g_out);
     let (k, coins) = g_out.split_at(32);
 
-    let mut ct2 = [0u8; CIPHERTEXT_BYTES];
-    indcpa_enc(pk.try_into().unwrap(), &m, coins.try_into().unwrap(), &mut ct2);
+    let mut ct2 = [0u8; MAX_CIPHERTEXT_BYTES];
+    let ct2 = &mut ct2[..ct_len];
+    indcpa_enc::<K>(pk, &m, coins.try_into().unwrap(), ct2);
 
-    let mut kdf_in = [0u8; 64];
-    if ct == &ct2 {
-        kdf_in[..32].copy_from_slice(k);
-        kdf_in[32..].copy_from_slice(ct);
-    } else {
-        kdf_in[..32].copy_from_slice(&sk[SECRET_KEY_BYTES - 96..SECRET_KEY_BYTES - 64]);
-        kdf_in[32..].copy_from_slice(ct);
-    }
-    kdf(&kdf_in, ss);
+    let fail = verify(ct, ct2);
+
+    // Always derive the rejection key, then overwrite it with K if the
+    // re-encryption matched, so both outcomes take the same time
+    let mut kdf_in = [0u8; 32 + MAX_CIPHERTEXT_BYTES];
+    kdf_in[..32].copy_from_slice(z);
+    kdf_in[32..32 + ct_len].copy_from_slice(ct);
+    kdf(&kdf_in[..32 + ct_len], ss);
+    cmov(ss, k, 1 - fail);
+
+    // m, K and the coins are secret, and kdf_in holds z
+    m.zeroize();
+    g_in.zeroize();
+    g_out.zeroize();
+    kdf_in.zeroize();
 }
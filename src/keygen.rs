@@ -1,46 +1,66 @@
-use crate::params::{K, PUBLIC_KEY_BYTES, SECRET_KEY_BYTES, POLY_BYTES};
+use crate::params::{eta1, public_key_bytes, indcpa_secret_key_bytes, MAX_ETA, POLY_BYTES};
 use crate::poly::Poly;
-use crate::utils::{xof, prf, g, sample_ntt, cbd, poly_to_bytes};
-use rand_core::{RngCore, CryptoRng};
+use crate::utils::{prf, g, sample_ntt, cbd, poly_to_bytes};
+use crate::declassify::declassify;
+use zeroize::Zeroize;
 
-pub fn indcpa_keypair<R: RngCore + CryptoRng>(
-    rng: &mut R,
-    pk: &mut [u8; PUBLIC_KEY_BYTES],
-    sk: &mut [u8; SECRET_KEY_BYTES],
+pub fn indcpa_keypair<const K: usize>(
+    d: &[u8; 32],
+    pk: &mut [u8],
+    sk: &mut [u8],
 ) {
-    let mut seed = [0u8; 64];
-    rng.fill_bytes(&mut seed);
-
+    let mut g_in = [0u8; 33];
+    g_in[..32].copy_from_slice(d);
+    g_in[32] = K as u8;
     let mut g_out = [0u8; 64];
-    g(&seed[..32], &mut g_out);
+    g(&g_in, &mut g_out);
     let (rho, sigma) = g_out.split_at(32);
+    indcpa_keypair_from_seeds::<K>(rho.try_into().unwrap(), sigma.try_into().unwrap(), pk, sk);
+
+    // d and sigma determine the secret key
+    g_in.zeroize();
+    g_out.zeroize();
+}
+
+// K-PKE.KeyGen after the seed expansion, so that the multi-recipient KEM can
+// pick a matrix seed shared by several keys
+pub fn indcpa_keypair_from_seeds<const K: usize>(
+    rho: &[u8; 32],
+    sigma: &[u8; 32],
+    pk: &mut [u8],
+    sk: &mut [u8],
+) {
+    debug_assert_eq!(pk.len(), public_key_bytes(K));
+    debug_assert_eq!(sk.len(), indcpa_secret_key_bytes(K));
+    let eta1 = eta1(K);
+
+    // rho becomes part of the public key; matrix sampling may branch on it
+    declassify(rho);
 
     let mut a = [[Poly::new(); K]; K];
-    for i in 0..K {
-        for j in 0..K {
-            let mut xof_out = [0u8; 672];
+    for (i, row) in a.iter_mut().enumerate() {
+        for (j, a_ij) in row.iter_mut().enumerate() {
             let mut seed_a = [0u8; 34];
             seed_a[..32].copy_from_slice(rho);
-            seed_a[32] = i as u8;
-            seed_a[33] = j as u8;
-            xof(&seed_a, xof_out.len(), &mut xof_out);
-            sample_ntt(&xof_out, &mut a[i][j]);
+            seed_a[32] = j as u8;
+            seed_a[33] = i as u8;
+            sample_ntt(&seed_a, a_ij);
         }
     }
 
     let mut s = [Poly::new(); K];
     let mut e = [Poly::new(); K];
     let mut nonce = 0;
-    for i in 0..K {
-        let mut prf_out = [0u8; 128];
-        prf(sigma, nonce, &mut prf_out);
-        cbd(&prf_out, &mut s[i]);
+    for s_i in s.iter_mut() {
+        let mut prf_out = [0u8; 64 * MAX_ETA];
+        prf(sigma, nonce, &mut prf_out[..64 * eta1]);
+        cbd(&prf_out[..64 * eta1], eta1, s_i);
         nonce += 1;
     }
-    for i in 0..K {
-        let mut prf_out = [0u8; 128];
-        prf(sigma, nonce, &mut prf_out);
-        cbd(&prf_out, &mut e[i]);
+    for e_i in e.iter_mut() {
+        let mut prf_out = [0u8; 64 * MAX_ETA];
+        prf(sigma, nonce, &mut prf_out[..64 * eta1]);
+        cbd(&prf_out[..64 * eta1], eta1, e_i);
         nonce += 1;
     }
 
@@ -57,7 +77,6 @@ pub fn indcpa_keypair<R: RngCore + CryptoRng>(
             pk_poly[i].add(&tmp);
         }
         pk_poly[i].add(&e[i]);
-        pk_poly[i].inv_ntt();
     }
 
     for i in 0..K {
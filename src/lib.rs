@@ -1,3 +1,7 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 pub mod params;
 pub mod poly;
 pub mod ntt;
@@ -6,25 +10,73 @@ pub mod kem;
 pub mod keygen;
 pub mod encaps;
 pub mod decaps;
-
-#[cfg(feature = "kyber512")]
+pub mod declassify;
+pub mod error;
+pub mod traits;
+pub mod combiner;
+#[cfg(feature = "hybrid")]
+pub mod ecdh;
+#[cfg(feature = "hybrid")]
+pub mod hybrid;
+#[cfg(feature = "xwing")]
+pub mod xwing;
+#[cfg(feature = "hpke")]
+pub mod hpke;
+#[cfg(feature = "sealed")]
+pub mod sealed;
+#[cfg(feature = "stream")]
+pub mod stream;
+#[cfg(feature = "envelope")]
+pub mod envelope;
+#[cfg(feature = "mkem")]
+pub mod mkem;
+#[cfg(feature = "kdf")]
+pub mod kdf;
+#[cfg(feature = "der")]
+pub mod der;
+#[cfg(feature = "pem")]
+pub mod pem;
+#[cfg(feature = "password")]
+pub mod password;
+#[cfg(feature = "jose")]
+pub mod jose;
+#[cfg(feature = "cose")]
+pub mod cose;
+#[cfg(feature = "cms")]
+pub mod cms;
+#[cfg(feature = "openpgp")]
+pub mod openpgp;
+#[cfg(feature = "age")]
+pub mod age;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+#[cfg(feature = "reference")]
+pub mod reference;
+#[cfg(kani)]
+mod proofs;
+
+pub use traits::Kem;
+
+// Each level lives in its own module and any combination can be enabled; the
+// top-level names follow the highest enabled level, like `params`.
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
 pub use kyber512::{
-    keypair, encaps, decaps, PublicKey, SecretKey, Ciphertext, SharedSecret,
+    keypair, encaps, decaps, PublicKey, SecretKey, SecretSeed, Ciphertext, SharedSecret,
 };
-#[cfg(feature = "kyber768")]
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
 pub use kyber768::{
-    keypair, encaps, decaps, PublicKey, SecretKey, Ciphertext, SharedSecret,
+    keypair, encaps, decaps, PublicKey, SecretKey, SecretSeed, Ciphertext, SharedSecret,
 };
 #[cfg(feature = "kyber1024")]
 pub use kyber1024::{
-    keypair, encaps, decaps, PublicKey, SecretKey, Ciphertext, SharedSecret,
+    keypair, encaps, decaps, PublicKey, SecretKey, SecretSeed, Ciphertext, SharedSecret,
 };
 
 #[cfg(feature = "kyber512")]
 pub mod kyber512 {
     use super::*;
     use crate::params::kyber512 as kyber_params;
-    use zeroize::Zeroize;
+    use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
     use rand_core::{RngCore, CryptoRng};
 
     #[derive(Clone, Copy)]
@@ -113,6 +165,129 @@ pub mod kyber512 {
         pub fn as_bytes(&self) -> &[u8] {
             &self.0
         }
+
+        /// Derives application keys from this secret, see [`crate::kdf`].
+        #[cfg(feature = "kdf")]
+        pub fn derive_keys(&self, algorithm: crate::kdf::Algorithm) -> crate::kdf::KeyDerivation<'_> {
+            crate::kdf::KeyDerivation::new(algorithm, &self.0)
+        }
+    }
+
+    impl AsRef<[u8]> for PublicKey {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for Ciphertext {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for SharedSecret {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    /// The 64-byte seed `d || z` of FIPS 203 ML-KEM.KeyGen_internal, the
+    /// compact way to store a secret key. It expands to the same key pair
+    /// every time, and zeroizes itself on drop.
+    #[derive(Clone, Zeroize, ZeroizeOnDrop)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SecretSeed {
+        d: [u8; 32],
+        z: [u8; 32],
+    }
+
+    impl From<[u8; kyber_params::SEED_BYTES]> for SecretSeed {
+        fn from(mut bytes: [u8; kyber_params::SEED_BYTES]) -> Self {
+            let seed = SecretSeed::from_bytes(&bytes);
+            bytes.zeroize();
+            seed
+        }
+    }
+
+    impl SecretSeed {
+        pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            rng.fill_bytes(&mut bytes[..]);
+            SecretSeed::from_bytes(&bytes)
+        }
+
+        fn from_bytes(bytes: &[u8; kyber_params::SEED_BYTES]) -> Self {
+            let (d, z) = bytes.split_at(32);
+            SecretSeed { d: d.try_into().unwrap(), z: z.try_into().unwrap() }
+        }
+
+        pub fn to_bytes(&self) -> Zeroizing<[u8; kyber_params::SEED_BYTES]> {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            bytes[..32].copy_from_slice(&self.d);
+            bytes[32..].copy_from_slice(&self.z);
+            bytes
+        }
+
+        /// The key pair this seed stands for.
+        pub fn expand(&self) -> (PublicKey, SecretKey) {
+            let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
+            let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
+            kem::keygen_internal::<{ kyber_params::K }>(&self.to_bytes(), &mut pk, &mut sk);
+            (PublicKey(pk), SecretKey(sk))
+        }
+
+        pub fn public_key(&self) -> PublicKey {
+            let (pk, mut sk) = self.expand();
+            sk.zeroize();
+            pk
+        }
+
+        /// Takes `bytes` as the seed of `expanded`, after checking in
+        /// constant time that it does expand to that key. Fails with
+        /// [`crate::error::Error::InconsistentKey`] otherwise.
+        pub fn from_expanded_with_check(
+            bytes: [u8; kyber_params::SEED_BYTES],
+            expanded: &SecretKey,
+        ) -> Result<Self, crate::error::Error> {
+            let seed = SecretSeed::from(bytes);
+            let (_, mut sk) = seed.expand();
+            let matches = utils::verify(&sk.0, &expanded.0) == 0;
+            sk.zeroize();
+            if !matches {
+                return Err(crate::error::Error::InconsistentKey);
+            }
+            Ok(seed)
+        }
+    }
+
+    /// ML-KEM-512 as a [`crate::Kem`], for composing with other KEMs.
+    pub struct MlKem512;
+
+    impl crate::Kem for MlKem512 {
+        const NAME: &'static str = "ML-KEM-512";
+
+        type PublicKey = PublicKey;
+        type SecretKey = SecretKey;
+        type Ciphertext = Ciphertext;
+        type SharedSecret = SharedSecret;
+
+        fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+            keypair(rng)
+        }
+
+        fn encaps<R: RngCore + CryptoRng>(
+            rng: &mut R,
+            pk: &PublicKey,
+        ) -> Result<(Ciphertext, SharedSecret), crate::error::Error> {
+            if !kem::check_public_key::<{ kyber_params::K }>(&pk.0) {
+                return Err(crate::error::Error::InvalidPublicKey);
+            }
+            Ok(encaps(rng, pk))
+        }
+
+        fn decaps(sk: &SecretKey, ct: &Ciphertext) -> Result<SharedSecret, crate::error::Error> {
+            Ok(decaps(sk, ct))
+        }
     }
 
     pub fn keypair<R: RngCore + CryptoRng>(
@@ -120,7 +295,7 @@ pub mod kyber512 {
     ) -> (PublicKey, SecretKey) {
         let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
         let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
-        kem::keygen(rng, &mut pk, &mut sk);
+        kem::keygen::<{ kyber_params::K }, _>(rng, &mut pk, &mut sk);
         (PublicKey(pk), SecretKey(sk))
     }
 
@@ -130,7 +305,7 @@ pub mod kyber512 {
     ) -> (Ciphertext, SharedSecret) {
         let mut ct = [0u8; kyber_params::CIPHERTEXT_BYTES];
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::encaps(rng, &pk.0, &mut ss, &mut ct);
+        kem::encaps::<{ kyber_params::K }, _>(rng, &pk.0, &mut ss, &mut ct);
         (Ciphertext(ct), SharedSecret(ss))
     }
 
@@ -139,7 +314,7 @@ pub mod kyber512 {
         ct: &Ciphertext,
     ) -> SharedSecret {
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::decaps(&sk.0, &ct.0, &mut ss);
+        kem::decaps::<{ kyber_params::K }>(&sk.0, &ct.0, &mut ss);
         SharedSecret(ss)
     }
 }
@@ -148,7 +323,7 @@ pub mod kyber512 {
 pub mod kyber768 {
     use super::*;
     use crate::params::kyber768 as kyber_params;
-    use zeroize::Zeroize;
+    use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
     use rand_core::{RngCore, CryptoRng};
 
     #[derive(Clone, Copy)]
@@ -237,6 +412,129 @@ pub mod kyber768 {
         pub fn as_bytes(&self) -> &[u8] {
             &self.0
         }
+
+        /// Derives application keys from this secret, see [`crate::kdf`].
+        #[cfg(feature = "kdf")]
+        pub fn derive_keys(&self, algorithm: crate::kdf::Algorithm) -> crate::kdf::KeyDerivation<'_> {
+            crate::kdf::KeyDerivation::new(algorithm, &self.0)
+        }
+    }
+
+    impl AsRef<[u8]> for PublicKey {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for Ciphertext {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for SharedSecret {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    /// The 64-byte seed `d || z` of FIPS 203 ML-KEM.KeyGen_internal, the
+    /// compact way to store a secret key. It expands to the same key pair
+    /// every time, and zeroizes itself on drop.
+    #[derive(Clone, Zeroize, ZeroizeOnDrop)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SecretSeed {
+        d: [u8; 32],
+        z: [u8; 32],
+    }
+
+    impl From<[u8; kyber_params::SEED_BYTES]> for SecretSeed {
+        fn from(mut bytes: [u8; kyber_params::SEED_BYTES]) -> Self {
+            let seed = SecretSeed::from_bytes(&bytes);
+            bytes.zeroize();
+            seed
+        }
+    }
+
+    impl SecretSeed {
+        pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            rng.fill_bytes(&mut bytes[..]);
+            SecretSeed::from_bytes(&bytes)
+        }
+
+        fn from_bytes(bytes: &[u8; kyber_params::SEED_BYTES]) -> Self {
+            let (d, z) = bytes.split_at(32);
+            SecretSeed { d: d.try_into().unwrap(), z: z.try_into().unwrap() }
+        }
+
+        pub fn to_bytes(&self) -> Zeroizing<[u8; kyber_params::SEED_BYTES]> {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            bytes[..32].copy_from_slice(&self.d);
+            bytes[32..].copy_from_slice(&self.z);
+            bytes
+        }
+
+        /// The key pair this seed stands for.
+        pub fn expand(&self) -> (PublicKey, SecretKey) {
+            let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
+            let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
+            kem::keygen_internal::<{ kyber_params::K }>(&self.to_bytes(), &mut pk, &mut sk);
+            (PublicKey(pk), SecretKey(sk))
+        }
+
+        pub fn public_key(&self) -> PublicKey {
+            let (pk, mut sk) = self.expand();
+            sk.zeroize();
+            pk
+        }
+
+        /// Takes `bytes` as the seed of `expanded`, after checking in
+        /// constant time that it does expand to that key. Fails with
+        /// [`crate::error::Error::InconsistentKey`] otherwise.
+        pub fn from_expanded_with_check(
+            bytes: [u8; kyber_params::SEED_BYTES],
+            expanded: &SecretKey,
+        ) -> Result<Self, crate::error::Error> {
+            let seed = SecretSeed::from(bytes);
+            let (_, mut sk) = seed.expand();
+            let matches = utils::verify(&sk.0, &expanded.0) == 0;
+            sk.zeroize();
+            if !matches {
+                return Err(crate::error::Error::InconsistentKey);
+            }
+            Ok(seed)
+        }
+    }
+
+    /// ML-KEM-768 as a [`crate::Kem`], for composing with other KEMs.
+    pub struct MlKem768;
+
+    impl crate::Kem for MlKem768 {
+        const NAME: &'static str = "ML-KEM-768";
+
+        type PublicKey = PublicKey;
+        type SecretKey = SecretKey;
+        type Ciphertext = Ciphertext;
+        type SharedSecret = SharedSecret;
+
+        fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+            keypair(rng)
+        }
+
+        fn encaps<R: RngCore + CryptoRng>(
+            rng: &mut R,
+            pk: &PublicKey,
+        ) -> Result<(Ciphertext, SharedSecret), crate::error::Error> {
+            if !kem::check_public_key::<{ kyber_params::K }>(&pk.0) {
+                return Err(crate::error::Error::InvalidPublicKey);
+            }
+            Ok(encaps(rng, pk))
+        }
+
+        fn decaps(sk: &SecretKey, ct: &Ciphertext) -> Result<SharedSecret, crate::error::Error> {
+            Ok(decaps(sk, ct))
+        }
     }
 
     pub fn keypair<R: RngCore + CryptoRng>(
@@ -244,7 +542,7 @@ pub mod kyber768 {
     ) -> (PublicKey, SecretKey) {
         let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
         let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
-        kem::keygen(rng, &mut pk, &mut sk);
+        kem::keygen::<{ kyber_params::K }, _>(rng, &mut pk, &mut sk);
         (PublicKey(pk), SecretKey(sk))
     }
 
@@ -254,7 +552,7 @@ pub mod kyber768 {
     ) -> (Ciphertext, SharedSecret) {
         let mut ct = [0u8; kyber_params::CIPHERTEXT_BYTES];
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::encaps(rng, &pk.0, &mut ss, &mut ct);
+        kem::encaps::<{ kyber_params::K }, _>(rng, &pk.0, &mut ss, &mut ct);
         (Ciphertext(ct), SharedSecret(ss))
     }
 
@@ -263,7 +561,7 @@ pub mod kyber768 {
         ct: &Ciphertext,
     ) -> SharedSecret {
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::decaps(&sk.0, &ct.0, &mut ss);
+        kem::decaps::<{ kyber_params::K }>(&sk.0, &ct.0, &mut ss);
         SharedSecret(ss)
     }
 }
@@ -272,7 +570,7 @@ pub mod kyber768 {
 pub mod kyber1024 {
     use super::*;
     use crate::params::kyber1024 as kyber_params;
-    use zeroize::Zeroize;
+    use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
     use rand_core::{RngCore, CryptoRng};
 
     #[derive(Clone, Copy)]
@@ -361,6 +659,129 @@ pub mod kyber1024 {
         pub fn as_bytes(&self) -> &[u8] {
             &self.0
         }
+
+        /// Derives application keys from this secret, see [`crate::kdf`].
+        #[cfg(feature = "kdf")]
+        pub fn derive_keys(&self, algorithm: crate::kdf::Algorithm) -> crate::kdf::KeyDerivation<'_> {
+            crate::kdf::KeyDerivation::new(algorithm, &self.0)
+        }
+    }
+
+    impl AsRef<[u8]> for PublicKey {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for Ciphertext {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    impl AsRef<[u8]> for SharedSecret {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    /// The 64-byte seed `d || z` of FIPS 203 ML-KEM.KeyGen_internal, the
+    /// compact way to store a secret key. It expands to the same key pair
+    /// every time, and zeroizes itself on drop.
+    #[derive(Clone, Zeroize, ZeroizeOnDrop)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub struct SecretSeed {
+        d: [u8; 32],
+        z: [u8; 32],
+    }
+
+    impl From<[u8; kyber_params::SEED_BYTES]> for SecretSeed {
+        fn from(mut bytes: [u8; kyber_params::SEED_BYTES]) -> Self {
+            let seed = SecretSeed::from_bytes(&bytes);
+            bytes.zeroize();
+            seed
+        }
+    }
+
+    impl SecretSeed {
+        pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            rng.fill_bytes(&mut bytes[..]);
+            SecretSeed::from_bytes(&bytes)
+        }
+
+        fn from_bytes(bytes: &[u8; kyber_params::SEED_BYTES]) -> Self {
+            let (d, z) = bytes.split_at(32);
+            SecretSeed { d: d.try_into().unwrap(), z: z.try_into().unwrap() }
+        }
+
+        pub fn to_bytes(&self) -> Zeroizing<[u8; kyber_params::SEED_BYTES]> {
+            let mut bytes = Zeroizing::new([0u8; kyber_params::SEED_BYTES]);
+            bytes[..32].copy_from_slice(&self.d);
+            bytes[32..].copy_from_slice(&self.z);
+            bytes
+        }
+
+        /// The key pair this seed stands for.
+        pub fn expand(&self) -> (PublicKey, SecretKey) {
+            let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
+            let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
+            kem::keygen_internal::<{ kyber_params::K }>(&self.to_bytes(), &mut pk, &mut sk);
+            (PublicKey(pk), SecretKey(sk))
+        }
+
+        pub fn public_key(&self) -> PublicKey {
+            let (pk, mut sk) = self.expand();
+            sk.zeroize();
+            pk
+        }
+
+        /// Takes `bytes` as the seed of `expanded`, after checking in
+        /// constant time that it does expand to that key. Fails with
+        /// [`crate::error::Error::InconsistentKey`] otherwise.
+        pub fn from_expanded_with_check(
+            bytes: [u8; kyber_params::SEED_BYTES],
+            expanded: &SecretKey,
+        ) -> Result<Self, crate::error::Error> {
+            let seed = SecretSeed::from(bytes);
+            let (_, mut sk) = seed.expand();
+            let matches = utils::verify(&sk.0, &expanded.0) == 0;
+            sk.zeroize();
+            if !matches {
+                return Err(crate::error::Error::InconsistentKey);
+            }
+            Ok(seed)
+        }
+    }
+
+    /// ML-KEM-1024 as a [`crate::Kem`], for composing with other KEMs.
+    pub struct MlKem1024;
+
+    impl crate::Kem for MlKem1024 {
+        const NAME: &'static str = "ML-KEM-1024";
+
+        type PublicKey = PublicKey;
+        type SecretKey = SecretKey;
+        type Ciphertext = Ciphertext;
+        type SharedSecret = SharedSecret;
+
+        fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+            keypair(rng)
+        }
+
+        fn encaps<R: RngCore + CryptoRng>(
+            rng: &mut R,
+            pk: &PublicKey,
+        ) -> Result<(Ciphertext, SharedSecret), crate::error::Error> {
+            if !kem::check_public_key::<{ kyber_params::K }>(&pk.0) {
+                return Err(crate::error::Error::InvalidPublicKey);
+            }
+            Ok(encaps(rng, pk))
+        }
+
+        fn decaps(sk: &SecretKey, ct: &Ciphertext) -> Result<SharedSecret, crate::error::Error> {
+            Ok(decaps(sk, ct))
+        }
     }
 
     pub fn keypair<R: RngCore + CryptoRng>(
@@ -368,7 +789,7 @@ pub mod kyber1024 {
     ) -> (PublicKey, SecretKey) {
         let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
         let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
-        kem::keygen(rng, &mut pk, &mut sk);
+        kem::keygen::<{ kyber_params::K }, _>(rng, &mut pk, &mut sk);
         (PublicKey(pk), SecretKey(sk))
     }
 
@@ -378,7 +799,7 @@ pub mod kyber1024 {
     ) -> (Ciphertext, SharedSecret) {
         let mut ct = [0u8; kyber_params::CIPHERTEXT_BYTES];
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::encaps(rng, &pk.0, &mut ss, &mut ct);
+        kem::encaps::<{ kyber_params::K }, _>(rng, &pk.0, &mut ss, &mut ct);
         (Ciphertext(ct), SharedSecret(ss))
     }
 
@@ -387,7 +808,7 @@ pub mod kyber1024 {
         ct: &Ciphertext,
     ) -> SharedSecret {
         let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
-        kem::decaps(&sk.0, &ct.0, &mut ss);
+        kem::decaps::<{ kyber_params::K }>(&sk.0, &ct.0, &mut ss);
         SharedSecret(ss)
     }
 }
@@ -0,0 +1,352 @@
+//! Experimental multi-recipient ML-KEM (mKEM), after Katsumata, Kwiatkowski,
+//! Pintore and Prest, "Scalable Ciphertext Compression Techniques for
+//! Post-Quantum KEMs and their Applications" (ASIACRYPT 2020).
+//!
+//! When every recipient key is generated from the same matrix seed `rho`, the
+//! half of an ML-KEM ciphertext that does not depend on the recipient,
+//! `u = A^T r + e1`, is the same for all of them. `encaps` therefore computes
+//! `u` once and only a short `v = t_i^T r + e2_i + m` per recipient, and every
+//! recipient decapsulates the same shared secret. For N recipients that is
+//! one `u` plus N * 128 bytes (160 for ML-KEM-1024) instead of N full
+//! ciphertexts, and one matrix expansion instead of N.
+//!
+//! The transform mirrors FIPS 203 with the recipient-independent inputs:
+//! `(K, coins) = G(m || rho)`, `r` and `e1` come from `coins` as in
+//! ML-KEM.Encaps, and `e2_i` is sampled from `SHAKE256(coins || H(ek_i))` so
+//! each recipient can re-encrypt its own `v`. Decapsulation re-encrypts
+//! `(u, v_i)` and falls back to the implicit rejection key `J(z || u || v_i)`,
+//! exactly like ML-KEM.Decaps on the concatenated ciphertext.
+//!
+//! # Security notes
+//!
+//! - This is not ML-KEM and not covered by FIPS 203. There are no test
+//!   vectors from elsewhere, and the hash and sampling choices above are this
+//!   crate's own instantiation of the paper's construction.
+//! - All recipients share one secret: any recipient can decrypt traffic to
+//!   the whole group and, knowing `m`, can make new ciphertexts for it. Use it
+//!   for broadcast to a trusted group, not where recipients must be isolated.
+//! - All keys of a group share the matrix `A`. ML-KEM gives every key its own
+//!   matrix so that precomputation against one `A` does not pay off across
+//!   keys; a group gives that up. `rho` must be drawn uniformly at random
+//!   (see `matrix_seed`) by someone the group trusts, never chosen freely.
+//! - The keys are ordinary ML-KEM key pairs and still work with the plain
+//!   KEM, with the shared-matrix caveat above.
+//! - The sender's choice of recipients is not authenticated. Bind the list
+//!   with a MAC or signature at the protocol level if it matters.
+
+use crate::decaps::indcpa_dec;
+use crate::encaps::{indcpa_enc_u, indcpa_enc_v};
+use crate::keygen::indcpa_keypair_from_seeds;
+use crate::params::{
+    dv, eta2, indcpa_secret_key_bytes, poly_vec_bytes, poly_vec_compressed_bytes, public_key_bytes,
+    secret_key_bytes, MAX_CIPHERTEXT_BYTES, MAX_ETA,
+};
+use crate::poly::Poly;
+use crate::utils::{cbd, cmov, g, h, kdf, verify};
+use rand_core::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+use zeroize::Zeroize;
+
+/// The seed of the public matrix shared by a group of recipient keys.
+pub type MatrixSeed = [u8; 32];
+
+/// Draws a fresh matrix seed for a new group.
+pub fn matrix_seed<R: RngCore + CryptoRng>(rng: &mut R) -> MatrixSeed {
+    let mut rho = [0u8; 32];
+    rng.fill_bytes(&mut rho);
+    rho
+}
+
+// An ML-KEM key pair with the given rho; sigma and z are random. The secret
+// key has the FIPS 203 layout, so the plain ML-KEM functions accept it.
+pub fn group_keygen<const K: usize, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    rho: &MatrixSeed,
+    pk: &mut [u8],
+    sk: &mut [u8],
+) {
+    debug_assert_eq!(sk.len(), secret_key_bytes(K));
+
+    let mut sigma = [0u8; 32];
+    rng.fill_bytes(&mut sigma);
+    let pk_len = public_key_bytes(K);
+    let (sk_pke, rest) = sk.split_at_mut(indcpa_secret_key_bytes(K));
+    indcpa_keypair_from_seeds::<K>(rho, &sigma, pk, sk_pke);
+    sigma.zeroize();
+
+    rest[..pk_len].copy_from_slice(pk);
+    h(pk, &mut rest[pk_len..pk_len + 32]);
+    rng.fill_bytes(&mut rest[pk_len + 32..]);
+}
+
+// G(m || rho): the same key and coins for every recipient of the group
+fn derive(m: &[u8; 32], rho: &[u8], g_out: &mut [u8; 64]) {
+    let mut g_in = [0u8; 64];
+    g_in[..32].copy_from_slice(m);
+    g_in[32..].copy_from_slice(rho);
+    g(&g_in, g_out);
+    g_in.zeroize();
+}
+
+// e2 for the recipient whose encapsulation key hashes to h_pk
+fn recipient_noise<const K: usize>(coins: &[u8], h_pk: &[u8], e2: &mut Poly) {
+    let eta2 = eta2(K);
+    let mut buf = [0u8; 64 * MAX_ETA];
+    let mut hasher = Shake256::default();
+    hasher.update(coins);
+    hasher.update(h_pk);
+    hasher.finalize_xof().read(&mut buf[..64 * eta2]);
+    cbd(&buf[..64 * eta2], eta2, e2);
+    buf.zeroize();
+}
+
+// The matrix seed at the end of an encapsulation key
+fn rho<const K: usize>(pk: &[u8]) -> &[u8] {
+    &pk[poly_vec_bytes(K)..]
+}
+
+// Encapsulates m to every key in pks, which must share rho. ct_v holds the
+// per-recipient parts back to back, in the order of pks.
+pub fn group_encaps_internal<const K: usize>(
+    m: &[u8; 32],
+    pks: &[&[u8]],
+    ss: &mut [u8; 32],
+    ct_u: &mut [u8],
+    ct_v: &mut [u8],
+) {
+    let v_len = 32 * dv(K);
+    debug_assert!(!pks.is_empty());
+    debug_assert_eq!(ct_u.len(), poly_vec_compressed_bytes(K));
+    debug_assert_eq!(ct_v.len(), pks.len() * v_len);
+
+    let rho = rho::<K>(pks[0]);
+    let mut g_out = [0u8; 64];
+    derive(m, rho, &mut g_out);
+    let coins: &[u8; 32] = g_out[32..].try_into().unwrap();
+
+    let mut r = [Poly::new(); K];
+    indcpa_enc_u::<K>(rho, coins, &mut r, ct_u);
+
+    for (pk, v) in pks.iter().zip(ct_v.chunks_exact_mut(v_len)) {
+        let mut h_pk = [0u8; 32];
+        h(pk, &mut h_pk);
+        let mut e2 = Poly::new();
+        recipient_noise::<K>(coins, &h_pk, &mut e2);
+        indcpa_enc_v::<K>(pk, &r, &e2, m, v);
+    }
+
+    ss.copy_from_slice(&g_out[..32]);
+    g_out.zeroize();
+}
+
+pub fn group_encaps<const K: usize, R: RngCore + CryptoRng>(
+    rng: &mut R,
+    pks: &[&[u8]],
+    ss: &mut [u8; 32],
+    ct_u: &mut [u8],
+    ct_v: &mut [u8],
+) {
+    let mut m = [0u8; 32];
+    rng.fill_bytes(&mut m);
+    group_encaps_internal::<K>(&m, pks, ss, ct_u, ct_v);
+    m.zeroize();
+}
+
+// Decapsulates the shared u and this recipient's v. Like ML-KEM.Decaps, a
+// ciphertext that does not re-encrypt yields J(z || u || v) instead.
+pub fn group_decaps<const K: usize>(
+    sk: &[u8],
+    ct_u: &[u8],
+    ct_v: &[u8],
+    ss: &mut [u8; 32],
+) {
+    let sk_len = secret_key_bytes(K);
+    let u_len = poly_vec_compressed_bytes(K);
+    let ct_len = u_len + ct_v.len();
+    debug_assert_eq!(sk.len(), sk_len);
+    debug_assert_eq!(ct_u.len(), u_len);
+    debug_assert_eq!(ct_v.len(), 32 * dv(K));
+
+    let sk_pke_len = indcpa_secret_key_bytes(K);
+    let sk_pke = &sk[..sk_pke_len];
+    let pk = &sk[sk_pke_len..sk_pke_len + public_key_bytes(K)];
+    let h_pk = &sk[sk_len - 64..sk_len - 32];
+    let z = &sk[sk_len - 32..];
+
+    let mut ct = [0u8; MAX_CIPHERTEXT_BYTES];
+    let ct = &mut ct[..ct_len];
+    ct[..u_len].copy_from_slice(ct_u);
+    ct[u_len..].copy_from_slice(ct_v);
+
+    let mut m = [0u8; 32];
+    indcpa_dec::<K>(sk_pke, ct, &mut m);
+
+    let mut g_out = [0u8; 64];
+    derive(&m, rho::<K>(pk), &mut g_out);
+    let coins: &[u8; 32] = g_out[32..].try_into().unwrap();
+
+    let mut ct2 = [0u8; MAX_CIPHERTEXT_BYTES];
+    let ct2 = &mut ct2[..ct_len];
+    let (u2, v2) = ct2.split_at_mut(u_len);
+    let mut r = [Poly::new(); K];
+    indcpa_enc_u::<K>(rho::<K>(pk), coins, &mut r, u2);
+    let mut e2 = Poly::new();
+    recipient_noise::<K>(coins, h_pk, &mut e2);
+    indcpa_enc_v::<K>(pk, &r, &e2, &m, v2);
+
+    let fail = verify(ct, ct2);
+
+    let mut kdf_in = [0u8; 32 + MAX_CIPHERTEXT_BYTES];
+    kdf_in[..32].copy_from_slice(z);
+    kdf_in[32..32 + ct_len].copy_from_slice(ct);
+    kdf(&kdf_in[..32 + ct_len], ss);
+    cmov(ss, &g_out[..32], 1 - fail);
+
+    m.zeroize();
+    g_out.zeroize();
+    kdf_in.zeroize();
+}
+
+macro_rules! mkem_level {
+    ($level:ident, $name:expr) => {
+        #[doc = concat!("Multi-recipient encapsulation to groups of ", $name, " keys.")]
+        pub mod $level {
+            use super::MatrixSeed;
+            use alloc::vec;
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::kem::check_public_key;
+            use crate::params::$level as kyber_params;
+            use crate::$level::{PublicKey, SecretKey, SharedSecret};
+            use rand_core::{CryptoRng, RngCore};
+
+            const K: usize = kyber_params::K;
+
+            pub const SHARED_CIPHERTEXT_BYTES: usize = kyber_params::POLY_VEC_COMPRESSED_BYTES;
+            pub const RECIPIENT_CIPHERTEXT_BYTES: usize = kyber_params::POLY_COMPRESSED_BYTES;
+
+            /// The part of a ciphertext common to all recipients, `u`.
+            #[derive(Clone, Copy)]
+            pub struct SharedCiphertext([u8; SHARED_CIPHERTEXT_BYTES]);
+
+            /// One recipient's part of a ciphertext, `v`.
+            #[derive(Clone, Copy)]
+            pub struct RecipientCiphertext([u8; RECIPIENT_CIPHERTEXT_BYTES]);
+
+            impl From<[u8; SHARED_CIPHERTEXT_BYTES]> for SharedCiphertext {
+                fn from(bytes: [u8; SHARED_CIPHERTEXT_BYTES]) -> Self {
+                    SharedCiphertext(bytes)
+                }
+            }
+
+            impl SharedCiphertext {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            impl AsRef<[u8]> for SharedCiphertext {
+                fn as_ref(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            impl From<[u8; RECIPIENT_CIPHERTEXT_BYTES]> for RecipientCiphertext {
+                fn from(bytes: [u8; RECIPIENT_CIPHERTEXT_BYTES]) -> Self {
+                    RecipientCiphertext(bytes)
+                }
+            }
+
+            impl RecipientCiphertext {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            impl AsRef<[u8]> for RecipientCiphertext {
+                fn as_ref(&self) -> &[u8] {
+                    &self.0
+                }
+            }
+
+            /// A key pair in the group of `rho`, usable as a plain ML-KEM key pair too.
+            pub fn keypair<R: RngCore + CryptoRng>(rng: &mut R, rho: &MatrixSeed) -> (PublicKey, SecretKey) {
+                let mut pk = [0u8; kyber_params::PUBLIC_KEY_BYTES];
+                let mut sk = [0u8; kyber_params::SECRET_KEY_BYTES];
+                super::group_keygen::<K, _>(rng, rho, &mut pk, &mut sk);
+                (PublicKey::from(pk), SecretKey::from(sk))
+            }
+
+            /// The matrix seed `pk` was generated with.
+            pub fn matrix_seed_of(pk: &PublicKey) -> MatrixSeed {
+                super::rho::<K>(pk.as_bytes()).try_into().unwrap()
+            }
+
+            /// Encapsulates one shared secret to every key in `recipients`,
+            /// returning the shared `u` and one `v` per recipient, in order.
+            ///
+            /// Fails with [`Error::InvalidLength`] for an empty list, with
+            /// [`Error::MatrixSeedMismatch`] if the keys are not all from the
+            /// same group, and with [`Error::InvalidPublicKey`] if any key does
+            /// not pass the FIPS 203 modulus check.
+            #[allow(clippy::type_complexity)]
+            pub fn encaps<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                recipients: &[PublicKey],
+            ) -> Result<(SharedCiphertext, Vec<RecipientCiphertext>, SharedSecret), Error> {
+                let first = recipients.first().ok_or(Error::InvalidLength)?;
+                let rho = matrix_seed_of(first);
+                for pk in recipients {
+                    if matrix_seed_of(pk) != rho {
+                        return Err(Error::MatrixSeedMismatch);
+                    }
+                    if !check_public_key::<K>(pk.as_bytes()) {
+                        return Err(Error::InvalidPublicKey);
+                    }
+                }
+
+                let pks: Vec<&[u8]> = recipients.iter().map(|pk| pk.as_bytes()).collect();
+                let mut ct_u = [0u8; SHARED_CIPHERTEXT_BYTES];
+                let mut ct_v = vec![0u8; recipients.len() * RECIPIENT_CIPHERTEXT_BYTES];
+                let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
+                super::group_encaps::<K, _>(rng, &pks, &mut ss, &mut ct_u, &mut ct_v);
+
+                let vs = ct_v
+                    .chunks_exact(RECIPIENT_CIPHERTEXT_BYTES)
+                    .map(|v| RecipientCiphertext(v.try_into().unwrap()))
+                    .collect();
+                Ok((SharedCiphertext(ct_u), vs, SharedSecret::from(ss)))
+            }
+
+            pub fn decaps(sk: &SecretKey, shared: &SharedCiphertext, own: &RecipientCiphertext) -> SharedSecret {
+                let mut ss = [0u8; kyber_params::SHARED_SECRET_BYTES];
+                super::group_decaps::<K>(sk.as_bytes(), &shared.0, &own.0, &mut ss);
+                SharedSecret::from(ss)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+mkem_level!(kyber512, "ML-KEM-512");
+#[cfg(feature = "kyber768")]
+mkem_level!(kyber768, "ML-KEM-768");
+#[cfg(feature = "kyber1024")]
+mkem_level!(kyber1024, "ML-KEM-1024");
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{
+    decaps, encaps, keypair, matrix_seed_of, RecipientCiphertext, SharedCiphertext, RECIPIENT_CIPHERTEXT_BYTES,
+    SHARED_CIPHERTEXT_BYTES,
+};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{
+    decaps, encaps, keypair, matrix_seed_of, RecipientCiphertext, SharedCiphertext, RECIPIENT_CIPHERTEXT_BYTES,
+    SHARED_CIPHERTEXT_BYTES,
+};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{
+    decaps, encaps, keypair, matrix_seed_of, RecipientCiphertext, SharedCiphertext, RECIPIENT_CIPHERTEXT_BYTES,
+    SHARED_CIPHERTEXT_BYTES,
+};
@@ -1,22 +1,53 @@
 use crate::params::{N, Q, Q_INV};
 
-// Precomputed tables for NTT
-const ZETAS: [i16; 128] = [
+// Powers of the 256th root of unity 17 in Montgomery form, in bit-reversed order
+pub const ZETAS: [i16; 128] = [
     -1044, -758, -359, -1517, 1493, 1422, 287, 202, -171, 622, 1577, 182, 962,
-    -250, 1120, -1164, -1182, -1530, -1278, 794, -1510, -854, -870, 478, -1036,
-    -658, -23, -1238, -1255, -430, -555, 1222, 1632, -122, -265, -1549, -143,
-    -1698, -1321, -1158, -1333, -731, -1141, -1590, 816, -114, -1343, -1289,
-    -1563, -338, -1163, -1176, -1619, -1110, 298, -1274, -1428, -884, -1421,
-    -1304, -496, -1607, -1527, -918, 1199, -1067, -1263, -310, -1692, -1218,
-    -1299, -1254, -1474, 446, -1445, -1398, -1345, 1223, 1479, -1021, 619,
-    -149, -1499, -1324, 401, -1208, -78, -561, -1580, -1384, -26, -1653, -219,
-    -1432, 1424, -1139, -1466, -1353, -1043, 1505, -785, -1292, -1472,
-    -1318, -1363, -1423, -1342, 1481, -1451, 1069, -1532, -1103, -1375, -28,
-    -1272, -113, -1503, -1393, -1172, 1494, -1579, -586, -1341, -1469, -1628,
-    -1242, -1491, -1455,
+    -1202, -1474, 1468, 573, -1325, 264, 383, -829, 1458, -1602, -130, -681,
+    1017, 732, 608, -1542, 411, -205, -1571, 1223, 652, -552, 1015, -1293,
+    1491, -282, -1544, 516, -8, -320, -666, -1618, -1162, 126, 1469, -853, -90,
+    -271, 830, 107, -1421, -247, -951, -398, 961, -1508, -725, 448, -1065, 677,
+    -1275, -1103, 430, 555, 843, -1251, 871, 1550, 105, 422, 587, 177, -235,
+    -291, -460, 1574, 1653, -246, 778, 1159, -147, -777, 1483, -602, 1119,
+    -1590, 644, -872, 349, 418, 329, -156, -75, 817, 1097, 603, 610, 1322,
+    -1285, -1465, 384, -1215, -136, 1218, -1335, -874, 220, -1187, -1659,
+    -1185, -1530, -1278, 794, -1510, -854, -870, 478, -108, -308, 996, 991,
+    958, -1460, 1522, 1628,
 ];
 
-// NTT forward transformation
+// 2^32 mod q, used to move a Montgomery product back to the normal domain
+const MONT_SQ: i16 = 1353;
+
+// 2^16 / 128 mod q, undoes the factor 128 left by the inverse butterflies
+const INV_NTT_SCALE: i16 = 512;
+
+// Montgomery reduction: for a in [-q * 2^15, q * 2^15), returns a * 2^-16 mod q in (-q, q)
+pub fn montgomery_reduce(a: i32) -> i16 {
+    let t = (a as i16).wrapping_mul(Q_INV as i16);
+    ((a - t as i32 * Q) >> 16) as i16
+}
+
+// Barrett reduction: returns the centered representative of a mod q in [-(q-1)/2, (q-1)/2]
+pub fn barrett_reduce(a: i16) -> i16 {
+    const V: i32 = ((1 << 26) + Q / 2) / Q;
+    let t = ((V * a as i32 + (1 << 25)) >> 26) as i16;
+    // t * q leaves the i16 range for |a| close to 2^15, but the difference
+    // always fits, so wrapping arithmetic gives the exact result
+    a.wrapping_sub(t.wrapping_mul(Q as i16))
+}
+
+// Maps a value in (-q, q) to its standard representative in [0, q)
+pub fn caddq(a: i16) -> i16 {
+    a + ((a >> 15) & Q as i16)
+}
+
+// Multiplication followed by Montgomery reduction: a * b * 2^-16 mod q
+pub fn fqmul(a: i16, b: i16) -> i16 {
+    montgomery_reduce(a as i32 * b as i32)
+}
+
+// NTT forward transformation; input coefficients must lie in (-q, q),
+// output coefficients lie in (-8q, 8q) in bit-reversed order
 pub fn ntt(p: &mut [i16; N]) {
     let mut k = 1;
     let mut len = 128;
@@ -26,9 +57,9 @@ pub fn ntt(p: &mut [i16; N]) {
             let zeta = ZETAS[k];
             k += 1;
             for j in start..start + len {
-                let t = (zeta as i32 * p[j + len] as i32) % Q as i32;
-                p[j + len] = (p[j] as i32 - t + Q as i32) as i16 % Q as i16;
-                p[j] = (p[j] as i32 + t) as i16 % Q as i16;
+                let t = fqmul(zeta, p[j + len]);
+                p[j + len] = p[j] - t;
+                p[j] += t;
             }
             start += 2 * len;
         }
@@ -36,7 +67,8 @@ pub fn ntt(p: &mut [i16; N]) {
     }
 }
 
-// NTT inverse transformation
+// NTT inverse transformation; input coefficients must lie in (-q, q),
+// output coefficients lie in (-q, q) in standard order
 pub fn inv_ntt(p: &mut [i16; N]) {
     let mut k = 127;
     let mut len = 2;
@@ -47,16 +79,27 @@ pub fn inv_ntt(p: &mut [i16; N]) {
             k -= 1;
             for j in start..start + len {
                 let t = p[j];
-                p[j] = (t + p[j + len]) % Q as i16;
-                p[j + len] = (t - p[j + len] + Q as i16) % Q as i16;
-                p[j + len] = ((zeta as i32 * p[j + len] as i32) % Q as i32) as i16;
+                p[j] = barrett_reduce(t + p[j + len]);
+                p[j + len] = fqmul(zeta, p[j + len] - t);
             }
             start += 2 * len;
         }
         len <<= 1;
     }
 
-    for i in 0..N {
-        p[i] = ((p[i] as i32 * Q_INV as i32) % Q as i32) as i16;
+    for c in p.iter_mut() {
+        *c = fqmul(*c, INV_NTT_SCALE);
     }
 }
+
+// Multiplication of two degree-one polynomials in Z_q[X]/(X^2 - zeta);
+// the result carries a factor 2^-16 from the Montgomery reductions
+pub fn basemul(r: &mut [i16], a: &[i16], b: &[i16], zeta: i16) {
+    r[0] = fqmul(fqmul(a[1], b[1]), zeta) + fqmul(a[0], b[0]);
+    r[1] = fqmul(a[0], b[1]) + fqmul(a[1], b[0]);
+}
+
+// Multiplies by 2^32 mod q under Montgomery reduction, i.e. by 2^16 mod q
+pub fn to_mont(a: i16) -> i16 {
+    fqmul(a, MONT_SQ)
+}
@@ -0,0 +1,724 @@
+//! OpenPGP (RFC 9580) public-key encryption with the composite ML-KEM + ECDH
+//! algorithms of RFC 9980:
+//!
+//! | ID   | Algorithm           | Public key material | Secret key material |
+//! |------|---------------------|---------------------|---------------------|
+//! | `35` | ML-KEM-768+X25519   | 32 + 1184 bytes     | 32 + 64 bytes       |
+//! | `36` | ML-KEM-1024+X448    | 56 + 1568 bytes     | 56 + 64 bytes       |
+//!
+//! Key material is the ECDH key followed by the ML-KEM key, where the ML-KEM
+//! secret is the 64-byte seed `d || z`. The session key is wrapped with
+//! AES-256 Key Wrap (RFC 3394) under
+//!
+//! ```text
+//! KEK = SHA3-256(mlkemKeyShare || ecdhKeyShare || ecdhCipherText ||
+//!                ecdhPublicKey || algId || "OpenPGPCompositeKDFv1" || 21)
+//! ```
+//!
+//! and travels in a Public-Key Encrypted Session Key packet (PKESK) with
+//! the ECDH and ML-KEM ciphertexts. Both v3 packets, to a key ID with the
+//! symmetric algorithm in the clear, and v6 packets, to a fingerprint or to
+//! no one in particular, are read and written.
+//!
+//! The module stops at the session key. Its packet framing is enough to
+//! find the PKESKs of a message and the encryption subkey of a transferable
+//! secret key; decrypting the SEIPD packet that follows is left to an
+//! OpenPGP implementation. Secret keys must be stored without a passphrase
+//! (S2K usage octet 0).
+
+use crate::error::Error;
+use aes_kw::KekAes256;
+use alloc::vec;
+use alloc::vec::Vec;
+use sha3::{Digest, Sha3_256};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// Packet tags.
+pub const TAG_PKESK: u8 = 1;
+pub const TAG_SECRET_KEY: u8 = 5;
+pub const TAG_PUBLIC_KEY: u8 = 6;
+pub const TAG_SECRET_SUBKEY: u8 = 7;
+pub const TAG_PUBLIC_SUBKEY: u8 = 14;
+
+/// The `domSep` input of the combiner.
+pub const DOMAIN_SEPARATOR: &[u8] = b"OpenPGPCompositeKDFv1";
+
+// Fingerprint lengths of v4 and v6 keys
+const V4_FINGERPRINT_BYTES: usize = 20;
+const V6_FINGERPRINT_BYTES: usize = 32;
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::InvalidEncoding);
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        core::mem::take(&mut self.0)
+    }
+
+    fn finish(&self) -> Result<(), Error> {
+        if !self.0.is_empty() {
+            return Err(Error::InvalidEncoding);
+        }
+        Ok(())
+    }
+}
+
+/// A packet without its header. The body is zeroized on drop, since it may
+/// be a secret key.
+#[derive(Clone, Debug, PartialEq, Eq, Zeroize, ZeroizeOnDrop)]
+pub struct Packet {
+    pub tag: u8,
+    pub body: Vec<u8>,
+}
+
+impl Packet {
+    /// The packet with an OpenPGP (new format) header and a definite length.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = self.body.len();
+        let mut out = Vec::with_capacity(len + 6);
+        out.push(0xc0 | self.tag);
+        if len < 192 {
+            out.push(len as u8);
+        } else if len < 8384 {
+            out.push((((len - 192) >> 8) + 192) as u8);
+            out.push((len - 192) as u8);
+        } else {
+            out.push(0xff);
+            out.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+        out.extend_from_slice(&self.body);
+        out
+    }
+}
+
+/// Splits binary (not ASCII-armored) OpenPGP data into its packets. Reads
+/// both header formats, and joins partial body lengths.
+pub fn packets(data: &[u8]) -> Result<Vec<Packet>, Error> {
+    let mut r = Reader(data);
+    let mut packets = Vec::new();
+    while !r.0.is_empty() {
+        let ctb = r.byte()?;
+        if ctb & 0x80 == 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let mut body = Vec::new();
+        let tag = if ctb & 0x40 != 0 {
+            loop {
+                let len = match r.byte()? {
+                    len @ 0..=191 => len as usize,
+                    len @ 192..=223 => ((len as usize - 192) << 8) + r.byte()? as usize + 192,
+                    255 => r.u32()? as usize,
+                    partial => {
+                        body.extend_from_slice(r.take(1 << (partial & 0x1f))?);
+                        continue;
+                    }
+                };
+                body.extend_from_slice(r.take(len)?);
+                break;
+            }
+            ctb & 0x3f
+        } else {
+            let len = match ctb & 0x03 {
+                0 => r.byte()? as usize,
+                1 => u16::from_be_bytes(r.take(2)?.try_into().unwrap()) as usize,
+                2 => r.u32()? as usize,
+                _ => r.0.len(),
+            };
+            body.extend_from_slice(r.take(len)?);
+            (ctb >> 2) & 0x0f
+        };
+        packets.push(Packet { tag, body });
+    }
+    Ok(packets)
+}
+
+// Public key material lengths, which a v4 secret key packet does not give
+fn public_key_material_bytes(algorithm: u8) -> Option<usize> {
+    match algorithm {
+        mlkem768_x25519::ALGORITHM => Some(mlkem768_x25519::PUBLIC_KEY_BYTES),
+        mlkem1024_x448::ALGORITHM => Some(mlkem1024_x448::PUBLIC_KEY_BYTES),
+        _ => None,
+    }
+}
+
+// ECDH and ML-KEM ciphertext lengths in a PKESK
+fn ciphertext_bytes(algorithm: u8) -> Option<(usize, usize)> {
+    match algorithm {
+        mlkem768_x25519::ALGORITHM => Some((mlkem768_x25519::ECDH_BYTES, mlkem768_x25519::MLKEM_CIPHERTEXT_BYTES)),
+        mlkem1024_x448::ALGORITHM => Some((mlkem1024_x448::ECDH_BYTES, mlkem1024_x448::MLKEM_CIPHERTEXT_BYTES)),
+        _ => None,
+    }
+}
+
+/// The public part of a v4 or v6 key or subkey packet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKeyPacket {
+    pub version: u8,
+    /// Creation time, in seconds since the epoch.
+    pub created: u32,
+    pub algorithm: u8,
+    pub key_material: Vec<u8>,
+}
+
+impl PublicKeyPacket {
+    /// Reads the body of a public key or public subkey packet, of any
+    /// algorithm. Fails with [`Error::UnsupportedAlgorithm`] for a key
+    /// version other than 4 and 6.
+    pub fn from_body(body: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(body);
+        let packet = Self::read(&mut r, false)?;
+        r.finish()?;
+        Ok(packet)
+    }
+
+    // In a secret key packet the secret part follows, so a v4 key has to
+    // be of an algorithm whose key material length is known here
+    fn read(r: &mut Reader, secret: bool) -> Result<Self, Error> {
+        let version = r.byte()?;
+        let created = r.u32()?;
+        let algorithm = r.byte()?;
+        let key_material = match version {
+            4 if secret => r.take(public_key_material_bytes(algorithm).ok_or(Error::UnsupportedAlgorithm)?)?,
+            4 => r.rest(),
+            6 => {
+                let len = r.u32()? as usize;
+                r.take(len)?
+            }
+            _ => return Err(Error::UnsupportedAlgorithm),
+        };
+        Ok(PublicKeyPacket {
+            version,
+            created,
+            algorithm,
+            key_material: key_material.to_vec(),
+        })
+    }
+
+    pub fn to_body(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.key_material.len() + 10);
+        out.push(self.version);
+        out.extend_from_slice(&self.created.to_be_bytes());
+        out.push(self.algorithm);
+        if self.version == 6 {
+            out.extend_from_slice(&(self.key_material.len() as u32).to_be_bytes());
+        }
+        out.extend_from_slice(&self.key_material);
+        out
+    }
+
+    /// SHA-1 of the body for a v4 key, SHA-256 for a v6 key.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        let body = self.to_body();
+        if self.version == 4 {
+            let mut hasher = sha1::Sha1::new();
+            hasher.update([0x99]);
+            hasher.update((body.len() as u16).to_be_bytes());
+            hasher.update(&body);
+            hasher.finalize().to_vec()
+        } else {
+            let mut hasher = sha2::Sha256::new();
+            hasher.update([0x9b]);
+            hasher.update((body.len() as u32).to_be_bytes());
+            hasher.update(&body);
+            hasher.finalize().to_vec()
+        }
+    }
+
+    /// The last eight bytes of a v4 fingerprint, the first eight of a v6
+    /// one.
+    pub fn key_id(&self) -> [u8; 8] {
+        let fingerprint = self.fingerprint();
+        let id = if self.version == 4 {
+            &fingerprint[fingerprint.len() - 8..]
+        } else {
+            &fingerprint[..8]
+        };
+        id.try_into().unwrap()
+    }
+}
+
+/// An unprotected secret key or secret subkey packet.
+#[derive(Clone)]
+pub struct SecretKeyPacket {
+    pub public: PublicKeyPacket,
+    pub secret_key_material: Zeroizing<Vec<u8>>,
+}
+
+impl SecretKeyPacket {
+    /// Reads the body of a secret key or secret subkey packet. Fails with
+    /// [`Error::UnsupportedAlgorithm`] for a key protected with a
+    /// passphrase and for a v4 key of an algorithm not in this module, and
+    /// with [`Error::InvalidEncoding`] when the checksum of a v4 key is
+    /// wrong.
+    pub fn from_body(body: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(body);
+        let public = PublicKeyPacket::read(&mut r, true)?;
+        if r.byte()? != 0 {
+            return Err(Error::UnsupportedAlgorithm);
+        }
+        let secret_key_material = if public.version == 4 {
+            let rest = r.rest();
+            if rest.len() < 2 {
+                return Err(Error::InvalidEncoding);
+            }
+            let (material, checksum) = rest.split_at(rest.len() - 2);
+            if u16::from_be_bytes(checksum.try_into().unwrap()) != Self::checksum(material) {
+                return Err(Error::InvalidEncoding);
+            }
+            material
+        } else {
+            r.rest()
+        };
+        Ok(SecretKeyPacket {
+            public,
+            secret_key_material: Zeroizing::new(secret_key_material.to_vec()),
+        })
+    }
+
+    pub fn to_body(&self) -> Zeroizing<Vec<u8>> {
+        let mut out = Zeroizing::new(self.public.to_body());
+        out.push(0);
+        out.extend_from_slice(&self.secret_key_material);
+        if self.public.version == 4 {
+            out.extend_from_slice(&Self::checksum(&self.secret_key_material).to_be_bytes());
+        }
+        out
+    }
+
+    // The sum of the octets modulo 65536
+    fn checksum(material: &[u8]) -> u16 {
+        material.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16))
+    }
+}
+
+/// Who a PKESK is for. A v3 packet gives a key ID, all zero when the
+/// recipient is hidden; a v6 packet gives the key version and fingerprint,
+/// or nothing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Recipient {
+    KeyId([u8; 8]),
+    Fingerprint { version: u8, fingerprint: Vec<u8> },
+    Anonymous,
+}
+
+impl Recipient {
+    /// The v6 recipient for `key`.
+    pub fn fingerprint(key: &PublicKeyPacket) -> Self {
+        Recipient::Fingerprint {
+            version: key.version,
+            fingerprint: key.fingerprint(),
+        }
+    }
+
+    /// The v3 recipient for `key`.
+    pub fn key_id(key: &PublicKeyPacket) -> Self {
+        Recipient::KeyId(key.key_id())
+    }
+
+    /// Whether this may be `key`. A hidden recipient may be any key.
+    pub fn matches(&self, key: &PublicKeyPacket) -> bool {
+        match self {
+            Recipient::KeyId(id) => *id == [0; 8] || *id == key.key_id(),
+            Recipient::Fingerprint { version, fingerprint } => {
+                *version == key.version && *fingerprint == key.fingerprint()
+            }
+            Recipient::Anonymous => true,
+        }
+    }
+}
+
+/// A Public-Key Encrypted Session Key packet for one of the composite
+/// algorithms. The version follows from the recipient: 3 for a key ID and
+/// 6 otherwise.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pkesk {
+    pub recipient: Recipient,
+    pub algorithm: u8,
+    pub ecdh_ciphertext: Vec<u8>,
+    pub mlkem_ciphertext: Vec<u8>,
+    /// The symmetric algorithm of the message, which a v3 packet gives in
+    /// the clear. `None` in a v6 packet.
+    pub symmetric_algorithm: Option<u8>,
+    pub wrapped_key: Vec<u8>,
+}
+
+impl Pkesk {
+    pub fn version(&self) -> u8 {
+        match self.recipient {
+            Recipient::KeyId(_) => 3,
+            _ => 6,
+        }
+    }
+
+    /// Reads a PKESK body. Fails with [`Error::UnsupportedAlgorithm`] for
+    /// a packet of another public-key algorithm, which a caller going
+    /// through the PKESKs of a message can skip.
+    pub fn from_body(body: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader(body);
+        let version = r.byte()?;
+        let recipient = match version {
+            3 => Recipient::KeyId(r.take(8)?.try_into().unwrap()),
+            6 => match r.byte()? {
+                0 => Recipient::Anonymous,
+                len => {
+                    let version = r.byte()?;
+                    let fingerprint = r.take(len as usize - 1)?.to_vec();
+                    match (version, fingerprint.len()) {
+                        (4, V4_FINGERPRINT_BYTES) | (6, V6_FINGERPRINT_BYTES) => {}
+                        _ => return Err(Error::InvalidEncoding),
+                    }
+                    Recipient::Fingerprint { version, fingerprint }
+                }
+            },
+            _ => return Err(Error::InvalidHeader),
+        };
+        let algorithm = r.byte()?;
+        let (ecdh_bytes, mlkem_bytes) = ciphertext_bytes(algorithm).ok_or(Error::UnsupportedAlgorithm)?;
+        let ecdh_ciphertext = r.take(ecdh_bytes)?.to_vec();
+        let mlkem_ciphertext = r.take(mlkem_bytes)?.to_vec();
+        let len = r.byte()? as usize;
+        let symmetric_algorithm = if version == 3 { Some(r.byte()?) } else { None };
+        let wrapped_len = len
+            .checked_sub(symmetric_algorithm.is_some() as usize)
+            .ok_or(Error::InvalidEncoding)?;
+        if wrapped_len == 0 {
+            return Err(Error::InvalidEncoding);
+        }
+        let wrapped_key = r.take(wrapped_len)?.to_vec();
+        r.finish()?;
+        Ok(Pkesk {
+            recipient,
+            algorithm,
+            ecdh_ciphertext,
+            mlkem_ciphertext,
+            symmetric_algorithm,
+            wrapped_key,
+        })
+    }
+
+    /// Fails with [`Error::InvalidParameters`] when the symmetric algorithm
+    /// is missing from a v3 packet or given for a v6 one, and with
+    /// [`Error::InvalidLength`] when a field does not fit the algorithm.
+    pub fn to_body(&self) -> Result<Vec<u8>, Error> {
+        if (self.version() == 3) != self.symmetric_algorithm.is_some() {
+            return Err(Error::InvalidParameters);
+        }
+        let (ecdh_bytes, mlkem_bytes) = ciphertext_bytes(self.algorithm).ok_or(Error::UnsupportedAlgorithm)?;
+        let len = self.wrapped_key.len() + self.symmetric_algorithm.is_some() as usize;
+        if self.ecdh_ciphertext.len() != ecdh_bytes
+            || self.mlkem_ciphertext.len() != mlkem_bytes
+            || self.wrapped_key.is_empty()
+            || len > 255
+        {
+            return Err(Error::InvalidLength);
+        }
+
+        let mut out = Vec::with_capacity(ecdh_bytes + mlkem_bytes + len + 44);
+        out.push(self.version());
+        match &self.recipient {
+            Recipient::KeyId(id) => out.extend_from_slice(id),
+            Recipient::Fingerprint { version, fingerprint } => {
+                if fingerprint.len() >= 255 {
+                    return Err(Error::InvalidLength);
+                }
+                out.push(fingerprint.len() as u8 + 1);
+                out.push(*version);
+                out.extend_from_slice(fingerprint);
+            }
+            Recipient::Anonymous => out.push(0),
+        }
+        out.push(self.algorithm);
+        out.extend_from_slice(&self.ecdh_ciphertext);
+        out.extend_from_slice(&self.mlkem_ciphertext);
+        out.push(len as u8);
+        out.extend(self.symmetric_algorithm);
+        out.extend_from_slice(&self.wrapped_key);
+        Ok(out)
+    }
+
+    pub fn to_packet(&self) -> Result<Packet, Error> {
+        Ok(Packet {
+            tag: TAG_PKESK,
+            body: self.to_body()?,
+        })
+    }
+}
+
+/// The `multiKeyCombine` function of RFC 9980, which gives the key that
+/// wraps the session key.
+pub fn multi_key_combine(
+    mlkem_key_share: &[u8],
+    ecdh_key_share: &[u8],
+    ecdh_ciphertext: &[u8],
+    ecdh_public_key: &[u8],
+    algorithm: u8,
+) -> Zeroizing<[u8; 32]> {
+    let mut hasher = Sha3_256::new();
+    hasher.update(mlkem_key_share);
+    hasher.update(ecdh_key_share);
+    hasher.update(ecdh_ciphertext);
+    hasher.update(ecdh_public_key);
+    hasher.update([algorithm]);
+    hasher.update(DOMAIN_SEPARATOR);
+    hasher.update([DOMAIN_SEPARATOR.len() as u8]);
+    Zeroizing::new(hasher.finalize().into())
+}
+
+fn wrap_key(kek: &[u8; 32], session_key: &[u8]) -> Result<Vec<u8>, Error> {
+    if session_key.len() < 16 || !session_key.len().is_multiple_of(8) {
+        return Err(Error::InvalidLength);
+    }
+    let mut out = vec![0u8; session_key.len() + 8];
+    KekAes256::from(*kek)
+        .wrap(session_key, &mut out)
+        .map_err(|_| Error::InvalidLength)?;
+    Ok(out)
+}
+
+fn unwrap_key(kek: &[u8; 32], wrapped: &[u8]) -> Result<Zeroizing<Vec<u8>>, Error> {
+    if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+        return Err(Error::InvalidLength);
+    }
+    let mut session_key = Zeroizing::new(vec![0u8; wrapped.len() - 8]);
+    KekAes256::from(*kek)
+        .unwrap(wrapped, &mut session_key)
+        .map_err(|_| Error::AuthenticationFailed)?;
+    Ok(session_key)
+}
+
+macro_rules! composite_algorithm {
+    ($module:ident, $id:expr, $name:expr, $level:ident, $kem:ident, $ecdh:ident, $ecdh_bytes:expr) => {
+        #[doc = concat!($name, ", OpenPGP public-key algorithm ", stringify!($id), ".")]
+        pub mod $module {
+            use super::{
+                multi_key_combine, packets, unwrap_key, wrap_key, Pkesk, PublicKeyPacket, Recipient, SecretKeyPacket,
+                TAG_PKESK,
+            };
+            use crate::ecdh::$ecdh;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{$kem, Ciphertext, SecretSeed};
+            use alloc::vec::Vec;
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+            pub const ALGORITHM: u8 = $id;
+            pub const ECDH_BYTES: usize = $ecdh_bytes;
+            pub const MLKEM_CIPHERTEXT_BYTES: usize = kyber_params::CIPHERTEXT_BYTES;
+            /// The ECDH public key followed by the ML-KEM encapsulation key.
+            pub const PUBLIC_KEY_BYTES: usize = ECDH_BYTES + kyber_params::PUBLIC_KEY_BYTES;
+            /// The ECDH secret key followed by the ML-KEM seed `d || z`.
+            pub const SECRET_KEY_BYTES: usize = ECDH_BYTES + kyber_params::SEED_BYTES;
+
+            /// Public key material.
+            #[derive(Clone, Copy)]
+            pub struct PublicKey([u8; PUBLIC_KEY_BYTES]);
+
+            /// Secret key material. The ML-KEM seed is expanded whenever the
+            /// key is used.
+            #[derive(Clone, Zeroize, ZeroizeOnDrop)]
+            pub struct SecretKey([u8; SECRET_KEY_BYTES]);
+
+            impl From<[u8; PUBLIC_KEY_BYTES]> for PublicKey {
+                fn from(bytes: [u8; PUBLIC_KEY_BYTES]) -> Self {
+                    PublicKey(bytes)
+                }
+            }
+
+            impl PublicKey {
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+
+                /// Fails with [`Error::UnsupportedAlgorithm`] for a key of
+                /// another algorithm.
+                pub fn from_packet(packet: &PublicKeyPacket) -> Result<Self, Error> {
+                    if packet.algorithm != ALGORITHM {
+                        return Err(Error::UnsupportedAlgorithm);
+                    }
+                    let bytes = packet.key_material[..]
+                        .try_into()
+                        .map_err(|_| Error::InvalidLength)?;
+                    Ok(PublicKey(bytes))
+                }
+
+                pub fn to_packet(&self, version: u8, created: u32) -> PublicKeyPacket {
+                    PublicKeyPacket {
+                        version,
+                        created,
+                        algorithm: ALGORITHM,
+                        key_material: self.0.to_vec(),
+                    }
+                }
+            }
+
+            impl From<[u8; SECRET_KEY_BYTES]> for SecretKey {
+                fn from(mut bytes: [u8; SECRET_KEY_BYTES]) -> Self {
+                    let sk = SecretKey(bytes);
+                    bytes.zeroize();
+                    sk
+                }
+            }
+
+            impl SecretKey {
+                pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+                    let (_, ecdh_sk) = $ecdh::keypair(rng);
+                    let seed = SecretSeed::generate(rng);
+                    let mut sk = SecretKey([0u8; SECRET_KEY_BYTES]);
+                    sk.0[..ECDH_BYTES].copy_from_slice(&ecdh_sk[..]);
+                    sk.0[ECDH_BYTES..].copy_from_slice(&seed.to_bytes()[..]);
+                    sk
+                }
+
+                pub fn as_bytes(&self) -> &[u8] {
+                    &self.0
+                }
+
+                pub fn public_key(&self) -> PublicKey {
+                    let mut pk = [0u8; PUBLIC_KEY_BYTES];
+                    pk[..ECDH_BYTES].copy_from_slice(&$ecdh::public_key(&self.ecdh()));
+                    pk[ECDH_BYTES..].copy_from_slice(self.seed().public_key().as_bytes());
+                    PublicKey(pk)
+                }
+
+                /// Fails with [`Error::UnsupportedAlgorithm`] for a key of
+                /// another algorithm, and with [`Error::InconsistentKey`]
+                /// when the secret key material does not belong to the
+                /// public key material.
+                pub fn from_packet(packet: &SecretKeyPacket) -> Result<Self, Error> {
+                    if packet.public.algorithm != ALGORITHM {
+                        return Err(Error::UnsupportedAlgorithm);
+                    }
+                    let bytes: [u8; SECRET_KEY_BYTES] = packet.secret_key_material[..]
+                        .try_into()
+                        .map_err(|_| Error::InvalidLength)?;
+                    let sk = SecretKey::from(bytes);
+                    if sk.public_key().as_bytes() != packet.public.key_material {
+                        return Err(Error::InconsistentKey);
+                    }
+                    Ok(sk)
+                }
+
+                pub fn to_packet(&self, version: u8, created: u32) -> SecretKeyPacket {
+                    SecretKeyPacket {
+                        public: self.public_key().to_packet(version, created),
+                        secret_key_material: Zeroizing::new(self.0.to_vec()),
+                    }
+                }
+
+                fn ecdh(&self) -> Zeroizing<[u8; ECDH_BYTES]> {
+                    Zeroizing::new(self.0[..ECDH_BYTES].try_into().unwrap())
+                }
+
+                fn seed(&self) -> SecretSeed {
+                    SecretSeed::from(<[u8; kyber_params::SEED_BYTES]>::try_from(&self.0[ECDH_BYTES..]).unwrap())
+                }
+            }
+
+            /// Wraps `session_key` for `pk`. A [`Recipient::KeyId`] makes a
+            /// v3 packet, which needs the symmetric algorithm of the
+            /// message; anything else makes a v6 packet, which must not
+            /// have it. Fails with [`Error::InvalidParameters`] otherwise,
+            /// and with [`Error::InvalidLength`] for a session key that is
+            /// not a multiple of 8 bytes of at least 16.
+            pub fn encrypt_session_key<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                recipient: Recipient,
+                symmetric_algorithm: Option<u8>,
+                session_key: &[u8],
+            ) -> Result<Pkesk, Error> {
+                if matches!(recipient, Recipient::KeyId(_)) != symmetric_algorithm.is_some() {
+                    return Err(Error::InvalidParameters);
+                }
+                let ecdh_pk: [u8; ECDH_BYTES] = pk.0[..ECDH_BYTES].try_into().unwrap();
+                let mlkem_pk: [u8; kyber_params::PUBLIC_KEY_BYTES] = pk.0[ECDH_BYTES..].try_into().unwrap();
+
+                let (ecdh_ct, ecdh_ss) = $ecdh::encaps(rng, &ecdh_pk)?;
+                let (mlkem_ct, mut mlkem_ss) = $kem::encaps(rng, &mlkem_pk.into())?;
+                let kek = multi_key_combine(mlkem_ss.as_bytes(), &ecdh_ss[..], &ecdh_ct, &ecdh_pk, ALGORITHM);
+                mlkem_ss.zeroize();
+
+                Ok(Pkesk {
+                    recipient,
+                    algorithm: ALGORITHM,
+                    ecdh_ciphertext: ecdh_ct.to_vec(),
+                    mlkem_ciphertext: mlkem_ct.as_bytes().to_vec(),
+                    symmetric_algorithm,
+                    wrapped_key: wrap_key(&kek, session_key)?,
+                })
+            }
+
+            /// The session key of `pkesk`. Fails with
+            /// [`Error::UnsupportedAlgorithm`] for a packet of another
+            /// algorithm, and with [`Error::AuthenticationFailed`] when the
+            /// key does not unwrap, which includes a packet for another key.
+            pub fn decrypt_session_key(sk: &SecretKey, pkesk: &Pkesk) -> Result<Zeroizing<Vec<u8>>, Error> {
+                if pkesk.algorithm != ALGORITHM {
+                    return Err(Error::UnsupportedAlgorithm);
+                }
+                let ecdh_ct: [u8; ECDH_BYTES] = pkesk.ecdh_ciphertext[..]
+                    .try_into()
+                    .map_err(|_| Error::InvalidLength)?;
+                let mlkem_ct: [u8; kyber_params::CIPHERTEXT_BYTES] = pkesk.mlkem_ciphertext[..]
+                    .try_into()
+                    .map_err(|_| Error::InvalidLength)?;
+
+                let ecdh_sk = sk.ecdh();
+                let ecdh_ss = $ecdh::decaps(&ecdh_sk, &ecdh_ct)?;
+                let (_, mut mlkem_sk) = sk.seed().expand();
+                let mut mlkem_ss = $kem::decaps(&mlkem_sk, &Ciphertext::from(mlkem_ct))?;
+                mlkem_sk.zeroize();
+                let ecdh_pk = $ecdh::public_key(&ecdh_sk);
+                let kek = multi_key_combine(mlkem_ss.as_bytes(), &ecdh_ss[..], &ecdh_ct, &ecdh_pk, ALGORITHM);
+                mlkem_ss.zeroize();
+                unwrap_key(&kek, &pkesk.wrapped_key)
+            }
+
+            /// Finds the session key of a binary OpenPGP message for the
+            /// (sub)key `key`, whose secret is `sk`, trying every PKESK of
+            /// this algorithm that may be for it. Returns the symmetric
+            /// algorithm too when the packet is a v3 one. Fails with
+            /// [`Error::NotRecipient`] if there is no such PKESK, and
+            /// otherwise with the error of the last one tried.
+            pub fn message_session_key(
+                sk: &SecretKey,
+                key: &PublicKeyPacket,
+                message: &[u8],
+            ) -> Result<(Option<u8>, Zeroizing<Vec<u8>>), Error> {
+                let mut result = Err(Error::NotRecipient);
+                for packet in packets(message)?.iter().filter(|packet| packet.tag == TAG_PKESK) {
+                    let pkesk = match Pkesk::from_body(&packet.body) {
+                        Ok(pkesk) if pkesk.algorithm == ALGORITHM && pkesk.recipient.matches(key) => pkesk,
+                        _ => continue,
+                    };
+                    result = decrypt_session_key(sk, &pkesk).map(|key| (pkesk.symmetric_algorithm, key));
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    };
+}
+
+composite_algorithm!(mlkem768_x25519, 35, "ML-KEM-768+X25519", kyber768, MlKem768, X25519, 32);
+composite_algorithm!(mlkem1024_x448, 36, "ML-KEM-1024+X448", kyber1024, MlKem1024, X448, 56);
@@ -3,10 +3,11 @@
 // Common parameters
 pub const N: usize = 256;
 pub const Q: i32 = 3329;
-pub const Q_INV: i32 = 62209; // -1/Q mod 2^16
+pub const Q_INV: i32 = 62209; // 1/Q mod 2^16
 
-// -- Default to Kyber512 parameters, unless feature is set
-#[cfg(feature = "kyber768")]
+// -- Default to Kyber512 parameters, unless feature is set. The highest
+// enabled level wins, matching the top-level re-exports in lib.rs.
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
 pub use kyber768::*;
 #[cfg(feature = "kyber1024")]
 pub use kyber1024::*;
@@ -15,51 +16,105 @@ pub use kyber1024::*;
 #[cfg(all(not(feature = "kyber768"), not(feature = "kyber1024")))]
 pub use kyber512::*;
 
-// Your previous submodules (unchanged)
+// The core (keygen, encaps, decaps, kem) is generic over the module rank K, so
+// any number of levels can be compiled together. Everything else follows from
+// K (FIPS 203, Table 2).
+pub const POLY_BYTES: usize = 384;
+
+pub const fn eta1(k: usize) -> usize {
+    if k == 2 { 3 } else { 2 }
+}
+
+pub const fn eta2(_k: usize) -> usize {
+    2
+}
+
+pub const fn du(k: usize) -> usize {
+    if k == 4 { 11 } else { 10 }
+}
+
+pub const fn dv(k: usize) -> usize {
+    if k == 4 { 5 } else { 4 }
+}
+
+pub const fn poly_vec_bytes(k: usize) -> usize {
+    k * POLY_BYTES
+}
+
+pub const fn poly_vec_compressed_bytes(k: usize) -> usize {
+    k * 32 * du(k)
+}
+
+pub const fn indcpa_secret_key_bytes(k: usize) -> usize {
+    poly_vec_bytes(k)
+}
+
+pub const fn public_key_bytes(k: usize) -> usize {
+    poly_vec_bytes(k) + 32
+}
+
+pub const fn secret_key_bytes(k: usize) -> usize {
+    indcpa_secret_key_bytes(k) + public_key_bytes(k) + 64
+}
+
+pub const fn ciphertext_bytes(k: usize) -> usize {
+    poly_vec_compressed_bytes(k) + 32 * dv(k)
+}
+
+// Scratch sizes for buffers that cannot be sized by K on stable Rust
+pub const MAX_ETA: usize = 3;
+pub const MAX_CIPHERTEXT_BYTES: usize = ciphertext_bytes(4);
+
 pub mod kyber512 {
     pub const K: usize = 2;
-    pub const ETA1: i32 = 3;
-    pub const ETA2: i32 = 2;
+    pub const ETA1: usize = 3;
+    pub const ETA2: usize = 2;
     pub const DU: usize = 10;
     pub const DV: usize = 4;
     pub const POLY_BYTES: usize = 384;
     pub const POLY_VEC_BYTES: usize = K * POLY_BYTES;
     pub const POLY_COMPRESSED_BYTES: usize = 128;
     pub const POLY_VEC_COMPRESSED_BYTES: usize = K * 320;
-    pub const SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
+    pub const INDCPA_SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
     pub const PUBLIC_KEY_BYTES: usize = POLY_VEC_BYTES + 32;
+    pub const SECRET_KEY_BYTES: usize = INDCPA_SECRET_KEY_BYTES + PUBLIC_KEY_BYTES + 64;
     pub const CIPHERTEXT_BYTES: usize = POLY_VEC_COMPRESSED_BYTES + POLY_COMPRESSED_BYTES;
     pub const SHARED_SECRET_BYTES: usize = 32;
+    pub const SEED_BYTES: usize = 64;
 }
 
 pub mod kyber768 {
     pub const K: usize = 3;
-    pub const ETA1: i32 = 2;
-    pub const ETA2: i32 = 2;
+    pub const ETA1: usize = 2;
+    pub const ETA2: usize = 2;
     pub const DU: usize = 10;
     pub const DV: usize = 4;
     pub const POLY_BYTES: usize = 384;
     pub const POLY_VEC_BYTES: usize = K * POLY_BYTES;
     pub const POLY_COMPRESSED_BYTES: usize = 128;
     pub const POLY_VEC_COMPRESSED_BYTES: usize = K * 320;
-    pub const SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
+    pub const INDCPA_SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
     pub const PUBLIC_KEY_BYTES: usize = POLY_VEC_BYTES + 32;
+    pub const SECRET_KEY_BYTES: usize = INDCPA_SECRET_KEY_BYTES + PUBLIC_KEY_BYTES + 64;
     pub const CIPHERTEXT_BYTES: usize = POLY_VEC_COMPRESSED_BYTES + POLY_COMPRESSED_BYTES;
     pub const SHARED_SECRET_BYTES: usize = 32;
+    pub const SEED_BYTES: usize = 64;
 }
 
 pub mod kyber1024 {
     pub const K: usize = 4;
-    pub const ETA1: i32 = 2;
-    pub const ETA2: i32 = 2;
+    pub const ETA1: usize = 2;
+    pub const ETA2: usize = 2;
     pub const DU: usize = 11;
     pub const DV: usize = 5;
     pub const POLY_BYTES: usize = 384;
     pub const POLY_VEC_BYTES: usize = K * POLY_BYTES;
     pub const POLY_COMPRESSED_BYTES: usize = 160;
     pub const POLY_VEC_COMPRESSED_BYTES: usize = K * 352;
-    pub const SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
+    pub const INDCPA_SECRET_KEY_BYTES: usize = POLY_VEC_BYTES;
     pub const PUBLIC_KEY_BYTES: usize = POLY_VEC_BYTES + 32;
+    pub const SECRET_KEY_BYTES: usize = INDCPA_SECRET_KEY_BYTES + PUBLIC_KEY_BYTES + 64;
     pub const CIPHERTEXT_BYTES: usize = POLY_VEC_COMPRESSED_BYTES + POLY_COMPRESSED_BYTES;
     pub const SHARED_SECRET_BYTES: usize = 32;
+    pub const SEED_BYTES: usize = 64;
 }
@@ -0,0 +1,552 @@
+//! Password-encrypted secret keys, for keeping keys on disk.
+//!
+//! Two formats are offered. PKCS#8 `EncryptedPrivateKeyInfo` (RFC 5958) with
+//! PBES2 (RFC 8018) wraps the PKCS#8 encoding of [`crate::der`] and is what
+//! OpenSSL reads and writes (`openssl pkcs8 -topk8`), with PBKDF2-HMAC-SHA256
+//! or scrypt (RFC 7914) and AES-256-CBC. AES-256-GCM with the RFC 5084
+//! parameters is supported as well, but OpenSSL refuses AEAD ciphers here.
+//! The algorithm identifiers carry the version, salt and key derivation
+//! costs.
+//!
+//! The crate's own key file is simpler and always authenticated:
+//!
+//! ```text
+//! version (1) || KEM ID (2) || form (1) || KDF ID (1) || memory in KiB (4) || passes (4)
+//!     || lanes (4) || salt (16) || nonce (24) || XChaCha20-Poly1305 ciphertext || tag (16)
+//! ```
+//!
+//! The KEM ID is the one sealed boxes use (`0x0040`, `0x0041`, `0x0042`), the
+//! form is 1 for a seed and 2 for an expanded key, and the payload is the raw
+//! seed or key. The only KDF is Argon2id version 0x13 (ID 1). Integers are
+//! big-endian and the whole header is the associated data.
+//!
+//! Decryption runs the key derivation at the cost recorded in its input, so it
+//! first checks that cost against a [`MaxCost`] and fails with
+//! [`Error::InvalidParameters`] above it. The `from_encrypted_der` and
+//! `from_key_file` methods use [`MaxCost::default`]; the level functions
+//! `private_key_from_encrypted_der` and `private_key_from_key_file` take the
+//! limit from the caller.
+
+use alloc::vec::Vec;
+use crate::der::{
+    algorithm, integer, parse, read_algorithm, read_integer, tlv, Reader, TAG_INTEGER, TAG_OCTET_STRING,
+    TAG_SEQUENCE,
+};
+use crate::error::Error;
+use aes_gcm::aead::{AeadInPlace, KeyInit};
+use aes_gcm::Aes256Gcm;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use chacha20poly1305::XChaCha20Poly1305;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+pub const KEY_FILE_VERSION: u8 = 1;
+pub const KEY_FILE_HEADER_BYTES: usize = 17 + SALT_BYTES + XNONCE_BYTES;
+
+const SALT_BYTES: usize = 16;
+const KEY_BYTES: usize = 32;
+const TAG_BYTES: usize = 16;
+const IV_BYTES: usize = 16;
+const GCM_NONCE_BYTES: usize = 12;
+const XNONCE_BYTES: usize = 24;
+const TAG_NULL: u8 = 0x05;
+
+const KDF_ARGON2ID: u8 = 1;
+const FORM_SEED: u8 = 1;
+const FORM_EXPANDED_KEY: u8 = 2;
+
+// OIDs without tag and length
+const OID_PBES2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0d];
+const OID_PBKDF2: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x05, 0x0c];
+const OID_HMAC_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x02, 0x09];
+const OID_SCRYPT: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x04, 0x0b];
+const OID_AES256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+const OID_AES256_GCM: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2e];
+
+/// The password-based key derivation of a PBES2 encryption.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbes2Kdf {
+    Pbkdf2Sha256 { iterations: u32 },
+    /// scrypt with cost N = 2^`log_n`. OpenSSL reads at most 32 MiB of
+    /// scrypt state, which with `r = 8` means `log_n` of 14 or less.
+    Scrypt { log_n: u8, r: u32, p: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbes2Cipher {
+    Aes256Cbc,
+    /// With a 12-byte nonce and a 16-byte tag.
+    Aes256Gcm,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Pbes2Params {
+    pub kdf: Pbes2Kdf,
+    pub cipher: Pbes2Cipher,
+}
+
+impl Default for Pbes2Params {
+    /// PBKDF2-HMAC-SHA256 with 600,000 iterations and AES-256-CBC, which
+    /// OpenSSL reads.
+    fn default() -> Self {
+        Pbes2Params {
+            kdf: Pbes2Kdf::Pbkdf2Sha256 { iterations: 600_000 },
+            cipher: Pbes2Cipher::Aes256Cbc,
+        }
+    }
+}
+
+/// Argon2id costs for the key file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Argon2Params {
+    pub memory_kib: u32,
+    pub passes: u32,
+    pub lanes: u32,
+}
+
+impl Default for Argon2Params {
+    /// The second recommended option of RFC 9106: 64 MiB, 3 passes and 4
+    /// lanes.
+    fn default() -> Self {
+        Argon2Params { memory_kib: 64 * 1024, passes: 3, lanes: 4 }
+    }
+}
+
+/// The highest key derivation cost decryption accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxCost {
+    /// PBKDF2 iterations.
+    pub iterations: u32,
+    /// Argon2id memory, and the `128 * r * N` bytes of scrypt, in KiB.
+    pub memory_kib: u32,
+    /// Argon2id passes, and scrypt's `p`, which runs its memory-hard loop
+    /// that many times.
+    pub passes: u32,
+}
+
+impl Default for MaxCost {
+    /// 10,000,000 PBKDF2 iterations, 1 GiB and 16 passes: well above the
+    /// defaults here and OpenSSL's, while bounding what a crafted file costs.
+    fn default() -> Self {
+        MaxCost { iterations: 10_000_000, memory_kib: 1024 * 1024, passes: 16 }
+    }
+}
+
+impl MaxCost {
+    fn check_pbes2(&self, kdf: &Pbes2Kdf) -> Result<(), Error> {
+        let within = match *kdf {
+            Pbes2Kdf::Pbkdf2Sha256 { iterations } => iterations <= self.iterations,
+            Pbes2Kdf::Scrypt { log_n, r, p } => {
+                // 128 * r * 2^log_n bytes is r * 2^log_n / 8 KiB
+                let memory_kib = 1u128
+                    .checked_shl(log_n.into())
+                    .and_then(|n| n.checked_mul(r.into()))
+                    .map(|bytes_over_128| bytes_over_128 / 8);
+                memory_kib.is_some_and(|kib| kib <= self.memory_kib.into()) && p <= self.passes
+            }
+        };
+        if !within {
+            return Err(Error::InvalidParameters);
+        }
+        Ok(())
+    }
+
+    fn check_argon2(&self, params: &Argon2Params) -> Result<(), Error> {
+        if params.memory_kib > self.memory_kib || params.passes > self.passes {
+            return Err(Error::InvalidParameters);
+        }
+        Ok(())
+    }
+}
+
+fn pbes2_key(kdf: &Pbes2Kdf, salt: &[u8], password: &[u8]) -> Result<Zeroizing<[u8; KEY_BYTES]>, Error> {
+    let mut key = Zeroizing::new([0u8; KEY_BYTES]);
+    match *kdf {
+        Pbes2Kdf::Pbkdf2Sha256 { iterations: 0 } => return Err(Error::InvalidParameters),
+        Pbes2Kdf::Pbkdf2Sha256 { iterations } => {
+            pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, iterations, &mut key[..])
+        }
+        Pbes2Kdf::Scrypt { log_n, r, p } => {
+            let params = scrypt::Params::new(log_n, r, p, KEY_BYTES).map_err(|_| Error::InvalidParameters)?;
+            scrypt::scrypt(password, salt, &params, &mut key[..]).map_err(|_| Error::InvalidParameters)?;
+        }
+    }
+    Ok(key)
+}
+
+// PBES2-encrypts a PKCS#8 PrivateKeyInfo into an EncryptedPrivateKeyInfo
+fn encrypt_pkcs8<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    password: &[u8],
+    params: &Pbes2Params,
+    private_key_info: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut salt = [0u8; SALT_BYTES];
+    rng.fill_bytes(&mut salt);
+    let key = pbes2_key(&params.kdf, &salt, password)?;
+    let kdf = match params.kdf {
+        Pbes2Kdf::Pbkdf2Sha256 { iterations } => {
+            let prf = algorithm(OID_HMAC_SHA256, &tlv(TAG_NULL, &[]));
+            let salt = tlv(TAG_OCTET_STRING, &[&salt]);
+            algorithm(OID_PBKDF2, &tlv(TAG_SEQUENCE, &[&salt, &integer(iterations.into()), &prf]))
+        }
+        Pbes2Kdf::Scrypt { log_n, r, p } => {
+            let salt = tlv(TAG_OCTET_STRING, &[&salt]);
+            let params = tlv(TAG_SEQUENCE, &[&salt, &integer(1 << log_n), &integer(r.into()), &integer(p.into())]);
+            algorithm(OID_SCRYPT, &params)
+        }
+    };
+
+    // Sized for padding or the tag, so the plaintext is never reallocated
+    let mut buf = Zeroizing::new(Vec::with_capacity(private_key_info.len() + TAG_BYTES));
+    buf.extend_from_slice(private_key_info);
+    let scheme = match params.cipher {
+        Pbes2Cipher::Aes256Cbc => {
+            let mut iv = [0u8; IV_BYTES];
+            rng.fill_bytes(&mut iv);
+            let len = buf.len();
+            buf.resize(len + IV_BYTES - len % IV_BYTES, 0);
+            cbc::Encryptor::<aes::Aes256>::new(&(*key).into(), &iv.into())
+                .encrypt_padded_mut::<Pkcs7>(&mut buf, len)
+                .unwrap();
+            algorithm(OID_AES256_CBC, &tlv(TAG_OCTET_STRING, &[&iv]))
+        }
+        Pbes2Cipher::Aes256Gcm => {
+            let mut nonce = [0u8; GCM_NONCE_BYTES];
+            rng.fill_bytes(&mut nonce);
+            Aes256Gcm::new(&(*key).into()).encrypt_in_place(&nonce.into(), &[], &mut *buf).unwrap();
+            let nonce = tlv(TAG_OCTET_STRING, &[&nonce]);
+            algorithm(OID_AES256_GCM, &tlv(TAG_SEQUENCE, &[&nonce, &integer(TAG_BYTES as u64)]))
+        }
+    };
+    let pbes2 = algorithm(OID_PBES2, &tlv(TAG_SEQUENCE, &[&kdf, &scheme]));
+    Ok(tlv(TAG_SEQUENCE, &[&pbes2, &tlv(TAG_OCTET_STRING, &[&buf])]))
+}
+
+fn read_pbkdf2(params: &[u8]) -> Result<(&[u8], Pbes2Kdf), Error> {
+    let mut params = Reader(parse(params, TAG_SEQUENCE)?);
+    let salt = params.read(TAG_OCTET_STRING)?;
+    let iterations = read_integer(&mut params)?;
+    let key_length = match params.peek() {
+        Some(TAG_INTEGER) => Some(read_integer(&mut params)?),
+        _ => None,
+    };
+    // Without a PRF the default is HMAC-SHA1, which is not supported
+    let prf = match params.peek() {
+        Some(TAG_SEQUENCE) => Some(read_algorithm(&mut params)?),
+        _ => None,
+    };
+    params.finish()?;
+    if key_length.is_some_and(|len| len != KEY_BYTES as u64)
+        || !matches!(prf, Some((OID_HMAC_SHA256, [] | [TAG_NULL, 0])))
+    {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    let iterations = u32::try_from(iterations).map_err(|_| Error::InvalidParameters)?;
+    Ok((salt, Pbes2Kdf::Pbkdf2Sha256 { iterations }))
+}
+
+fn read_scrypt(params: &[u8]) -> Result<(&[u8], Pbes2Kdf), Error> {
+    let mut params = Reader(parse(params, TAG_SEQUENCE)?);
+    let salt = params.read(TAG_OCTET_STRING)?;
+    let n = read_integer(&mut params)?;
+    let r = read_integer(&mut params)?;
+    let p = read_integer(&mut params)?;
+    let key_length = match params.peek() {
+        Some(TAG_INTEGER) => Some(read_integer(&mut params)?),
+        _ => None,
+    };
+    params.finish()?;
+    if key_length.is_some_and(|len| len != KEY_BYTES as u64) {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+    if !n.is_power_of_two() {
+        return Err(Error::InvalidParameters);
+    }
+    let kdf = Pbes2Kdf::Scrypt {
+        log_n: n.trailing_zeros() as u8,
+        r: u32::try_from(r).map_err(|_| Error::InvalidParameters)?,
+        p: u32::try_from(p).map_err(|_| Error::InvalidParameters)?,
+    };
+    Ok((salt, kdf))
+}
+
+// The PrivateKeyInfo inside an EncryptedPrivateKeyInfo. Everything is parsed
+// and the cost checked before the (costly) key derivation runs.
+fn decrypt_pkcs8(der: &[u8], password: &[u8], max_cost: &MaxCost) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let mut info = Reader(parse(der, TAG_SEQUENCE)?);
+    let (scheme, pbes2) = read_algorithm(&mut info)?;
+    let encrypted = info.read(TAG_OCTET_STRING)?;
+    info.finish()?;
+    if scheme != OID_PBES2 {
+        return Err(Error::UnsupportedAlgorithm);
+    }
+
+    let mut pbes2 = Reader(parse(pbes2, TAG_SEQUENCE)?);
+    let (kdf, kdf_params) = read_algorithm(&mut pbes2)?;
+    let (cipher, cipher_params) = read_algorithm(&mut pbes2)?;
+    pbes2.finish()?;
+    let (salt, kdf) = match kdf {
+        OID_PBKDF2 => read_pbkdf2(kdf_params)?,
+        OID_SCRYPT => read_scrypt(kdf_params)?,
+        _ => return Err(Error::UnsupportedAlgorithm),
+    };
+    let (cipher, iv) = match cipher {
+        OID_AES256_CBC => (Pbes2Cipher::Aes256Cbc, parse(cipher_params, TAG_OCTET_STRING)?),
+        OID_AES256_GCM => {
+            let mut params = Reader(parse(cipher_params, TAG_SEQUENCE)?);
+            let nonce = params.read(TAG_OCTET_STRING)?;
+            // The tag length defaults to 12 bytes; only 16 is supported
+            let tag_bytes = match params.peek() {
+                Some(TAG_INTEGER) => read_integer(&mut params)?,
+                _ => 12,
+            };
+            params.finish()?;
+            if tag_bytes != TAG_BYTES as u64 {
+                return Err(Error::UnsupportedAlgorithm);
+            }
+            (Pbes2Cipher::Aes256Gcm, nonce)
+        }
+        _ => return Err(Error::UnsupportedAlgorithm),
+    };
+
+    max_cost.check_pbes2(&kdf)?;
+    let key = pbes2_key(&kdf, salt, password)?;
+    let mut buf = Zeroizing::new(encrypted.to_vec());
+    match cipher {
+        Pbes2Cipher::Aes256Cbc => {
+            let iv: &[u8; IV_BYTES] = iv.try_into().map_err(|_| Error::InvalidLength)?;
+            let len = cbc::Decryptor::<aes::Aes256>::new(&(*key).into(), iv.into())
+                .decrypt_padded_mut::<Pkcs7>(&mut buf)
+                .map_err(|_| Error::AuthenticationFailed)?
+                .len();
+            buf.truncate(len);
+        }
+        Pbes2Cipher::Aes256Gcm => {
+            let nonce: &[u8; GCM_NONCE_BYTES] = iv.try_into().map_err(|_| Error::InvalidLength)?;
+            Aes256Gcm::new(&(*key).into())
+                .decrypt_in_place(nonce.into(), &[], &mut *buf)
+                .map_err(|_| Error::AuthenticationFailed)?;
+        }
+    }
+    Ok(buf)
+}
+
+fn argon2_key(params: &Argon2Params, salt: &[u8], password: &[u8]) -> Result<Zeroizing<[u8; KEY_BYTES]>, Error> {
+    let params = argon2::Params::new(params.memory_kib, params.passes, params.lanes, Some(KEY_BYTES))
+        .map_err(|_| Error::InvalidParameters)?;
+    let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    let mut key = Zeroizing::new([0u8; KEY_BYTES]);
+    argon2
+        .hash_password_into(password, salt, &mut key[..])
+        .map_err(|_| Error::InvalidParameters)?;
+    Ok(key)
+}
+
+fn seal_key_file<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    password: &[u8],
+    params: &Argon2Params,
+    kem_id: u16,
+    form: u8,
+    payload: &[u8],
+) -> Result<Vec<u8>, Error> {
+    let mut header = Vec::with_capacity(KEY_FILE_HEADER_BYTES + payload.len() + TAG_BYTES);
+    header.push(KEY_FILE_VERSION);
+    header.extend_from_slice(&kem_id.to_be_bytes());
+    header.extend_from_slice(&[form, KDF_ARGON2ID]);
+    for cost in [params.memory_kib, params.passes, params.lanes] {
+        header.extend_from_slice(&cost.to_be_bytes());
+    }
+    let mut salt = [0u8; SALT_BYTES];
+    let mut nonce = [0u8; XNONCE_BYTES];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let key = argon2_key(params, &salt, password)?;
+    let mut body = Zeroizing::new(Vec::with_capacity(payload.len() + TAG_BYTES));
+    body.extend_from_slice(payload);
+    XChaCha20Poly1305::new(&(*key).into())
+        .encrypt_in_place(&nonce.into(), &header, &mut *body)
+        .unwrap();
+    header.extend_from_slice(&body);
+    Ok(header)
+}
+
+// The form and payload of a key file for `kem_id`
+fn open_key_file(
+    file: &[u8],
+    password: &[u8],
+    kem_id: u16,
+    max_cost: &MaxCost,
+) -> Result<(u8, Zeroizing<Vec<u8>>), Error> {
+    if file.len() < KEY_FILE_HEADER_BYTES + TAG_BYTES {
+        return Err(Error::InvalidLength);
+    }
+    let (header, body) = file.split_at(KEY_FILE_HEADER_BYTES);
+    let form = header[3];
+    if header[0] != KEY_FILE_VERSION
+        || header[1..3] != kem_id.to_be_bytes()
+        || !matches!(form, FORM_SEED | FORM_EXPANDED_KEY)
+        || header[4] != KDF_ARGON2ID
+    {
+        return Err(Error::InvalidHeader);
+    }
+    let cost = |i: usize| u32::from_be_bytes(header[5 + 4 * i..9 + 4 * i].try_into().unwrap());
+    let params = Argon2Params { memory_kib: cost(0), passes: cost(1), lanes: cost(2) };
+    let salt = &header[17..17 + SALT_BYTES];
+    let nonce: &[u8; XNONCE_BYTES] = header[17 + SALT_BYTES..].try_into().unwrap();
+
+    max_cost.check_argon2(&params)?;
+    let key = argon2_key(&params, salt, password)?;
+    let mut payload = Zeroizing::new(body.to_vec());
+    XChaCha20Poly1305::new(&(*key).into())
+        .decrypt_in_place(nonce.into(), header, &mut *payload)
+        .map_err(|_| Error::AuthenticationFailed)?;
+    Ok((form, payload))
+}
+
+macro_rules! password_level {
+    ($level:ident, $name:expr, $kem_id:expr) => {
+        #[doc = concat!("Password-encrypted ", $name, " secret keys.")]
+        pub mod $level {
+            use super::{
+                decrypt_pkcs8, encrypt_pkcs8, open_key_file, seal_key_file, Argon2Params, MaxCost, Pbes2Params,
+                FORM_EXPANDED_KEY, FORM_SEED,
+            };
+            use crate::der::$level::{check_expanded, private_key_from_der};
+            use crate::der::PrivateKeyForm;
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::$level::{SecretKey, SecretSeed};
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::Zeroize;
+
+            const KEM_ID: u16 = $kem_id;
+
+            impl SecretKey {
+                /// The `expandedKey` PKCS#8 encoding, encrypted under `password`.
+                pub fn to_encrypted_der<R: RngCore + CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    params: &Pbes2Params,
+                ) -> Result<Vec<u8>, Error> {
+                    encrypt_pkcs8(rng, password, params, &self.to_der())
+                }
+
+                /// Reads an encrypted PKCS#8 key in any of the three forms.
+                pub fn from_encrypted_der(der: &[u8], password: &[u8]) -> Result<Self, Error> {
+                    private_key_from_encrypted_der(der, password, &MaxCost::default()).map(|(sk, _)| sk)
+                }
+
+                /// A key file holding the expanded key.
+                pub fn to_key_file<R: RngCore + CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    params: &Argon2Params,
+                ) -> Result<Vec<u8>, Error> {
+                    seal_key_file(rng, password, params, KEM_ID, FORM_EXPANDED_KEY, self.as_bytes())
+                }
+
+                /// Reads a key file holding a seed or an expanded key.
+                pub fn from_key_file(file: &[u8], password: &[u8]) -> Result<Self, Error> {
+                    private_key_from_key_file(file, password, &MaxCost::default()).map(|(sk, _)| sk)
+                }
+            }
+
+            impl SecretSeed {
+                /// The PKCS#8 encoding in the given form, encrypted under
+                /// `password`.
+                pub fn to_encrypted_der<R: RngCore + CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    form: PrivateKeyForm,
+                    params: &Pbes2Params,
+                ) -> Result<Vec<u8>, Error> {
+                    encrypt_pkcs8(rng, password, params, &self.to_der(form))
+                }
+
+                /// Reads an encrypted PKCS#8 key in the `seed` or `both` form.
+                pub fn from_encrypted_der(der: &[u8], password: &[u8]) -> Result<Self, Error> {
+                    let (mut sk, seed) = private_key_from_encrypted_der(der, password, &MaxCost::default())?;
+                    sk.zeroize();
+                    seed.ok_or(Error::InvalidEncoding)
+                }
+
+                pub fn to_key_file<R: RngCore + CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    params: &Argon2Params,
+                ) -> Result<Vec<u8>, Error> {
+                    seal_key_file(rng, password, params, KEM_ID, FORM_SEED, &self.to_bytes()[..])
+                }
+
+                /// Reads a key file holding a seed; one with an expanded key
+                /// fails with [`Error::InvalidEncoding`].
+                pub fn from_key_file(file: &[u8], password: &[u8]) -> Result<Self, Error> {
+                    let (mut sk, seed) = private_key_from_key_file(file, password, &MaxCost::default())?;
+                    sk.zeroize();
+                    seed.ok_or(Error::InvalidEncoding)
+                }
+            }
+
+            /// Decrypts and reads a PKCS#8 `EncryptedPrivateKeyInfo`, see
+            /// [`private_key_from_der`]. Fails with
+            /// [`Error::AuthenticationFailed`] for a wrong password; with
+            /// AES-256-CBC a wrong password can also surface as a malformed
+            /// key. Fails with [`Error::InvalidParameters`] if the key
+            /// derivation costs more than `max_cost`.
+            pub fn private_key_from_encrypted_der(
+                der: &[u8],
+                password: &[u8],
+                max_cost: &MaxCost,
+            ) -> Result<(SecretKey, Option<SecretSeed>), Error> {
+                private_key_from_der(&decrypt_pkcs8(der, password, max_cost)?)
+            }
+
+            /// Decrypts and reads a key file, returning the seed as well when
+            /// the file holds one. Fails with [`Error::InvalidHeader`] for a
+            /// file of another version or level, with
+            /// [`Error::AuthenticationFailed`] for a wrong password, and with
+            /// [`Error::InvalidParameters`] if the Argon2id costs exceed
+            /// `max_cost`.
+            pub fn private_key_from_key_file(
+                file: &[u8],
+                password: &[u8],
+                max_cost: &MaxCost,
+            ) -> Result<(SecretKey, Option<SecretSeed>), Error> {
+                let (form, payload) = open_key_file(file, password, KEM_ID, max_cost)?;
+                if form == FORM_SEED {
+                    let seed: [u8; kyber_params::SEED_BYTES] =
+                        payload[..].try_into().map_err(|_| Error::InvalidLength)?;
+                    let seed = SecretSeed::from(seed);
+                    Ok((seed.expand().1, Some(seed)))
+                } else {
+                    Ok((check_expanded(&payload)?, None))
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+password_level!(kyber512, "ML-KEM-512", 0x0040);
+#[cfg(feature = "kyber768")]
+password_level!(kyber768, "ML-KEM-768", 0x0041);
+#[cfg(feature = "kyber1024")]
+password_level!(kyber1024, "ML-KEM-1024", 0x0042);
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{private_key_from_encrypted_der, private_key_from_key_file};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{private_key_from_encrypted_der, private_key_from_key_file};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{private_key_from_encrypted_der, private_key_from_key_file};
@@ -0,0 +1,260 @@
+//! PEM armor (RFC 7468) for keys and ciphertexts.
+//!
+//! Public keys are `PUBLIC KEY` blocks around the `SubjectPublicKeyInfo` and
+//! secret keys `PRIVATE KEY` blocks around the PKCS#8 encoding from
+//! [`crate::der`], so files written by `openssl genpkey` and `openssl pkey`
+//! load directly and the other way round. Ciphertexts have no standard
+//! encoding; this crate armors the raw ciphertext under a label naming its
+//! level, such as `MLKEM768 CIPHERTEXT`, so a pasted encapsulation says
+//! what it is. (The label drops the hyphens of the algorithm name because
+//! `pem-rfc7468` does not accept them.)
+//!
+//! Each level's types get `to_pem` and `from_pem`, which fail with
+//! [`Error::UnsupportedAlgorithm`] on a key or ciphertext of another level.
+//! When the level is not known in advance, [`public_key_from_pem`],
+//! [`secret_key_from_pem`] and [`ciphertext_from_pem`] detect it from the
+//! key's OID or the ciphertext's label. Output uses `\n` line endings;
+//! input may use `\n` or `\r\n`.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use crate::error::Error;
+use pem_rfc7468::LineEnding;
+use zeroize::Zeroizing;
+
+pub const PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+pub const PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+#[cfg(feature = "password")]
+pub const ENCRYPTED_PRIVATE_KEY_LABEL: &str = "ENCRYPTED PRIVATE KEY";
+
+fn encode(label: &str, der: &[u8]) -> String {
+    pem_rfc7468::encode_string(label, LineEnding::LF, der).expect("PEM label and length are valid")
+}
+
+// Encodes into a buffer of the exact size, so no copy of the secret is left
+// behind by a reallocation
+fn encode_secret(label: &str, der: &[u8]) -> Zeroizing<String> {
+    let len = pem_rfc7468::encoded_len(label, LineEnding::LF, der).expect("PEM label and length are valid");
+    let mut buf = Zeroizing::new(vec![0u8; len]);
+    pem_rfc7468::encode(label, LineEnding::LF, der, &mut buf).expect("buffer has the encoded length");
+    let pem = core::str::from_utf8(&buf).expect("PEM is ASCII");
+    Zeroizing::new(pem.into())
+}
+
+// The label and contents of a PEM block
+fn decode(pem: &str) -> Result<(&str, Zeroizing<Vec<u8>>), Error> {
+    let (label, der) = pem_rfc7468::decode_vec(pem.as_bytes()).map_err(|_| Error::InvalidEncoding)?;
+    Ok((label, Zeroizing::new(der)))
+}
+
+fn decode_expecting(pem: &str, expected: &str) -> Result<Zeroizing<Vec<u8>>, Error> {
+    match decode(pem)? {
+        (label, der) if label == expected => Ok(der),
+        (label, _) if label.ends_with(" CIPHERTEXT") && expected.ends_with(" CIPHERTEXT") => {
+            Err(Error::UnsupportedAlgorithm)
+        }
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+macro_rules! pem_level {
+    ($level:ident, $name:expr, $label:expr) => {
+        #[doc = concat!("PEM armor for ", $name, " keys and ciphertexts.")]
+        pub mod $level {
+            use super::{decode_expecting, encode, encode_secret, PRIVATE_KEY_LABEL, PUBLIC_KEY_LABEL};
+            use alloc::string::String;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::der::PrivateKeyForm;
+            use crate::$level::{Ciphertext, PublicKey, SecretKey, SecretSeed};
+            use zeroize::Zeroizing;
+
+            pub const CIPHERTEXT_LABEL: &str = concat!($label, " CIPHERTEXT");
+
+            impl PublicKey {
+                /// A `PUBLIC KEY` block.
+                pub fn to_pem(&self) -> String {
+                    encode(PUBLIC_KEY_LABEL, &self.to_der())
+                }
+
+                pub fn from_pem(pem: &str) -> Result<Self, Error> {
+                    PublicKey::from_der(&decode_expecting(pem, PUBLIC_KEY_LABEL)?)
+                }
+            }
+
+            impl SecretKey {
+                /// A `PRIVATE KEY` block with the `expandedKey` form.
+                pub fn to_pem(&self) -> Zeroizing<String> {
+                    encode_secret(PRIVATE_KEY_LABEL, &self.to_der())
+                }
+
+                /// Reads a `PRIVATE KEY` block in any of the three PKCS#8 forms.
+                pub fn from_pem(pem: &str) -> Result<Self, Error> {
+                    SecretKey::from_der(&decode_expecting(pem, PRIVATE_KEY_LABEL)?)
+                }
+            }
+
+            impl SecretSeed {
+                /// A `PRIVATE KEY` block with the given PKCS#8 form.
+                pub fn to_pem(&self, form: PrivateKeyForm) -> Zeroizing<String> {
+                    encode_secret(PRIVATE_KEY_LABEL, &self.to_der(form))
+                }
+
+                /// Reads a `PRIVATE KEY` block in the `seed` or `both` form.
+                pub fn from_pem(pem: &str) -> Result<Self, Error> {
+                    SecretSeed::from_der(&decode_expecting(pem, PRIVATE_KEY_LABEL)?)
+                }
+            }
+
+            #[cfg(feature = "password")]
+            impl SecretKey {
+                /// An `ENCRYPTED PRIVATE KEY` block, see [`crate::password`].
+                pub fn to_encrypted_pem<R: rand_core::RngCore + rand_core::CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    params: &crate::password::Pbes2Params,
+                ) -> Result<String, Error> {
+                    Ok(encode(super::ENCRYPTED_PRIVATE_KEY_LABEL, &self.to_encrypted_der(rng, password, params)?))
+                }
+
+                pub fn from_encrypted_pem(pem: &str, password: &[u8]) -> Result<Self, Error> {
+                    SecretKey::from_encrypted_der(&decode_expecting(pem, super::ENCRYPTED_PRIVATE_KEY_LABEL)?, password)
+                }
+            }
+
+            #[cfg(feature = "password")]
+            impl SecretSeed {
+                /// An `ENCRYPTED PRIVATE KEY` block with the given PKCS#8 form.
+                pub fn to_encrypted_pem<R: rand_core::RngCore + rand_core::CryptoRng>(
+                    &self,
+                    rng: &mut R,
+                    password: &[u8],
+                    form: PrivateKeyForm,
+                    params: &crate::password::Pbes2Params,
+                ) -> Result<String, Error> {
+                    let der = self.to_encrypted_der(rng, password, form, params)?;
+                    Ok(encode(super::ENCRYPTED_PRIVATE_KEY_LABEL, &der))
+                }
+
+                pub fn from_encrypted_pem(pem: &str, password: &[u8]) -> Result<Self, Error> {
+                    SecretSeed::from_encrypted_der(&decode_expecting(pem, super::ENCRYPTED_PRIVATE_KEY_LABEL)?, password)
+                }
+            }
+
+            impl Ciphertext {
+                pub fn to_pem(&self) -> String {
+                    encode(CIPHERTEXT_LABEL, self.as_bytes())
+                }
+
+                pub fn from_pem(pem: &str) -> Result<Self, Error> {
+                    let ct = decode_expecting(pem, CIPHERTEXT_LABEL)?;
+                    let ct: [u8; kyber_params::CIPHERTEXT_BYTES] =
+                        ct[..].try_into().map_err(|_| Error::InvalidLength)?;
+                    Ok(Ciphertext::from(ct))
+                }
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+pem_level!(kyber512, "ML-KEM-512", "MLKEM512");
+#[cfg(feature = "kyber768")]
+pem_level!(kyber768, "ML-KEM-768", "MLKEM768");
+#[cfg(feature = "kyber1024")]
+pem_level!(kyber1024, "ML-KEM-1024", "MLKEM1024");
+
+/// A public key of whichever level its PEM named.
+#[derive(Clone, Copy)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyPublicKey {
+    #[cfg(feature = "kyber512")]
+    MlKem512(crate::kyber512::PublicKey),
+    #[cfg(feature = "kyber768")]
+    MlKem768(crate::kyber768::PublicKey),
+    #[cfg(feature = "kyber1024")]
+    MlKem1024(crate::kyber1024::PublicKey),
+}
+
+/// A secret key of whichever level its PEM named.
+#[derive(Clone, Copy)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnySecretKey {
+    #[cfg(feature = "kyber512")]
+    MlKem512(crate::kyber512::SecretKey),
+    #[cfg(feature = "kyber768")]
+    MlKem768(crate::kyber768::SecretKey),
+    #[cfg(feature = "kyber1024")]
+    MlKem1024(crate::kyber1024::SecretKey),
+}
+
+/// A ciphertext of whichever level its PEM label named.
+#[derive(Clone, Copy)]
+#[allow(clippy::large_enum_variant)]
+pub enum AnyCiphertext {
+    #[cfg(feature = "kyber512")]
+    MlKem512(crate::kyber512::Ciphertext),
+    #[cfg(feature = "kyber768")]
+    MlKem768(crate::kyber768::Ciphertext),
+    #[cfg(feature = "kyber1024")]
+    MlKem1024(crate::kyber1024::Ciphertext),
+}
+
+// Tries each enabled level in turn; a level that does not match the OID
+// reports UnsupportedAlgorithm and the next one gets a go
+macro_rules! detect {
+    ($der:expr, $ty:ident, $any:ident) => {{
+        #[cfg(feature = "kyber512")]
+        match crate::kyber512::$ty::from_der($der) {
+            Err(Error::UnsupportedAlgorithm) => {}
+            result => return result.map($any::MlKem512),
+        }
+        #[cfg(feature = "kyber768")]
+        match crate::kyber768::$ty::from_der($der) {
+            Err(Error::UnsupportedAlgorithm) => {}
+            result => return result.map($any::MlKem768),
+        }
+        #[cfg(feature = "kyber1024")]
+        match crate::kyber1024::$ty::from_der($der) {
+            Err(Error::UnsupportedAlgorithm) => {}
+            result => return result.map($any::MlKem1024),
+        }
+        Err(Error::UnsupportedAlgorithm)
+    }};
+}
+
+/// Reads a `PUBLIC KEY` block of any enabled level.
+pub fn public_key_from_pem(pem: &str) -> Result<AnyPublicKey, Error> {
+    let der = decode_expecting(pem, PUBLIC_KEY_LABEL)?;
+    detect!(&der, PublicKey, AnyPublicKey)
+}
+
+/// Reads a `PRIVATE KEY` block of any enabled level.
+pub fn secret_key_from_pem(pem: &str) -> Result<AnySecretKey, Error> {
+    let der = decode_expecting(pem, PRIVATE_KEY_LABEL)?;
+    detect!(&der, SecretKey, AnySecretKey)
+}
+
+/// Reads a ciphertext block of any enabled level.
+pub fn ciphertext_from_pem(pem: &str) -> Result<AnyCiphertext, Error> {
+    let (label, _) = decode(pem)?;
+    #[cfg(feature = "kyber512")]
+    if label == kyber512::CIPHERTEXT_LABEL {
+        return crate::kyber512::Ciphertext::from_pem(pem).map(AnyCiphertext::MlKem512);
+    }
+    #[cfg(feature = "kyber768")]
+    if label == kyber768::CIPHERTEXT_LABEL {
+        return crate::kyber768::Ciphertext::from_pem(pem).map(AnyCiphertext::MlKem768);
+    }
+    #[cfg(feature = "kyber1024")]
+    if label == kyber1024::CIPHERTEXT_LABEL {
+        return crate::kyber1024::Ciphertext::from_pem(pem).map(AnyCiphertext::MlKem1024);
+    }
+    if label.ends_with(" CIPHERTEXT") {
+        Err(Error::UnsupportedAlgorithm)
+    } else {
+        Err(Error::InvalidEncoding)
+    }
+}
@@ -1,7 +1,7 @@
-use crate::params::{N, Q};
+use crate::params::N;
 use crate::ntt;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct Poly {
     pub coeffs: [i16; N],
 }
@@ -17,34 +17,50 @@ impl Poly {
         Poly::default()
     }
 
-    // Add two polynomials
+    // Add two polynomials, leaving coefficients in [0, q)
     pub fn add(&mut self, b: &Poly) {
-        for i in 0..N {
-            self.coeffs[i] = (self.coeffs[i] + b.coeffs[i]) % (Q as i16);
+        for (c, &bc) in self.coeffs.iter_mut().zip(b.coeffs.iter()) {
+            *c = ntt::caddq(ntt::barrett_reduce(*c + bc));
         }
     }
 
-    // Subtract two polynomials
+    // Subtract two polynomials, leaving coefficients in [0, q)
     pub fn sub(&mut self, b: &Poly) {
-        for i in 0..N {
-            self.coeffs[i] = (self.coeffs[i] - b.coeffs[i] + Q as i16) % (Q as i16);
+        for (c, &bc) in self.coeffs.iter_mut().zip(b.coeffs.iter()) {
+            *c = ntt::caddq(ntt::barrett_reduce(*c - bc));
+        }
+    }
+
+    // Map every coefficient to its standard representative in [0, q)
+    pub fn reduce(&mut self) {
+        for c in self.coeffs.iter_mut() {
+            *c = ntt::caddq(ntt::barrett_reduce(*c));
         }
     }
 
     // Transform polynomial to NTT domain
     pub fn ntt(&mut self) {
         ntt::ntt(&mut self.coeffs);
+        self.reduce();
     }
 
     // Transform polynomial from NTT domain
     pub fn inv_ntt(&mut self) {
         ntt::inv_ntt(&mut self.coeffs);
+        self.reduce();
     }
 
-    // Pointwise multiplication of two polynomials in NTT domain
+    // Multiplication of two polynomials in NTT domain
     pub fn pointwise_mul(&mut self, b: &Poly) {
-        for i in 0..N {
-            self.coeffs[i] = ((self.coeffs[i] as i32 * b.coeffs[i] as i32) % Q as i32) as i16;
+        let a = self.coeffs;
+        for i in 0..N / 4 {
+            let zeta = ntt::ZETAS[64 + i];
+            ntt::basemul(&mut self.coeffs[4 * i..], &a[4 * i..], &b.coeffs[4 * i..], zeta);
+            ntt::basemul(&mut self.coeffs[4 * i + 2..], &a[4 * i + 2..], &b.coeffs[4 * i + 2..], -zeta);
+        }
+        for c in self.coeffs.iter_mut() {
+            *c = ntt::to_mont(*c);
         }
+        self.reduce();
     }
 }
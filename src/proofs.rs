@@ -0,0 +1,176 @@
+//! Bounded model-checking harnesses for the arithmetic helpers.
+//!
+//! Run with `cargo kani`. Kani checks every harness for arithmetic overflow,
+//! out-of-bounds indexing and panics in addition to the explicit assertions,
+//! which pin down the output ranges the callers rely on.
+
+use crate::ntt::{barrett_reduce, caddq, fqmul, montgomery_reduce};
+use crate::params::{N, Q};
+use crate::poly::Poly;
+use crate::utils::{
+    cbd, compress_coeff, decompress_coeff, poly_compress, poly_decompress, poly_from_msg,
+    poly_frombytes_round, poly_to_msg, poly_tobytes_round,
+};
+
+const QI16: i16 = Q as i16;
+
+fn any_canonical_poly() -> Poly {
+    let mut p = Poly::new();
+    for c in p.coeffs.iter_mut() {
+        *c = kani::any();
+        kani::assume(*c >= 0 && *c < QI16);
+    }
+    p
+}
+
+#[kani::proof]
+fn montgomery_reduce_range() {
+    let a: i32 = kani::any();
+    kani::assume(a >= -Q * (1 << 15) && a < Q * (1 << 15));
+    let r = montgomery_reduce(a);
+    assert!(r > -QI16 && r < QI16);
+    assert!((r as i64 * (1 << 16) - a as i64) % Q as i64 == 0);
+}
+
+#[kani::proof]
+fn barrett_reduce_range() {
+    let a: i16 = kani::any();
+    let r = barrett_reduce(a);
+    assert!(r >= -(QI16 - 1) / 2 && r <= (QI16 - 1) / 2);
+    assert!((a as i32 - r as i32) % Q == 0);
+}
+
+#[kani::proof]
+fn caddq_range() {
+    let a: i16 = kani::any();
+    kani::assume(a > -QI16 && a < QI16);
+    let r = caddq(a);
+    assert!(r >= 0 && r < QI16);
+    assert!((a as i32 - r as i32) % Q == 0);
+}
+
+#[kani::proof]
+fn fqmul_range() {
+    let a: i16 = kani::any();
+    let b: i16 = kani::any();
+    kani::assume(b > -QI16 && b < QI16);
+    let r = fqmul(a, b);
+    assert!(r > -QI16 && r < QI16);
+}
+
+#[kani::proof]
+#[kani::unwind(13)]
+fn tobytes_round_trip() {
+    let p: [i16; 8] = kani::any();
+    for &c in p.iter() {
+        kani::assume(c >= 0 && c < QI16);
+    }
+    assert!(poly_frombytes_round(&poly_tobytes_round(p)) == p);
+}
+
+#[kani::proof]
+#[kani::unwind(13)]
+fn tobytes_accepts_centered_input() {
+    let p: [i16; 8] = kani::any();
+    for &c in p.iter() {
+        kani::assume(c > -QI16 && c < QI16);
+    }
+    let r = poly_frombytes_round(&poly_tobytes_round(p));
+    for i in 0..8 {
+        assert!(r[i] >= 0 && r[i] < QI16);
+        assert!((r[i] as i32 - p[i] as i32) % Q == 0);
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(13)]
+fn frombytes_round_range() {
+    let b: [u8; 12] = kani::any();
+    for c in poly_frombytes_round(&b) {
+        assert!((0..4096).contains(&c));
+    }
+}
+
+macro_rules! compress_proofs {
+    ($coeff:ident, $poly:ident, $d:expr) => {
+        #[kani::proof]
+        fn $coeff() {
+            let x: i16 = kani::any();
+            kani::assume(x >= 0 && x < QI16);
+            let y = compress_coeff(x, $d);
+            assert!((y as u32) < (1 << $d));
+
+            let x2 = decompress_coeff(y, $d);
+            assert!(x2 >= 0 && x2 < QI16);
+
+            // FIPS 203, section 4.2.1: |x2 - x| mod± q <= round(q / 2^(d + 1))
+            let diff = (x2 as i32 - x as i32).rem_euclid(Q);
+            let bound = (Q + (1 << $d)) >> ($d + 1);
+            assert!(diff.min(Q - diff) <= bound);
+        }
+
+        #[kani::proof]
+        #[kani::unwind(257)]
+        fn $poly() {
+            let p = any_canonical_poly();
+            let mut buf = [0u8; 32 * $d];
+            poly_compress(&p, $d, &mut buf);
+            let mut r = Poly::new();
+            poly_decompress(&buf, $d, &mut r);
+            for i in 0..N {
+                assert!(r.coeffs[i] >= 0 && r.coeffs[i] < QI16);
+            }
+        }
+    };
+}
+
+compress_proofs!(compress_coeff_d1, poly_compress_d1, 1);
+compress_proofs!(compress_coeff_d4, poly_compress_d4, 4);
+compress_proofs!(compress_coeff_d5, poly_compress_d5, 5);
+compress_proofs!(compress_coeff_d10, poly_compress_d10, 10);
+compress_proofs!(compress_coeff_d11, poly_compress_d11, 11);
+
+#[kani::proof]
+#[kani::unwind(257)]
+fn cbd_eta2_range() {
+    let buf: [u8; 128] = kani::any();
+    let mut p = Poly::new();
+    cbd(&buf, 2, &mut p);
+    for i in 0..N {
+        assert!(p.coeffs[i] >= -2 && p.coeffs[i] <= 2);
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(257)]
+fn cbd_eta3_range() {
+    let buf: [u8; 192] = kani::any();
+    let mut p = Poly::new();
+    cbd(&buf, 3, &mut p);
+    for i in 0..N {
+        assert!(p.coeffs[i] >= -3 && p.coeffs[i] <= 3);
+    }
+}
+
+#[kani::proof]
+#[kani::unwind(257)]
+fn msg_encoding() {
+    let msg: [u8; 32] = kani::any();
+    let mut p = Poly::new();
+    poly_from_msg(&msg, &mut p);
+    for i in 0..N {
+        assert!(p.coeffs[i] == 0 || p.coeffs[i] == (QI16 + 1) / 2);
+    }
+
+    let mut out = [0u8; 32];
+    poly_to_msg(&p, &mut out);
+    assert!(out == msg);
+}
+
+#[kani::proof]
+#[kani::unwind(257)]
+fn msg_decoding_no_panic() {
+    let p = any_canonical_poly();
+    let mut out = [0u8; 32];
+    poly_to_msg(&p, &mut out);
+}
@@ -0,0 +1,389 @@
+//! Slow, readable ML-KEM following the FIPS 203 pseudocode line by line.
+//!
+//! Coefficients are plain `u16` values in `[0, q)` and every product is reduced
+//! with `%`. The NTT is evaluated directly from its definition via `BitRev7`, so
+//! nothing here shares code with `ntt.rs`, `poly.rs` or `utils.rs`. This module
+//! exists only as an oracle for the optimized path; do not use it for anything
+//! else, it is neither fast nor constant time.
+
+// Index loops are kept so the code reads like the spec's pseudocode
+#![allow(clippy::needless_range_loop)]
+
+use alloc::vec;
+use alloc::vec::Vec;
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Sha3_512, Shake128, Shake256};
+
+const N: usize = 256;
+const Q: u32 = 3329;
+const ZETA: u32 = 17;
+
+type Poly = [u16; N];
+
+/// An ML-KEM parameter set (FIPS 203, Table 2).
+#[derive(Clone, Copy, Debug)]
+pub struct ParamSet {
+    pub k: usize,
+    pub eta1: usize,
+    pub eta2: usize,
+    pub du: usize,
+    pub dv: usize,
+}
+
+pub const ML_KEM_512: ParamSet = ParamSet { k: 2, eta1: 3, eta2: 2, du: 10, dv: 4 };
+pub const ML_KEM_768: ParamSet = ParamSet { k: 3, eta1: 2, eta2: 2, du: 10, dv: 4 };
+pub const ML_KEM_1024: ParamSet = ParamSet { k: 4, eta1: 2, eta2: 2, du: 11, dv: 5 };
+
+fn add(a: u16, b: u16) -> u16 {
+    ((a as u32 + b as u32) % Q) as u16
+}
+
+fn sub(a: u16, b: u16) -> u16 {
+    ((a as u32 + Q - b as u32) % Q) as u16
+}
+
+fn mul(a: u16, b: u16) -> u16 {
+    ((a as u32 * b as u32) % Q) as u16
+}
+
+fn pow(base: u32, exp: u32) -> u16 {
+    let mut r = 1u32;
+    for _ in 0..exp {
+        r = r * base % Q;
+    }
+    r as u16
+}
+
+// BitRev7: reverse the 7-bit binary representation of i
+fn bitrev7(i: u32) -> u32 {
+    (0..7).fold(0, |r, b| r | (((i >> b) & 1) << (6 - b)))
+}
+
+// Algorithm 3, BitsToBytes
+fn bits_to_bytes(bits: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0u8; bits.len() / 8];
+    for (i, &bit) in bits.iter().enumerate() {
+        bytes[i / 8] += bit << (i % 8);
+    }
+    bytes
+}
+
+// Algorithm 4, BytesToBits
+fn bytes_to_bits(bytes: &[u8]) -> Vec<u8> {
+    let mut bits = Vec::with_capacity(8 * bytes.len());
+    for &byte in bytes {
+        for j in 0..8 {
+            bits.push((byte >> j) & 1);
+        }
+    }
+    bits
+}
+
+// Section 4.2.1, Compress_d(x) = round((2^d / q) * x) mod 2^d
+fn compress(x: u16, d: usize) -> u16 {
+    (((((x as u32) << (d + 1)) + Q) / (2 * Q)) % (1 << d)) as u16
+}
+
+// Section 4.2.1, Decompress_d(y) = round((q / 2^d) * y)
+fn decompress(y: u16, d: usize) -> u16 {
+    ((2 * y as u32 * Q + (1 << d)) >> (d + 1)) as u16
+}
+
+// Algorithm 5, ByteEncode_d
+fn byte_encode(f: &Poly, d: usize) -> Vec<u8> {
+    let mut bits = vec![0u8; N * d];
+    for i in 0..N {
+        let mut a = f[i];
+        for j in 0..d {
+            bits[i * d + j] = (a & 1) as u8;
+            a >>= 1;
+        }
+    }
+    bits_to_bytes(&bits)
+}
+
+// Algorithm 6, ByteDecode_d
+fn byte_decode(bytes: &[u8], d: usize) -> Poly {
+    let m = if d < 12 { 1u32 << d } else { Q };
+    let bits = bytes_to_bits(bytes);
+    let mut f = [0u16; N];
+    for i in 0..N {
+        let v: u32 = (0..d).map(|j| (bits[i * d + j] as u32) << j).sum();
+        f[i] = (v % m) as u16;
+    }
+    f
+}
+
+// Algorithm 7, SampleNTT
+fn sample_ntt(seed: &[u8; 34]) -> Poly {
+    let mut ctx = Shake128::default();
+    ctx.update(seed);
+    let mut reader = ctx.finalize_xof();
+    let mut a = [0u16; N];
+    let mut j = 0;
+    while j < N {
+        let mut c = [0u8; 3];
+        reader.read(&mut c);
+        let d1 = c[0] as u16 + 256 * (c[1] as u16 % 16);
+        let d2 = c[1] as u16 / 16 + 16 * c[2] as u16;
+        if (d1 as u32) < Q {
+            a[j] = d1;
+            j += 1;
+        }
+        if (d2 as u32) < Q && j < N {
+            a[j] = d2;
+            j += 1;
+        }
+    }
+    a
+}
+
+// Algorithm 8, SamplePolyCBD_eta
+fn sample_poly_cbd(bytes: &[u8], eta: usize) -> Poly {
+    let b = bytes_to_bits(bytes);
+    let mut f = [0u16; N];
+    for i in 0..N {
+        let x: u16 = (0..eta).map(|j| b[2 * i * eta + j] as u16).sum();
+        let y: u16 = (0..eta).map(|j| b[2 * i * eta + eta + j] as u16).sum();
+        f[i] = sub(x, y);
+    }
+    f
+}
+
+// Algorithm 9, NTT, evaluated directly from its definition:
+// f_hat[2i] + f_hat[2i+1] X = f mod (X^2 - zeta^(2 BitRev7(i) + 1))
+fn ntt(f: &Poly) -> Poly {
+    let mut f_hat = [0u16; N];
+    for i in 0..N / 2 {
+        let gamma = pow(ZETA, 2 * bitrev7(i as u32) + 1);
+        let mut even = 0u16;
+        let mut odd = 0u16;
+        let mut gamma_j = 1u16;
+        for j in 0..N / 2 {
+            even = add(even, mul(f[2 * j], gamma_j));
+            odd = add(odd, mul(f[2 * j + 1], gamma_j));
+            gamma_j = mul(gamma_j, gamma);
+        }
+        f_hat[2 * i] = even;
+        f_hat[2 * i + 1] = odd;
+    }
+    f_hat
+}
+
+// Algorithm 10, NTT^-1, as the inverse of the evaluation map above:
+// f[2j] = 128^-1 * sum_i f_hat[2i] * zeta^-((2 BitRev7(i) + 1) j)
+fn ntt_inv(f_hat: &Poly) -> Poly {
+    let inv_128 = pow(128, Q - 2);
+    let mut f = [0u16; N];
+    for j in 0..N / 2 {
+        let mut even = 0u16;
+        let mut odd = 0u16;
+        for i in 0..N / 2 {
+            let exp = (2 * bitrev7(i as u32) + 1) * j as u32 % 256;
+            let gamma_inv = pow(ZETA, (256 - exp) % 256);
+            even = add(even, mul(f_hat[2 * i], gamma_inv));
+            odd = add(odd, mul(f_hat[2 * i + 1], gamma_inv));
+        }
+        f[2 * j] = mul(even, inv_128);
+        f[2 * j + 1] = mul(odd, inv_128);
+    }
+    f
+}
+
+// Algorithm 12, BaseCaseMultiply
+fn base_case_multiply(a0: u16, a1: u16, b0: u16, b1: u16, gamma: u16) -> (u16, u16) {
+    let c0 = add(mul(a0, b0), mul(mul(a1, b1), gamma));
+    let c1 = add(mul(a0, b1), mul(a1, b0));
+    (c0, c1)
+}
+
+// Algorithm 11, MultiplyNTTs
+fn multiply_ntts(f: &Poly, g: &Poly) -> Poly {
+    let mut h = [0u16; N];
+    for i in 0..N / 2 {
+        let gamma = pow(ZETA, 2 * bitrev7(i as u32) + 1);
+        let (c0, c1) = base_case_multiply(f[2 * i], f[2 * i + 1], g[2 * i], g[2 * i + 1], gamma);
+        h[2 * i] = c0;
+        h[2 * i + 1] = c1;
+    }
+    h
+}
+
+fn poly_add(a: &Poly, b: &Poly) -> Poly {
+    let mut r = [0u16; N];
+    for i in 0..N {
+        r[i] = add(a[i], b[i]);
+    }
+    r
+}
+
+fn poly_sub(a: &Poly, b: &Poly) -> Poly {
+    let mut r = [0u16; N];
+    for i in 0..N {
+        r[i] = sub(a[i], b[i]);
+    }
+    r
+}
+
+fn g(c: &[u8]) -> ([u8; 32], [u8; 32]) {
+    let out = Sha3_512::digest(c);
+    (out[..32].try_into().unwrap(), out[32..].try_into().unwrap())
+}
+
+fn h(s: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(s).into()
+}
+
+fn j(s: &[u8]) -> [u8; 32] {
+    let mut ctx = Shake256::default();
+    ctx.update(s);
+    let mut out = [0u8; 32];
+    ctx.finalize_xof().read(&mut out);
+    out
+}
+
+fn prf(eta: usize, s: &[u8; 32], b: u8) -> Vec<u8> {
+    let mut ctx = Shake256::default();
+    ctx.update(s);
+    ctx.update(&[b]);
+    let mut out = vec![0u8; 64 * eta];
+    ctx.finalize_xof().read(&mut out);
+    out
+}
+
+fn matrix_entry(rho: &[u8], i: usize, j: usize) -> Poly {
+    let mut seed = [0u8; 34];
+    seed[..32].copy_from_slice(rho);
+    seed[32] = j as u8;
+    seed[33] = i as u8;
+    sample_ntt(&seed)
+}
+
+// Algorithm 13, K-PKE.KeyGen
+fn k_pke_keygen(p: &ParamSet, d: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+    let mut d_k = d.to_vec();
+    d_k.push(p.k as u8);
+    let (rho, sigma) = g(&d_k);
+    let mut n = 0u8;
+
+    let mut s = Vec::new();
+    for _ in 0..p.k {
+        s.push(ntt(&sample_poly_cbd(&prf(p.eta1, &sigma, n), p.eta1)));
+        n += 1;
+    }
+    let mut e = Vec::new();
+    for _ in 0..p.k {
+        e.push(ntt(&sample_poly_cbd(&prf(p.eta1, &sigma, n), p.eta1)));
+        n += 1;
+    }
+
+    let mut ek = Vec::new();
+    let mut dk = Vec::new();
+    for i in 0..p.k {
+        let mut t = e[i];
+        for j in 0..p.k {
+            t = poly_add(&t, &multiply_ntts(&matrix_entry(&rho, i, j), &s[j]));
+        }
+        ek.extend(byte_encode(&t, 12));
+        dk.extend(byte_encode(&s[i], 12));
+    }
+    ek.extend_from_slice(&rho);
+    (ek, dk)
+}
+
+// Algorithm 14, K-PKE.Encrypt
+fn k_pke_encrypt(p: &ParamSet, ek: &[u8], m: &[u8; 32], r: &[u8; 32]) -> Vec<u8> {
+    let mut n = 0u8;
+    let t: Vec<Poly> = (0..p.k).map(|i| byte_decode(&ek[384 * i..384 * (i + 1)], 12)).collect();
+    let rho = &ek[384 * p.k..];
+
+    let mut y = Vec::new();
+    for _ in 0..p.k {
+        y.push(ntt(&sample_poly_cbd(&prf(p.eta1, r, n), p.eta1)));
+        n += 1;
+    }
+    let mut e1 = Vec::new();
+    for _ in 0..p.k {
+        e1.push(sample_poly_cbd(&prf(p.eta2, r, n), p.eta2));
+        n += 1;
+    }
+    let e2 = sample_poly_cbd(&prf(p.eta2, r, n), p.eta2);
+
+    let mut c = Vec::new();
+    for i in 0..p.k {
+        let mut u_hat = [0u16; N];
+        for j in 0..p.k {
+            // A^T[i][j] = A[j][i]
+            u_hat = poly_add(&u_hat, &multiply_ntts(&matrix_entry(rho, j, i), &y[j]));
+        }
+        let u = poly_add(&ntt_inv(&u_hat), &e1[i]);
+        c.extend(byte_encode(&u.map(|x| compress(x, p.du)), p.du));
+    }
+
+    let mu = byte_decode(m, 1).map(|x| decompress(x, 1));
+    let mut v_hat = [0u16; N];
+    for i in 0..p.k {
+        v_hat = poly_add(&v_hat, &multiply_ntts(&t[i], &y[i]));
+    }
+    let v = poly_add(&poly_add(&ntt_inv(&v_hat), &e2), &mu);
+    c.extend(byte_encode(&v.map(|x| compress(x, p.dv)), p.dv));
+    c
+}
+
+// Algorithm 15, K-PKE.Decrypt
+fn k_pke_decrypt(p: &ParamSet, dk: &[u8], c: &[u8]) -> [u8; 32] {
+    let c1 = &c[..32 * p.du * p.k];
+    let c2 = &c[32 * p.du * p.k..];
+
+    let mut w_hat = [0u16; N];
+    for i in 0..p.k {
+        let u = byte_decode(&c1[32 * p.du * i..32 * p.du * (i + 1)], p.du).map(|x| decompress(x, p.du));
+        let s_hat = byte_decode(&dk[384 * i..384 * (i + 1)], 12);
+        w_hat = poly_add(&w_hat, &multiply_ntts(&s_hat, &ntt(&u)));
+    }
+    let v = byte_decode(c2, p.dv).map(|x| decompress(x, p.dv));
+    let w = poly_sub(&v, &ntt_inv(&w_hat));
+    byte_encode(&w.map(|x| compress(x, 1)), 1).try_into().unwrap()
+}
+
+/// Algorithm 16, ML-KEM.KeyGen_internal. Returns `(ek, dk)`.
+pub fn keygen_internal(p: &ParamSet, d: &[u8; 32], z: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+    let (ek, dk_pke) = k_pke_keygen(p, d);
+    let mut dk = dk_pke;
+    dk.extend_from_slice(&ek);
+    dk.extend_from_slice(&h(&ek));
+    dk.extend_from_slice(z);
+    (ek, dk)
+}
+
+/// Algorithm 17, ML-KEM.Encaps_internal. Returns `(K, c)`.
+pub fn encaps_internal(p: &ParamSet, ek: &[u8], m: &[u8; 32]) -> ([u8; 32], Vec<u8>) {
+    let mut m_h = m.to_vec();
+    m_h.extend_from_slice(&h(ek));
+    let (k, r) = g(&m_h);
+    let c = k_pke_encrypt(p, ek, m, &r);
+    (k, c)
+}
+
+/// Algorithm 18, ML-KEM.Decaps_internal. Returns `K`.
+pub fn decaps_internal(p: &ParamSet, dk: &[u8], c: &[u8]) -> [u8; 32] {
+    let dk_pke = &dk[..384 * p.k];
+    let ek_pke = &dk[384 * p.k..768 * p.k + 32];
+    let h_ek = &dk[768 * p.k + 32..768 * p.k + 64];
+    let z = &dk[768 * p.k + 64..768 * p.k + 96];
+
+    let m_prime = k_pke_decrypt(p, dk_pke, c);
+    let mut m_h = m_prime.to_vec();
+    m_h.extend_from_slice(h_ek);
+    let (k_prime, r_prime) = g(&m_h);
+
+    let mut z_c = z.to_vec();
+    z_c.extend_from_slice(c);
+    let k_bar = j(&z_c);
+
+    let c_prime = k_pke_encrypt(p, ek_pke, &m_prime, &r_prime);
+    if c != c_prime.as_slice() {
+        return k_bar;
+    }
+    k_prime
+}
@@ -0,0 +1,132 @@
+//! Sealed boxes: one-shot public-key encryption of a byte string, in the
+//! spirit of libsodium's `crypto_box_seal`.
+//!
+//! `seal` encapsulates to the recipient's ML-KEM key, derives a fresh
+//! ChaCha20-Poly1305 key and nonce from the shared secret with HKDF-SHA256
+//! and encrypts the plaintext under the caller's associated data. The sender
+//! stays anonymous and cannot open the box afterwards.
+//!
+//! A sealed box is laid out as
+//!
+//! ```text
+//! version (1) || KEM ID (2) || ML-KEM ciphertext || AEAD ciphertext || tag (16)
+//! ```
+//!
+//! where the KEM ID names the ML-KEM level with its draft-ietf-hpke-pq
+//! identifier (`0x0040`, `0x0041`, `0x0042`). The header also goes into the
+//! HKDF info, so a box cannot be replayed under another version or level.
+//!
+//! Each level has its own module; `seal` and `open` at the top of this module
+//! follow the highest enabled level, like the crate root.
+
+use crate::error::Error;
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::ChaCha20Poly1305;
+use zeroize::Zeroizing;
+
+pub const VERSION: u8 = 1;
+pub const HEADER_BYTES: usize = 3;
+pub const TAG_BYTES: usize = 16;
+
+const INFO_LABEL: &[u8] = b"rusty-kyber sealed box";
+const KEY_BYTES: usize = 32;
+const NONCE_BYTES: usize = 12;
+
+/// Reads the KEM ID from the header of a sealed box, to pick the right key.
+pub fn kem_id(sealed: &[u8]) -> Result<u16, Error> {
+    match sealed {
+        [VERSION, hi, lo, ..] => Ok(u16::from_be_bytes([*hi, *lo])),
+        [_, _, _, ..] => Err(Error::InvalidHeader),
+        _ => Err(Error::InvalidLength),
+    }
+}
+
+fn header(kem_id: u16) -> [u8; HEADER_BYTES] {
+    let id = kem_id.to_be_bytes();
+    [VERSION, id[0], id[1]]
+}
+
+fn cipher(shared_secret: &[u8], header: &[u8; HEADER_BYTES]) -> (ChaCha20Poly1305, [u8; NONCE_BYTES]) {
+    let mut okm = Zeroizing::new([0u8; KEY_BYTES + NONCE_BYTES]);
+    hkdf::Hkdf::<sha2::Sha256>::new(None, shared_secret)
+        .expand_multi_info(&[INFO_LABEL, header], &mut okm[..])
+        .unwrap();
+    let cipher = ChaCha20Poly1305::new_from_slice(&okm[..KEY_BYTES]).unwrap();
+    (cipher, okm[KEY_BYTES..].try_into().unwrap())
+}
+
+macro_rules! sealed_box {
+    ($level:ident, $kem:ident, $name:expr, $kem_id:expr) => {
+        #[doc = concat!("Sealed boxes to ", $name, " keys.")]
+        pub mod $level {
+            use super::{cipher, header, kem_id, HEADER_BYTES, TAG_BYTES};
+            use alloc::vec::Vec;
+            use crate::error::Error;
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{Ciphertext, PublicKey, SecretKey, $kem};
+            use chacha20poly1305::aead::{Aead, Payload};
+            use rand_core::{CryptoRng, RngCore};
+            use zeroize::Zeroize;
+
+            pub const KEM_ID: u16 = $kem_id;
+
+            /// Length a sealed box adds to the plaintext.
+            pub const OVERHEAD: usize = HEADER_BYTES + kyber_params::CIPHERTEXT_BYTES + TAG_BYTES;
+
+            /// Fails if `pk` does not pass the FIPS 203 modulus check.
+            pub fn seal<R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                plaintext: &[u8],
+                aad: &[u8],
+            ) -> Result<Vec<u8>, Error> {
+                let (ct, mut ss) = $kem::encaps(rng, pk)?;
+                let header = header(KEM_ID);
+                let (cipher, nonce) = cipher(ss.as_bytes(), &header);
+                ss.zeroize();
+
+                let mut sealed = Vec::with_capacity(OVERHEAD + plaintext.len());
+                sealed.extend_from_slice(&header);
+                sealed.extend_from_slice(ct.as_bytes());
+                sealed.extend(cipher.encrypt(&nonce.into(), Payload { msg: plaintext, aad }).unwrap());
+                Ok(sealed)
+            }
+
+            /// Returns [`Error::InvalidHeader`] for a box sealed to another
+            /// level or version, and [`Error::AuthenticationFailed`] if the box
+            /// or `aad` was modified or the box is for another key.
+            pub fn open(sk: &SecretKey, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error> {
+                if kem_id(sealed)? != KEM_ID {
+                    return Err(Error::InvalidHeader);
+                }
+                if sealed.len() < OVERHEAD {
+                    return Err(Error::InvalidLength);
+                }
+                let (ct, body) = sealed[HEADER_BYTES..].split_at(kyber_params::CIPHERTEXT_BYTES);
+                let ct = Ciphertext::from(<[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(ct).unwrap());
+
+                let mut ss = $kem::decaps(sk, &ct)?;
+                let (cipher, nonce) = cipher(ss.as_bytes(), &header(KEM_ID));
+                ss.zeroize();
+                cipher
+                    .decrypt(&nonce.into(), Payload { msg: body, aad })
+                    .map_err(|_| Error::AuthenticationFailed)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+sealed_box!(kyber512, MlKem512, "ML-KEM-512", 0x0040);
+#[cfg(feature = "kyber768")]
+sealed_box!(kyber768, MlKem768, "ML-KEM-768", 0x0041);
+#[cfg(feature = "kyber1024")]
+sealed_box!(kyber1024, MlKem1024, "ML-KEM-1024", 0x0042);
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{open, seal, KEM_ID, OVERHEAD};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{open, seal, KEM_ID, OVERHEAD};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{open, seal, KEM_ID, OVERHEAD};
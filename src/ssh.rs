@@ -0,0 +1,225 @@
+//! The `mlkem768x25519-sha256` SSH key exchange method
+//! (draft-ietf-sshm-mlkem-hybrid-kex), as in OpenSSH 9.9 and later.
+//!
+//! The exchange values are the X25519MLKEM768 key shares from
+//! [`crate::hybrid::x25519mlkem768`], ML-KEM part first:
+//!
+//! - client `Q_C = ek || x25519_pk`, in `SSH_MSG_KEX_HYBRID_INIT`
+//! - server `Q_S = ct || x25519_pk`, in `SSH_MSG_KEX_HYBRID_REPLY` with the
+//!   host key and the signature over the exchange hash
+//! - shared secret `K = SHA256(K_PQ || K_CL)`
+//!
+//! Unlike the classical methods, `K` enters the exchange hash and the key
+//! derivation as a `string`, not an `mpint`: [`SharedSecret::to_ssh_string`]
+//! gives that encoding, and [`Exchange::hash`] and
+//! [`SharedSecret::derive_key`] use it. Everything else in the transport
+//! (KEXINIT, packet framing, host key signatures) is left to the SSH library.
+
+use crate::error::Error;
+use crate::hybrid::x25519mlkem768;
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+pub use crate::hybrid::x25519mlkem768::{
+    ClientSecret, ClientShare, ServerShare, CLIENT_SHARE_BYTES, SERVER_SHARE_BYTES,
+};
+
+/// The method name for KEXINIT.
+pub const NAME: &str = "mlkem768x25519-sha256";
+
+pub const MSG_KEX_HYBRID_INIT: u8 = 30;
+pub const MSG_KEX_HYBRID_REPLY: u8 = 31;
+
+pub const SHARED_SECRET_BYTES: usize = 32;
+/// `K` as an SSH `string`: a 4-byte length, then the 32 bytes.
+pub const SHARED_SECRET_STRING_BYTES: usize = 4 + SHARED_SECRET_BYTES;
+
+/// `K = SHA256(K_PQ || K_CL)`.
+#[derive(Clone, Copy, Zeroize)]
+pub struct SharedSecret([u8; SHARED_SECRET_BYTES]);
+
+impl From<SharedSecret> for [u8; SHARED_SECRET_BYTES] {
+    fn from(k: SharedSecret) -> Self {
+        k.0
+    }
+}
+
+impl SharedSecret {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// `K` encoded as an SSH `string`, as hashed into `H` and the keys.
+    pub fn to_ssh_string(&self) -> Zeroizing<[u8; SHARED_SECRET_STRING_BYTES]> {
+        let mut out = Zeroizing::new([0u8; SHARED_SECRET_STRING_BYTES]);
+        out[..4].copy_from_slice(&(SHARED_SECRET_BYTES as u32).to_be_bytes());
+        out[4..].copy_from_slice(&self.0);
+        out
+    }
+
+    /// Derives `len` bytes of the key for `letter` (`b'A'` to `b'F'`) as in
+    /// RFC 4253, section 7.2: `HASH(K || H || letter || session_id)`,
+    /// extended with `HASH(K || H || K1 || ... )` while too short.
+    pub fn derive_key(&self, exchange_hash: &[u8], session_id: &[u8], letter: u8, len: usize) -> Zeroizing<Vec<u8>> {
+        let k = self.to_ssh_string();
+        let mut out = Zeroizing::new(Vec::with_capacity(len.next_multiple_of(SHARED_SECRET_BYTES)));
+        out.extend_from_slice(
+            &Sha256::new()
+                .chain_update(k.as_ref())
+                .chain_update(exchange_hash)
+                .chain_update([letter])
+                .chain_update(session_id)
+                .finalize(),
+        );
+        while out.len() < len {
+            let next = Sha256::new()
+                .chain_update(k.as_ref())
+                .chain_update(exchange_hash)
+                .chain_update(&out[..])
+                .finalize();
+            out.extend_from_slice(&next);
+        }
+        out.truncate(len);
+        out
+    }
+}
+
+/// Client side: the ML-KEM-768 and X25519 keys for `Q_C`.
+pub fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (ClientShare, ClientSecret) {
+    x25519mlkem768::keypair(rng)
+}
+
+/// Server side: validates `Q_C` (the FIPS 203 modulus check and a non-zero
+/// X25519 result) and returns `Q_S` with `K`.
+pub fn encaps<R: RngCore + CryptoRng>(rng: &mut R, client: &ClientShare) -> Result<(ServerShare, SharedSecret), Error> {
+    let (server, ss) = x25519mlkem768::encaps(rng, client)?;
+    Ok((server, hash_shared_secret(ss)))
+}
+
+/// Client side: consumes the ephemeral secrets and recovers `K` from `Q_S`.
+pub fn decaps(sk: ClientSecret, server: &ServerShare) -> Result<SharedSecret, Error> {
+    Ok(hash_shared_secret(x25519mlkem768::decaps(sk, server)?))
+}
+
+// The hybrid secret is already K_PQ || K_CL
+fn hash_shared_secret(mut ss: x25519mlkem768::SharedSecret) -> SharedSecret {
+    let k = SharedSecret(Sha256::digest(ss.as_bytes()).into());
+    ss.zeroize();
+    k
+}
+
+fn put_string(out: &mut Vec<u8>, s: &[u8]) {
+    out.extend_from_slice(&(s.len() as u32).to_be_bytes());
+    out.extend_from_slice(s);
+}
+
+// The next `string` of an SSH message
+fn read_string<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], Error> {
+    let (len, rest) = data.split_first_chunk::<4>().ok_or(Error::InvalidEncoding)?;
+    let len = u32::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return Err(Error::InvalidEncoding);
+    }
+    let (s, rest) = rest.split_at(len);
+    *data = rest;
+    Ok(s)
+}
+
+fn strip_message_number(payload: &[u8], number: u8) -> Result<&[u8], Error> {
+    match payload.split_first() {
+        Some((&n, rest)) if n == number => Ok(rest),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// The `SSH_MSG_KEX_HYBRID_INIT` payload: `byte 30 || string Q_C`.
+pub fn init_message(client: &ClientShare) -> Vec<u8> {
+    let mut out = Vec::with_capacity(5 + CLIENT_SHARE_BYTES);
+    out.push(MSG_KEX_HYBRID_INIT);
+    put_string(&mut out, client.as_bytes());
+    out
+}
+
+/// Reads `Q_C` from an `SSH_MSG_KEX_HYBRID_INIT` payload.
+pub fn parse_init(payload: &[u8]) -> Result<ClientShare, Error> {
+    let mut rest = strip_message_number(payload, MSG_KEX_HYBRID_INIT)?;
+    let client = ClientShare::try_from(read_string(&mut rest)?)?;
+    if !rest.is_empty() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(client)
+}
+
+/// The fields of an `SSH_MSG_KEX_HYBRID_REPLY`.
+pub struct Reply<'a> {
+    /// `K_S`, the server's public host key blob.
+    pub host_key: &'a [u8],
+    pub server: ServerShare,
+    /// The host key signature over the exchange hash.
+    pub signature: &'a [u8],
+}
+
+/// The `SSH_MSG_KEX_HYBRID_REPLY` payload:
+/// `byte 31 || string K_S || string Q_S || string signature`.
+pub fn reply_message(host_key: &[u8], server: &ServerShare, signature: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(13 + host_key.len() + SERVER_SHARE_BYTES + signature.len());
+    out.push(MSG_KEX_HYBRID_REPLY);
+    put_string(&mut out, host_key);
+    put_string(&mut out, server.as_bytes());
+    put_string(&mut out, signature);
+    out
+}
+
+/// Reads an `SSH_MSG_KEX_HYBRID_REPLY` payload.
+pub fn parse_reply(payload: &[u8]) -> Result<Reply<'_>, Error> {
+    let mut rest = strip_message_number(payload, MSG_KEX_HYBRID_REPLY)?;
+    let host_key = read_string(&mut rest)?;
+    let server = ServerShare::try_from(read_string(&mut rest)?)?;
+    let signature = read_string(&mut rest)?;
+    if !rest.is_empty() {
+        return Err(Error::InvalidEncoding);
+    }
+    Ok(Reply {
+        host_key,
+        server,
+        signature,
+    })
+}
+
+/// The inputs of the exchange hash besides `K`.
+pub struct Exchange<'a> {
+    /// `V_C` and `V_S`, the identification lines without CR LF.
+    pub client_version: &'a [u8],
+    pub server_version: &'a [u8],
+    /// `I_C` and `I_S`, the KEXINIT payloads.
+    pub client_kexinit: &'a [u8],
+    pub server_kexinit: &'a [u8],
+    /// `K_S`, the server's public host key blob.
+    pub host_key: &'a [u8],
+    pub client: &'a ClientShare,
+    pub server: &'a ServerShare,
+}
+
+impl Exchange<'_> {
+    /// `H = SHA256(V_C || V_S || I_C || I_S || K_S || Q_C || Q_S || K)`, each
+    /// as a `string`. The first `H` of a connection is also its session ID.
+    pub fn hash(&self, k: &SharedSecret) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for s in [
+            self.client_version,
+            self.server_version,
+            self.client_kexinit,
+            self.server_kexinit,
+            self.host_key,
+            self.client.as_bytes(),
+            self.server.as_bytes(),
+        ] {
+            hasher.update((s.len() as u32).to_be_bytes());
+            hasher.update(s);
+        }
+        hasher.update(k.to_ssh_string().as_ref());
+        hasher.finalize().into()
+    }
+}
@@ -0,0 +1,342 @@
+//! Streaming encryption to an ML-KEM public key, for payloads too large to
+//! hold in memory.
+//!
+//! A stream starts with a header
+//!
+//! ```text
+//! version (1) || KEM ID (2) || ML-KEM ciphertext
+//! ```
+//!
+//! with the same KEM IDs as [`crate::sealed`]. HKDF-SHA256 derives a
+//! ChaCha20-Poly1305 key from the shared secret and the whole header, so a
+//! modified header fails on the first chunk. The plaintext follows in chunks
+//! of [`CHUNK_BYTES`], each sealed on its own in the STREAM construction
+//! (Hoang, Reyhanitabar, Rogaway and Vizár): the nonce is an 11-byte
+//! big-endian chunk counter followed by a flag byte that is 1 only for the
+//! last chunk. Reordered chunks fail authentication, and a stream that ends
+//! before a chunk flagged as last is rejected as truncated.
+//!
+//! Only the last chunk may be shorter than [`CHUNK_BYTES`], and it is empty
+//! only when the whole plaintext is.
+//!
+//! Each level has a module with `encrypt`, which writes the header and
+//! returns an [`EncryptWriter`], and `decrypt`, which reads the header and
+//! returns a [`DecryptReader`]. To decrypt a stream whose level is not known
+//! in advance, read its prefix once with [`read_prefix`], pick the key by
+//! [`Prefix::kem_id`] and pass both to that level's `decrypt_with_prefix`.
+//! Errors in the stream come back as [`io::ErrorKind::InvalidData`] wrapping
+//! an [`Error`].
+
+use crate::error::Error;
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::ChaCha20Poly1305;
+use std::io::{self, Read, Write};
+use zeroize::Zeroizing;
+
+pub const VERSION: u8 = 1;
+/// Version and KEM ID, before the ML-KEM ciphertext.
+pub const PREFIX_BYTES: usize = 3;
+/// Plaintext bytes per chunk.
+pub const CHUNK_BYTES: usize = 64 * 1024;
+pub const TAG_BYTES: usize = 16;
+
+const INFO_LABEL: &[u8] = b"rusty-kyber stream";
+const NONCE_BYTES: usize = 12;
+const SEALED_CHUNK_BYTES: usize = CHUNK_BYTES + TAG_BYTES;
+
+/// Reads the KEM ID from the start of a stream, to pick the right key.
+pub fn kem_id(prefix: &[u8]) -> Result<u16, Error> {
+    match prefix {
+        [VERSION, hi, lo, ..] => Ok(u16::from_be_bytes([*hi, *lo])),
+        [_, _, _, ..] => Err(Error::InvalidHeader),
+        _ => Err(Error::InvalidLength),
+    }
+}
+
+fn invalid_data(err: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn prefix(kem_id: u16) -> [u8; PREFIX_BYTES] {
+    let id = kem_id.to_be_bytes();
+    [VERSION, id[0], id[1]]
+}
+
+fn cipher(shared_secret: &[u8], header: &[u8]) -> ChaCha20Poly1305 {
+    let mut key = Zeroizing::new([0u8; 32]);
+    hkdf::Hkdf::<sha2::Sha256>::new(None, shared_secret)
+        .expand_multi_info(&[INFO_LABEL, header], &mut key[..])
+        .unwrap();
+    ChaCha20Poly1305::new_from_slice(&key[..]).unwrap()
+}
+
+fn eof_is_truncation(e: io::Error) -> io::Error {
+    match e.kind() {
+        io::ErrorKind::UnexpectedEof => invalid_data(Error::Truncated),
+        _ => e,
+    }
+}
+
+/// The version and KEM ID at the start of a stream, as read by [`read_prefix`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Prefix {
+    kem_id: u16,
+}
+
+impl Prefix {
+    pub fn kem_id(&self) -> u16 {
+        self.kem_id
+    }
+}
+
+/// Reads the version and KEM ID from `inner`, leaving it at the ML-KEM
+/// ciphertext. Fails with [`Error::InvalidHeader`] for another version.
+pub fn read_prefix<R: Read>(inner: &mut R) -> io::Result<Prefix> {
+    let mut bytes = [0u8; PREFIX_BYTES];
+    inner.read_exact(&mut bytes).map_err(eof_is_truncation)?;
+    Ok(Prefix { kem_id: kem_id(&bytes).map_err(invalid_data)? })
+}
+
+// Reads the rest of the header of a stream for `kem_id` into `header`
+fn read_header<R: Read>(inner: &mut R, prefix: &Prefix, kem_id: u16, header: &mut [u8]) -> io::Result<()> {
+    if prefix.kem_id != kem_id {
+        return Err(invalid_data(Error::InvalidHeader));
+    }
+    header[..PREFIX_BYTES].copy_from_slice(&self::prefix(kem_id));
+    inner.read_exact(&mut header[PREFIX_BYTES..]).map_err(eof_is_truncation)
+}
+
+fn nonce(counter: u64, last: bool) -> [u8; NONCE_BYTES] {
+    let mut nonce = [0u8; NONCE_BYTES];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+/// Encrypts everything written to it into `W`.
+///
+/// Call [`EncryptWriter::finish`] at the end: it writes the last chunk, and a
+/// stream without one does not decrypt.
+pub struct EncryptWriter<W: Write> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+    fn new(mut inner: W, header: &[u8], cipher: ChaCha20Poly1305) -> io::Result<Self> {
+        inner.write_all(header)?;
+        Ok(EncryptWriter { inner, cipher, counter: 0, buf: Vec::with_capacity(SEALED_CHUNK_BYTES) })
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let counter = self.counter.checked_add(1).ok_or_else(|| invalid_data(Error::MessageLimitReached))?;
+        self.cipher
+            .encrypt_in_place(&nonce(self.counter, last).into(), b"", &mut self.buf)
+            .unwrap();
+        self.inner.write_all(&self.buf)?;
+        self.buf.clear();
+        self.counter = counter;
+        Ok(())
+    }
+
+    /// Writes the last chunk and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut rest = data;
+        while !rest.is_empty() {
+            // A full chunk is only sealed once more data shows it is not the last
+            if self.buf.len() == CHUNK_BYTES {
+                self.seal_chunk(false)?;
+            }
+            let n = rest.len().min(CHUNK_BYTES - self.buf.len());
+            self.buf.extend_from_slice(&rest[..n]);
+            rest = &rest[n..];
+        }
+        Ok(data.len())
+    }
+
+    /// Flushes the inner writer. Buffered plaintext stays buffered until its
+    /// chunk is complete.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a stream read from `R`, failing on the first chunk that does not
+/// authenticate. Nothing from a chunk is returned before it is verified.
+pub struct DecryptReader<R: Read> {
+    inner: R,
+    cipher: ChaCha20Poly1305,
+    counter: u64,
+    // Ciphertext read ahead, up to one sealed chunk and one byte
+    pending: Vec<u8>,
+    plain: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    fn new(inner: R, cipher: ChaCha20Poly1305) -> Self {
+        DecryptReader {
+            inner,
+            cipher,
+            counter: 0,
+            pending: Vec::with_capacity(SEALED_CHUNK_BYTES + 1),
+            plain: Vec::with_capacity(SEALED_CHUNK_BYTES),
+            pos: 0,
+            done: false,
+        }
+    }
+
+    fn fill_pending(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 8192];
+        while self.pending.len() <= SEALED_CHUNK_BYTES {
+            let want = (SEALED_CHUNK_BYTES + 1 - self.pending.len()).min(chunk.len());
+            match self.inner.read(&mut chunk[..want]) {
+                Ok(0) => break,
+                Ok(n) => self.pending.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    fn open_chunk(&mut self) -> io::Result<()> {
+        self.fill_pending()?;
+        // More data after a full chunk means it is not the last one
+        let last = self.pending.len() <= SEALED_CHUNK_BYTES;
+        let len = self.pending.len().min(SEALED_CHUNK_BYTES);
+        if len < TAG_BYTES {
+            return Err(invalid_data(Error::Truncated));
+        }
+        if last && len == TAG_BYTES && self.counter > 0 {
+            return Err(invalid_data(Error::InvalidLength));
+        }
+        let counter = self.counter.checked_add(1).ok_or_else(|| invalid_data(Error::MessageLimitReached))?;
+
+        self.plain.clear();
+        self.plain.extend_from_slice(&self.pending[..len]);
+        if self.cipher.decrypt_in_place(&nonce(self.counter, last).into(), b"", &mut self.plain).is_err() {
+            // A chunk that opens as a middle one at the end of the input
+            // means the chunks after it were cut off
+            let mut copy = self.pending[..len].to_vec();
+            if last && self.cipher.decrypt_in_place(&nonce(self.counter, false).into(), b"", &mut copy).is_ok() {
+                return Err(invalid_data(Error::Truncated));
+            }
+            return Err(invalid_data(Error::AuthenticationFailed));
+        }
+
+        self.pending.drain(..len);
+        self.counter = counter;
+        self.pos = 0;
+        self.done = last;
+        Ok(())
+    }
+
+    /// Returns the inner reader, positioned after whatever was read ahead.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            if self.done || out.is_empty() {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let n = out.len().min(self.plain.len() - self.pos);
+        out[..n].copy_from_slice(&self.plain[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+macro_rules! stream_level {
+    ($level:ident, $kem:ident, $name:expr, $kem_id:expr) => {
+        #[doc = concat!("Streams encrypted to ", $name, " keys.")]
+        pub mod $level {
+            use super::{
+                cipher, invalid_data, prefix, read_header, read_prefix, DecryptReader, EncryptWriter, Prefix,
+                PREFIX_BYTES,
+            };
+            use crate::params::$level as kyber_params;
+            use crate::traits::Kem;
+            use crate::$level::{Ciphertext, PublicKey, SecretKey, $kem};
+            use rand_core::{CryptoRng, RngCore};
+            use std::io::{self, Read, Write};
+            use zeroize::Zeroize;
+
+            pub const KEM_ID: u16 = $kem_id;
+            pub const HEADER_BYTES: usize = PREFIX_BYTES + kyber_params::CIPHERTEXT_BYTES;
+
+            /// Writes the header to `inner`. Fails if `pk` does not pass the
+            /// FIPS 203 modulus check.
+            pub fn encrypt<W: Write, R: RngCore + CryptoRng>(
+                rng: &mut R,
+                pk: &PublicKey,
+                inner: W,
+            ) -> io::Result<EncryptWriter<W>> {
+                let (ct, mut ss) = $kem::encaps(rng, pk).map_err(invalid_data)?;
+                let mut header = [0u8; HEADER_BYTES];
+                header[..PREFIX_BYTES].copy_from_slice(&prefix(KEM_ID));
+                header[PREFIX_BYTES..].copy_from_slice(ct.as_bytes());
+                let cipher = cipher(ss.as_bytes(), &header);
+                ss.zeroize();
+                EncryptWriter::new(inner, &header, cipher)
+            }
+
+            /// Reads the header from `inner`. Fails with
+            /// [`Error::InvalidHeader`](crate::error::Error::InvalidHeader) for
+            /// a stream to another level or of another version.
+            pub fn decrypt<R: Read>(sk: &SecretKey, mut inner: R) -> io::Result<DecryptReader<R>> {
+                let prefix = read_prefix(&mut inner)?;
+                decrypt_with_prefix(sk, &prefix, inner)
+            }
+
+            /// Continues after [`read_prefix`](super::read_prefix) has read the
+            /// start of `inner`. Fails with
+            /// [`Error::InvalidHeader`](crate::error::Error::InvalidHeader) if
+            /// `prefix` is for another level.
+            pub fn decrypt_with_prefix<R: Read>(
+                sk: &SecretKey,
+                prefix: &Prefix,
+                mut inner: R,
+            ) -> io::Result<DecryptReader<R>> {
+                let mut header = [0u8; HEADER_BYTES];
+                read_header(&mut inner, prefix, KEM_ID, &mut header)?;
+                let ct = Ciphertext::from(<[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(&header[PREFIX_BYTES..]).unwrap());
+                let mut ss = $kem::decaps(sk, &ct).map_err(invalid_data)?;
+                let cipher = cipher(ss.as_bytes(), &header);
+                ss.zeroize();
+                Ok(DecryptReader::new(inner, cipher))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+stream_level!(kyber512, MlKem512, "ML-KEM-512", 0x0040);
+#[cfg(feature = "kyber768")]
+stream_level!(kyber768, MlKem768, "ML-KEM-768", 0x0041);
+#[cfg(feature = "kyber1024")]
+stream_level!(kyber1024, MlKem1024, "ML-KEM-1024", 0x0042);
+
+#[cfg(all(feature = "kyber512", not(feature = "kyber768"), not(feature = "kyber1024")))]
+pub use kyber512::{decrypt, decrypt_with_prefix, encrypt, HEADER_BYTES, KEM_ID};
+#[cfg(all(feature = "kyber768", not(feature = "kyber1024")))]
+pub use kyber768::{decrypt, decrypt_with_prefix, encrypt, HEADER_BYTES, KEM_ID};
+#[cfg(feature = "kyber1024")]
+pub use kyber1024::{decrypt, decrypt_with_prefix, encrypt, HEADER_BYTES, KEM_ID};
@@ -0,0 +1,33 @@
+//! The KEM abstraction shared by the ML-KEM levels and anything composed with them.
+
+use crate::error::Error;
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+/// A key encapsulation mechanism.
+///
+/// Implemented by every enabled ML-KEM level (`kyber512::MlKem512`, ...), by
+/// `xwing::XWing` and, with the `hybrid` features, by the plain ECDH KEMs in
+/// [`crate::ecdh`].
+/// Public keys, ciphertexts and shared secrets expose their encoding through
+/// `AsRef<[u8]>` so combiners can bind them, and shared secrets can be wiped
+/// once they have been used.
+pub trait Kem {
+    /// Algorithm name, e.g. "ML-KEM-768".
+    const NAME: &'static str;
+
+    type PublicKey: AsRef<[u8]>;
+    type SecretKey;
+    type Ciphertext: AsRef<[u8]>;
+    type SharedSecret: AsRef<[u8]> + Zeroize;
+
+    fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (Self::PublicKey, Self::SecretKey);
+
+    /// Fails if `pk` does not pass the algorithm's public key validation.
+    fn encaps<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pk: &Self::PublicKey,
+    ) -> Result<(Self::Ciphertext, Self::SharedSecret), Error>;
+
+    fn decaps(sk: &Self::SecretKey, ct: &Self::Ciphertext) -> Result<Self::SharedSecret, Error>;
+}
@@ -1,125 +1,187 @@
 use crate::params::{N, Q};
 use crate::poly::Poly;
-use sha3::{Digest, Sha3_256, Sha3_512, Shake128, Shake256};
+use sha3::{CShake128, CShake128Core, CShake256, CShake256Core, Digest, Sha3_256, Sha3_512, Shake128, Shake256};
 use sha3::digest::{Update, XofReader, ExtendableOutput};
 
-// Centered Binomial Distribution
-pub fn cbd(buf: &[u8], poly: &mut Poly) {
-    for i in 0..N / 4 {
-        let t = u32::from_le_bytes([buf[i], buf[i + 1], buf[i + 2], buf[i + 3]]);
+// Centered Binomial Distribution with parameter eta (2 or 3); buf holds 64 * eta bytes
+pub fn cbd(buf: &[u8], eta: usize, poly: &mut Poly) {
+    match eta {
+        2 => cbd2(buf, poly),
+        3 => cbd3(buf, poly),
+        _ => panic!("unsupported CBD parameter eta = {}", eta),
+    }
+}
+
+fn cbd2(buf: &[u8], poly: &mut Poly) {
+    for i in 0..N / 8 {
+        let t = u32::from_le_bytes([buf[4 * i], buf[4 * i + 1], buf[4 * i + 2], buf[4 * i + 3]]);
         let mut d = t & 0x55555555;
         d += (t >> 1) & 0x55555555;
-        for j in 0..4 {
+        for j in 0..8 {
             let a = ((d >> (4 * j)) & 0x3) as i16;
             let b = ((d >> (4 * j + 2)) & 0x3) as i16;
+            poly.coeffs[8 * i + j] = a - b;
+        }
+    }
+}
+
+fn cbd3(buf: &[u8], poly: &mut Poly) {
+    for i in 0..N / 4 {
+        let t = u32::from_le_bytes([buf[3 * i], buf[3 * i + 1], buf[3 * i + 2], 0]);
+        let mut d = t & 0x00249249;
+        d += (t >> 1) & 0x00249249;
+        d += (t >> 2) & 0x00249249;
+        for j in 0..4 {
+            let a = ((d >> (6 * j)) & 0x7) as i16;
+            let b = ((d >> (6 * j + 3)) & 0x7) as i16;
             poly.coeffs[4 * i + j] = a - b;
         }
     }
 }
 
-pub fn poly_compress(poly: &Poly, buf: &mut [u8]) {
-    let mut t = [0u8; 8];
+// Compress every coefficient to d bits and pack them into 32 * d bytes;
+// coefficients must be in [0, q)
+pub fn poly_compress(poly: &Poly, d: usize, buf: &mut [u8]) {
+    let mut acc = 0u32;
+    let mut bits = 0;
     let mut k = 0;
-    for i in 0..N / 8 {
-        for j in 0..8 {
-            t[j] = ((((poly.coeffs[8 * i + j] as u32) << 4) + (Q as u32 / 2)) / (Q as u32) & 0xF) as u8;
+    for &c in poly.coeffs.iter() {
+        let t = compress_coeff(c, d) as u32;
+        acc |= t << bits;
+        bits += d;
+        while bits >= 8 {
+            buf[k] = acc as u8;
+            acc >>= 8;
+            bits -= 8;
+            k += 1;
         }
-        buf[k] = t[0] | (t[1] << 4);
-        buf[k + 1] = t[2] | (t[3] << 4);
-        buf[k + 2] = t[4] | (t[5] << 4);
-        buf[k + 3] = t[6] | (t[7] << 4);
-        k += 4;
     }
 }
 
-pub fn poly_decompress(buf: &[u8], poly: &mut Poly) {
+// Unpack 32 * d bytes of d-bit values and decompress them to coefficients in [0, q)
+pub fn poly_decompress(buf: &[u8], d: usize, poly: &mut Poly) {
+    let mask = (1u32 << d) - 1;
+    let mut acc = 0u32;
+    let mut bits = 0;
     let mut k = 0;
-    for i in 0..N / 2 {
-        let t0 = buf[k] & 0x0F;
-        let t1 = buf[k] >> 4;
-        k += 1;
-        poly.coeffs[2 * i] = (((t0 as u32) * (Q as u32) + 8) >> 4) as i16;
-        poly.coeffs[2 * i + 1] = (((t1 as u32) * (Q as u32) + 8) >> 4) as i16;
+    for c in poly.coeffs.iter_mut() {
+        while bits < d {
+            acc |= (buf[k] as u32) << bits;
+            bits += 8;
+            k += 1;
+        }
+        *c = decompress_coeff((acc & mask) as u16, d);
+        acc >>= d;
+        bits -= d;
     }
 }
 
-// Serialize a polynomial to bytes
+// ceil(2^36 / q): n * DIV_Q_MUL >> 36 == n / q for all n < 2^25, without a
+// data-dependent division instruction
+const DIV_Q_MUL: u64 = 20642679;
+
+// round(2^d / q * x) mod 2^d for x in [0, q)
+pub(crate) fn compress_coeff(x: i16, d: usize) -> u16 {
+    let n = ((x as u64) << d) + (Q as u64 / 2);
+    (((n * DIV_Q_MUL) >> 36) & ((1 << d) - 1)) as u16
+}
+
+// round(q / 2^d * y) for y in [0, 2^d)
+pub(crate) fn decompress_coeff(y: u16, d: usize) -> i16 {
+    ((y as u32 * Q as u32 + (1 << (d - 1))) >> d) as i16
+}
+
+// Encode a 32-byte message as a polynomial with coefficients 0 or (q + 1) / 2
+pub fn poly_from_msg(msg: &[u8; 32], poly: &mut Poly) {
+    poly_decompress(msg, 1, poly);
+}
+
+// Decode a polynomial with coefficients in [0, q) to a 32-byte message
+pub fn poly_to_msg(poly: &Poly, msg: &mut [u8; 32]) {
+    poly_compress(poly, 1, msg);
+}
+
+// Serialize a polynomial to bytes, 12 bits per coefficient
 pub fn poly_to_bytes(poly: &Poly, buf: &mut [u8]) {
     let mut t = [0i16; 8];
     for i in 0..N / 8 {
-        for j in 0..8 {
-            t[j] = poly.coeffs[8 * i + j];
-        }
-        buf[13 * i..13 * i + 13].copy_from_slice(&poly_tobytes_round(t));
+        t.copy_from_slice(&poly.coeffs[8 * i..8 * i + 8]);
+        buf[12 * i..12 * i + 12].copy_from_slice(&poly_tobytes_round(t));
     }
 }
 
-// Deserialize a polynomial from bytes
+// Deserialize a polynomial from bytes, 12 bits per coefficient
 pub fn poly_from_bytes(buf: &[u8], poly: &mut Poly) {
     for i in 0..N / 8 {
         poly.coeffs[8 * i..8 * i + 8].copy_from_slice(&poly_frombytes_round(
-            &buf[13 * i..13 * i + 13].try_into().unwrap(),
+            &buf[12 * i..12 * i + 12].try_into().unwrap(),
         ));
     }
 }
 
-fn poly_tobytes_round(p: [i16; 8]) -> [u8; 13] {
-    let mut r = [0u8; 13];
-    r[0] = (p[0] & 0xff) as u8;
-    r[1] = ((p[0] >> 8) | (p[1] << 5)) as u8;
-    r[2] = ((p[1] >> 3) & 0xff) as u8;
-    r[3] = ((p[1] >> 11) | (p[2] << 2)) as u8;
-    r[4] = ((p[2] >> 6) | (p[3] << 7)) as u8;
-    r[5] = ((p[3] >> 1) & 0xff) as u8;
-    r[6] = ((p[3] >> 9) | (p[4] << 4)) as u8;
-    r[7] = ((p[4] >> 4) & 0xff) as u8;
-    r[8] = ((p[4] >> 12) | (p[5] << 1)) as u8;
-    r[9] = ((p[5] >> 7) | (p[6] << 6)) as u8;
-    r[10] = ((p[6] >> 2) & 0xff) as u8;
-    r[11] = ((p[6] >> 10) | (p[7] << 3)) as u8;
-    r[12] = (p[7] >> 5) as u8;
+pub(crate) fn poly_tobytes_round(p: [i16; 8]) -> [u8; 12] {
+    let mut r = [0u8; 12];
+    for i in 0..4 {
+        // map to positive standard representatives
+        let t0 = (p[2 * i] + ((p[2 * i] >> 15) & Q as i16)) as u16;
+        let t1 = (p[2 * i + 1] + ((p[2 * i + 1] >> 15) & Q as i16)) as u16;
+        r[3 * i] = t0 as u8;
+        r[3 * i + 1] = ((t0 >> 8) | (t1 << 4)) as u8;
+        r[3 * i + 2] = (t1 >> 4) as u8;
+    }
     r
 }
 
-fn poly_frombytes_round(p: &[u8; 13]) -> [i16; 8] {
+pub(crate) fn poly_frombytes_round(p: &[u8; 12]) -> [i16; 8] {
     let mut r = [0i16; 8];
-    r[0] = (p[0] as i16) | ((p[1] as i16 & 0x1f) << 8);
-    r[1] = (p[1] >> 5) as i16 | ((p[2] as i16) << 3) | ((p[3] as i16 & 0x03) << 11);
-    r[2] = (p[3] >> 2) as i16 | ((p[4] as i16 & 0x7f) << 6);
-    r[3] = (p[4] >> 7) as i16 | ((p[5] as i16) << 1) | ((p[6] as i16 & 0x0f) << 9);
-    r[4] = (p[6] >> 4) as i16 | ((p[7] as i16) << 4) | ((p[8] as i16 & 0x01) << 12);
-    r[5] = (p[8] >> 1) as i16 | ((p[9] as i16 & 0x3f) << 7);
-    r[6] = (p[9] >> 6) as i16 | ((p[10] as i16) << 2) | ((p[11] as i16 & 0x07) << 10);
-    r[7] = (p[11] >> 3) as i16 | ((p[12] as i16 & 0xff) << 5);
+    for i in 0..4 {
+        r[2 * i] = ((p[3 * i] as u16 | ((p[3 * i + 1] as u16) << 8)) & 0xfff) as i16;
+        r[2 * i + 1] = (((p[3 * i + 1] >> 4) as u16 | ((p[3 * i + 2] as u16) << 4)) & 0xfff) as i16;
+    }
     r
 }
 
+// Compare two byte strings in constant time; returns 0 if equal and 1 otherwise
+pub fn verify(a: &[u8], b: &[u8]) -> u8 {
+    let r = a.iter().zip(b.iter()).fold(0u8, |r, (x, y)| r | (x ^ y));
+    ((0u16.wrapping_sub(r as u16)) >> 15) as u8
+}
+
+// Copy x into r in constant time if b is 1, leave r untouched if b is 0
+pub fn cmov(r: &mut [u8], x: &[u8], b: u8) {
+    let mask = core::hint::black_box(b).wrapping_neg();
+    for (ri, &xi) in r.iter_mut().zip(x.iter()) {
+        *ri ^= mask & (*ri ^ xi);
+    }
+}
+
 // H: SHA3-256
 pub fn h(data: &[u8], out: &mut [u8]) {
     let mut hasher = Sha3_256::new();
-    sha3::digest::Update::update(&mut hasher, data);
+    Digest::update(&mut hasher, data);
     out.copy_from_slice(&hasher.finalize());
 }
 
 // G: SHA3-512
 pub fn g(data: &[u8], out: &mut [u8]) {
     let mut hasher = Sha3_512::new();
-    sha3::digest::Update::update(&mut hasher, data);
+    Digest::update(&mut hasher, data);
     out.copy_from_slice(&hasher.finalize());
 }
 
-// PRF: SHAKE256
-pub fn prf(data: &[u8], out_len: usize, out: &mut [u8]) {
+// PRF: SHAKE256(seed || nonce)
+pub fn prf(seed: &[u8], nonce: u8, out: &mut [u8]) {
     let mut hasher = Shake256::default();
-    sha3::digest::Update::update(&mut hasher, data);
+    hasher.update(seed);
+    hasher.update(&[nonce]);
     let mut reader = hasher.finalize_xof();
-    reader.read(&mut out[..out_len]);
+    reader.read(out);
 }
 
-// KDF: SHAKE256
+// J: SHAKE256, used to derive the implicit rejection key
 pub fn kdf(data: &[u8], out: &mut [u8]) {
     let mut hasher = Shake256::default();
-    sha3::digest::Update::update(&mut hasher, data);
+    hasher.update(data);
     let mut reader = hasher.finalize_xof();
     reader.read(out);
 }
@@ -127,31 +189,86 @@ pub fn kdf(data: &[u8], out: &mut [u8]) {
 // XOF: SHAKE128
 pub fn xof(data: &[u8], out_len: usize, out: &mut [u8]) {
     let mut hasher = Shake128::default();
-    sha3::digest::Update::update(&mut hasher, data);
+    hasher.update(data);
     let mut reader = hasher.finalize_xof();
     reader.read(&mut out[..out_len]);
 }
 
-// Sample a polynomial in NTT domain
-pub fn sample_ntt(xof_out: &[u8], poly: &mut Poly) {
-    let mut i = 0;
+// KMAC128 and KMAC256 (NIST SP 800-185, section 4.3). The key and message
+// are given in parts so callers can feed several fields without
+// concatenating them first.
+pub fn kmac128(key: &[&[u8]], data: &[&[u8]], custom: &[u8], out: &mut [u8]) {
+    let hasher = CShake128::from_core(CShake128Core::new_with_function_name(b"KMAC", custom));
+    kmac(hasher, 168, key, data, out);
+}
+
+pub fn kmac256(key: &[&[u8]], data: &[&[u8]], custom: &[u8], out: &mut [u8]) {
+    let hasher = CShake256::from_core(CShake256Core::new_with_function_name(b"KMAC", custom));
+    kmac(hasher, 136, key, data, out);
+}
+
+fn kmac<H: Update + ExtendableOutput>(mut hasher: H, rate: usize, key: &[&[u8]], data: &[&[u8]], out: &mut [u8]) {
+    // bytepad(encode_string(K), rate)
+    let key_len: usize = key.iter().map(|k| k.len()).sum();
+    let mut enc = [0u8; 9];
+    let encoded = left_encode(rate as u64, &mut enc);
+    let mut absorbed = encoded.len();
+    hasher.update(encoded);
+    let encoded = left_encode(key_len as u64 * 8, &mut enc);
+    absorbed += encoded.len();
+    hasher.update(encoded);
+    for k in key {
+        hasher.update(k);
+    }
+    absorbed += key_len;
+    let zeros = [0u8; 168];
+    hasher.update(&zeros[..(rate - absorbed % rate) % rate]);
+
+    for d in data {
+        hasher.update(d);
+    }
+    hasher.update(right_encode(out.len() as u64 * 8, &mut enc));
+    let mut reader = hasher.finalize_xof();
+    reader.read(out);
+}
+
+// left_encode / right_encode from SP 800-185, section 2.3.1
+fn left_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+    let n = (8 - x.leading_zeros() as usize / 8).max(1);
+    buf[0] = n as u8;
+    buf[1..=n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+    &buf[..=n]
+}
+
+fn right_encode(x: u64, buf: &mut [u8; 9]) -> &[u8] {
+    let n = (8 - x.leading_zeros() as usize / 8).max(1);
+    buf[..n].copy_from_slice(&x.to_be_bytes()[8 - n..]);
+    buf[n] = n as u8;
+    &buf[..=n]
+}
+
+// Sample a polynomial in NTT domain by rejection sampling SHAKE128(seed)
+pub fn sample_ntt(seed: &[u8], poly: &mut Poly) {
+    let mut hasher = Shake128::default();
+    hasher.update(seed);
+    let mut reader = hasher.finalize_xof();
+
+    let mut block = [0u8; 168];
     let mut j = 0;
     while j < N {
-        let t1 = xof_out[i] as u16;
-        let t2 = xof_out[i + 1] as u16;
-        let t3 = xof_out[i + 2] as u16;
-        i += 3;
-
-        let d1 = t1 | (t2 << 8);
-        let d2 = (t2 >> 4) | (t3 << 4);
+        reader.read(&mut block);
+        for chunk in block.chunks_exact(3) {
+            let d1 = (chunk[0] as u16 | ((chunk[1] as u16) << 8)) & 0xfff;
+            let d2 = ((chunk[1] >> 4) as u16 | ((chunk[2] as u16) << 4)) & 0xfff;
 
-        if d1 < 3329 {
-            poly.coeffs[j] = d1 as i16;
-            j += 1;
-        }
-        if j < N && d2 < 3329 {
-            poly.coeffs[j] = d2 as i16;
-            j += 1;
+            if j < N && d1 < Q as u16 {
+                poly.coeffs[j] = d1 as i16;
+                j += 1;
+            }
+            if j < N && d2 < Q as u16 {
+                poly.coeffs[j] = d2 as i16;
+                j += 1;
+            }
         }
     }
 }
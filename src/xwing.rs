@@ -0,0 +1,251 @@
+//! X-Wing: ML-KEM-768 combined with X25519 (draft-connolly-cfrg-xwing-kem).
+//!
+//! The secret key is a 32-byte seed. It is expanded with SHAKE256 into the
+//! ML-KEM-768 seed `d || z` and the X25519 secret whenever it is used, so it
+//! is the only thing that needs storing.
+//!
+//! - public key: `pk_M || pk_X` (1216 bytes)
+//! - ciphertext: `ct_M || ct_X` (1120 bytes)
+//! - shared secret: `SHA3-256(ss_M || ss_X || ct_X || pk_X || label)`
+
+use crate::error::Error;
+use crate::kem;
+use crate::traits::Kem;
+use crate::params::kyber768 as kyber_params;
+use rand_core::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Digest, Sha3_256, Shake256};
+use x25519_dalek::{x25519, X25519_BASEPOINT_BYTES};
+use zeroize::{Zeroize, Zeroizing};
+
+// "\.//^\"
+const LABEL: &[u8; 6] = b"\\.//^\\";
+
+pub const X25519_BYTES: usize = 32;
+pub const PUBLIC_KEY_BYTES: usize = kyber_params::PUBLIC_KEY_BYTES + X25519_BYTES;
+pub const SECRET_KEY_BYTES: usize = 32;
+pub const CIPHERTEXT_BYTES: usize = kyber_params::CIPHERTEXT_BYTES + X25519_BYTES;
+pub const SHARED_SECRET_BYTES: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct PublicKey([u8; PUBLIC_KEY_BYTES]);
+
+#[derive(Clone, Copy, Zeroize)]
+pub struct SecretKey([u8; SECRET_KEY_BYTES]);
+
+#[derive(Clone, Copy)]
+pub struct Ciphertext([u8; CIPHERTEXT_BYTES]);
+
+#[derive(Clone, Copy, Zeroize)]
+pub struct SharedSecret([u8; SHARED_SECRET_BYTES]);
+
+impl From<[u8; PUBLIC_KEY_BYTES]> for PublicKey {
+    fn from(bytes: [u8; PUBLIC_KEY_BYTES]) -> Self {
+        PublicKey(bytes)
+    }
+}
+
+impl From<PublicKey> for [u8; PUBLIC_KEY_BYTES] {
+    fn from(pk: PublicKey) -> Self {
+        pk.0
+    }
+}
+
+impl PublicKey {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; SECRET_KEY_BYTES]> for SecretKey {
+    fn from(bytes: [u8; SECRET_KEY_BYTES]) -> Self {
+        SecretKey(bytes)
+    }
+}
+
+impl From<SecretKey> for [u8; SECRET_KEY_BYTES] {
+    fn from(sk: SecretKey) -> Self {
+        sk.0
+    }
+}
+
+impl SecretKey {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Recomputes the public key from the seed.
+    pub fn public_key(&self) -> PublicKey {
+        expand(self).pk
+    }
+}
+
+impl From<[u8; CIPHERTEXT_BYTES]> for Ciphertext {
+    fn from(bytes: [u8; CIPHERTEXT_BYTES]) -> Self {
+        Ciphertext(bytes)
+    }
+}
+
+impl From<Ciphertext> for [u8; CIPHERTEXT_BYTES] {
+    fn from(ct: Ciphertext) -> Self {
+        ct.0
+    }
+}
+
+impl Ciphertext {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; SHARED_SECRET_BYTES]> for SharedSecret {
+    fn from(bytes: [u8; SHARED_SECRET_BYTES]) -> Self {
+        SharedSecret(bytes)
+    }
+}
+
+impl From<SharedSecret> for [u8; SHARED_SECRET_BYTES] {
+    fn from(ss: SharedSecret) -> Self {
+        ss.0
+    }
+}
+
+impl SharedSecret {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Derives application keys from this secret, see [`crate::kdf`].
+    #[cfg(feature = "kdf")]
+    pub fn derive_keys(&self, algorithm: crate::kdf::Algorithm) -> crate::kdf::KeyDerivation<'_> {
+        crate::kdf::KeyDerivation::new(algorithm, &self.0)
+    }
+}
+
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Ciphertext {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SharedSecret {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// X-Wing as a [`Kem`], for HPKE and other generic users.
+pub struct XWing;
+
+impl Kem for XWing {
+    const NAME: &'static str = "X-Wing";
+
+    type PublicKey = PublicKey;
+    type SecretKey = SecretKey;
+    type Ciphertext = Ciphertext;
+    type SharedSecret = SharedSecret;
+
+    fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+        keypair(rng)
+    }
+
+    fn encaps<R: RngCore + CryptoRng>(rng: &mut R, pk: &PublicKey) -> Result<(Ciphertext, SharedSecret), Error> {
+        encaps(rng, pk)
+    }
+
+    fn decaps(sk: &SecretKey, ct: &Ciphertext) -> Result<SharedSecret, Error> {
+        Ok(decaps(sk, ct))
+    }
+}
+
+// The seed expanded into both component key pairs
+struct Expanded {
+    sk_m: Zeroizing<[u8; kyber_params::SECRET_KEY_BYTES]>,
+    sk_x: Zeroizing<[u8; X25519_BYTES]>,
+    pk: PublicKey,
+}
+
+fn expand(sk: &SecretKey) -> Expanded {
+    let mut shake = Shake256::default();
+    shake.update(&sk.0);
+    let mut reader = shake.finalize_xof();
+    let mut seed_m = Zeroizing::new([0u8; 64]);
+    let mut sk_x = Zeroizing::new([0u8; X25519_BYTES]);
+    reader.read(&mut seed_m[..]);
+    reader.read(&mut sk_x[..]);
+
+    let mut pk = [0u8; PUBLIC_KEY_BYTES];
+    let mut sk_m = Zeroizing::new([0u8; kyber_params::SECRET_KEY_BYTES]);
+    kem::keygen_internal::<{ kyber_params::K }>(
+        &seed_m,
+        &mut pk[..kyber_params::PUBLIC_KEY_BYTES],
+        &mut sk_m[..],
+    );
+    pk[kyber_params::PUBLIC_KEY_BYTES..].copy_from_slice(&x25519(*sk_x, X25519_BASEPOINT_BYTES));
+
+    Expanded { sk_m, sk_x, pk: PublicKey(pk) }
+}
+
+fn combiner(ss_m: &[u8], ss_x: &[u8], ct_x: &[u8], pk_x: &[u8]) -> SharedSecret {
+    let mut hasher = Sha3_256::new();
+    Digest::update(&mut hasher, ss_m);
+    Digest::update(&mut hasher, ss_x);
+    Digest::update(&mut hasher, ct_x);
+    Digest::update(&mut hasher, pk_x);
+    Digest::update(&mut hasher, LABEL);
+    SharedSecret(hasher.finalize().into())
+}
+
+pub fn keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (PublicKey, SecretKey) {
+    let mut sk = SecretKey([0u8; SECRET_KEY_BYTES]);
+    rng.fill_bytes(&mut sk.0);
+    (sk.public_key(), sk)
+}
+
+/// Fails if the ML-KEM half of `pk` does not pass the FIPS 203 modulus check.
+pub fn encaps<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    pk: &PublicKey,
+) -> Result<(Ciphertext, SharedSecret), Error> {
+    let (pk_m, pk_x) = pk.0.split_at(kyber_params::PUBLIC_KEY_BYTES);
+    if !kem::check_public_key::<{ kyber_params::K }>(pk_m) {
+        return Err(Error::InvalidPublicKey);
+    }
+
+    let mut eseed = Zeroizing::new([0u8; 64]);
+    rng.fill_bytes(&mut eseed[..]);
+    let m: &[u8; 32] = eseed[..32].try_into().unwrap();
+    let ek_x: [u8; X25519_BYTES] = eseed[32..].try_into().unwrap();
+
+    let mut ct = [0u8; CIPHERTEXT_BYTES];
+    let mut ss_m = Zeroizing::new([0u8; 32]);
+    kem::encaps_internal::<{ kyber_params::K }>(
+        m,
+        pk_m,
+        &mut ss_m,
+        &mut ct[..kyber_params::CIPHERTEXT_BYTES],
+    );
+    let ct_x = x25519(ek_x, X25519_BASEPOINT_BYTES);
+    let ss_x = Zeroizing::new(x25519(ek_x, pk_x.try_into().unwrap()));
+    ct[kyber_params::CIPHERTEXT_BYTES..].copy_from_slice(&ct_x);
+
+    Ok((Ciphertext(ct), combiner(&ss_m[..], &ss_x[..], &ct_x, pk_x)))
+}
+
+pub fn decaps(sk: &SecretKey, ct: &Ciphertext) -> SharedSecret {
+    let expanded = expand(sk);
+    let (ct_m, ct_x) = ct.0.split_at(kyber_params::CIPHERTEXT_BYTES);
+    let pk_x = &expanded.pk.0[kyber_params::PUBLIC_KEY_BYTES..];
+
+    let mut ss_m = Zeroizing::new([0u8; 32]);
+    kem::decaps::<{ kyber_params::K }>(&expanded.sk_m[..], ct_m, &mut ss_m);
+    let ss_x = Zeroizing::new(x25519(*expanded.sk_x, ct_x.try_into().unwrap()));
+
+    combiner(&ss_m[..], &ss_x[..], ct_x, pk_x)
+}
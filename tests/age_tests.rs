@@ -0,0 +1,196 @@
+#![cfg(feature = "age")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::age::{decrypt, encrypt, Header, Identity, Recipient, CHUNK_BYTES, STANZA_TAG};
+use rusty_kyber::error::Error;
+
+// Files made by the age crate (0.11) with an mlkem768x25519 recipient over
+// the hpke crate's X-Wing; the key files use the seeds [0x42; 32] and
+// [0x17; 32].
+const KEY0: &str = include_str!("kat_vectors/age/key0.txt");
+const KEY1: &str = include_str!("kat_vectors/age/key1.txt");
+const CONTENT: &[u8] = include_bytes!("kat_vectors/age/content.txt");
+const ONE_RECIPIENT: &[u8] = include_bytes!("kat_vectors/age/mlkem768x25519.age");
+const TWO_RECIPIENTS: &[u8] = include_bytes!("kat_vectors/age/two-recipients.age");
+const MULTI_CHUNK: &[u8] = include_bytes!("kat_vectors/age/multi-chunk.age");
+
+// The identity and the recipient from its "# public key:" comment
+fn key_file(text: &str) -> (Identity, String) {
+    let mut lines = text.lines();
+    let recipient = lines.next().unwrap().strip_prefix("# public key: ").unwrap();
+    (
+        Identity::from_bech32(lines.next().unwrap()).unwrap(),
+        recipient.to_string(),
+    )
+}
+
+fn multi_chunk_content() -> Vec<u8> {
+    (0..150_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn key_file_encodings() {
+    for (text, seed) in [(KEY0, [0x42; 32]), (KEY1, [0x17; 32])] {
+        let (identity, recipient) = key_file(text);
+        assert_eq!(identity.secret_key().as_bytes(), seed);
+        assert_eq!(identity.to_recipient().to_bech32(), recipient);
+        assert_eq!(*identity.to_bech32(), text.lines().nth(1).unwrap());
+        assert!(recipient.starts_with("age1pq1"));
+
+        let parsed: Recipient = recipient.parse().unwrap();
+        assert_eq!(
+            parsed.public_key().as_bytes(),
+            identity.to_recipient().public_key().as_bytes()
+        );
+        assert_eq!(parsed.to_string(), recipient);
+    }
+}
+
+#[test]
+fn decrypts_reference_files() {
+    let (id0, _) = key_file(KEY0);
+    let (id1, _) = key_file(KEY1);
+    assert_eq!(*decrypt(&id0, ONE_RECIPIENT).unwrap(), CONTENT);
+    assert_eq!(decrypt(&id1, ONE_RECIPIENT).unwrap_err(), Error::NotRecipient);
+    assert_eq!(*decrypt(&id0, TWO_RECIPIENTS).unwrap(), CONTENT);
+    assert_eq!(*decrypt(&id1, TWO_RECIPIENTS).unwrap(), CONTENT);
+    assert_eq!(*decrypt(&id0, MULTI_CHUNK).unwrap(), multi_chunk_content());
+}
+
+#[test]
+fn reference_headers_round_trip() {
+    // age adds a "grease" stanza of a random type to every file
+    for (file, ours) in [(ONE_RECIPIENT, 1), (TWO_RECIPIENTS, 2), (MULTI_CHUNK, 1)] {
+        let (header, mac_len, payload) = Header::parse(file).unwrap();
+        assert_eq!(header.recipients.len(), ours + 1);
+        let stanzas = header.recipients.iter().filter(|stanza| stanza.tag == STANZA_TAG);
+        assert!(stanzas
+            .clone()
+            .all(|stanza| stanza.args.len() == 1 && stanza.body.len() == 32));
+        assert_eq!(stanzas.count(), ours);
+        assert_eq!(&file[mac_len..mac_len + 1], b" ");
+        assert_eq!(header.to_bytes(), &file[..payload]);
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(49);
+    let ids: Vec<Identity> = (0..3).map(|_| Identity::generate(&mut rng)).collect();
+    let recipients = [ids[0].to_recipient(), ids[1].to_recipient()];
+
+    for len in [0, 1, CHUNK_BYTES - 1, CHUNK_BYTES, CHUNK_BYTES + 1, 2 * CHUNK_BYTES] {
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let file = encrypt(&mut rng, &recipients, &plaintext).unwrap();
+        assert_eq!(*decrypt(&ids[0], &file).unwrap(), plaintext);
+        assert_eq!(*decrypt(&ids[1], &file).unwrap(), plaintext);
+        assert_eq!(decrypt(&ids[2], &file).unwrap_err(), Error::NotRecipient);
+    }
+
+    // A stanza moves between files only with its own file key
+    let (id0, _) = key_file(KEY0);
+    let file = encrypt(&mut rng, &[id0.to_recipient()], CONTENT).unwrap();
+    let (header, _, _) = Header::parse(&file).unwrap();
+    assert!(id0.unwrap_file_key(&header.recipients[0]).is_ok());
+    assert_eq!(
+        id0.unwrap_any(&Header::parse(ONE_RECIPIENT).unwrap().0.recipients)
+            .map(|k| k.len()),
+        Ok(16)
+    );
+    assert_eq!(encrypt(&mut rng, &[], CONTENT).unwrap_err(), Error::NotRecipient);
+}
+
+#[test]
+fn rejects_tampering() {
+    let (id0, _) = key_file(KEY0);
+    let (_, mac_len, payload) = Header::parse(ONE_RECIPIENT).unwrap();
+
+    // The header MAC covers the stanzas, and the MAC itself must match
+    let mut file = ONE_RECIPIENT.to_vec();
+    file[mac_len + 2] ^= 0x01;
+    assert!(decrypt(&id0, &file).is_err());
+    let mut file = ONE_RECIPIENT.to_vec();
+    let arg = file
+        .windows(STANZA_TAG.len())
+        .position(|w| w == STANZA_TAG.as_bytes())
+        .unwrap()
+        + STANZA_TAG.len()
+        + 1;
+    file[arg] = if file[arg] == b'A' { b'B' } else { b'A' };
+    assert!(decrypt(&id0, &file).is_err());
+
+    // Payload bit flips and truncation
+    let mut file = ONE_RECIPIENT.to_vec();
+    *file.last_mut().unwrap() ^= 0x01;
+    assert_eq!(decrypt(&id0, &file).unwrap_err(), Error::AuthenticationFailed);
+    assert_eq!(
+        decrypt(&id0, &ONE_RECIPIENT[..payload + 20]).unwrap_err(),
+        Error::Truncated
+    );
+    let first_chunk = Header::parse(MULTI_CHUNK).unwrap().2 + 16 + CHUNK_BYTES + 16;
+    assert_eq!(
+        decrypt(&id0, &MULTI_CHUNK[..first_chunk]).unwrap_err(),
+        Error::AuthenticationFailed
+    );
+    let mut file = MULTI_CHUNK[..first_chunk].to_vec();
+    file.extend_from_slice(&[0; 16]);
+    assert_eq!(decrypt(&id0, &file).unwrap_err(), Error::Truncated);
+
+    // Unknown versions and broken header syntax
+    let mut file = ONE_RECIPIENT.to_vec();
+    file[20] = b'2';
+    assert_eq!(decrypt(&id0, &file).unwrap_err(), Error::InvalidHeader);
+    assert_eq!(Header::parse(&ONE_RECIPIENT[..100]).unwrap_err(), Error::Truncated);
+    let file = [
+        &b"age-encryption.org/v1\n->  x\n\n--- "[..],
+        &ONE_RECIPIENT[mac_len + 1..],
+    ]
+    .concat();
+    assert_eq!(Header::parse(&file).unwrap_err(), Error::InvalidEncoding);
+}
+
+#[test]
+fn rejects_bad_encodings() {
+    let (identity, recipient) = key_file(KEY0);
+    let secret = identity.to_bech32();
+
+    // Classic X25519 recipients and identities are another type
+    let x25519 = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+    assert_eq!(
+        Recipient::from_bech32(x25519).err().unwrap(),
+        Error::UnsupportedAlgorithm
+    );
+    assert_eq!(
+        Recipient::from_bech32(&secret).err().unwrap(),
+        Error::UnsupportedAlgorithm
+    );
+    assert_eq!(
+        Identity::from_bech32(&recipient).err().unwrap().to_string(),
+        "unsupported algorithm"
+    );
+
+    // Either case works, a mix does not
+    assert!(Recipient::from_bech32(&recipient.to_uppercase()).is_ok());
+    assert!(Identity::from_bech32(&secret.to_lowercase()).is_ok());
+    let mixed = format!("AGE1PQ{}", &recipient[6..]);
+    assert_eq!(Recipient::from_bech32(&mixed).err().unwrap(), Error::InvalidEncoding);
+
+    // Checksum, alphabet and length
+    let mut typo = recipient.clone().into_bytes();
+    typo[100] = if typo[100] == b'q' { b'p' } else { b'q' };
+    assert_eq!(
+        Recipient::from_bech32(core::str::from_utf8(&typo).unwrap())
+            .err()
+            .unwrap(),
+        Error::InvalidEncoding
+    );
+    let bad_char = format!("{}b{}", &recipient[..100], &recipient[101..]);
+    assert_eq!(Recipient::from_bech32(&bad_char).err().unwrap(), Error::InvalidEncoding);
+    let short = &secret[..secret.len() - 7];
+    assert!(Identity::from_bech32(short).is_err());
+    assert_eq!(
+        Identity::from_bech32("AGE-SECRET-KEY-PQ-1QQQQQQQQ").err().unwrap(),
+        Error::InvalidEncoding
+    );
+}
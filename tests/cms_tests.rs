@@ -0,0 +1,431 @@
+#![cfg(feature = "cms")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::cms::{
+    cms_ori_for_kem_other_info, ContentCipher, EnvelopedData, Kdf, KemRecipientInfo, KeyWrap, RecipientId,
+};
+use rusty_kyber::error::Error;
+
+const CONTENT: &[u8] = include_bytes!("kat_vectors/cms/content.txt");
+const CIPHERS: [ContentCipher; 3] = [
+    ContentCipher::Aes128Cbc,
+    ContentCipher::Aes192Cbc,
+    ContentCipher::Aes256Cbc,
+];
+
+// Issuer `CN=ca` and a two-byte serial, as in the OpenSSL test certificates
+fn issuer_and_serial(serial: [u8; 2]) -> RecipientId {
+    let mut der = hex::decode("3013300d310b300906035504030c0263610202").unwrap();
+    der.extend_from_slice(&serial);
+    RecipientId::IssuerAndSerialNumber(der)
+}
+
+#[test]
+fn other_info_encoding() {
+    assert_eq!(
+        hex::encode(cms_ori_for_kem_other_info(KeyWrap::Aes256, None)),
+        "3010300b060960864801650304012d020120"
+    );
+    assert_eq!(
+        hex::encode(cms_ori_for_kem_other_info(KeyWrap::Aes128, Some(b"abc"))),
+        "3017300b0609608648016503040105020110a0050403616263"
+    );
+}
+
+macro_rules! generate_cms_tests {
+    ($level:ident, $feature:literal, $file:literal, $serial:expr, $ski:literal, $cipher:expr) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::cms::$level::{decrypt, encrypt, unwrap_content_key, wrap_content_key};
+            use rusty_kyber::$level::{SecretKey, SecretSeed};
+
+            fn openssl_key() -> SecretKey {
+                SecretKey::from_der(include_bytes!(concat!("kat_vectors/cms/", $file, ".key.der"))).unwrap()
+            }
+
+            // `openssl cms -encrypt -binary -outform DER` (OpenSSL 3.6) to a
+            // certificate for the key: by issuer and serial number, with
+            // `-stream` and with `-keyid`
+            #[test]
+            fn decrypts_openssl_messages() {
+                let sk = openssl_key();
+                let by_serial = issuer_and_serial($serial);
+                let by_keyid = RecipientId::SubjectKeyIdentifier(hex::decode($ski).unwrap());
+                for (message, rid, cipher) in [
+                    (
+                        &include_bytes!(concat!("kat_vectors/cms/", $file, ".der"))[..],
+                        &by_serial,
+                        $cipher,
+                    ),
+                    (
+                        &include_bytes!(concat!("kat_vectors/cms/", $file, ".stream.der"))[..],
+                        &by_serial,
+                        ContentCipher::Aes256Cbc,
+                    ),
+                    (
+                        &include_bytes!(concat!("kat_vectors/cms/", $file, ".keyid.der"))[..],
+                        &by_keyid,
+                        ContentCipher::Aes256Cbc,
+                    ),
+                ] {
+                    let enveloped = EnvelopedData::from_der(message).unwrap();
+                    assert_eq!(enveloped.cipher, cipher);
+                    let [ri] = &enveloped.recipients[..] else {
+                        panic!("one recipient")
+                    };
+                    assert_eq!(&ri.rid, rid);
+                    assert_eq!(ri.kem, rusty_kyber::der::$level::OID);
+                    assert_eq!(
+                        (ri.kdf, ri.wrap, ri.ukm.as_ref()),
+                        (Kdf::HkdfSha256, cipher.key_wrap(), None)
+                    );
+
+                    assert_eq!(&decrypt(&sk, None, message).unwrap()[..], CONTENT);
+                    assert_eq!(&decrypt(&sk, Some(rid), message).unwrap()[..], CONTENT);
+                    let other = if rid == &by_keyid { &by_serial } else { &by_keyid };
+                    assert_eq!(
+                        decrypt(&sk, Some(other), message).err(),
+                        Some(Error::NotRecipient)
+                    );
+                }
+
+                // The DER round-trips; the streamed BER is re-encoded as DER
+                let der = include_bytes!(concat!("kat_vectors/cms/", $file, ".der"));
+                assert_eq!(EnvelopedData::from_der(der).unwrap().to_der(), der);
+                let stream = include_bytes!(concat!("kat_vectors/cms/", $file, ".stream.der"));
+                let reencoded = EnvelopedData::from_der(stream).unwrap().to_der();
+                assert_eq!(&decrypt(&sk, None, &reencoded).unwrap()[..], CONTENT);
+            }
+
+            // To ML-KEM-512, ML-KEM-768 and ML-KEM-1024 certificates at once
+            #[test]
+            fn decrypts_openssl_multi_level_message() {
+                let message = include_bytes!("kat_vectors/cms/three-recipients.der");
+                assert_eq!(EnvelopedData::from_der(message).unwrap().recipients.len(), 3);
+                let rid = issuer_and_serial($serial);
+                assert_eq!(
+                    &decrypt(&openssl_key(), Some(&rid), message).unwrap()[..],
+                    CONTENT
+                );
+            }
+
+            #[test]
+            fn round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([170; 32]);
+                let (pk1, sk1) = SecretSeed::generate(&mut rng).expand();
+                let (pk2, sk2) = SecretSeed::generate(&mut rng).expand();
+                let (_, stranger) = SecretSeed::generate(&mut rng).expand();
+                let rid1 = issuer_and_serial([0x01, 0x23]);
+                let rid2 = RecipientId::SubjectKeyIdentifier(vec![7; 20]);
+
+                for cipher in CIPHERS {
+                    for content in [&b""[..], b"0123456789abcdef", CONTENT] {
+                        let der = encrypt(
+                            &mut rng,
+                            &[(&pk1, rid1.clone()), (&pk2, rid2.clone())],
+                            cipher,
+                            content,
+                        )
+                        .unwrap();
+                        let enveloped = EnvelopedData::from_der(&der).unwrap();
+                        assert_eq!(enveloped.to_der(), der);
+                        assert_eq!(enveloped.cipher, cipher);
+                        assert_eq!(enveloped.encrypted_content.len(), content.len() / 16 * 16 + 16);
+                        assert_eq!(enveloped.recipients.len(), 2);
+                        for ri in &enveloped.recipients {
+                            assert_eq!((ri.kdf, ri.wrap), (Kdf::HkdfSha256, cipher.key_wrap()));
+                            assert_eq!(KemRecipientInfo::from_der(&ri.to_der()).unwrap(), *ri);
+                        }
+
+                        assert_eq!(&decrypt(&sk1, None, &der).unwrap()[..], content);
+                        assert_eq!(&decrypt(&sk2, None, &der).unwrap()[..], content);
+                        assert_eq!(&decrypt(&sk2, Some(&rid2), &der).unwrap()[..], content);
+                        assert_eq!(
+                            decrypt(&sk1, Some(&rid2), &der).err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+                        assert_eq!(
+                            decrypt(&stranger, None, &der).err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+                    }
+                }
+                assert_eq!(
+                    encrypt(&mut rng, &[], ContentCipher::Aes256Cbc, b"").err(),
+                    Some(Error::InvalidLength)
+                );
+            }
+
+            #[test]
+            fn content_key_with_ukm() {
+                let mut rng = ChaCha20Rng::from_seed([171; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+                let cek = [9u8; 32];
+                let rid = RecipientId::SubjectKeyIdentifier(vec![1, 2, 3]);
+
+                for kdf in [Kdf::HkdfSha256, Kdf::HkdfSha384, Kdf::HkdfSha512] {
+                    for wrap in [KeyWrap::Aes128, KeyWrap::Aes192, KeyWrap::Aes256] {
+                        let ri = wrap_content_key(&mut rng, &pk, rid.clone(), kdf, wrap, Some(b"ukm"), &cek).unwrap();
+                        assert_eq!(ri.encrypted_key.len(), 40);
+                        let ri = KemRecipientInfo::from_der(&ri.to_der()).unwrap();
+                        assert_eq!(ri.ukm.as_deref(), Some(&b"ukm"[..]));
+                        assert_eq!(&unwrap_content_key(&sk, &ri).unwrap()[..], &cek);
+
+                        // The ukm, KDF and wrap all feed the key-encryption key
+                        let mut other = ri.clone();
+                        other.ukm = None;
+                        assert_eq!(
+                            unwrap_content_key(&sk, &other).err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+                        let mut other = ri.clone();
+                        other.kdf = if kdf == Kdf::HkdfSha256 {
+                            Kdf::HkdfSha512
+                        } else {
+                            Kdf::HkdfSha256
+                        };
+                        assert_eq!(
+                            unwrap_content_key(&sk, &other).err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+                        let mut other = ri.clone();
+                        other.wrap = if wrap == KeyWrap::Aes256 {
+                            KeyWrap::Aes128
+                        } else {
+                            KeyWrap::Aes256
+                        };
+                        assert_eq!(
+                            unwrap_content_key(&sk, &other).err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+                    }
+                }
+            }
+
+            #[test]
+            fn rejects_bad_input() {
+                let mut rng = ChaCha20Rng::from_seed([172; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+                let rid = issuer_and_serial([0, 1]);
+                let mut ri = wrap_content_key(
+                    &mut rng,
+                    &pk,
+                    rid.clone(),
+                    Kdf::HkdfSha256,
+                    KeyWrap::Aes256,
+                    None,
+                    &[5; 32],
+                )
+                .unwrap();
+                assert_eq!(
+                    wrap_content_key(
+                        &mut rng,
+                        &pk,
+                        rid.clone(),
+                        Kdf::HkdfSha256,
+                        KeyWrap::Aes256,
+                        None,
+                        &[5; 20]
+                    )
+                    .err(),
+                    Some(Error::InvalidLength)
+                );
+
+                // kekLength, just before the wrap algorithm and encrypted key
+                let mut der = ri.to_der();
+                let at = der.len() - 42 - 13 - 1;
+                assert_eq!(der[at], 32);
+                der[at] = 16;
+                assert_eq!(
+                    KemRecipientInfo::from_der(&der).err(),
+                    Some(Error::InvalidParameters)
+                );
+                assert_eq!(
+                    KemRecipientInfo::from_der(&ri.to_der()[1..]).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                ri.encrypted_key[0] ^= 1;
+                assert_eq!(
+                    unwrap_content_key(&sk, &ri).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+                ri.encrypted_key.truncate(36);
+                assert_eq!(unwrap_content_key(&sk, &ri).err(), Some(Error::InvalidLength));
+                ri.kemct.pop();
+                assert_eq!(unwrap_content_key(&sk, &ri).err(), Some(Error::InvalidLength));
+                ri.kem = vec![0x2a, 0x03];
+                assert_eq!(
+                    unwrap_content_key(&sk, &ri).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+
+                let der = encrypt(&mut rng, &[(&pk, rid)], ContentCipher::Aes256Cbc, CONTENT).unwrap();
+                assert_eq!(
+                    EnvelopedData::from_der(&der[..der.len() - 1]).err(),
+                    Some(Error::InvalidEncoding)
+                );
+                let mut trailing = der.clone();
+                trailing.push(0);
+                assert_eq!(
+                    EnvelopedData::from_der(&trailing).err(),
+                    Some(Error::InvalidEncoding)
+                );
+                // id-signedData in place of id-envelopedData
+                let mut signed = der.clone();
+                signed[14] = 0x02;
+                assert_eq!(
+                    EnvelopedData::from_der(&signed).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+
+                let mut enveloped = EnvelopedData::from_der(&der).unwrap();
+                assert_eq!(
+                    enveloped.decrypt_content(&[0; 16]).err(),
+                    Some(Error::InvalidLength)
+                );
+                enveloped.encrypted_content.pop();
+                let cek = unwrap_content_key(&sk, &enveloped.recipients[0]).unwrap();
+                assert_eq!(
+                    enveloped.decrypt_content(&cek).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+            }
+        }
+    };
+}
+
+generate_cms_tests!(
+    kyber512,
+    "kyber512",
+    "ml-kem-512",
+    [0x05, 0x12],
+    "3a89a78f06d3b1dddae1dcd6a6d78c26f199b231",
+    ContentCipher::Aes128Cbc
+);
+generate_cms_tests!(
+    kyber768,
+    "kyber768",
+    "ml-kem-768",
+    [0x07, 0x68],
+    "64813b27364e919a5d2c49c74bdb3b42f8866e36",
+    ContentCipher::Aes192Cbc
+);
+generate_cms_tests!(
+    kyber1024,
+    "kyber1024",
+    "ml-kem-1024",
+    [0x10, 0x24],
+    "7a8fb909b91cce5486efa1377145dc9f3819980d",
+    ContentCipher::Aes256Cbc
+);
+
+#[cfg(all(feature = "kyber512", feature = "kyber768"))]
+#[test]
+fn rejects_another_level() {
+    let mut rng = ChaCha20Rng::from_seed([173; 32]);
+    let (pk, _) = rusty_kyber::kyber512::SecretSeed::generate(&mut rng).expand();
+    let (_, sk) = rusty_kyber::kyber768::SecretSeed::generate(&mut rng).expand();
+    let rid = RecipientId::SubjectKeyIdentifier(vec![1]);
+    let der = rusty_kyber::cms::kyber512::encrypt(&mut rng, &[(&pk, rid)], ContentCipher::Aes128Cbc, b"x").unwrap();
+    let ri = &EnvelopedData::from_der(&der).unwrap().recipients[0];
+    assert_eq!(
+        rusty_kyber::cms::kyber768::unwrap_content_key(&sk, ri).err(),
+        Some(Error::UnsupportedAlgorithm)
+    );
+    assert_eq!(
+        rusty_kyber::cms::kyber768::decrypt(&sk, None, &der).err(),
+        Some(Error::NotRecipient)
+    );
+}
+
+// DER with a definite length, long enough for the recipient infos here
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        len @ 0..=127 => out.push(len as u8),
+        len @ 128..=255 => out.extend_from_slice(&[0x81, len as u8]),
+        len => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+// A KEMRecipientInfo with the given KEM and KDF AlgorithmIdentifiers, as
+// another implementation might write it
+fn foreign_kem_recipient(kem: &[u8], kdf: &[u8]) -> Vec<u8> {
+    let id_ori_kem = tlv(0x06, &hex::decode("2a864886f70d0109100d03").unwrap());
+    let aes256_wrap = tlv(0x30, &tlv(0x06, &hex::decode("60864801650304012d").unwrap()));
+    let info = tlv(
+        0x30,
+        &[
+            &tlv(0x02, &[0])[..],
+            &tlv(0x80, &[9; 20]),
+            kem,
+            &tlv(0x04, &[1; 32]),
+            kdf,
+            &tlv(0x02, &[32]),
+            &aes256_wrap,
+            &tlv(0x04, &[2; 40]),
+        ]
+        .concat(),
+    );
+    tlv(0xa4, &[id_ori_kem, info].concat())
+}
+
+// Inserts `ori` at the start of the recipient infos of an `encrypt` output,
+// fixing up the four enclosing lengths
+fn insert_recipient(der: &[u8], ori: &[u8]) -> Vec<u8> {
+    let mut out = der.to_vec();
+    for at in [1, 16, 20, 27] {
+        assert_eq!(out[at], 0x82);
+        let len = u16::from_be_bytes([out[at + 1], out[at + 2]]) as usize + ori.len();
+        out[at + 1..at + 3].copy_from_slice(&(len as u16).to_be_bytes());
+    }
+    assert_eq!(out[26], 0x31);
+    out.splice(30..30, ori.iter().copied());
+    out
+}
+
+#[cfg(feature = "kyber768")]
+#[test]
+fn skips_kem_recipients_for_other_software() {
+    use rusty_kyber::cms::kyber768::{decrypt, encrypt};
+    use rusty_kyber::kyber768::SecretSeed;
+
+    let mut rng = ChaCha20Rng::from_seed([174; 32]);
+    let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+    let rid = RecipientId::SubjectKeyIdentifier(vec![7]);
+    let der = encrypt(&mut rng, &[(&pk, rid.clone())], ContentCipher::Aes256Cbc, CONTENT).unwrap();
+
+    let ml_kem_768 = tlv(0x30, &tlv(0x06, &hex::decode("608648016503040402").unwrap()));
+    let hkdf_sha256 = tlv(0x30, &tlv(0x06, &hex::decode("2a864886f70d010910031c").unwrap()));
+    // ML-KEM-768 with id-kmac256 as the KDF
+    let kmac = foreign_kem_recipient(&ml_kem_768, &tlv(0x30, &tlv(0x06, &hex::decode("608648016503040214").unwrap())));
+    // RSA-KEM (RFC 9690), whose KEM AlgorithmIdentifier has parameters
+    let rsa_kem = foreign_kem_recipient(
+        &tlv(
+            0x30,
+            &[tlv(0x06, &hex::decode("2a864886f70d010910030e").unwrap()), tlv(0x30, &[])].concat(),
+        ),
+        &hkdf_sha256,
+    );
+
+    let der = insert_recipient(&insert_recipient(&der, &kmac), &rsa_kem);
+    let enveloped = EnvelopedData::from_der(&der).unwrap();
+    assert_eq!(enveloped.recipients.len(), 1);
+    assert_eq!(enveloped.recipients[0].rid, rid);
+    assert_eq!(&decrypt(&sk, Some(&rid), &der).unwrap()[..], CONTENT);
+    assert_eq!(&decrypt(&sk, None, &der).unwrap()[..], CONTENT);
+
+    // A malformed recipient info still fails the whole message
+    let mut broken = foreign_kem_recipient(&ml_kem_768, &hkdf_sha256);
+    let last = broken.len() - 1;
+    broken[last - 40] = 0x05;
+    assert_eq!(
+        EnvelopedData::from_der(&insert_recipient(&der, &broken)).err(),
+        Some(Error::InvalidEncoding)
+    );
+}
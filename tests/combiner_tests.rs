@@ -0,0 +1,253 @@
+#![cfg(feature = "kyber512")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::combiner::{Ciphertext, Combiner, Kdf};
+use rusty_kyber::kyber512::MlKem512;
+use rusty_kyber::utils::{h, kmac256};
+use rusty_kyber::Kem;
+
+#[cfg(feature = "hybrid")]
+mod common;
+
+const LABEL: &[u8] = b"rusty-kyber combiner test";
+
+const ALL_KDFS: [Kdf; 6] = [
+    Kdf::Composite,
+    Kdf::KitchenSink,
+    Kdf::Qsf,
+    Kdf::LengthPrefixedSha3_256,
+    Kdf::LengthPrefixedShake256,
+    Kdf::LengthPrefixedKmac256,
+];
+
+// NIST SP 800-185 KMAC256 samples 4 to 6
+#[test]
+fn kmac256_sp800_185_samples() {
+    let key: Vec<u8> = (0x40..0x60).collect();
+    let short: Vec<u8> = (0..4).collect();
+    let long: Vec<u8> = (0..200).collect();
+    let tag = b"My Tagged Application";
+
+    let cases: [(&[u8], &[u8], &str); 3] = [
+        (&short, tag, "20C570C31346F703C9AC36C61C03CB64C3970D0CFC787E9B79599D273A68D2F7F69D4CC3DE9D104A351689F27CF6F5951F0103F33F4F24871024D9C27773A8DD"),
+        (&long, b"", "75358CF39E41494E949707927CEE0AF20A3FF553904C86B08F21CC414BCFD691589D27CF5E15369CBBFF8B9A4C2EB17800855D0235FF635DA82533EC6B759B69"),
+        (&long, tag, "B58618F71F92E1D56C1B8C55DDD7CD188B97B4CA4D99831EB2699A837DA2E4D970FBACFDE50033AEA585F1A2708510C32D07880801BD182898FE476876FC8965"),
+    ];
+    for (data, custom, expected) in cases {
+        let mut out = [0u8; 64];
+        kmac256(&[&key], &[data], custom, &mut out);
+        assert_eq!(hex::encode_upper(out), expected);
+
+        // Splitting the key and message into parts does not change the result
+        let mut split = [0u8; 64];
+        kmac256(&[&key[..5], &key[5..]], &[&data[..1], &data[1..]], custom, &mut split);
+        assert_eq!(out, split);
+    }
+}
+
+fn round_trip<K1: Kem, K2: Kem>(kdf: Kdf, seed: u8) -> [u8; 32] {
+    let combiner = Combiner::<K1, K2>::new(kdf, LABEL);
+    let mut rng = ChaCha20Rng::from_seed([seed; 32]);
+    let (pk, sk) = combiner.keypair(&mut rng);
+    let (ct, ss) = combiner.encaps(&mut rng, &pk).unwrap();
+    let ss2 = combiner.decaps(&sk, &pk, &ct).unwrap();
+    assert_eq!(ss.as_bytes(), ss2.as_bytes());
+    ss.into()
+}
+
+#[test]
+fn kdfs_agree_on_both_sides_and_differ_from_each_other() {
+    let secrets = ALL_KDFS.map(|kdf| round_trip::<MlKem512, MlKem512>(kdf, 50));
+    // Composite and QSF are the same function
+    let function = |kdf| if kdf == Kdf::Composite { Kdf::Qsf } else { kdf };
+    for (a, kdf_a) in secrets.iter().zip(ALL_KDFS) {
+        for (b, kdf_b) in secrets.iter().zip(ALL_KDFS) {
+            assert_eq!(a == b, function(kdf_a) == function(kdf_b), "{:?} vs {:?}", kdf_a, kdf_b);
+        }
+    }
+}
+
+#[test]
+fn kitchen_sink_hashes_every_field_without_length_prefixes() {
+    let combiner = Combiner::<MlKem512, MlKem512>::new(Kdf::KitchenSink, LABEL);
+    let mut rng = ChaCha20Rng::from_seed([59; 32]);
+    let (pk, sk) = combiner.keypair(&mut rng);
+    let (ct, ss) = combiner.encaps(&mut rng, &pk).unwrap();
+
+    let ss1 = MlKem512::decaps(&sk.first, &ct.first).unwrap();
+    let ss2 = MlKem512::decaps(&sk.second, &ct.second).unwrap();
+    let input = [
+        ss1.as_bytes(),
+        ss2.as_bytes(),
+        ct.first.as_bytes(),
+        ct.second.as_bytes(),
+        pk.first.as_bytes(),
+        pk.second.as_bytes(),
+        LABEL,
+    ]
+    .concat();
+    let mut expected = [0u8; 32];
+    h(&input, &mut expected);
+    assert_eq!(ss.as_bytes(), expected);
+
+    // QSF drops the first ciphertext and public key
+    let qsf = Combiner::<MlKem512, MlKem512>::new(Kdf::Qsf, LABEL);
+    let input = [ss1.as_bytes(), ss2.as_bytes(), ct.second.as_bytes(), pk.second.as_bytes(), LABEL].concat();
+    h(&input, &mut expected);
+    assert_eq!(qsf.decaps(&sk, &pk, &ct).unwrap().as_bytes(), expected);
+}
+
+#[test]
+fn sha3_binds_secrets_ciphertexts_keys_and_label() {
+    let combiner = Combiner::<MlKem512, MlKem512>::new(Kdf::LengthPrefixedSha3_256, LABEL);
+    assert_eq!(combiner.algorithms(), ["ML-KEM-512", "ML-KEM-512"]);
+    assert_eq!(combiner.label(), LABEL);
+
+    let mut rng = ChaCha20Rng::from_seed([51; 32]);
+    let (pk, sk) = combiner.keypair(&mut rng);
+    let (ct, ss) = combiner.encaps(&mut rng, &pk).unwrap();
+
+    let ss1 = MlKem512::decaps(&sk.first, &ct.first).unwrap();
+    let ss2 = MlKem512::decaps(&sk.second, &ct.second).unwrap();
+    let mut input = Vec::new();
+    for part in [
+        ss1.as_bytes(),
+        ss2.as_bytes(),
+        ct.first.as_bytes(),
+        ct.second.as_bytes(),
+        pk.first.as_bytes(),
+        pk.second.as_bytes(),
+    ] {
+        input.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        input.extend_from_slice(part);
+    }
+    input.extend_from_slice(LABEL);
+    let mut expected = [0u8; 32];
+    h(&input, &mut expected);
+    assert_eq!(ss.as_bytes(), expected);
+
+    // A different label gives a different secret from the same exchange
+    let other = Combiner::<MlKem512, MlKem512>::new(Kdf::LengthPrefixedSha3_256, b"other");
+    let ss3 = other.decaps(&sk, &pk, &ct).unwrap();
+    assert_ne!(ss.as_bytes(), ss3.as_bytes());
+}
+
+#[test]
+fn kmac256_keys_on_the_secrets_and_customizes_with_the_label() {
+    let combiner = Combiner::<MlKem512, MlKem512>::new(Kdf::LengthPrefixedKmac256, LABEL);
+    let mut rng = ChaCha20Rng::from_seed([58; 32]);
+    let (pk, sk) = combiner.keypair(&mut rng);
+    let (ct, ss) = combiner.encaps(&mut rng, &pk).unwrap();
+
+    let ss1 = MlKem512::decaps(&sk.first, &ct.first).unwrap();
+    let ss2 = MlKem512::decaps(&sk.second, &ct.second).unwrap();
+    let prefixed = |parts: &[&[u8]]| -> Vec<u8> {
+        parts
+            .iter()
+            .flat_map(|part| [&(part.len() as u32).to_be_bytes()[..], part].concat())
+            .collect()
+    };
+    let key = prefixed(&[ss1.as_bytes(), ss2.as_bytes()]);
+    let data = prefixed(&[
+        ct.first.as_bytes(),
+        ct.second.as_bytes(),
+        pk.first.as_bytes(),
+        pk.second.as_bytes(),
+    ]);
+    let mut expected = [0u8; 32];
+    kmac256(&[&key], &[&data], LABEL, &mut expected);
+    assert_eq!(ss.as_bytes(), expected);
+}
+
+#[test]
+fn tampering_with_either_ciphertext_changes_the_secret() {
+    for kdf in ALL_KDFS {
+        let combiner = Combiner::<MlKem512, MlKem512>::new(kdf, LABEL);
+        let mut rng = ChaCha20Rng::from_seed([52; 32]);
+        let (pk, sk) = combiner.keypair(&mut rng);
+        let (ct, ss) = combiner.encaps(&mut rng, &pk).unwrap();
+
+        let mut first: [u8; 768] = ct.first.into();
+        first[0] ^= 1;
+        let bad = Ciphertext::<MlKem512, MlKem512> { first: first.into(), second: ct.second };
+        assert_ne!(combiner.decaps(&sk, &pk, &bad).unwrap().as_bytes(), ss.as_bytes());
+
+        let mut second: [u8; 768] = ct.second.into();
+        second[767] ^= 1;
+        let bad = Ciphertext::<MlKem512, MlKem512> { first: ct.first, second: second.into() };
+        assert_ne!(combiner.decaps(&sk, &pk, &bad).unwrap().as_bytes(), ss.as_bytes());
+    }
+}
+
+#[cfg(feature = "hybrid")]
+#[test]
+fn mlkem768_with_x25519() {
+    use rusty_kyber::ecdh::X25519;
+    use rusty_kyber::error::Error;
+    use rusty_kyber::kyber768::MlKem768;
+
+    for kdf in ALL_KDFS {
+        round_trip::<MlKem768, X25519>(kdf, 53);
+    }
+
+    // A low-order X25519 key is rejected instead of giving an all-zero secret
+    let combiner = Combiner::<MlKem768, X25519>::new(Kdf::LengthPrefixedSha3_256, LABEL);
+    let mut rng = ChaCha20Rng::from_seed([54; 32]);
+    let (mut pk, _) = combiner.keypair(&mut rng);
+    pk.second = [0; 32];
+    assert_eq!(combiner.encaps(&mut rng, &pk).err(), Some(Error::InvalidPublicKey));
+}
+
+// X-Wing is QSF over ML-KEM-768 and X25519, so the combiner has to reproduce
+// spec/test-vectors.json from draft-connolly-cfrg-xwing-kem. The X-Wing key
+// seed expands to the ML-KEM seed (d, z) followed by the X25519 secret, and
+// the encapsulation seed is the ML-KEM message followed by the ephemeral
+// X25519 secret.
+#[cfg(feature = "hybrid")]
+#[test]
+fn qsf_reproduces_the_xwing_vectors() {
+    use common::ReplayRng;
+    use rusty_kyber::ecdh::X25519;
+    use rusty_kyber::kyber768::MlKem768;
+    use rusty_kyber::utils::kdf;
+    use serde_json::Value;
+
+    let vectors: Vec<Value> = serde_json::from_str(include_str!("kat_vectors/xwing.json")).unwrap();
+    for v in vectors {
+        let field = |name: &str| hex::decode(v[name].as_str().unwrap()).unwrap();
+        let mut expanded = vec![0u8; 96];
+        kdf(&field("seed"), &mut expanded);
+
+        for combiner_kdf in [Kdf::Qsf, Kdf::Composite] {
+            let combiner = Combiner::<MlKem768, X25519>::new(combiner_kdf, b"\\.//^\\");
+            let (pk, sk) = combiner.keypair(&mut ReplayRng::new(expanded.clone()));
+            assert_eq!([pk.first.as_bytes(), &pk.second[..]].concat(), field("pk"));
+
+            let (ct, ss) = combiner.encaps(&mut ReplayRng::new(field("eseed")), &pk).unwrap();
+            assert_eq!([ct.first.as_bytes(), &ct.second[..]].concat(), field("ct"));
+            assert_eq!(ss.as_bytes(), field("ss"));
+            assert_eq!(combiner.decaps(&sk, &pk, &ct).unwrap().as_bytes(), field("ss"));
+        }
+    }
+}
+
+#[cfg(feature = "hybrid-nist")]
+#[test]
+fn mlkem_with_nist_curves() {
+    use rusty_kyber::ecdh::{P256, P384};
+    use rusty_kyber::error::Error;
+    use rusty_kyber::kyber1024::MlKem1024;
+    use rusty_kyber::kyber768::MlKem768;
+
+    for kdf in ALL_KDFS {
+        round_trip::<MlKem768, P256>(kdf, 55);
+        round_trip::<MlKem1024, P384>(kdf, 56);
+    }
+
+    let combiner = Combiner::<MlKem768, P256>::new(Kdf::LengthPrefixedSha3_256, LABEL);
+    let mut rng = ChaCha20Rng::from_seed([57; 32]);
+    let (mut pk, _) = combiner.keypair(&mut rng);
+    pk.second[64] ^= 1;
+    assert_eq!(combiner.encaps(&mut rng, &pk).err(), Some(Error::InvalidPublicKey));
+}
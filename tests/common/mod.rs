@@ -0,0 +1,37 @@
+// Helpers shared by the integration tests
+
+use rand_core::{CryptoRng, RngCore};
+
+// Hands out a fixed byte stream, so the vectors can pin every random input
+pub struct ReplayRng {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl ReplayRng {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        ReplayRng { bytes, pos: 0 }
+    }
+}
+
+impl RngCore for ReplayRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.copy_from_slice(&self.bytes[self.pos..self.pos + dest.len()]);
+        self.pos += dest.len();
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for ReplayRng {}
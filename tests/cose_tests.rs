@@ -0,0 +1,443 @@
+#![cfg(feature = "cose")]
+
+use ciborium::Value;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::cose::{
+    CoseKey, RecipientMode, A128GCM, A256GCM, HEADER_ALG, HEADER_EK, HEADER_IV, HEADER_KID,
+};
+use rusty_kyber::error::Error;
+
+const MODES: [RecipientMode; 3] = [
+    RecipientMode::Hpke,
+    RecipientMode::Direct,
+    RecipientMode::KeyWrap,
+];
+
+// Parses with an independent CBOR implementation
+fn parse(cbor: &[u8]) -> Value {
+    ciborium::from_reader(cbor).unwrap()
+}
+
+fn lookup(map: &Value, label: i64) -> Option<&Value> {
+    map.as_map()
+        .unwrap()
+        .iter()
+        .find(|(key, _)| key.as_integer() == Some(label.into()))
+        .map(|(_, value)| value)
+}
+
+fn int(value: &Value) -> i64 {
+    i64::try_from(value.as_integer().unwrap()).unwrap()
+}
+
+macro_rules! generate_cose_tests {
+    ($level:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::cose::$level::{
+                decrypt, decrypt0, encrypt, encrypt0, ALG, ALG_HPKE, ALG_HPKE_KE, ALG_KW,
+            };
+            use rusty_kyber::params::$level as kyber_params;
+            use rusty_kyber::$level::{PublicKey, SecretKey, SecretSeed};
+
+            #[test]
+            fn cose_key_round_trip() {
+                let seed = SecretSeed::generate(&mut ChaCha20Rng::from_seed([170; 32]));
+                let (pk, sk) = seed.expand();
+
+                let cbor = pk.to_cose_key().to_cbor();
+                let value = parse(&cbor);
+                assert_eq!(value.as_map().unwrap().len(), 3);
+                assert_eq!(int(lookup(&value, 1).unwrap()), 7);
+                assert_eq!(int(lookup(&value, 3).unwrap()), ALG);
+                assert_eq!(
+                    lookup(&value, -1).unwrap().as_bytes().unwrap()[..],
+                    pk.as_bytes()[..]
+                );
+                // Deterministic order: kty, alg, pub
+                assert_eq!(cbor[..3], [0xa3, 0x01, 0x07]);
+
+                let key = CoseKey::from_cbor(&cbor).unwrap();
+                assert_eq!(
+                    PublicKey::from_cose_key(&key).unwrap().as_bytes(),
+                    pk.as_bytes()
+                );
+                assert_eq!(
+                    SecretSeed::from_cose_key(&key).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                let mut private = seed.to_cose_key();
+                private.kid = Some(b"device-1".to_vec());
+                let cbor = private.to_cbor();
+                let value = parse(&cbor);
+                assert_eq!(lookup(&value, 2).unwrap().as_bytes().unwrap(), b"device-1");
+                assert_eq!(
+                    lookup(&value, -2).unwrap().as_bytes().unwrap()[..],
+                    seed.to_bytes()[..]
+                );
+
+                let key = CoseKey::from_cbor(&cbor).unwrap();
+                assert_eq!(key.kid.as_deref(), Some(&b"device-1"[..]));
+                assert_eq!(
+                    SecretSeed::from_cose_key(&key).unwrap().to_bytes(),
+                    seed.to_bytes()
+                );
+                assert_eq!(
+                    SecretKey::from_cose_key(&key).unwrap().as_bytes(),
+                    sk.as_bytes()
+                );
+                assert_eq!(
+                    PublicKey::from_cose_key(&key).unwrap().as_bytes(),
+                    pk.as_bytes()
+                );
+
+                // Any algorithm of the level is accepted, and unknown labels ignored
+                for alg in [ALG_KW, ALG_HPKE, ALG_HPKE_KE] {
+                    let mut key = pk.to_cose_key();
+                    key.alg = alg;
+                    let mut cbor = key.to_cbor().to_vec();
+                    cbor[0] += 1;
+                    cbor.extend_from_slice(&[0x3a, 0x00, 0x01, 0x00, 0x00, 0xf5]);
+                    let key = CoseKey::from_cbor(&cbor).unwrap();
+                    assert_eq!(
+                        PublicKey::from_cose_key(&key).unwrap().as_bytes(),
+                        pk.as_bytes()
+                    );
+                }
+            }
+
+            #[test]
+            fn cose_key_rejects_bad_keys() {
+                let mut rng = ChaCha20Rng::from_seed([171; 32]);
+                let seed = SecretSeed::generate(&mut rng);
+                let other = SecretSeed::generate(&mut rng);
+
+                let mut key = seed.to_cose_key();
+                key.kty = 1;
+                assert_eq!(
+                    PublicKey::from_cose_key(&key).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+                let mut key = seed.to_cose_key();
+                key.alg = -48;
+                assert_eq!(
+                    SecretSeed::from_cose_key(&key).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+
+                let mut key = seed.to_cose_key();
+                key.public = other.public_key().as_bytes().to_vec();
+                assert_eq!(
+                    SecretSeed::from_cose_key(&key).err(),
+                    Some(Error::InconsistentKey)
+                );
+                let mut key = seed.to_cose_key();
+                key.private.as_mut().unwrap().pop();
+                assert_eq!(
+                    SecretSeed::from_cose_key(&key).err(),
+                    Some(Error::InvalidLength)
+                );
+                let mut key = seed.to_cose_key();
+                key.public.pop();
+                assert_eq!(
+                    PublicKey::from_cose_key(&key).err(),
+                    Some(Error::InvalidLength)
+                );
+
+                // An unreduced first coefficient
+                let mut key = seed.to_cose_key();
+                key.public[..2].copy_from_slice(&[0xff, 0x0f]);
+                assert_eq!(
+                    PublicKey::from_cose_key(&key).err(),
+                    Some(Error::InvalidPublicKey)
+                );
+
+                let cbor = seed.public_key().to_cose_key().to_cbor();
+                let mut trailing = cbor.to_vec();
+                trailing.push(0);
+                assert_eq!(
+                    CoseKey::from_cbor(&trailing).err(),
+                    Some(Error::InvalidEncoding)
+                );
+                assert_eq!(
+                    CoseKey::from_cbor(&cbor[..cbor.len() - 1]).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                // {1: 7, 1: 7, 3: alg, -1: pub}
+                let mut duplicate = cbor.to_vec();
+                duplicate[0] += 1;
+                duplicate.splice(1..1, [0x01, 0x07]);
+                assert_eq!(
+                    CoseKey::from_cbor(&duplicate).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                // Text kty, an indefinite-length map, and no pub
+                assert_eq!(
+                    CoseKey::from_cbor(&[0xa2, 0x01, 0x63, b'A', b'K', b'P', 0x03, 0x20]).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+                assert_eq!(
+                    CoseKey::from_cbor(&[0xbf, 0x01, 0x07, 0xff]).err(),
+                    Some(Error::InvalidEncoding)
+                );
+                assert_eq!(
+                    CoseKey::from_cbor(&[0xa2, 0x01, 0x07, 0x03, 0x20]).err(),
+                    Some(Error::InvalidEncoding)
+                );
+            }
+
+            #[test]
+            fn encrypt0_round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([172; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+
+                let message =
+                    encrypt0(&mut rng, &pk, Some(b"k1"), b"sensor reading", b"aad").unwrap();
+                let value = parse(&message);
+                let (tag, inner) = value.as_tag().unwrap();
+                assert_eq!(tag, 16);
+                let items = inner.as_array().unwrap();
+                assert_eq!(items.len(), 3);
+                assert_eq!(
+                    int(lookup(&parse(items[0].as_bytes().unwrap()), HEADER_ALG).unwrap()),
+                    ALG_HPKE
+                );
+                assert_eq!(
+                    lookup(&items[1], HEADER_KID).unwrap().as_bytes().unwrap(),
+                    b"k1"
+                );
+                assert_eq!(
+                    lookup(&items[1], HEADER_EK)
+                        .unwrap()
+                        .as_bytes()
+                        .unwrap()
+                        .len(),
+                    kyber_params::CIPHERTEXT_BYTES
+                );
+                assert_eq!(items[2].as_bytes().unwrap().len(), 14 + 16);
+
+                assert_eq!(decrypt0(&sk, &message, b"aad").unwrap(), b"sensor reading");
+                assert_eq!(
+                    decrypt0(&sk, &message, b"other").err(),
+                    Some(Error::AuthenticationFailed)
+                );
+
+                // Untagged is accepted too
+                let untagged = &message[1..];
+                assert_eq!(decrypt0(&sk, untagged, b"aad").unwrap(), b"sensor reading");
+
+                let (_, other) = SecretSeed::generate(&mut rng).expand();
+                assert_eq!(
+                    decrypt0(&other, &message, b"aad").err(),
+                    Some(Error::AuthenticationFailed)
+                );
+                let mut tampered = message.clone();
+                *tampered.last_mut().unwrap() ^= 1;
+                assert_eq!(
+                    decrypt0(&sk, &tampered, b"aad").err(),
+                    Some(Error::AuthenticationFailed)
+                );
+
+                let multi = encrypt(
+                    &mut rng,
+                    &[(&pk, None)],
+                    RecipientMode::Hpke,
+                    A128GCM,
+                    b"x",
+                    b"",
+                )
+                .unwrap();
+                assert_eq!(decrypt0(&sk, &multi, b"").err(), Some(Error::InvalidHeader));
+                assert_eq!(
+                    decrypt0(&sk, &multi[1..], b"").err(),
+                    Some(Error::InvalidEncoding)
+                );
+            }
+
+            #[test]
+            fn encrypt_round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([173; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+
+                for mode in MODES {
+                    for content_alg in [A128GCM, A256GCM] {
+                        let message = encrypt(
+                            &mut rng,
+                            &[(&pk, Some(b"k1"))],
+                            mode,
+                            content_alg,
+                            b"firmware",
+                            b"aad",
+                        )
+                        .unwrap();
+                        assert_eq!(
+                            decrypt(&sk, Some(b"k1"), &message, b"aad").unwrap(),
+                            b"firmware"
+                        );
+                        assert_eq!(decrypt(&sk, None, &message, b"aad").unwrap(), b"firmware");
+                        assert_eq!(
+                            decrypt(&sk, Some(b"k2"), &message, b"aad").err(),
+                            Some(Error::NotRecipient)
+                        );
+                        assert_eq!(
+                            decrypt(&sk, None, &message, b"other").err(),
+                            Some(Error::AuthenticationFailed)
+                        );
+
+                        let value = parse(&message);
+                        let (tag, inner) = value.as_tag().unwrap();
+                        assert_eq!(tag, 96);
+                        let items = inner.as_array().unwrap();
+                        assert_eq!(
+                            int(lookup(&parse(items[0].as_bytes().unwrap()), HEADER_ALG).unwrap()),
+                            content_alg
+                        );
+                        assert_eq!(
+                            lookup(&items[1], HEADER_IV)
+                                .unwrap()
+                                .as_bytes()
+                                .unwrap()
+                                .len(),
+                            12
+                        );
+
+                        let recipient = items[3].as_array().unwrap()[0].as_array().unwrap();
+                        let alg =
+                            int(lookup(&parse(recipient[0].as_bytes().unwrap()), HEADER_ALG)
+                                .unwrap());
+                        let cek = if content_alg == A128GCM { 16 } else { 32 };
+                        let encrypted_key = recipient[2].as_bytes().unwrap().len();
+                        match mode {
+                            RecipientMode::Hpke => {
+                                assert_eq!((alg, encrypted_key), (ALG_HPKE_KE, cek + 16))
+                            }
+                            RecipientMode::Direct => assert_eq!((alg, encrypted_key), (ALG, 0)),
+                            RecipientMode::KeyWrap => {
+                                assert_eq!((alg, encrypted_key), (ALG_KW, cek + 8))
+                            }
+                        }
+                        assert_eq!(
+                            lookup(&recipient[1], HEADER_EK)
+                                .unwrap()
+                                .as_bytes()
+                                .unwrap()
+                                .len(),
+                            kyber_params::CIPHERTEXT_BYTES
+                        );
+                    }
+                }
+            }
+
+            #[test]
+            fn encrypt_to_several_recipients() {
+                let mut rng = ChaCha20Rng::from_seed([174; 32]);
+                let keys: Vec<_> = (0..3)
+                    .map(|_| SecretSeed::generate(&mut rng).expand())
+                    .collect();
+                let kids: [&[u8]; 3] = [b"a", b"b", b"c"];
+                let recipients: Vec<_> = keys
+                    .iter()
+                    .zip(kids)
+                    .map(|((pk, _), kid)| (pk, Some(kid)))
+                    .collect();
+
+                for mode in [RecipientMode::Hpke, RecipientMode::KeyWrap] {
+                    let message =
+                        encrypt(&mut rng, &recipients, mode, A256GCM, b"broadcast", b"").unwrap();
+                    for ((_, sk), kid) in keys.iter().zip(kids) {
+                        assert_eq!(decrypt(sk, Some(kid), &message, b"").unwrap(), b"broadcast");
+                        // Without a kid every recipient structure is tried
+                        assert_eq!(decrypt(sk, None, &message, b"").unwrap(), b"broadcast");
+                    }
+                    // The right kid with the wrong key
+                    assert_eq!(
+                        decrypt(&keys[0].1, Some(b"b"), &message, b"").err(),
+                        Some(Error::AuthenticationFailed)
+                    );
+                }
+
+                let err = encrypt(
+                    &mut rng,
+                    &recipients,
+                    RecipientMode::Direct,
+                    A256GCM,
+                    b"",
+                    b"",
+                )
+                .err();
+                assert_eq!(err, Some(Error::InvalidLength));
+                let err = encrypt(&mut rng, &[], RecipientMode::Hpke, A256GCM, b"", b"").err();
+                assert_eq!(err, Some(Error::InvalidLength));
+                let err =
+                    encrypt(&mut rng, &recipients[..1], RecipientMode::Hpke, 2, b"", b"").err();
+                assert_eq!(err, Some(Error::UnsupportedAlgorithm));
+            }
+
+            #[test]
+            fn decrypt_rejects_tampering() {
+                let mut rng = ChaCha20Rng::from_seed([175; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+
+                for mode in MODES {
+                    let message =
+                        encrypt(&mut rng, &[(&pk, None)], mode, A128GCM, b"payload", b"").unwrap();
+                    // The last byte of the recipient's ciphertext, or of ek before
+                    // the empty ciphertext in direct mode
+                    let at = message.len() - if mode == RecipientMode::Direct { 2 } else { 1 };
+                    let mut tampered = message.clone();
+                    tampered[at] ^= 1;
+                    assert_eq!(
+                        decrypt(&sk, None, &tampered, b"").err(),
+                        Some(Error::AuthenticationFailed)
+                    );
+                    assert_eq!(
+                        decrypt(&sk, None, &message[..message.len() - 1], b"").err(),
+                        Some(Error::InvalidEncoding)
+                    );
+                }
+            }
+        }
+    };
+}
+
+generate_cose_tests!(kyber512, "kyber512");
+generate_cose_tests!(kyber768, "kyber768");
+generate_cose_tests!(kyber1024, "kyber1024");
+
+#[cfg(all(feature = "kyber512", feature = "kyber768"))]
+#[test]
+fn rejects_another_level() {
+    let mut rng = ChaCha20Rng::from_seed([176; 32]);
+    let (pk, _) = rusty_kyber::kyber512::SecretSeed::generate(&mut rng).expand();
+    let (_, sk) = rusty_kyber::kyber768::SecretSeed::generate(&mut rng).expand();
+
+    let key = pk.to_cose_key();
+    assert_eq!(
+        rusty_kyber::kyber768::PublicKey::from_cose_key(&key).err(),
+        Some(Error::UnsupportedAlgorithm)
+    );
+
+    let message = rusty_kyber::cose::kyber512::encrypt0(&mut rng, &pk, None, b"", b"").unwrap();
+    assert_eq!(
+        rusty_kyber::cose::kyber768::decrypt0(&sk, &message, b"").err(),
+        Some(Error::UnsupportedAlgorithm)
+    );
+    let message = rusty_kyber::cose::kyber512::encrypt(
+        &mut rng,
+        &[(&pk, None)],
+        RecipientMode::Hpke,
+        A128GCM,
+        b"",
+        b"",
+    )
+    .unwrap();
+    assert_eq!(
+        rusty_kyber::cose::kyber768::decrypt(&sk, None, &message, b"").err(),
+        Some(Error::NotRecipient)
+    );
+}
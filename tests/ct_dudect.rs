@@ -0,0 +1,160 @@
+// dudect-style timing leakage checks (Reparaz, Balasch, Verbauwhede, 2017).
+//
+// Each test times an operation on two input classes, interleaved at random,
+// and runs Welch's t-test on the two timing distributions. |t| above 10 means
+// the classes are distinguishable and the code is not constant time.
+//
+// These are slow and need an optimized build, so they are ignored by default:
+//
+//     cargo test --release --test ct_dudect -- --ignored --nocapture
+//
+// Set DUDECT_SAMPLES to change the number of measurements per test.
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use rusty_kyber::params::{N, Q};
+use rusty_kyber::poly::Poly;
+use rusty_kyber::utils::poly_compress;
+use rusty_kyber::{decaps, encaps, keypair, Ciphertext, SecretKey};
+use std::hint::black_box;
+use std::time::Instant;
+
+const T_THRESHOLD: f64 = 10.0;
+
+// Percentiles used to crop the long tail of the timing distribution
+const CROP_PERCENTILES: [f64; 4] = [1.0, 0.9, 0.75, 0.5];
+
+// Online mean and variance (Welford)
+#[derive(Default)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        self.m2 / (self.n - 1.0)
+    }
+}
+
+// Welch's t statistic for two classes
+fn welch_t(a: &Moments, b: &Moments) -> f64 {
+    (a.mean - b.mean) / (a.variance() / a.n + b.variance() / b.n).sqrt()
+}
+
+fn samples() -> usize {
+    std::env::var("DUDECT_SAMPLES")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(100_000)
+}
+
+// Times `op` on class 0 and class 1 inputs chosen at random, and returns the
+// largest |t| over the cropping percentiles.
+fn measure<F: FnMut(usize)>(name: &str, mut op: F) -> f64 {
+    let mut rng = ChaCha20Rng::from_seed([0x5a; 32]);
+    let n = samples();
+
+    let mut classes = Vec::with_capacity(n);
+    let mut times = Vec::with_capacity(n);
+    for _ in 0..n {
+        let class = (rng.next_u32() & 1) as usize;
+        let start = Instant::now();
+        op(class);
+        times.push(start.elapsed().as_nanos() as f64);
+        classes.push(class);
+    }
+
+    // Discard the first 10% as warm-up
+    let skip = n / 10;
+    let mut sorted = times[skip..].to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let mut max_t: f64 = 0.0;
+    for &p in CROP_PERCENTILES.iter() {
+        let cutoff = sorted[((sorted.len() - 1) as f64 * p) as usize];
+        let mut m = [Moments::default(), Moments::default()];
+        for (&t, &c) in times[skip..].iter().zip(classes[skip..].iter()) {
+            if t <= cutoff {
+                m[c].push(t);
+            }
+        }
+        let t = welch_t(&m[0], &m[1]);
+        println!(
+            "{}: p{:>3} n = {:>6}/{:>6}, mean = {:.0}/{:.0} ns, t = {:.2}",
+            name,
+            (p * 100.0) as u32,
+            m[0].n,
+            m[1].n,
+            m[0].mean,
+            m[1].mean,
+            t
+        );
+        max_t = max_t.max(t.abs());
+    }
+    max_t
+}
+
+#[test]
+#[ignore]
+fn decaps_valid_vs_rejected_ciphertext() {
+    let mut rng = ChaCha20Rng::from_seed([1; 32]);
+    let (pk, sk) = keypair(&mut rng);
+    let (valid, _) = encaps(&mut rng, &pk);
+    let mut bytes: Vec<u8> = valid.as_bytes().to_vec();
+    bytes[0] ^= 1;
+    let rejected = Ciphertext::from(<[u8; _]>::try_from(&bytes[..]).unwrap());
+
+    let cts = [valid, rejected];
+    let t = measure("decaps valid/rejected", |class| {
+        black_box(decaps(black_box(&sk), black_box(&cts[class])));
+    });
+    assert!(t < T_THRESHOLD, "timing leak: |t| = {:.2}", t);
+}
+
+#[test]
+#[ignore]
+fn decaps_different_secret_keys() {
+    let mut rng = ChaCha20Rng::from_seed([2; 32]);
+    let (pk0, sk0) = keypair(&mut rng);
+    let (pk1, sk1) = keypair(&mut rng);
+    let (ct0, _) = encaps(&mut rng, &pk0);
+    let (ct1, _) = encaps(&mut rng, &pk1);
+
+    let inputs: [(SecretKey, Ciphertext); 2] = [(sk0, ct0), (sk1, ct1)];
+    let t = measure("decaps key0/key1", |class| {
+        let (sk, ct) = &inputs[class];
+        black_box(decaps(black_box(sk), black_box(ct)));
+    });
+    assert!(t < T_THRESHOLD, "timing leak: |t| = {:.2}", t);
+}
+
+#[test]
+#[ignore]
+fn compress_small_vs_large_coefficients() {
+    let mut rng = ChaCha20Rng::from_seed([3; 32]);
+    let small = Poly::new();
+    let mut large = Poly::new();
+    for c in large.coeffs.iter_mut() {
+        *c = (rng.next_u32() % Q as u32) as i16;
+    }
+    assert_eq!(large.coeffs.len(), N);
+
+    let polys = [small, large];
+    let mut buf = [0u8; 32 * 11];
+    let t = measure("poly_compress small/large", |class| {
+        for d in [1, 4, 5, 10, 11] {
+            poly_compress(black_box(&polys[class]), d, &mut buf[..32 * d]);
+        }
+        black_box(&buf);
+    });
+    assert!(t < T_THRESHOLD, "timing leak: |t| = {:.2}", t);
+}
@@ -0,0 +1,84 @@
+// Secret-taint ("ctgrind") checks. Every secret input is marked undefined for
+// Valgrind memcheck, so any branch or table lookup that depends on it shows up
+// as a "conditional jump depends on uninitialised value" error.
+//
+//     CARGO_TARGET_X86_64_UNKNOWN_LINUX_GNU_RUNNER="valgrind --error-exitcode=1 --track-origins=yes" \
+//         cargo test --release --features ctgrind --test ctgrind
+//
+// must pass, and the same with `-- --ignored planted` must fail, which is what
+// the ctgrind CI job checks. Outside Valgrind the client requests are no-ops
+// and this is a plain round trip.
+#![cfg(feature = "ctgrind")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+use rusty_kyber::declassify::{declassify, poison};
+use rusty_kyber::{decaps, encaps, keypair, Ciphertext};
+
+// Marks everything it hands out as secret: the keygen seed (d, z) and the
+// encapsulation message m.
+struct PoisonRng(ChaCha20Rng);
+
+impl RngCore for PoisonRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest);
+        poison(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for PoisonRng {}
+
+#[test]
+fn kem_is_secret_independent() {
+    let mut rng = PoisonRng(ChaCha20Rng::from_seed([7; 32]));
+
+    let (pk, sk) = keypair(&mut rng);
+    declassify(pk.as_bytes());
+    poison(sk.as_bytes());
+
+    let (ct, ss) = encaps(&mut rng, &pk);
+    declassify(ct.as_bytes());
+
+    let ss2 = decaps(&sk, &ct);
+
+    let mut bytes: Vec<u8> = ct.as_bytes().to_vec();
+    bytes[0] ^= 1;
+    let bad = Ciphertext::from(<[u8; _]>::try_from(&bytes[..]).unwrap());
+    let ss3 = decaps(&sk, &bad);
+
+    // Only the final comparisons look at the secrets
+    declassify(ss.as_bytes());
+    declassify(ss2.as_bytes());
+    declassify(ss3.as_bytes());
+    assert_eq!(ss.as_bytes(), ss2.as_bytes());
+    assert_ne!(ss.as_bytes(), ss3.as_bytes());
+}
+
+// Branches on a poisoned byte on purpose. Under Valgrind this must be
+// reported, which shows the client requests really reach memcheck.
+#[test]
+#[ignore = "fails under Valgrind by design"]
+fn planted_secret_branch_is_reported() {
+    let secret = [std::hint::black_box(0x5au8)];
+    poison(&secret);
+    let mut parity = 0u8;
+    if std::hint::black_box(secret[0]) & 1 == 1 {
+        parity = std::hint::black_box(1);
+    }
+    declassify(&secret);
+    declassify(core::slice::from_ref(&parity));
+    assert_eq!(parity, 0);
+}
@@ -0,0 +1,148 @@
+#![cfg(feature = "der")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::der::PrivateKeyForm;
+use rusty_kyber::error::Error;
+use serde_json::Value;
+
+// Keys generated with OpenSSL 3.5 `genpkey` and written with each of its
+// `ml-kem.output_formats`: seed-only, priv-only and seed-priv
+fn openssl_vectors(name: &str) -> (Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>) {
+    let all: Value = serde_json::from_str(include_str!("kat_vectors/der.json")).unwrap();
+    let get = |field: &str| hex::decode(all[name][field].as_str().unwrap()).unwrap();
+    (get("seed"), get("expanded"), get("both"), get("spki"))
+}
+
+macro_rules! generate_der_tests {
+    ($level:ident, $feature:literal, $name:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::der::$level::private_key_from_der;
+            use rusty_kyber::params::$level as kyber_params;
+            use rusty_kyber::$level::{self as level, PublicKey, SecretKey, SecretSeed};
+
+            const EK_OFFSET: usize = kyber_params::INDCPA_SECRET_KEY_BYTES;
+
+            #[test]
+            fn openssl_keys() {
+                let (seed_der, expanded_der, both_der, spki) = openssl_vectors($name);
+
+                let (sk, seed) = private_key_from_der(&seed_der).unwrap();
+                let seed = seed.unwrap();
+                let (from_expanded, no_seed) = private_key_from_der(&expanded_der).unwrap();
+                assert!(no_seed.is_none());
+                let (from_both, both_seed) = private_key_from_der(&both_der).unwrap();
+                assert_eq!(sk.as_bytes(), from_expanded.as_bytes());
+                assert_eq!(sk.as_bytes(), from_both.as_bytes());
+                assert_eq!(seed.to_bytes(), both_seed.unwrap().to_bytes());
+
+                let pk = PublicKey::from_der(&spki).unwrap();
+                assert_eq!(pk.as_bytes(), &sk.as_bytes()[EK_OFFSET..EK_OFFSET + kyber_params::PUBLIC_KEY_BYTES]);
+
+                // Our encodings are byte for byte what OpenSSL writes
+                assert_eq!(pk.to_der(), spki);
+                assert_eq!(sk.to_der()[..], expanded_der[..]);
+                assert_eq!(seed.to_der(PrivateKeyForm::Seed)[..], seed_der[..]);
+                assert_eq!(seed.to_der(PrivateKeyForm::ExpandedKey)[..], expanded_der[..]);
+                assert_eq!(seed.to_der(PrivateKeyForm::Both)[..], both_der[..]);
+                assert_eq!(SecretSeed::from_der(&both_der).unwrap().to_bytes(), seed.to_bytes());
+                assert_eq!(SecretSeed::from_der(&expanded_der).err(), Some(Error::InvalidEncoding));
+
+                let (ct, ss) = level::encaps(&mut ChaCha20Rng::from_seed([120; 32]), &pk);
+                assert_eq!(level::decaps(&sk, &ct).as_bytes(), ss.as_bytes());
+            }
+
+            #[test]
+            fn round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([121; 32]);
+                let (pk, sk) = level::keypair(&mut rng);
+                assert_eq!(PublicKey::from_der(&pk.to_der()).unwrap().as_bytes(), pk.as_bytes());
+                assert_eq!(SecretKey::from_der(&sk.to_der()).unwrap().as_bytes(), sk.as_bytes());
+
+                let seed = SecretSeed::generate(&mut rng);
+                let expected = SecretKey::from_der(&seed.to_der(PrivateKeyForm::Seed)).unwrap();
+                for form in [PrivateKeyForm::Seed, PrivateKeyForm::ExpandedKey, PrivateKeyForm::Both] {
+                    let (sk, got) = private_key_from_der(&seed.to_der(form)).unwrap();
+                    assert_eq!(got.map(|s| s.to_bytes()), (form != PrivateKeyForm::ExpandedKey).then(|| seed.to_bytes()));
+                    assert_eq!(sk.as_bytes(), expected.as_bytes());
+                }
+            }
+
+            #[test]
+            fn rejects_inconsistent_keys() {
+                let (_, expanded_der, both_der, _) = openssl_vectors($name);
+
+                // The expanded key in `both` from another seed
+                let mut bad = both_der.clone();
+                let last = bad.len() - 1;
+                bad[last] ^= 1;
+                assert_eq!(SecretKey::from_der(&bad).err(), Some(Error::InconsistentKey));
+
+                // A seed byte changed, so the expanded key no longer matches
+                let mut bad = both_der.clone();
+                bad[both_der.len() - kyber_params::SECRET_KEY_BYTES - 5] ^= 1;
+                assert_eq!(SecretKey::from_der(&bad).err(), Some(Error::InconsistentKey));
+
+                // H(ek) in the expanded key does not match ek
+                let mut bad = expanded_der.clone();
+                bad[expanded_der.len() - 40] ^= 1;
+                assert_eq!(SecretKey::from_der(&bad).err(), Some(Error::InconsistentKey));
+
+                // An unreduced coefficient in the embedded ek
+                let mut bad = expanded_der.clone();
+                let ek = expanded_der.len() - kyber_params::SECRET_KEY_BYTES + EK_OFFSET;
+                bad[ek] = 0xff;
+                bad[ek + 1] |= 0x0f;
+                assert_eq!(SecretKey::from_der(&bad).err(), Some(Error::InvalidPublicKey));
+            }
+
+            #[test]
+            fn rejects_malformed() {
+                let (seed_der, _, _, spki) = openssl_vectors($name);
+
+                let mut longer = spki.clone();
+                longer.push(0);
+                assert_eq!(PublicKey::from_der(&longer).err(), Some(Error::InvalidEncoding));
+                assert_eq!(PublicKey::from_der(&spki[..spki.len() - 1]).err(), Some(Error::InvalidEncoding));
+
+                // Unused bits in the BIT STRING
+                let mut bad = spki.clone();
+                bad[21] = 1;
+                assert_eq!(PublicKey::from_der(&bad).err(), Some(Error::InvalidEncoding));
+
+                // A public key where a private key is expected and the other way round
+                assert_eq!(SecretKey::from_der(&spki).err(), Some(Error::InvalidEncoding));
+                assert_eq!(PublicKey::from_der(&seed_der).err(), Some(Error::InvalidEncoding));
+
+                // Seeds must be 64 bytes: shorten the seed and every length around it
+                let mut short = seed_der.clone();
+                short.pop();
+                for i in [1, 19, 21] {
+                    short[i] -= 1;
+                }
+                assert_eq!(SecretKey::from_der(&short).err(), Some(Error::InvalidLength));
+
+                // Long-form length for a short value
+                let mut bad = seed_der[..19].to_vec();
+                bad[1] += 1;
+                bad.extend_from_slice(&[0x81, 0x42]);
+                bad.extend_from_slice(&seed_der[20..]);
+                assert_eq!(SecretKey::from_der(&bad).err(), Some(Error::InvalidEncoding));
+            }
+        }
+    };
+}
+
+generate_der_tests!(kyber512, "kyber512", "ML-KEM-512");
+generate_der_tests!(kyber768, "kyber768", "ML-KEM-768");
+generate_der_tests!(kyber1024, "kyber1024", "ML-KEM-1024");
+
+#[cfg(all(feature = "kyber512", feature = "kyber768"))]
+#[test]
+fn rejects_keys_for_another_level() {
+    let (seed_der, _, _, spki) = openssl_vectors("ML-KEM-512");
+    assert_eq!(rusty_kyber::kyber768::PublicKey::from_der(&spki).err(), Some(Error::UnsupportedAlgorithm));
+    assert_eq!(rusty_kyber::kyber768::SecretKey::from_der(&seed_der).err(), Some(Error::UnsupportedAlgorithm));
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "x448")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::ecdh::X448;
+use rusty_kyber::error::Error;
+use rusty_kyber::Kem;
+
+// RFC 7748 section 5.2 and 6.2
+#[test]
+fn x448_rfc7748_vectors() {
+    fn x448(scalar: &str, u: &str) -> Result<Vec<u8>, Error> {
+        let sk: [u8; 56] = hex::decode(scalar).unwrap().try_into().unwrap();
+        let pk: [u8; 56] = hex::decode(u).unwrap().try_into().unwrap();
+        X448::decaps(&sk.into(), &pk).map(|ss| ss.to_vec())
+    }
+
+    assert_eq!(
+        x448(
+            "3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3",
+            "06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086",
+        )
+        .unwrap(),
+        hex::decode("ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f").unwrap()
+    );
+    assert_eq!(
+        x448(
+            "203d494428b8399352665ddca42f9de8fef600908e0d461cb021f8c538345dd77c3e4806e25f46d3315c44e0a5b4371282dd2c8d5be3095f",
+            "0fbcc2f993cd56d3305b0b7d9e55d4c1a8fb5dbb52f8e9a1e9b6201b165d015894e56c4d3570bee52fe205e28a78b91cdfbde71ce8d157db",
+        )
+        .unwrap(),
+        hex::decode("884a02576239ff7a2f2f63b2db6a9ff37047ac13568e1e30fe63c4a7ad1b3ee3a5700df34321d62077e63633c575c1c954514e99da7c179d").unwrap()
+    );
+
+    let alice: [u8; 56] = hex::decode("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b").unwrap().try_into().unwrap();
+    let bob: [u8; 56] = hex::decode("1c306a7ac2a0e2e0990b294470cba339e6453772b075811d8fad0d1d6927c120bb5ee8972b0d3e21374c9c921b09d1b0366f10b65173992d").unwrap().try_into().unwrap();
+    let alice_pk = X448::public_key(&alice);
+    let bob_pk = X448::public_key(&bob);
+    assert_eq!(hex::encode(alice_pk), "9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bbc836647241d953d40c5b12da88120d53177f80e532c41fa0");
+    assert_eq!(hex::encode(bob_pk), "3eb7a829b0cd20f5bcfc0b599b6feccf6da4627107bdb0d4f345b43027d8b972fc3e34fb4232a13ca706dcb57aec3dae07bdc1c67bf33609");
+    let shared = "07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56fd2464c335543936521c24403085d59a449a5037514a879d";
+    assert_eq!(hex::encode(*X448::decaps(&alice.into(), &bob_pk).unwrap()), shared);
+    assert_eq!(hex::encode(*X448::decaps(&bob.into(), &alice_pk).unwrap()), shared);
+}
+
+// RFC 7748 section 5.2, iterated; the 1,000,000 step is left out for time
+#[test]
+fn x448_rfc7748_iterations() {
+    let mut k = X448::BASEPOINT;
+    let mut u = X448::BASEPOINT;
+    for i in 1..=1000 {
+        let out = *X448::decaps(&k.into(), &u).unwrap();
+        u = k;
+        k = out;
+        if i == 1 {
+            assert_eq!(hex::encode(k), "3f482c8a9f19b01e6c46ee9711d9dc14fd4bf67af30765c2ae2b846a4d23a8cd0db897086239492caf350b51f833868b9bc2b3bca9cf4113");
+        }
+    }
+    assert_eq!(hex::encode(k), "aa3b4749d55b9daf1e5b00288826c467274ce3ebbdd5c17b975e09d4af6c67cf10d087202db88286e2b79fceea3ec353ef54faa26e219f38");
+}
+
+#[test]
+fn x448_rejects_low_order_points() {
+    let mut rng = ChaCha20Rng::from_seed([36; 32]);
+    let (pk, sk) = X448::keypair(&mut rng);
+    let (ct, ss) = X448::encaps(&mut rng, &pk).unwrap();
+    assert_eq!(*X448::decaps(&sk, &ct).unwrap(), *ss);
+
+    // 0, 1 and p - 1
+    let mut minus_one = [0xff; 56];
+    minus_one[0] = 0xfe;
+    minus_one[28] = 0xfe;
+    let mut one = [0; 56];
+    one[0] = 1;
+    for point in [[0; 56], one, minus_one] {
+        assert_eq!(X448::decaps(&sk, &point).err(), Some(Error::InvalidPublicKey));
+        assert_eq!(X448::encaps(&mut rng, &point).err(), Some(Error::InvalidPublicKey));
+    }
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "envelope")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::envelope::{self, PREFIX_BYTES};
+use rusty_kyber::error::Error;
+
+macro_rules! generate_envelope_tests {
+    ($level:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::envelope::$level::{key_id, open, recipients, seal, secret_key_id, STANZA_BYTES};
+            use rusty_kyber::$level::{self as level, PublicKey, SecretKey};
+
+            fn team(rng: &mut ChaCha20Rng, n: usize) -> (Vec<PublicKey>, Vec<SecretKey>) {
+                (0..n).map(|_| level::keypair(rng)).unzip()
+            }
+
+            #[test]
+            fn every_recipient_opens() {
+                let mut rng = ChaCha20Rng::from_seed([90; 32]);
+                let (pks, sks) = team(&mut rng, 3);
+                let sealed = seal(&mut rng, &pks, b"release artifact", b"v1.2").unwrap();
+                assert_eq!(sealed.len(), PREFIX_BYTES + 3 * STANZA_BYTES + 16 + 16);
+
+                for sk in &sks {
+                    assert_eq!(open(sk, &sealed, b"v1.2").unwrap(), b"release artifact");
+                }
+
+                let (_, outsider) = level::keypair(&mut rng);
+                assert_eq!(open(&outsider, &sealed, b"v1.2"), Err(Error::NotRecipient));
+            }
+
+            #[test]
+            fn key_ids() {
+                let mut rng = ChaCha20Rng::from_seed([91; 32]);
+                let (pks, sks) = team(&mut rng, 2);
+                for (pk, sk) in pks.iter().zip(&sks) {
+                    assert_eq!(key_id(pk), secret_key_id(sk));
+                }
+                assert_ne!(key_id(&pks[0]), key_id(&pks[1]));
+
+                let sealed = seal(&mut rng, &pks, b"", b"").unwrap();
+                assert_eq!(recipients(&sealed).unwrap(), vec![key_id(&pks[0]), key_id(&pks[1])]);
+            }
+
+            #[test]
+            fn rejects_tampering() {
+                let mut rng = ChaCha20Rng::from_seed([92; 32]);
+                let (pks, sks) = team(&mut rng, 2);
+                let sealed = seal(&mut rng, &pks, b"payload", b"aad").unwrap();
+
+                assert_eq!(open(&sks[0], &sealed, b"other"), Err(Error::AuthenticationFailed));
+
+                // Our ML-KEM ciphertext, our wrapped key, the other stanza and the payload
+                let second = PREFIX_BYTES + STANZA_BYTES;
+                for i in [PREFIX_BYTES + 32, second - 1, second + 40, sealed.len() - 1] {
+                    let mut bad = sealed.clone();
+                    bad[i] ^= 1;
+                    assert_eq!(open(&sks[0], &bad, b"aad"), Err(Error::AuthenticationFailed), "byte {i}");
+                }
+
+                // Changing our key ID hides our stanza
+                let mut bad = sealed.clone();
+                bad[PREFIX_BYTES] ^= 1;
+                assert_eq!(open(&sks[0], &bad, b"aad"), Err(Error::NotRecipient));
+
+                let mut bad = sealed.clone();
+                bad[0] = 2;
+                assert_eq!(open(&sks[0], &bad, b"aad"), Err(Error::InvalidHeader));
+            }
+
+            #[test]
+            fn rejects_removed_stanza() {
+                let mut rng = ChaCha20Rng::from_seed([93; 32]);
+                let (pks, sks) = team(&mut rng, 2);
+                let sealed = seal(&mut rng, &pks, b"payload", b"").unwrap();
+
+                // Drop the second recipient and fix up the count
+                let mut cut = sealed[..PREFIX_BYTES + STANZA_BYTES].to_vec();
+                cut[4] = 1;
+                cut.extend_from_slice(&sealed[PREFIX_BYTES + 2 * STANZA_BYTES..]);
+                assert_eq!(open(&sks[0], &cut, b""), Err(Error::AuthenticationFailed));
+
+                // A count that runs past the end
+                let mut bad = sealed.clone();
+                bad[4] = 3;
+                assert_eq!(open(&sks[0], &bad, b""), Err(Error::InvalidLength));
+            }
+
+            #[test]
+            fn rejects_empty_recipient_list() {
+                let mut rng = ChaCha20Rng::from_seed([94; 32]);
+                assert_eq!(seal(&mut rng, &[], b"payload", b""), Err(Error::InvalidLength));
+            }
+        }
+    };
+}
+
+generate_envelope_tests!(kyber512, "kyber512");
+generate_envelope_tests!(kyber768, "kyber768");
+generate_envelope_tests!(kyber1024, "kyber1024");
+
+#[test]
+fn top_level_follows_highest_level() {
+    let mut rng = ChaCha20Rng::from_seed([95; 32]);
+    let (pk, sk) = rusty_kyber::keypair(&mut rng);
+    let sealed = envelope::seal(&mut rng, &[pk], b"msg", b"").unwrap();
+    assert_eq!(envelope::kem_id(&sealed), Ok(envelope::KEM_ID));
+    assert_eq!(envelope::open(&sk, &sealed, b"").unwrap(), b"msg");
+}
@@ -0,0 +1,281 @@
+#![cfg(feature = "hpke")]
+
+use rand_core::{CryptoRng, RngCore};
+use rusty_kyber::error::Error;
+use rusty_kyber::hpke::{
+    Aead, AesGcm128, AesGcm256, ChaCha20Poly1305, HkdfSha256, HkdfSha512, Hpke, HpkeKem, Kdf,
+};
+use rusty_kyber::Kem;
+use serde_json::Value;
+
+mod common;
+use common::ReplayRng;
+
+fn vectors(set: &str) -> Vec<Value> {
+    let all: Value = serde_json::from_str(include_str!("kat_vectors/hpke.json")).unwrap();
+    all[set].as_array().unwrap().clone()
+}
+
+fn field(v: &Value, name: &str) -> Vec<u8> {
+    hex::decode(v[name].as_str().unwrap()).unwrap()
+}
+
+fn suite_ids(v: &Value) -> (u64, u64, u64) {
+    (v["kem_id"].as_u64().unwrap(), v["kdf_id"].as_u64().unwrap(), v["aead_id"].as_u64().unwrap())
+}
+
+// Checks the sender against every encryption and export of the vector, then
+// the recipient with the vector's enc
+fn check_contexts<K: HpkeKem, H: Kdf, A: Aead>(
+    v: &Value,
+    pk: &K::PublicKey,
+    sk: &K::SecretKey,
+    enc: impl Fn(&[u8]) -> K::Ciphertext,
+    ikm_e: Vec<u8>,
+) {
+    let info = field(v, "info");
+    let psk = v.get("psk").map(|_| (field(v, "psk"), field(v, "psk_id")));
+    let mut rng = ReplayRng::new(ikm_e);
+
+    let (sent_enc, mut sender) = match &psk {
+        None => Hpke::<K, H, A>::setup_base_s(&mut rng, pk, &info).unwrap(),
+        Some((psk, psk_id)) => Hpke::<K, H, A>::setup_psk_s(&mut rng, pk, &info, psk, psk_id).unwrap(),
+    };
+    assert_eq!(sent_enc.as_ref(), &field(v, "enc")[..]);
+
+    let enc = enc(&field(v, "enc"));
+    let mut recipient = match &psk {
+        None => Hpke::<K, H, A>::setup_base_r(&enc, sk, &info).unwrap(),
+        Some((psk, psk_id)) => Hpke::<K, H, A>::setup_psk_r(&enc, sk, &info, psk, psk_id).unwrap(),
+    };
+
+    for e in v["encryptions"].as_array().unwrap() {
+        let (aad, pt, ct) = (field(e, "aad"), field(e, "pt"), field(e, "ct"));
+        assert_eq!(sender.seal(&aad, &pt).unwrap(), ct);
+        assert_eq!(recipient.open(&aad, &ct).unwrap(), pt);
+    }
+
+    for e in v["exports"].as_array().unwrap() {
+        let mut out = vec![0u8; e["L"].as_u64().unwrap() as usize];
+        sender.export(&field(e, "exporter_context"), &mut out).unwrap();
+        assert_eq!(out, field(e, "exported_value"));
+        recipient.export(&field(e, "exporter_context"), &mut out).unwrap();
+        assert_eq!(out, field(e, "exported_value"));
+    }
+}
+
+macro_rules! generate_pq_vector_test {
+    ($name:ident, $feature:literal, $kem:ty, $kdf:ty, $aead:ty, $ciphertext:ty) => {
+        // Base mode vectors from draft-ietf-hpke-pq
+        #[cfg(feature = $feature)]
+        #[test]
+        fn $name() {
+            let kem_id = <$kem as HpkeKem>::KEM_ID as u64;
+            let suite = (kem_id, <$kdf as Kdf>::KDF_ID as u64, <$aead as Aead>::AEAD_ID as u64);
+            let v = vectors("pq").into_iter().find(|v| suite_ids(v) == suite).unwrap();
+
+            let (pk, sk) = <$kem>::derive_keypair(&field(&v, "ikmR"));
+            assert_eq!(pk.as_ref(), &field(&v, "pkRm")[..]);
+
+            check_contexts::<$kem, $kdf, $aead>(
+                &v,
+                &pk,
+                &sk,
+                |enc| <$ciphertext>::from(<[u8; <$kem as HpkeKem>::NENC]>::try_from(enc).unwrap()),
+                field(&v, "ikmE"),
+            );
+        }
+    };
+}
+
+generate_pq_vector_test!(
+    mlkem512_vectors,
+    "kyber512",
+    rusty_kyber::kyber512::MlKem512,
+    HkdfSha256,
+    AesGcm128,
+    rusty_kyber::kyber512::Ciphertext
+);
+generate_pq_vector_test!(
+    mlkem768_vectors,
+    "kyber768",
+    rusty_kyber::kyber768::MlKem768,
+    HkdfSha256,
+    AesGcm128,
+    rusty_kyber::kyber768::Ciphertext
+);
+generate_pq_vector_test!(
+    mlkem1024_vectors,
+    "kyber1024",
+    rusty_kyber::kyber1024::MlKem1024,
+    rusty_kyber::hpke::HkdfSha384,
+    AesGcm256,
+    rusty_kyber::kyber1024::Ciphertext
+);
+generate_pq_vector_test!(
+    xwing_vectors,
+    "xwing",
+    rusty_kyber::xwing::XWing,
+    HkdfSha256,
+    ChaCha20Poly1305,
+    rusty_kyber::xwing::Ciphertext
+);
+
+// Stands in for DHKEM(X25519, HKDF-SHA256) by replaying the vector's shared
+// secret, so the RFC 9180 vectors cover the key schedule, PSK mode and AEADs
+struct FixedKem;
+
+struct FixedPublicKey {
+    enc: Vec<u8>,
+    shared_secret: Vec<u8>,
+}
+
+impl AsRef<[u8]> for FixedPublicKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.enc
+    }
+}
+
+impl Kem for FixedKem {
+    const NAME: &'static str = "DHKEM(X25519, HKDF-SHA256)";
+
+    type PublicKey = FixedPublicKey;
+    type SecretKey = Vec<u8>;
+    type Ciphertext = Vec<u8>;
+    type SharedSecret = [u8; 32];
+
+    fn keypair<R: RngCore + CryptoRng>(_rng: &mut R) -> (FixedPublicKey, Vec<u8>) {
+        unreachable!("RFC 9180 vectors only use fixed encaps/decaps")
+    }
+
+    fn encaps<R: RngCore + CryptoRng>(_rng: &mut R, pk: &FixedPublicKey) -> Result<(Vec<u8>, [u8; 32]), Error> {
+        Ok((pk.enc.clone(), pk.shared_secret[..].try_into().unwrap()))
+    }
+
+    fn decaps(sk: &Vec<u8>, _ct: &Vec<u8>) -> Result<[u8; 32], Error> {
+        Ok(sk[..].try_into().unwrap())
+    }
+}
+
+impl HpkeKem for FixedKem {
+    const KEM_ID: u16 = 0x0020;
+    const NPK: usize = 32;
+    const NENC: usize = 32;
+
+    fn derive_keypair(_ikm: &[u8]) -> (FixedPublicKey, Vec<u8>) {
+        unreachable!("RFC 9180 vectors only use fixed encaps/decaps")
+    }
+}
+
+fn check_rfc_vector<H: Kdf, A: Aead>(v: &Value) {
+    let pk = FixedPublicKey { enc: field(v, "enc"), shared_secret: field(v, "shared_secret") };
+    check_contexts::<FixedKem, H, A>(v, &pk, &field(v, "shared_secret"), |enc| enc.to_vec(), Vec::new());
+}
+
+#[test]
+fn rfc9180_key_schedule_vectors() {
+    let vectors = vectors("rfc9180");
+    assert_eq!(vectors.len(), 12);
+
+    for v in &vectors {
+        match suite_ids(v) {
+            (0x20, 1, 1) => check_rfc_vector::<HkdfSha256, AesGcm128>(v),
+            (0x20, 1, 2) => check_rfc_vector::<HkdfSha256, AesGcm256>(v),
+            (0x20, 1, 3) => check_rfc_vector::<HkdfSha256, ChaCha20Poly1305>(v),
+            (0x20, 3, 1) => check_rfc_vector::<HkdfSha512, AesGcm128>(v),
+            (0x20, 3, 2) => check_rfc_vector::<HkdfSha512, AesGcm256>(v),
+            (0x20, 3, 3) => check_rfc_vector::<HkdfSha512, ChaCha20Poly1305>(v),
+            ids => panic!("unexpected suite {ids:?}"),
+        }
+    }
+}
+
+#[cfg(feature = "kyber512")]
+mod mlkem512 {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::SeedableRng;
+    use rusty_kyber::kyber512::{MlKem512, PublicKey};
+
+    type Suite = Hpke<MlKem512, HkdfSha256, ChaCha20Poly1305>;
+
+    #[test]
+    fn suite_id() {
+        assert_eq!(&Suite::SUITE_ID, b"HPKE\x00\x40\x00\x01\x00\x03");
+    }
+
+    #[test]
+    fn single_shot_round_trip() {
+        let mut rng = ChaCha20Rng::from_seed([60; 32]);
+        let (pk, sk) = MlKem512::keypair(&mut rng);
+
+        let (enc, ct) = Suite::seal_base(&mut rng, &pk, b"info", b"aad", b"plaintext").unwrap();
+        assert_eq!(ct.len(), b"plaintext".len() + rusty_kyber::hpke::TAG_BYTES);
+        assert_eq!(Suite::open_base(&enc, &sk, b"info", b"aad", &ct).unwrap(), b"plaintext");
+        assert_eq!(Suite::open_base(&enc, &sk, b"other", b"aad", &ct), Err(Error::AuthenticationFailed));
+        assert_eq!(Suite::open_base(&enc, &sk, b"info", b"other", &ct), Err(Error::AuthenticationFailed));
+
+        let (enc, ct) = Suite::seal_psk(&mut rng, &pk, b"info", b"aad", b"plaintext", &[7; 32], b"id").unwrap();
+        assert_eq!(Suite::open_psk(&enc, &sk, b"info", b"aad", &ct, &[7; 32], b"id").unwrap(), b"plaintext");
+        assert_eq!(
+            Suite::open_psk(&enc, &sk, b"info", b"aad", &ct, &[8; 32], b"id"),
+            Err(Error::AuthenticationFailed)
+        );
+        assert_eq!(Suite::open_base(&enc, &sk, b"info", b"aad", &ct), Err(Error::AuthenticationFailed));
+    }
+
+    #[test]
+    fn rejects_inconsistent_psk_inputs() {
+        let mut rng = ChaCha20Rng::from_seed([61; 32]);
+        let (pk, _) = MlKem512::keypair(&mut rng);
+
+        for (psk, psk_id) in [(&[7u8; 32][..], &b""[..]), (b"", b"id"), (b"", b"")] {
+            let res = Suite::setup_psk_s(&mut rng, &pk, b"", psk, psk_id);
+            assert_eq!(res.err(), Some(Error::InvalidPsk));
+        }
+    }
+
+    #[test]
+    fn failed_open_does_not_advance_the_sequence() {
+        let mut rng = ChaCha20Rng::from_seed([62; 32]);
+        let (pk, sk) = MlKem512::keypair(&mut rng);
+        let (enc, mut sender) = Suite::setup_base_s(&mut rng, &pk, b"").unwrap();
+        let mut recipient = Suite::setup_base_r(&enc, &sk, b"").unwrap();
+
+        let first = sender.seal(b"", b"first").unwrap();
+        let second = sender.seal(b"", b"second").unwrap();
+
+        // Out of order, and then tampered with
+        assert_eq!(recipient.open(b"", &second), Err(Error::AuthenticationFailed));
+        let mut tampered = first.clone();
+        tampered[0] ^= 1;
+        assert_eq!(recipient.open(b"", &tampered), Err(Error::AuthenticationFailed));
+
+        assert_eq!(recipient.open(b"", &first).unwrap(), b"first");
+        assert_eq!(recipient.open(b"", &second).unwrap(), b"second");
+    }
+
+    #[test]
+    fn export_length_is_bounded() {
+        let mut rng = ChaCha20Rng::from_seed([63; 32]);
+        let (pk, _) = MlKem512::keypair(&mut rng);
+        let (_, sender) = Suite::setup_base_s(&mut rng, &pk, b"").unwrap();
+
+        let mut out = vec![0u8; 255 * 32];
+        sender.export(b"ctx", &mut out).unwrap();
+        out.push(0);
+        assert_eq!(sender.export(b"ctx", &mut out), Err(Error::InvalidLength));
+    }
+
+    #[test]
+    fn rejects_unreduced_encapsulation_key() {
+        let mut rng = ChaCha20Rng::from_seed([64; 32]);
+        let (pk, _) = MlKem512::keypair(&mut rng);
+        let mut bytes: [u8; 800] = pk.into();
+        // First coefficient = 0xfff >= q
+        bytes[0] = 0xff;
+        bytes[1] |= 0x0f;
+        let res = Suite::setup_base_s(&mut rng, &PublicKey::from(bytes), b"");
+        assert_eq!(res.err(), Some(Error::InvalidPublicKey));
+    }
+}
@@ -0,0 +1,178 @@
+#![cfg(feature = "hybrid")]
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::hybrid::{self, ClientShare, ServerShare};
+use rusty_kyber::params::kyber768 as kyber_params;
+
+mod common;
+use common::ReplayRng;
+
+macro_rules! generate_hybrid_vector_test {
+    ($name:ident, $group:ident, $vector_file:expr) => {
+        #[test]
+        fn $name() {
+            use rusty_kyber::hybrid::$group::{self as group, ClientShare, ServerShare};
+
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join($vector_file);
+            let reader = BufReader::new(File::open(path).unwrap());
+
+            let mut client_seed = None;
+            let mut server_seed = None;
+            let mut client_share = Vec::new();
+            let mut server_share = Vec::new();
+            let mut checked = 0;
+
+            for line in reader.lines() {
+                let line = line.unwrap();
+                let parts: Vec<&str> = line.split(" = ").collect();
+                if parts.len() != 2 {
+                    continue;
+                }
+                if parts[0] == "count" {
+                    client_seed = None;
+                    server_seed = None;
+                    continue;
+                }
+                let value = hex::decode(parts[1]).unwrap();
+                match parts[0] {
+                    "client_seed" => client_seed = Some(value),
+                    "server_seed" => server_seed = Some(value),
+                    "client_share" => client_share = value,
+                    "server_share" => server_share = value,
+                    "shared_secret" => {
+                        let client = ClientShare::try_from(&client_share[..]).unwrap();
+                        let server = ServerShare::try_from(&server_share[..]).unwrap();
+
+                        if let Some(bytes) = client_seed.take() {
+                            let (share, sk) = group::keypair(&mut ReplayRng::new(bytes));
+                            assert_eq!(share.as_bytes(), &client_share[..]);
+                            let ss = group::decaps(sk, &server).unwrap();
+                            assert_eq!(ss.as_bytes(), &value[..]);
+                        }
+                        if let Some(bytes) = server_seed.take() {
+                            let (share, ss) = group::encaps(&mut ReplayRng::new(bytes), &client).unwrap();
+                            assert_eq!(share.as_bytes(), &server_share[..]);
+                            assert_eq!(ss.as_bytes(), &value[..]);
+                        }
+                        checked += 1;
+                    }
+                    _ => (),
+                }
+            }
+            assert_eq!(checked, 2);
+        }
+    };
+}
+
+generate_hybrid_vector_test!(x25519mlkem768_vectors, x25519mlkem768, "tests/kat_vectors/x25519mlkem768.rsp");
+#[cfg(feature = "hybrid-nist")]
+generate_hybrid_vector_test!(secp256r1mlkem768_vectors, secp256r1mlkem768, "tests/kat_vectors/secp256r1mlkem768.rsp");
+#[cfg(feature = "hybrid-nist")]
+generate_hybrid_vector_test!(secp384r1mlkem1024_vectors, secp384r1mlkem1024, "tests/kat_vectors/secp384r1mlkem1024.rsp");
+
+#[test]
+fn shares_split_mlkem_first() {
+    let mut rng = ChaCha20Rng::from_seed([31; 32]);
+    let (client, sk) = hybrid::keypair(&mut rng);
+    let (server, ss) = hybrid::encaps(&mut rng, &client).unwrap();
+    let ss2 = hybrid::decaps(sk, &server).unwrap();
+
+    assert_eq!(hybrid::CODEPOINT, 0x11EC);
+    assert_eq!(client.as_bytes().len(), 1216);
+    assert_eq!(server.as_bytes().len(), 1120);
+    assert_eq!(ss.as_bytes(), ss2.as_bytes());
+
+    // The first 32 bytes are the ML-KEM shared secret, which the client can
+    // recompute from its own ML-KEM key alone
+    let mut rng = ChaCha20Rng::from_seed([31; 32]);
+    let (_, dk) = rusty_kyber::kyber768::keypair(&mut rng);
+    let ct = <[u8; kyber_params::CIPHERTEXT_BYTES]>::try_from(&server.as_bytes()[..1088]).unwrap();
+    let mlkem_ss = rusty_kyber::kyber768::decaps(&dk, &ct.into());
+    assert_eq!(&ss.as_bytes()[..32], mlkem_ss.as_bytes());
+}
+
+#[test]
+fn rejects_wrong_lengths() {
+    assert_eq!(ClientShare::try_from(&[0u8; 1215][..]).err(), Some(Error::InvalidLength));
+    assert_eq!(ServerShare::try_from(&[0u8; 1121][..]).err(), Some(Error::InvalidLength));
+}
+
+#[test]
+fn rejects_unreduced_encapsulation_key() {
+    let mut rng = ChaCha20Rng::from_seed([32; 32]);
+    let (client, _) = hybrid::keypair(&mut rng);
+    let mut bytes: [u8; hybrid::CLIENT_SHARE_BYTES] = client.as_bytes().try_into().unwrap();
+    // First coefficient = 0xfff >= q
+    bytes[0] = 0xff;
+    bytes[1] |= 0x0f;
+    let res = hybrid::encaps(&mut rng, &ClientShare::from(bytes));
+    assert_eq!(res.err(), Some(Error::InvalidPublicKey));
+}
+
+#[test]
+fn rejects_low_order_x25519_share() {
+    let mut rng = ChaCha20Rng::from_seed([33; 32]);
+    let (client, sk) = hybrid::keypair(&mut rng);
+
+    let mut bytes: [u8; hybrid::CLIENT_SHARE_BYTES] = client.as_bytes().try_into().unwrap();
+    bytes[kyber_params::PUBLIC_KEY_BYTES..].fill(0);
+    let res = hybrid::encaps(&mut rng, &ClientShare::from(bytes));
+    assert_eq!(res.err(), Some(Error::InvalidPublicKey));
+
+    let (server, _) = hybrid::encaps(&mut rng, &client).unwrap();
+    let mut bytes: [u8; hybrid::SERVER_SHARE_BYTES] = server.as_bytes().try_into().unwrap();
+    bytes[kyber_params::CIPHERTEXT_BYTES..].fill(0);
+    assert_eq!(hybrid::decaps(sk, &ServerShare::from(bytes)).err(), Some(Error::InvalidPublicKey));
+}
+
+// SecP256r1MLKEM768 and SecP384r1MLKEM1024 lead with an uncompressed point,
+// which must be on the curve
+macro_rules! generate_point_validation_test {
+    ($name:ident, $group:ident, $level:ident, $seed:expr) => {
+        #[cfg(feature = "hybrid-nist")]
+        #[test]
+        fn $name() {
+            use rusty_kyber::hybrid::$group::{self as group, ClientShare, ServerShare};
+            use rusty_kyber::params::$level as level_params;
+
+            let mut rng = ChaCha20Rng::from_seed([$seed; 32]);
+            let (client, sk) = group::keypair(&mut rng);
+            let (server, ss) = group::encaps(&mut rng, &client).unwrap();
+            assert_eq!(client.as_bytes()[0], 0x04);
+            assert_eq!(server.as_bytes()[0], 0x04);
+            assert_eq!(group::CLIENT_SHARE_BYTES, group::POINT_BYTES + level_params::PUBLIC_KEY_BYTES);
+            assert_eq!(ss.as_bytes().len(), group::ECDH_SECRET_BYTES + 32);
+
+            let client_bytes: [u8; group::CLIENT_SHARE_BYTES] = client.as_bytes().try_into().unwrap();
+
+            // Compressed encoding with the y parity tag
+            let mut bytes = client_bytes;
+            bytes[0] = 0x02;
+            assert_eq!(group::encaps(&mut rng, &ClientShare::from(bytes)).err(), Some(Error::InvalidPublicKey));
+
+            // Off the curve
+            let mut bytes = client_bytes;
+            bytes[group::POINT_BYTES - 1] ^= 1;
+            assert_eq!(group::encaps(&mut rng, &ClientShare::from(bytes)).err(), Some(Error::InvalidPublicKey));
+
+            // Unreduced ML-KEM coefficient
+            let mut bytes = client_bytes;
+            bytes[group::POINT_BYTES] = 0xff;
+            bytes[group::POINT_BYTES + 1] |= 0x0f;
+            assert_eq!(group::encaps(&mut rng, &ClientShare::from(bytes)).err(), Some(Error::InvalidPublicKey));
+
+            // The client checks the server's point too
+            let mut bytes: [u8; group::SERVER_SHARE_BYTES] = server.as_bytes().try_into().unwrap();
+            bytes[group::POINT_BYTES - 1] ^= 1;
+            assert_eq!(group::decaps(sk, &ServerShare::from(bytes)).err(), Some(Error::InvalidPublicKey));
+        }
+    };
+}
+
+generate_point_validation_test!(secp256r1mlkem768_point_validation, secp256r1mlkem768, kyber768, 34);
+generate_point_validation_test!(secp384r1mlkem1024_point_validation, secp384r1mlkem1024, kyber1024, 35);
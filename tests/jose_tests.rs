@@ -0,0 +1,229 @@
+#![cfg(feature = "jose")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::jose::{concat_kdf, Jwk, KeyEncapsulation};
+
+const ENCS: [(&str, usize); 6] = [
+    ("A128GCM", 16),
+    ("A192GCM", 24),
+    ("A256GCM", 32),
+    ("A128CBC-HS256", 32),
+    ("A192CBC-HS384", 48),
+    ("A256CBC-HS512", 64),
+];
+
+// The ECDH-ES example of RFC 7518, appendix C
+#[test]
+fn concat_kdf_matches_rfc7518() {
+    let z = [
+        158, 86, 217, 29, 129, 113, 53, 211, 114, 131, 66, 131, 191, 132, 38, 156, 251, 49, 110,
+        163, 218, 128, 106, 72, 246, 218, 167, 121, 140, 254, 144, 196,
+    ];
+    let mut key = [0u8; 16];
+    concat_kdf(&z, b"A128GCM", b"Alice", b"Bob", &mut key);
+    assert_eq!(
+        key,
+        [86, 170, 141, 234, 248, 35, 109, 32, 92, 34, 40, 205, 113, 167, 16, 26]
+    );
+}
+
+macro_rules! generate_jose_tests {
+    ($level:ident, $feature:literal, $alg:literal, $alg_kw:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::jose::$level::{decapsulate, encapsulate, ALG, ALG_KW};
+            use rusty_kyber::$level::{PublicKey, SecretSeed};
+
+            #[test]
+            fn jwk_round_trip() {
+                assert_eq!((ALG, ALG_KW), ($alg, $alg_kw));
+                let seed = SecretSeed::generate(&mut ChaCha20Rng::from_seed([160; 32]));
+                let pk = seed.public_key();
+
+                let json = pk.to_jwk().to_json();
+                let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+                assert_eq!(value["kty"], "AKP");
+                assert_eq!(value["alg"], $alg);
+                assert!(value.get("priv").is_none());
+                let public = value["pub"].as_str().unwrap();
+                assert!(!public.contains(['=', '+', '/']));
+
+                let jwk = Jwk::from_json(&json).unwrap();
+                assert_eq!(PublicKey::from_jwk(&jwk).unwrap().as_bytes(), pk.as_bytes());
+                assert_eq!(
+                    SecretSeed::from_jwk(&jwk).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                let private = Jwk::from_json(&seed.to_jwk().to_json()).unwrap();
+                assert_eq!(private.public, public);
+                assert_eq!(
+                    SecretSeed::from_jwk(&private).unwrap().to_bytes(),
+                    seed.to_bytes()
+                );
+                assert_eq!(
+                    PublicKey::from_jwk(&private).unwrap().as_bytes(),
+                    pk.as_bytes()
+                );
+
+                // Unknown members are ignored and `kid` is kept
+                let with_kid = format!(
+                    r#"{{"kty":"AKP","alg":"{}","pub":"{}","kid":"k1","use":"enc"}}"#,
+                    ALG_KW, public
+                );
+                let jwk = Jwk::from_json(&with_kid).unwrap();
+                assert_eq!(jwk.kid.as_deref(), Some("k1"));
+                assert_eq!(PublicKey::from_jwk(&jwk).unwrap().as_bytes(), pk.as_bytes());
+            }
+
+            #[test]
+            fn jwk_rejects_bad_keys() {
+                let mut rng = ChaCha20Rng::from_seed([161; 32]);
+                let seed = SecretSeed::generate(&mut rng);
+                let other = SecretSeed::generate(&mut rng);
+
+                let mut jwk = seed.to_jwk();
+                jwk.kty = "OKP".into();
+                assert_eq!(
+                    PublicKey::from_jwk(&jwk).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+                let mut jwk = seed.to_jwk();
+                jwk.alg = "ML-DSA-65".into();
+                assert_eq!(
+                    SecretSeed::from_jwk(&jwk).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+
+                // `pub` from another key pair
+                let mut jwk = seed.to_jwk();
+                jwk.public = other.to_jwk().public.clone();
+                assert_eq!(
+                    SecretSeed::from_jwk(&jwk).err(),
+                    Some(Error::InconsistentKey)
+                );
+
+                let mut jwk = seed.to_jwk();
+                jwk.private = Some("AAAA".into());
+                assert_eq!(SecretSeed::from_jwk(&jwk).err(), Some(Error::InvalidLength));
+                let mut jwk = seed.to_jwk();
+                jwk.public.replace_range(..1, "*");
+                assert_eq!(
+                    PublicKey::from_jwk(&jwk).err(),
+                    Some(Error::InvalidEncoding)
+                );
+
+                // An unreduced first coefficient: 0xfff from the first two bytes
+                let mut jwk = seed.to_jwk();
+                jwk.public.replace_range(..3, "___");
+                assert_eq!(
+                    PublicKey::from_jwk(&jwk).err(),
+                    Some(Error::InvalidPublicKey)
+                );
+
+                assert_eq!(
+                    Jwk::from_json(r#"{"kty":"AKP","alg":"ML-KEM-768"}"#).err(),
+                    Some(Error::InvalidEncoding)
+                );
+            }
+
+            #[test]
+            fn key_management_round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([162; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+
+                for alg in [ALG, ALG_KW] {
+                    for (enc, len) in ENCS {
+                        let (cek, encapsulation) =
+                            encapsulate(&mut rng, &pk, alg, enc, b"Alice", b"Bob").unwrap();
+                        assert_eq!(cek.len(), len);
+                        assert_eq!(
+                            encapsulation.encrypted_key.len(),
+                            if alg == ALG { 0 } else { len + 8 }
+                        );
+
+                        let (ek, encrypted_key) = encapsulation.to_base64url();
+                        let received =
+                            KeyEncapsulation::from_base64url(&ek, &encrypted_key).unwrap();
+                        assert_eq!(received, encapsulation);
+                        let got = decapsulate(&sk, alg, enc, &received, b"Alice", b"Bob").unwrap();
+                        assert_eq!(got[..], cek[..]);
+
+                        // apu and apv feed the derivation
+                        match decapsulate(&sk, alg, enc, &received, b"Mallory", b"Bob") {
+                            Ok(other) => assert_ne!(other[..], cek[..]),
+                            Err(err) => assert_eq!(err, Error::AuthenticationFailed),
+                        }
+                    }
+                }
+            }
+
+            #[test]
+            fn key_management_rejects_bad_input() {
+                let mut rng = ChaCha20Rng::from_seed([163; 32]);
+                let (pk, sk) = SecretSeed::generate(&mut rng).expand();
+
+                assert_eq!(
+                    encapsulate(&mut rng, &pk, "ECDH-ES", "A256GCM", &[], &[]).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+                assert_eq!(
+                    encapsulate(&mut rng, &pk, ALG, "A256CTR", &[], &[]).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+
+                let (_, mut wrapped) =
+                    encapsulate(&mut rng, &pk, ALG_KW, "A256GCM", &[], &[]).unwrap();
+                assert_eq!(
+                    decapsulate(&sk, ALG, "A256GCM", &wrapped, &[], &[]).err(),
+                    Some(Error::InvalidLength)
+                );
+                assert_eq!(
+                    decapsulate(&sk, ALG_KW, "A128GCM", &wrapped, &[], &[]).err(),
+                    Some(Error::InvalidLength)
+                );
+                wrapped.encrypted_key[0] ^= 1;
+                assert_eq!(
+                    decapsulate(&sk, ALG_KW, "A256GCM", &wrapped, &[], &[]).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+
+                // A modified ciphertext decapsulates to an unrelated CEK
+                let (cek, mut direct) =
+                    encapsulate(&mut rng, &pk, ALG, "A256GCM", &[], &[]).unwrap();
+                direct.ek[0] ^= 1;
+                assert_ne!(
+                    decapsulate(&sk, ALG, "A256GCM", &direct, &[], &[]).unwrap()[..],
+                    cek[..]
+                );
+                direct.ek.pop();
+                assert_eq!(
+                    decapsulate(&sk, ALG, "A256GCM", &direct, &[], &[]).err(),
+                    Some(Error::InvalidLength)
+                );
+            }
+        }
+    };
+}
+
+generate_jose_tests!(kyber512, "kyber512", "ML-KEM-512", "ML-KEM-512+A128KW");
+generate_jose_tests!(kyber768, "kyber768", "ML-KEM-768", "ML-KEM-768+A192KW");
+generate_jose_tests!(kyber1024, "kyber1024", "ML-KEM-1024", "ML-KEM-1024+A256KW");
+
+#[cfg(all(feature = "kyber512", feature = "kyber768"))]
+#[test]
+fn rejects_another_level() {
+    let seed = rusty_kyber::kyber512::SecretSeed::generate(&mut ChaCha20Rng::from_seed([164; 32]));
+    let jwk = seed.to_jwk();
+    assert_eq!(
+        rusty_kyber::kyber768::PublicKey::from_jwk(&jwk).err(),
+        Some(Error::UnsupportedAlgorithm)
+    );
+    assert_eq!(
+        rusty_kyber::kyber768::SecretSeed::from_jwk(&jwk).err(),
+        Some(Error::UnsupportedAlgorithm)
+    );
+}
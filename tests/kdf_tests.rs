@@ -0,0 +1,136 @@
+#![cfg(feature = "kdf")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::kdf::{Algorithm, DerivedKey, KeyDerivation};
+
+const ALGORITHMS: [Algorithm; 4] = [Algorithm::HkdfSha256, Algorithm::HkdfSha3_256, Algorithm::Kmac128, Algorithm::Kmac256];
+
+fn secret() -> [u8; 32] {
+    core::array::from_fn(|i| i as u8)
+}
+
+fn bound(algorithm: Algorithm, secret: &[u8]) -> KeyDerivation<'_> {
+    KeyDerivation::new(algorithm, secret)
+        .context(b"chat v1")
+        .bind_ciphertext(b"ct")
+        .bind_public_keys(b"alice", b"bob")
+}
+
+// Generated with OpenSSL 3.5: `openssl kdf` with HKDF for the HKDF variants
+// and SSKDF with mac:KMAC128 / mac:KMAC256 for the one-step variants, over
+// the fixed info encoding of label "encryption" and the bindings above
+#[test]
+fn matches_openssl() {
+    let vectors = [
+        (Algorithm::HkdfSha256, "ca747cd926d657753d6bcb96554fc76a42799b7a6f5a3d3b59044cbb24a07542"),
+        (Algorithm::HkdfSha3_256, "82a8a68994f168684de4722dfa34608c7f656a36b75dc34c73b7df6ca1ae1d58"),
+        (Algorithm::Kmac128, "15d32f4382163bc3c3646d3c3554eb87e40ce92d9f6c0eb9596b21b16efbba03"),
+        (Algorithm::Kmac256, "5cff47322738de5ac57cd78f458b3a5e958bab037f657a433a3f662bc66b732c"),
+    ];
+    let ss = secret();
+    for (algorithm, expected) in vectors {
+        let key: DerivedKey<32> = bound(algorithm, &ss).key(b"encryption").unwrap();
+        assert_eq!(hex::encode(key.as_bytes()), expected, "{algorithm:?}");
+    }
+
+    let long = [
+        (Algorithm::HkdfSha256, "8037ccdb8a0b907fcb10f9c2b3f27b4c4b8db5ee8b1a1649dce33d142fc02c628d6914ee6fffa0ce0e50b3be1dfcd9914ba240e211f21378d2bca9b5ae5a028b16383a0c0c0f111315d141a017aad8fe5beb5105ed28ed5812f393ef7be3838d99b28771"),
+        (Algorithm::HkdfSha3_256, "6390ec1fa717770986dd1128c51d3ecf342c4c56464015e7f6fc1f6ade0d45e264803701d9b38e9160e54a073bf79f7f92b0b3743ee141e8b46b03739d5c94ae77c0ca2ad80a399844e31afaa05fd894f888fd4ffaef7b7d82f6e62b4403ea3f1c14d21a"),
+        (Algorithm::Kmac128, "d8e487e5e2cfb169c0215c3e351b1b01cfe82005001aea1480d2ed40baea9bc98caf6d1a791ae6955cca95818736478b4bf089ca646aaf49ffce6984e2bd27419d6cef1bc37535642d6fdf2ada08627b4748e20a9437aa3a6dbcb99ca789d5b853c7e823"),
+        (Algorithm::Kmac256, "d4a57b2eefa47ee9367b44110e7aa011a67fc1f3bf209a52b2d04cb644ce068ea607d3c2e29d88b2d2b451125f612bcdb7fe56723319546de837a36092beee02e6bfddb354a3071ec0afca4127deb5f198571b91fc91898709af6a49a29404729740c34f"),
+    ];
+    for (algorithm, expected) in long {
+        let key = bound(algorithm, &ss).key_vec(b"encryption", 100).unwrap();
+        assert_eq!(hex::encode(&key[..]), expected, "{algorithm:?}");
+    }
+}
+
+#[test]
+fn inputs_separate_keys() {
+    let ss = secret();
+    for algorithm in ALGORITHMS {
+        let base: DerivedKey<32> = bound(algorithm, &ss).key(b"encryption").unwrap();
+        let others: [DerivedKey<32>; 5] = [
+            bound(algorithm, &ss).key(b"authentication").unwrap(),
+            bound(algorithm, &ss).context(b"chat v2").key(b"encryption").unwrap(),
+            bound(algorithm, &ss).bind_ciphertext(b"cu").key(b"encryption").unwrap(),
+            bound(algorithm, &ss).bind_public_keys(b"bob", b"alice").key(b"encryption").unwrap(),
+            KeyDerivation::new(algorithm, &ss).context(b"chat v1").key(b"encryption").unwrap(),
+        ];
+        for other in &others {
+            assert_ne!(base.as_bytes(), other.as_bytes(), "{algorithm:?}");
+        }
+
+        // The length is part of the input, so a shorter key is not a prefix
+        let short: DerivedKey<16> = bound(algorithm, &ss).key(b"encryption").unwrap();
+        assert_ne!(short.as_bytes()[..], base.as_bytes()[..16], "{algorithm:?}");
+    }
+}
+
+#[test]
+fn output_length_limits() {
+    let ss = secret();
+    for algorithm in [Algorithm::HkdfSha256, Algorithm::HkdfSha3_256] {
+        let kdf = KeyDerivation::new(algorithm, &ss);
+        assert_eq!(kdf.key_vec(b"", 255 * 32).unwrap().len(), 255 * 32);
+        assert_eq!(kdf.key_vec(b"", 255 * 32 + 1).err(), Some(Error::InvalidLength));
+    }
+    let kdf = KeyDerivation::new(Algorithm::Kmac256, &ss);
+    assert_eq!(kdf.key_vec(b"", 20000).unwrap().len(), 20000);
+}
+
+#[test]
+fn fill_matches_key() {
+    let ss = secret();
+    for algorithm in ALGORITHMS {
+        let kdf = bound(algorithm, &ss);
+        let mut out = [0u8; 48];
+        kdf.fill(b"label", &mut out).unwrap();
+        let key: DerivedKey<48> = kdf.key(b"label").unwrap();
+        assert_eq!(&out, key.as_bytes());
+        assert_eq!(kdf.key_vec(b"label", 48).unwrap()[..], out);
+    }
+}
+
+#[test]
+fn shared_secrets_derive_the_same_keys() {
+    let mut rng = ChaCha20Rng::from_seed([110; 32]);
+    let (pk, sk) = rusty_kyber::keypair(&mut rng);
+    let (ct, ss) = rusty_kyber::encaps(&mut rng, &pk);
+    let ss2 = rusty_kyber::decaps(&sk, &ct);
+
+    let sender: DerivedKey<32> = ss
+        .derive_keys(Algorithm::Kmac256)
+        .bind_ciphertext(ct.as_bytes())
+        .bind_public_keys(&[], pk.as_bytes())
+        .key(b"k")
+        .unwrap();
+    let recipient: DerivedKey<32> = ss2
+        .derive_keys(Algorithm::Kmac256)
+        .bind_ciphertext(ct.as_bytes())
+        .bind_public_keys(&[], pk.as_bytes())
+        .key(b"k")
+        .unwrap();
+    assert_eq!(sender.as_bytes(), recipient.as_bytes());
+
+    let direct: DerivedKey<32> = KeyDerivation::new(Algorithm::Kmac256, ss.as_bytes())
+        .bind_ciphertext(ct.as_bytes())
+        .bind_public_keys(&[], pk.as_bytes())
+        .key(b"k")
+        .unwrap();
+    assert_eq!(direct.as_bytes(), sender.as_bytes());
+}
+
+#[cfg(feature = "xwing")]
+#[test]
+fn xwing_shared_secret_derives_keys() {
+    let mut rng = ChaCha20Rng::from_seed([111; 32]);
+    let (pk, sk) = rusty_kyber::xwing::keypair(&mut rng);
+    let (ct, ss) = rusty_kyber::xwing::encaps(&mut rng, &pk).unwrap();
+    let ss2 = rusty_kyber::xwing::decaps(&sk, &ct);
+    let a: DerivedKey<32> = ss.derive_keys(Algorithm::HkdfSha256).key(b"k").unwrap();
+    let b: DerivedKey<32> = ss2.derive_keys(Algorithm::HkdfSha256).key(b"k").unwrap();
+    assert_eq!(a.as_bytes(), b.as_bytes());
+}
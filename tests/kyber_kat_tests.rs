@@ -1,10 +1,79 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use hex;
-use rand_chacha::ChaCha20Rng;
-use rand_core::SeedableRng;
+use aes::Aes256;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use rand_core::{CryptoRng, RngCore};
 use std::path::Path;
 
+// AES-256 CTR_DRBG used by the NIST PQC KAT generator (randombytes_init / randombytes)
+struct NistDrbg {
+    key: [u8; 32],
+    v: [u8; 16],
+}
+
+impl NistDrbg {
+    fn new(entropy: &[u8; 48]) -> Self {
+        let mut drbg = NistDrbg { key: [0u8; 32], v: [0u8; 16] };
+        drbg.update(Some(entropy));
+        drbg
+    }
+
+    fn increment_v(&mut self) {
+        for b in self.v.iter_mut().rev() {
+            *b = b.wrapping_add(1);
+            if *b != 0 {
+                break;
+            }
+        }
+    }
+
+    fn next_block(&mut self) -> [u8; 16] {
+        self.increment_v();
+        let mut block = self.v.into();
+        Aes256::new(&self.key.into()).encrypt_block(&mut block);
+        block.into()
+    }
+
+    fn update(&mut self, provided: Option<&[u8; 48]>) {
+        let mut temp = [0u8; 48];
+        for chunk in temp.chunks_exact_mut(16) {
+            chunk.copy_from_slice(&self.next_block());
+        }
+        if let Some(data) = provided {
+            for (t, d) in temp.iter_mut().zip(data.iter()) {
+                *t ^= d;
+            }
+        }
+        self.key.copy_from_slice(&temp[..32]);
+        self.v.copy_from_slice(&temp[32..]);
+    }
+}
+
+impl RngCore for NistDrbg {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(16) {
+            let block = self.next_block();
+            chunk.copy_from_slice(&block[..chunk.len()]);
+        }
+        self.update(None);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for NistDrbg {}
+
 macro_rules! generate_kat_test {
     ($name:ident, $kyber_mod:ident, $kat_file:expr) => {
         #[test]
@@ -35,7 +104,7 @@ macro_rules! generate_kat_test {
                         "ss" => {
                             ss_kat.copy_from_slice(&hex::decode(parts[1]).unwrap());
 
-                            let mut rng = ChaCha20Rng::from_seed(seed[..32].try_into().unwrap());
+                            let mut rng = NistDrbg::new(&seed);
                             let (pk, sk): (PublicKey, SecretKey) = keypair(&mut rng);
                             assert_eq!(pk_kat, pk.as_bytes());
                             assert_eq!(sk_kat, sk.as_bytes());
@@ -56,7 +125,7 @@ macro_rules! generate_kat_test {
 }
 
 #[cfg(feature = "kyber512")]
-generate_kat_test!(kyber512_kat, kyber512, "KAT/kyber512.rsp");
+generate_kat_test!(kyber512_kat, kyber512, "tests/kat_vectors/kyber512.rsp");
 #[cfg(feature = "kyber768")]
 generate_kat_test!(kyber768_kat, kyber768, "tests/kat_vectors/kyber768.rsp");
 #[cfg(feature = "kyber1024")]
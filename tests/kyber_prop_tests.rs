@@ -0,0 +1,168 @@
+use proptest::prelude::*;
+use rusty_kyber::ntt::barrett_reduce;
+use rusty_kyber::params::{N, Q};
+use rusty_kyber::poly::Poly;
+use rusty_kyber::utils::{
+    cbd, poly_compress, poly_decompress, poly_from_bytes, poly_from_msg, poly_to_bytes, poly_to_msg,
+};
+
+fn poly_strategy() -> impl Strategy<Value = Poly> {
+    prop::collection::vec(0..Q as i16, N).prop_map(|v| {
+        let mut p = Poly::new();
+        p.coeffs.copy_from_slice(&v);
+        p
+    })
+}
+
+fn assert_canonical(p: &Poly) {
+    for &c in p.coeffs.iter() {
+        assert!((0..Q as i16).contains(&c), "coefficient {} not in [0, q)", c);
+    }
+}
+
+// Negacyclic schoolbook multiplication in Z_q[X]/(X^256 + 1)
+fn schoolbook_mul(a: &Poly, b: &Poly) -> Poly {
+    let mut acc = [0i64; N];
+    for i in 0..N {
+        for j in 0..N {
+            let prod = a.coeffs[i] as i64 * b.coeffs[j] as i64;
+            if i + j < N {
+                acc[i + j] += prod;
+            } else {
+                acc[i + j - N] -= prod;
+            }
+        }
+    }
+    let mut r = Poly::new();
+    for (c, &a) in r.coeffs.iter_mut().zip(acc.iter()) {
+        *c = a.rem_euclid(Q as i64) as i16;
+    }
+    r
+}
+
+// Distance between x and y modulo q, taken in the centered range
+fn centered_distance(x: i16, y: i16) -> i32 {
+    let d = (x as i32 - y as i32).rem_euclid(Q);
+    d.min(Q - d)
+}
+
+proptest! {
+    #[test]
+    fn ntt_round_trip(a in poly_strategy()) {
+        let mut p = a;
+        p.ntt();
+        assert_canonical(&p);
+        p.inv_ntt();
+        assert_canonical(&p);
+        prop_assert_eq!(p.coeffs, a.coeffs);
+    }
+
+    #[test]
+    fn ntt_is_linear(a in poly_strategy(), b in poly_strategy()) {
+        let mut sum = a;
+        sum.add(&b);
+        sum.ntt();
+
+        let mut a_hat = a;
+        a_hat.ntt();
+        let mut b_hat = b;
+        b_hat.ntt();
+        a_hat.add(&b_hat);
+
+        prop_assert_eq!(sum.coeffs, a_hat.coeffs);
+    }
+
+    #[test]
+    fn pointwise_mul_matches_schoolbook(a in poly_strategy(), b in poly_strategy()) {
+        let mut a_hat = a;
+        a_hat.ntt();
+        let mut b_hat = b;
+        b_hat.ntt();
+        a_hat.pointwise_mul(&b_hat);
+        assert_canonical(&a_hat);
+        a_hat.inv_ntt();
+
+        prop_assert_eq!(a_hat.coeffs, schoolbook_mul(&a, &b).coeffs);
+    }
+
+    #[test]
+    fn add_sub_are_inverse(a in poly_strategy(), b in poly_strategy()) {
+        let mut p = a;
+        p.add(&b);
+        assert_canonical(&p);
+        p.sub(&b);
+        assert_canonical(&p);
+        prop_assert_eq!(p.coeffs, a.coeffs);
+
+        let mut p = a;
+        p.sub(&b);
+        assert_canonical(&p);
+        p.add(&b);
+        prop_assert_eq!(p.coeffs, a.coeffs);
+    }
+
+    #[test]
+    fn compress_error_is_bounded(a in poly_strategy(), d in prop::sample::select(vec![1usize, 4, 5, 10, 11])) {
+        let mut buf = vec![0u8; 32 * d];
+        poly_compress(&a, d, &mut buf);
+        let mut p = Poly::new();
+        poly_decompress(&buf, d, &mut p);
+        assert_canonical(&p);
+
+        // FIPS 203, section 4.2.1: |Decompress(Compress(x)) - x| mod± q <= round(q / 2^(d + 1))
+        let bound = (Q + (1 << d)) >> (d + 1);
+        for i in 0..N {
+            prop_assert!(centered_distance(p.coeffs[i], a.coeffs[i]) <= bound);
+        }
+    }
+
+    #[test]
+    fn decompress_compress_is_identity(bytes in prop::collection::vec(any::<u8>(), 32 * 11), d in prop::sample::select(vec![1usize, 4, 5, 10, 11])) {
+        let buf = &bytes[..32 * d];
+        let mut p = Poly::new();
+        poly_decompress(buf, d, &mut p);
+        let mut out = vec![0u8; 32 * d];
+        poly_compress(&p, d, &mut out);
+        prop_assert_eq!(&out[..], buf);
+    }
+
+    #[test]
+    fn encode_decode_round_trip(a in poly_strategy()) {
+        let mut buf = [0u8; 384];
+        poly_to_bytes(&a, &mut buf);
+        let mut p = Poly::new();
+        poly_from_bytes(&buf, &mut p);
+        prop_assert_eq!(p.coeffs, a.coeffs);
+
+        let mut buf2 = [0u8; 384];
+        poly_to_bytes(&p, &mut buf2);
+        prop_assert_eq!(buf, buf2);
+    }
+
+    #[test]
+    fn msg_round_trip(msg in any::<[u8; 32]>()) {
+        let mut p = Poly::new();
+        poly_from_msg(&msg, &mut p);
+        let mut out = [0u8; 32];
+        poly_to_msg(&p, &mut out);
+        prop_assert_eq!(out, msg);
+    }
+
+    #[test]
+    fn cbd_output_is_bounded(buf in prop::collection::vec(any::<u8>(), 64 * 3), eta in 2usize..=3) {
+        let mut p = Poly::new();
+        cbd(&buf[..64 * eta], eta, &mut p);
+        for &c in p.coeffs.iter() {
+            prop_assert!((-(eta as i16)..=eta as i16).contains(&c));
+        }
+    }
+}
+
+#[test]
+fn barrett_reduce_is_exact_on_all_inputs() {
+    for a in i16::MIN..=i16::MAX {
+        let r = barrett_reduce(a);
+        assert!((-(Q as i16 - 1) / 2..=(Q as i16 - 1) / 2).contains(&r), "barrett_reduce({}) = {}", a, r);
+        assert_eq!((a as i32 - r as i32) % Q, 0);
+    }
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "mkem")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::mkem::{self, MatrixSeed};
+
+macro_rules! generate_mkem_tests {
+    ($level:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::mkem::$level::{
+                decaps, encaps, keypair, matrix_seed_of, RecipientCiphertext, SharedCiphertext,
+                RECIPIENT_CIPHERTEXT_BYTES, SHARED_CIPHERTEXT_BYTES,
+            };
+            use rusty_kyber::params::$level as kyber_params;
+            use rusty_kyber::utils::kdf;
+            use rusty_kyber::$level::{self as level, PublicKey, SecretKey};
+
+            fn group(rng: &mut ChaCha20Rng, rho: &MatrixSeed, n: usize) -> (Vec<PublicKey>, Vec<SecretKey>) {
+                (0..n).map(|_| keypair(rng, rho)).unzip()
+            }
+
+            #[test]
+            fn every_recipient_gets_the_secret() {
+                let mut rng = ChaCha20Rng::from_seed([100; 32]);
+                let rho = mkem::matrix_seed(&mut rng);
+                let (pks, sks) = group(&mut rng, &rho, 5);
+
+                let (u, vs, ss) = encaps(&mut rng, &pks).unwrap();
+                assert_eq!(vs.len(), 5);
+                assert_eq!(SHARED_CIPHERTEXT_BYTES + RECIPIENT_CIPHERTEXT_BYTES, kyber_params::CIPHERTEXT_BYTES);
+                for (sk, v) in sks.iter().zip(&vs) {
+                    assert_eq!(decaps(sk, &u, v).as_bytes(), ss.as_bytes());
+                }
+                assert_ne!(vs[0].as_bytes(), vs[1].as_bytes());
+            }
+
+            #[test]
+            fn keys_are_ml_kem_keys() {
+                let mut rng = ChaCha20Rng::from_seed([101; 32]);
+                let rho = mkem::matrix_seed(&mut rng);
+                let (pk, sk) = keypair(&mut rng, &rho);
+                assert_eq!(matrix_seed_of(&pk), rho);
+                assert_eq!(pk.as_bytes()[kyber_params::POLY_VEC_BYTES..], rho);
+
+                let (ct, ss) = level::encaps(&mut rng, &pk);
+                assert_eq!(level::decaps(&sk, &ct).as_bytes(), ss.as_bytes());
+
+                // Same group, different keys
+                let (other, _) = keypair(&mut rng, &rho);
+                assert_ne!(pk.as_bytes(), other.as_bytes());
+            }
+
+            #[test]
+            fn rejects_tampering_implicitly() {
+                let mut rng = ChaCha20Rng::from_seed([102; 32]);
+                let rho = mkem::matrix_seed(&mut rng);
+                let (pks, sks) = group(&mut rng, &rho, 2);
+                let (u, vs, ss) = encaps(&mut rng, &pks).unwrap();
+
+                let mut bad_u: [u8; SHARED_CIPHERTEXT_BYTES] = u.as_bytes().try_into().unwrap();
+                bad_u[0] ^= 1;
+                let bad_u = SharedCiphertext::from(bad_u);
+                let mut bad_v: [u8; RECIPIENT_CIPHERTEXT_BYTES] = vs[0].as_bytes().try_into().unwrap();
+                bad_v[0] ^= 1;
+                let bad_v = RecipientCiphertext::from(bad_v);
+
+                // The rejection key is J(z || u || v), as in ML-KEM.Decaps
+                let z = &sks[0].as_bytes()[kyber_params::SECRET_KEY_BYTES - 32..];
+                for (u, v) in [(&bad_u, &vs[0]), (&u, &bad_v), (&u, &vs[1])] {
+                    let got = decaps(&sks[0], u, v);
+                    assert_ne!(got.as_bytes(), ss.as_bytes());
+
+                    let mut expected = [0u8; 32];
+                    kdf(&[z, u.as_bytes(), v.as_bytes()].concat(), &mut expected);
+                    assert_eq!(got.as_bytes(), expected);
+                }
+            }
+
+            #[test]
+            fn rejects_mixed_groups() {
+                let mut rng = ChaCha20Rng::from_seed([103; 32]);
+                let (first, second) = (mkem::matrix_seed(&mut rng), mkem::matrix_seed(&mut rng));
+                let (pk, _) = keypair(&mut rng, &first);
+                let (other, _) = keypair(&mut rng, &second);
+                assert_eq!(encaps(&mut rng, &[pk, other]).err(), Some(Error::MatrixSeedMismatch));
+                assert_eq!(encaps(&mut rng, &[]).err(), Some(Error::InvalidLength));
+            }
+
+            #[test]
+            fn rejects_unreduced_key() {
+                let mut rng = ChaCha20Rng::from_seed([104; 32]);
+                let rho = mkem::matrix_seed(&mut rng);
+                let (pk, _) = keypair(&mut rng, &rho);
+                let mut bytes: [u8; kyber_params::PUBLIC_KEY_BYTES] = pk.into();
+                bytes[0] = 0xff;
+                bytes[1] |= 0x0f;
+                let bad = PublicKey::from(bytes);
+                assert_eq!(encaps(&mut rng, &[pk, bad]).err(), Some(Error::InvalidPublicKey));
+            }
+        }
+    };
+}
+
+generate_mkem_tests!(kyber512, "kyber512");
+generate_mkem_tests!(kyber768, "kyber768");
+generate_mkem_tests!(kyber1024, "kyber1024");
+
+#[test]
+fn top_level_follows_highest_level() {
+    let mut rng = ChaCha20Rng::from_seed([105; 32]);
+    let rho = mkem::matrix_seed(&mut rng);
+    let (pk, sk) = mkem::keypair(&mut rng, &rho);
+    let (u, vs, ss) = mkem::encaps(&mut rng, &[pk]).unwrap();
+    assert_eq!(mkem::decaps(&sk, &u, &vs[0]).as_bytes(), ss.as_bytes());
+    let _: rusty_kyber::SecretKey = sk;
+}
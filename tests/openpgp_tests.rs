@@ -0,0 +1,319 @@
+#![cfg(feature = "openpgp")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::openpgp::{
+    multi_key_combine, packets, Packet, Pkesk, PublicKeyPacket, Recipient, SecretKeyPacket, TAG_PKESK,
+    TAG_SECRET_SUBKEY,
+};
+
+const AES256: u8 = 9;
+
+// The first secret subkey of `algorithm` in a transferable secret key
+fn secret_subkey(key: &[u8], algorithm: u8) -> SecretKeyPacket {
+    packets(key)
+        .unwrap()
+        .iter()
+        .filter(|packet| packet.tag == TAG_SECRET_SUBKEY)
+        .filter_map(|packet| SecretKeyPacket::from_body(&packet.body).ok())
+        .find(|subkey| subkey.public.algorithm == algorithm)
+        .unwrap()
+}
+
+fn pkesks(message: &[u8]) -> Vec<Packet> {
+    packets(message)
+        .unwrap()
+        .into_iter()
+        .filter(|packet| packet.tag == TAG_PKESK)
+        .collect()
+}
+
+#[test]
+fn combiner_encoding() {
+    let kek = multi_key_combine(&[1; 32], &[2; 32], &[3; 32], &[4; 32], 35);
+    assert_eq!(
+        hex::encode(*kek),
+        "fd2ccfc34013a809badabf7ab7ab5b053557b0a852886049f548d64314cb295d"
+    );
+}
+
+#[test]
+fn reads_both_header_formats() {
+    // Old format with a one-octet length, then new format with partial
+    // body lengths of 1 and 2 octets and a final 3-octet part
+    let data = hex::decode("b802aabbcbe0aae1bbcc03ddeeff").unwrap();
+    let parsed = packets(&data).unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!((parsed[0].tag, &parsed[0].body[..]), (14, &[0xaa, 0xbb][..]));
+    assert_eq!(
+        (parsed[1].tag, &parsed[1].body[..]),
+        (11, &hex::decode("aabbccddeeff").unwrap()[..])
+    );
+
+    for len in [0, 191, 192, 8383, 8384, 70000] {
+        let packet = Packet {
+            tag: 18,
+            body: vec![7; len],
+        };
+        assert_eq!(packets(&packet.to_bytes()).unwrap(), [packet]);
+    }
+
+    assert_eq!(packets(&[0x3f]).err(), Some(Error::InvalidEncoding));
+    assert_eq!(packets(&[0xc1, 0x05, 0x00]).err(), Some(Error::InvalidEncoding));
+}
+
+// The samples of RFC 9980 appendix A: a transferable secret key with an
+// encryption subkey, and a message to it
+macro_rules! generate_openpgp_vector_test {
+    ($name:ident, $module:ident, $key:literal, $message:literal, $fingerprint:literal, $version:expr) => {
+        #[test]
+        fn $name() {
+            use rusty_kyber::openpgp::$module::{decrypt_session_key, message_session_key, SecretKey, ALGORITHM};
+
+            let key = include_bytes!(concat!("kat_vectors/openpgp/", $key, ".pgp"));
+            let message = include_bytes!(concat!("kat_vectors/openpgp/", $message, ".pgp"));
+
+            let subkey = secret_subkey(key, ALGORITHM);
+            assert_eq!(hex::encode(subkey.public.fingerprint()), $fingerprint);
+            let original = packets(key)
+                .unwrap()
+                .into_iter()
+                .find(|p| p.tag == TAG_SECRET_SUBKEY)
+                .unwrap();
+            assert_eq!(*subkey.to_body(), original.body);
+            let sk = SecretKey::from_packet(&subkey).unwrap();
+
+            let packet = &pkesks(message)[0];
+            let pkesk = Pkesk::from_body(&packet.body).unwrap();
+            assert_eq!(pkesk.version(), $version);
+            assert_eq!(pkesk.algorithm, ALGORITHM);
+            assert!(pkesk.recipient.matches(&subkey.public));
+            assert_eq!(pkesk.to_body().unwrap(), packet.body);
+
+            // AES Key Wrap checks the key it unwraps
+            let session_key = decrypt_session_key(&sk, &pkesk).unwrap();
+            assert_eq!(session_key.len(), 32);
+            if $version == 3 {
+                assert_eq!(pkesk.symmetric_algorithm, Some(AES256));
+            }
+
+            let (symmetric_algorithm, found) = message_session_key(&sk, &subkey.public, message).unwrap();
+            assert_eq!(symmetric_algorithm, pkesk.symmetric_algorithm);
+            assert_eq!(*found, *session_key);
+        }
+    };
+}
+
+generate_openpgp_vector_test!(
+    v4_key_v3_pkesk,
+    mlkem768_x25519,
+    "v4-eddsa-sample-sk",
+    "v4-eddsa-sample-message-v1",
+    "e51dbfea51936988b5428fffa4f95f985ed61a51",
+    3
+);
+generate_openpgp_vector_test!(
+    v4_key_v6_pkesk,
+    mlkem768_x25519,
+    "v4-eddsa-sample-sk",
+    "v4-eddsa-sample-message-v2",
+    "e51dbfea51936988b5428fffa4f95f985ed61a51",
+    6
+);
+generate_openpgp_vector_test!(
+    v6_key_v6_pkesk,
+    mlkem768_x25519,
+    "v6-eddsa-sample-sk",
+    "v6-eddsa-sample-message",
+    "dafe0eebb2675ecfcdc20a23fe89ca5d12e83f527dfa354b6dcf662131a48b9d",
+    6
+);
+generate_openpgp_vector_test!(
+    v6_key_v6_pkesk_x448,
+    mlkem1024_x448,
+    "v6-mldsa-87-sample-sk",
+    "v6-mldsa-87-sample-message",
+    "65090e147a8116ab7f62ab4ec7aae59d9e6532feb2af230c73cdc869fbc60c8f",
+    6
+);
+
+macro_rules! generate_openpgp_tests {
+    ($module:ident, $algorithm:expr, $other:ident, $seed:expr) => {
+        mod $module {
+            use super::*;
+            use rusty_kyber::openpgp::$module::{
+                decrypt_session_key, encrypt_session_key, message_session_key, PublicKey, SecretKey, ALGORITHM,
+                PUBLIC_KEY_BYTES, SECRET_KEY_BYTES,
+            };
+
+            #[test]
+            fn round_trip() {
+                assert_eq!(ALGORITHM, $algorithm);
+                let mut rng = ChaCha20Rng::from_seed([$seed; 32]);
+                let sk = SecretKey::generate(&mut rng);
+                let pk = sk.public_key();
+                assert_eq!(pk.as_bytes().len(), PUBLIC_KEY_BYTES);
+                assert_eq!(sk.as_bytes().len(), SECRET_KEY_BYTES);
+
+                for version in [4, 6] {
+                    let subkey = sk.to_packet(version, 0x6500_0000);
+                    let parsed = SecretKeyPacket::from_body(&subkey.to_body()).unwrap();
+                    assert_eq!(parsed.public, subkey.public);
+                    assert_eq!(SecretKey::from_packet(&parsed).unwrap().as_bytes(), sk.as_bytes());
+                    let public = PublicKeyPacket::from_body(&subkey.public.to_body()).unwrap();
+                    assert_eq!(PublicKey::from_packet(&public).unwrap().as_bytes(), pk.as_bytes());
+
+                    for (recipient, symmetric_algorithm) in [
+                        (Recipient::key_id(&public), Some(AES256)),
+                        (Recipient::KeyId([0; 8]), Some(AES256)),
+                        (Recipient::fingerprint(&public), None),
+                        (Recipient::Anonymous, None),
+                    ] {
+                        let session_key = [0x5a; 32];
+                        let pkesk =
+                            encrypt_session_key(&mut rng, &pk, recipient, symmetric_algorithm, &session_key).unwrap();
+                        let message = pkesk.to_packet().unwrap().to_bytes();
+                        let parsed = Pkesk::from_body(&pkesks(&message)[0].body).unwrap();
+                        assert_eq!(parsed, pkesk);
+                        assert_eq!(*decrypt_session_key(&sk, &parsed).unwrap(), session_key);
+                        let (alg, found) = message_session_key(&sk, &public, &message).unwrap();
+                        assert_eq!((alg, &found[..]), (symmetric_algorithm, &session_key[..]));
+                    }
+                }
+            }
+
+            #[test]
+            fn finds_own_pkesk() {
+                let mut rng = ChaCha20Rng::from_seed([$seed + 1; 32]);
+                let sk = SecretKey::generate(&mut rng);
+                let other = SecretKey::generate(&mut rng);
+                let public = sk.public_key().to_packet(6, 0);
+                let other_public = other.public_key().to_packet(6, 0);
+
+                let mut message = Vec::new();
+                for (pk, key) in [(other.public_key(), &other_public), (sk.public_key(), &public)] {
+                    let pkesk =
+                        encrypt_session_key(&mut rng, &pk, Recipient::fingerprint(key), None, &[1; 16]).unwrap();
+                    message.extend(pkesk.to_packet().unwrap().to_bytes());
+                }
+                let (_, found) = message_session_key(&sk, &public, &message).unwrap();
+                assert_eq!(*found, [1; 16]);
+
+                // An anonymous PKESK for another key fails to unwrap
+                let pkesk = encrypt_session_key(
+                    &mut rng,
+                    &other.public_key(),
+                    Recipient::Anonymous,
+                    None,
+                    &[1; 16],
+                )
+                .unwrap();
+                let anonymous = pkesk.to_packet().unwrap().to_bytes();
+                assert_eq!(
+                    message_session_key(&sk, &public, &anonymous).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+
+                // Only for another key
+                let pkesk = encrypt_session_key(
+                    &mut rng,
+                    &other.public_key(),
+                    Recipient::fingerprint(&other_public),
+                    None,
+                    &[1; 16],
+                )
+                .unwrap();
+                let message = pkesk.to_packet().unwrap().to_bytes();
+                assert_eq!(
+                    message_session_key(&sk, &public, &message).err(),
+                    Some(Error::NotRecipient)
+                );
+            }
+
+            #[test]
+            fn rejects_bad_input() {
+                let mut rng = ChaCha20Rng::from_seed([$seed + 2; 32]);
+                let sk = SecretKey::generate(&mut rng);
+                let pk = sk.public_key();
+                let public = pk.to_packet(6, 0);
+
+                // The symmetric algorithm goes with v3 and only v3
+                assert_eq!(
+                    encrypt_session_key(&mut rng, &pk, Recipient::key_id(&public), None, &[0; 32]).err(),
+                    Some(Error::InvalidParameters)
+                );
+                assert_eq!(
+                    encrypt_session_key(&mut rng, &pk, Recipient::Anonymous, Some(AES256), &[0; 32]).err(),
+                    Some(Error::InvalidParameters)
+                );
+                assert_eq!(
+                    encrypt_session_key(&mut rng, &pk, Recipient::Anonymous, None, &[0; 20]).err(),
+                    Some(Error::InvalidLength)
+                );
+
+                let pkesk = encrypt_session_key(&mut rng, &pk, Recipient::Anonymous, None, &[0; 32]).unwrap();
+                let mut tampered = pkesk.clone();
+                tampered.wrapped_key[0] ^= 1;
+                assert_eq!(
+                    decrypt_session_key(&sk, &tampered).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+                let mut tampered = pkesk.clone();
+                tampered.mlkem_ciphertext[0] ^= 1;
+                assert_eq!(
+                    decrypt_session_key(&sk, &tampered).err(),
+                    Some(Error::AuthenticationFailed)
+                );
+                let mut tampered = pkesk.clone();
+                tampered.ecdh_ciphertext.fill(0);
+                assert_eq!(
+                    decrypt_session_key(&sk, &tampered).err(),
+                    Some(Error::InvalidPublicKey)
+                );
+                let mut tampered = pkesk.clone();
+                tampered.algorithm = rusty_kyber::openpgp::$other::ALGORITHM;
+                assert_eq!(
+                    decrypt_session_key(&sk, &tampered).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+                assert_eq!(tampered.to_body().err(), Some(Error::InvalidLength));
+
+                // A zero-length wrapped key, and an unknown algorithm
+                let mut body = pkesk.to_body().unwrap();
+                let len_at = body.len() - pkesk.wrapped_key.len() - 1;
+                body.truncate(len_at);
+                body.push(0);
+                assert_eq!(Pkesk::from_body(&body).err(), Some(Error::InvalidEncoding));
+                let mut body = pkesk.to_body().unwrap();
+                body[2] = 25;
+                assert_eq!(Pkesk::from_body(&body).err(), Some(Error::UnsupportedAlgorithm));
+
+                // Secret key material for another public key, and a bad
+                // v4 checksum
+                let mut subkey = sk.to_packet(6, 0);
+                subkey.public = SecretKey::generate(&mut rng).public_key().to_packet(6, 0);
+                assert_eq!(
+                    SecretKey::from_packet(&subkey).err(),
+                    Some(Error::InconsistentKey)
+                );
+                let mut body = sk.to_packet(4, 0).to_body();
+                let last = body.len() - 1;
+                body[last] ^= 1;
+                assert_eq!(
+                    SecretKeyPacket::from_body(&body).err(),
+                    Some(Error::InvalidEncoding)
+                );
+                let mut other = public.clone();
+                other.algorithm = rusty_kyber::openpgp::$other::ALGORITHM;
+                assert_eq!(
+                    PublicKey::from_packet(&other).err(),
+                    Some(Error::UnsupportedAlgorithm)
+                );
+            }
+        }
+    };
+}
+
+generate_openpgp_tests!(mlkem768_x25519, 35, mlkem1024_x448, 40);
+generate_openpgp_tests!(mlkem1024_x448, 36, mlkem768_x25519, 50);
@@ -0,0 +1,58 @@
+// Cross-checks the optimized KEM against the FIPS 203 transcription in
+// `rusty_kyber::reference`. Run with `cargo test --features reference`.
+#![cfg(feature = "reference")]
+
+use proptest::prelude::*;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+
+macro_rules! generate_reference_test {
+    ($name:ident, $kyber_mod:ident, $ref_params:ident) => {
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(16))]
+
+            #[test]
+            fn $name(seed in any::<[u8; 32]>(), flip in any::<usize>()) {
+                use rusty_kyber::$kyber_mod::*;
+                use rusty_kyber::reference::{self, $ref_params as params};
+
+                let mut rng = ChaCha20Rng::from_seed(seed);
+                let mut ref_rng = rng.clone();
+
+                let (pk, sk) = keypair(&mut rng);
+                let mut d = [0u8; 32];
+                let mut z = [0u8; 32];
+                ref_rng.fill_bytes(&mut d);
+                ref_rng.fill_bytes(&mut z);
+                let (ek, dk) = reference::keygen_internal(&params, &d, &z);
+                prop_assert_eq!(pk.as_bytes(), &ek[..]);
+                prop_assert_eq!(sk.as_bytes(), &dk[..]);
+
+                let (ct, ss) = encaps(&mut rng, &pk);
+                let mut m = [0u8; 32];
+                ref_rng.fill_bytes(&mut m);
+                let (k, c) = reference::encaps_internal(&params, &ek, &m);
+                prop_assert_eq!(ct.as_bytes(), &c[..]);
+                prop_assert_eq!(ss.as_bytes(), &k[..]);
+
+                let ss2 = decaps(&sk, &ct);
+                prop_assert_eq!(ss2.as_bytes(), &reference::decaps_internal(&params, &dk, &c)[..]);
+
+                // Implicit rejection path
+                let mut bad = c.clone();
+                bad[flip % c.len()] ^= 1;
+                let bad_ct = Ciphertext::from(<[u8; _]>::try_from(&bad[..]).unwrap());
+                let ss3 = decaps(&sk, &bad_ct);
+                prop_assert_eq!(ss3.as_bytes(), &reference::decaps_internal(&params, &dk, &bad)[..]);
+                prop_assert_ne!(ss3.as_bytes(), ss.as_bytes());
+            }
+        }
+    };
+}
+
+#[cfg(feature = "kyber512")]
+generate_reference_test!(kyber512_matches_reference, kyber512, ML_KEM_512);
+#[cfg(feature = "kyber768")]
+generate_reference_test!(kyber768_matches_reference, kyber768, ML_KEM_768);
+#[cfg(feature = "kyber1024")]
+generate_reference_test!(kyber1024_matches_reference, kyber1024, ML_KEM_1024);
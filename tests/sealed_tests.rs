@@ -0,0 +1,108 @@
+#![cfg(feature = "sealed")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::sealed;
+
+macro_rules! generate_sealed_tests {
+    ($level:ident, $feature:literal, $kem_id:expr) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::params::$level as kyber_params;
+            use rusty_kyber::sealed::$level::{open, seal, KEM_ID, OVERHEAD};
+            use rusty_kyber::$level::{self as level, Ciphertext};
+
+            #[test]
+            fn round_trip() {
+                let mut rng = ChaCha20Rng::from_seed([70; 32]);
+                let (pk, sk) = level::keypair(&mut rng);
+
+                for len in [0, 1, 1000] {
+                    let msg = vec![0xa5; len];
+                    let boxed = seal(&mut rng, &pk, &msg, b"aad").unwrap();
+                    assert_eq!(boxed.len(), OVERHEAD + len);
+                    assert_eq!(open(&sk, &boxed, b"aad").unwrap(), msg);
+                }
+            }
+
+            #[test]
+            fn header_and_encapsulation() {
+                let mut rng = ChaCha20Rng::from_seed([71; 32]);
+                let (pk, sk) = level::keypair(&mut rng);
+                let boxed = seal(&mut rng, &pk, b"msg", b"").unwrap();
+
+                assert_eq!(KEM_ID, $kem_id);
+                assert_eq!(boxed[..3], [sealed::VERSION, ($kem_id >> 8) as u8, $kem_id as u8]);
+                assert_eq!(sealed::kem_id(&boxed), Ok(KEM_ID));
+
+                // The ML-KEM ciphertext follows the header as is
+                let ct: [u8; kyber_params::CIPHERTEXT_BYTES] =
+                    boxed[3..3 + kyber_params::CIPHERTEXT_BYTES].try_into().unwrap();
+                let ss = level::decaps(&sk, &Ciphertext::from(ct));
+                assert_ne!(ss.as_bytes(), [0u8; 32]);
+            }
+
+            #[test]
+            fn rejects_tampering() {
+                let mut rng = ChaCha20Rng::from_seed([72; 32]);
+                let (pk, sk) = level::keypair(&mut rng);
+                let boxed = seal(&mut rng, &pk, b"attack at dawn", b"aad").unwrap();
+
+                assert_eq!(open(&sk, &boxed, b"other"), Err(Error::AuthenticationFailed));
+
+                // Version, KEM ID, ML-KEM ciphertext, AEAD ciphertext and tag
+                let cases = [
+                    (0, Error::InvalidHeader),
+                    (2, Error::InvalidHeader),
+                    (3, Error::AuthenticationFailed),
+                    (OVERHEAD - 17, Error::AuthenticationFailed),
+                    (OVERHEAD - 16, Error::AuthenticationFailed),
+                    (boxed.len() - 1, Error::AuthenticationFailed),
+                ];
+                for (i, err) in cases {
+                    let mut bad = boxed.clone();
+                    bad[i] ^= 1;
+                    assert_eq!(open(&sk, &bad, b"aad"), Err(err), "byte {i}");
+                }
+
+                assert_eq!(open(&sk, &boxed[..boxed.len() - 1], b"aad"), Err(Error::AuthenticationFailed));
+                assert_eq!(open(&sk, &boxed[..OVERHEAD - 1], b"aad"), Err(Error::InvalidLength));
+                assert_eq!(open(&sk, &boxed[..2], b"aad"), Err(Error::InvalidLength));
+            }
+
+            #[test]
+            fn rejects_other_key() {
+                let mut rng = ChaCha20Rng::from_seed([73; 32]);
+                let (pk, _) = level::keypair(&mut rng);
+                let (_, other) = level::keypair(&mut rng);
+                let boxed = seal(&mut rng, &pk, b"msg", b"").unwrap();
+                assert_eq!(open(&other, &boxed, b""), Err(Error::AuthenticationFailed));
+            }
+        }
+    };
+}
+
+generate_sealed_tests!(kyber512, "kyber512", 0x0040u16);
+generate_sealed_tests!(kyber768, "kyber768", 0x0041u16);
+generate_sealed_tests!(kyber1024, "kyber1024", 0x0042u16);
+
+#[cfg(all(feature = "kyber512", feature = "kyber768"))]
+#[test]
+fn rejects_box_for_another_level() {
+    let mut rng = ChaCha20Rng::from_seed([74; 32]);
+    let (pk, _) = rusty_kyber::kyber512::keypair(&mut rng);
+    let (_, sk) = rusty_kyber::kyber768::keypair(&mut rng);
+    let boxed = sealed::kyber512::seal(&mut rng, &pk, b"msg", b"").unwrap();
+    assert_eq!(sealed::kyber768::open(&sk, &boxed, b""), Err(Error::InvalidHeader));
+}
+
+#[test]
+fn top_level_follows_highest_level() {
+    let mut rng = ChaCha20Rng::from_seed([75; 32]);
+    let (pk, sk) = rusty_kyber::keypair(&mut rng);
+    let boxed = sealed::seal(&mut rng, &pk, b"msg", b"").unwrap();
+    assert_eq!(sealed::kem_id(&boxed), Ok(sealed::KEM_ID));
+    assert_eq!(sealed::open(&sk, &boxed, b"").unwrap(), b"msg");
+}
@@ -0,0 +1,73 @@
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+
+macro_rules! generate_seed_tests {
+    ($level:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::params::$level as kyber_params;
+            use rusty_kyber::$level::{self as level, SecretKey, SecretSeed};
+
+            #[test]
+            fn expands_like_keypair() {
+                let mut rng = ChaCha20Rng::from_seed([140; 32]);
+                let (pk, sk) = level::keypair(&mut rng.clone());
+                let seed = SecretSeed::generate(&mut rng);
+
+                let (pk2, sk2) = seed.expand();
+                assert_eq!(pk2.as_bytes(), pk.as_bytes());
+                assert_eq!(sk2.as_bytes(), sk.as_bytes());
+                assert_eq!(seed.public_key().as_bytes(), pk.as_bytes());
+
+                // d || z: z is the tail of the expanded key
+                let bytes = seed.to_bytes();
+                assert_eq!(&sk.as_bytes()[kyber_params::SECRET_KEY_BYTES - 32..], &bytes[32..]);
+
+                let again = SecretSeed::from(*bytes);
+                assert_eq!(again.expand().1.as_bytes(), sk.as_bytes());
+
+                let (ct, ss) = level::encaps(&mut rng, &pk2);
+                assert_eq!(level::decaps(&sk2, &ct).as_bytes(), ss.as_bytes());
+            }
+
+            #[test]
+            fn from_expanded_with_check() {
+                let mut rng = ChaCha20Rng::from_seed([141; 32]);
+                let seed = SecretSeed::generate(&mut rng);
+                let (_, sk) = seed.expand();
+
+                let checked = SecretSeed::from_expanded_with_check(*seed.to_bytes(), &sk).unwrap();
+                assert_eq!(checked.to_bytes(), seed.to_bytes());
+
+                // Another seed, and the same seed with z or d changed
+                let other = SecretSeed::generate(&mut rng);
+                assert_eq!(
+                    SecretSeed::from_expanded_with_check(*other.to_bytes(), &sk).err(),
+                    Some(Error::InconsistentKey)
+                );
+                for i in [0, 63] {
+                    let mut bytes = *seed.to_bytes();
+                    bytes[i] ^= 1;
+                    assert_eq!(
+                        SecretSeed::from_expanded_with_check(bytes, &sk).err(),
+                        Some(Error::InconsistentKey)
+                    );
+                }
+
+                // The expanded key with a byte changed
+                let mut bytes: [u8; kyber_params::SECRET_KEY_BYTES] = sk.into();
+                bytes[0] ^= 1;
+                assert_eq!(
+                    SecretSeed::from_expanded_with_check(*seed.to_bytes(), &SecretKey::from(bytes)).err(),
+                    Some(Error::InconsistentKey)
+                );
+            }
+        }
+    };
+}
+
+generate_seed_tests!(kyber512, "kyber512");
+generate_seed_tests!(kyber768, "kyber768");
+generate_seed_tests!(kyber1024, "kyber1024");
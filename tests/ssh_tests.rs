@@ -0,0 +1,194 @@
+#![cfg(feature = "ssh")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::ssh::{self, ClientShare, Exchange, ServerShare, MSG_KEX_HYBRID_INIT, MSG_KEX_HYBRID_REPLY};
+use std::path::Path;
+
+mod common;
+use common::ReplayRng;
+
+// The X25519MLKEM768 interop vectors: Q_C and Q_S are the TLS key shares, and
+// K is SHA-256 of the TLS shared secret
+struct Vector {
+    client_seed: Option<Vec<u8>>,
+    server_seed: Option<Vec<u8>>,
+    client_share: Vec<u8>,
+    server_share: Vec<u8>,
+}
+
+fn vectors() -> Vec<Vector> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/kat_vectors/x25519mlkem768.rsp");
+    let mut vectors = Vec::new();
+    for line in std::fs::read_to_string(path).unwrap().lines() {
+        let Some((key, value)) = line.split_once(" = ") else {
+            continue;
+        };
+        if key == "count" {
+            vectors.push(Vector {
+                client_seed: None,
+                server_seed: None,
+                client_share: vec![],
+                server_share: vec![],
+            });
+            continue;
+        }
+        let vector = vectors.last_mut().unwrap();
+        let value = hex::decode(value).unwrap();
+        match key {
+            "client_seed" => vector.client_seed = Some(value),
+            "server_seed" => vector.server_seed = Some(value),
+            "client_share" => vector.client_share = value,
+            "server_share" => vector.server_share = value,
+            _ => (),
+        }
+    }
+    vectors
+}
+
+const EXPECTED_K: [&str; 2] = [
+    "280f8351de4b173af1b2993a3317ab6c4f8944f9589dc648308541d017dc8597",
+    "a57f9159115a2b4199b3d7c4cdfeef095b8c82185a01ca63e138b7fb02b6cb70",
+];
+
+#[test]
+fn interop_vectors() {
+    let vectors = vectors();
+    assert_eq!(vectors.len(), 2);
+    for (vector, expected) in vectors.into_iter().zip(EXPECTED_K) {
+        let client = ClientShare::try_from(&vector.client_share[..]).unwrap();
+        let server = ServerShare::try_from(&vector.server_share[..]).unwrap();
+        if let Some(bytes) = vector.client_seed {
+            let (share, sk) = ssh::keypair(&mut ReplayRng::new(bytes));
+            assert_eq!(share.as_bytes(), client.as_bytes());
+            assert_eq!(hex::encode(ssh::decaps(sk, &server).unwrap().as_bytes()), expected);
+        }
+        if let Some(bytes) = vector.server_seed {
+            let (share, k) = ssh::encaps(&mut ReplayRng::new(bytes), &client).unwrap();
+            assert_eq!(share.as_bytes(), server.as_bytes());
+            assert_eq!(hex::encode(k.as_bytes()), expected);
+        }
+    }
+}
+
+#[test]
+fn exchange_hash_and_keys() {
+    let vector = vectors().remove(0);
+    let client = ClientShare::try_from(&vector.client_share[..]).unwrap();
+    let server = ServerShare::try_from(&vector.server_share[..]).unwrap();
+    let (_, sk) = ssh::keypair(&mut ReplayRng::new(vector.client_seed.unwrap()));
+    let k = ssh::decaps(sk, &server).unwrap();
+
+    let string = k.to_ssh_string();
+    assert_eq!(string[..4], [0, 0, 0, 32]);
+    assert_eq!(&string[4..], k.as_bytes());
+
+    let exchange = Exchange {
+        client_version: b"SSH-2.0-OpenSSH_9.9",
+        server_version: b"SSH-2.0-russh_0.50",
+        client_kexinit: b"\x14client kexinit",
+        server_kexinit: b"\x14server kexinit",
+        host_key: b"host key blob",
+        client: &client,
+        server: &server,
+    };
+    let h = exchange.hash(&k);
+    assert_eq!(
+        hex::encode(h),
+        "fa99ae61b759e56310bc280c069c15f12441956bfd8d955bc695a1634d15bca9"
+    );
+
+    let session_id = [1; 32];
+    assert_eq!(
+        hex::encode(&*k.derive_key(&h, &session_id, b'A', 16)),
+        "41cca3c5357df6a029559f7a6013dbcb"
+    );
+    assert_eq!(
+        hex::encode(&*k.derive_key(&h, &session_id, b'C', 64)),
+        "0e43ed27d0cab3269e898c219bb0701b9a728215b161f466946a7e06595f6283\
+         956f17e905ac805cb069ed0270e08abf15b7bba31a4c15816c7b3415f22890b8"
+    );
+    assert_eq!(
+        k.derive_key(&h, &session_id, b'C', 32)[..],
+        k.derive_key(&h, &session_id, b'C', 64)[..32]
+    );
+}
+
+#[test]
+fn round_trip_messages() {
+    let mut rng = ChaCha20Rng::seed_from_u64(50);
+    let (client, sk) = ssh::keypair(&mut rng);
+
+    let init = ssh::init_message(&client);
+    assert_eq!(init[0], MSG_KEX_HYBRID_INIT);
+    assert_eq!(init[1..5], 1216u32.to_be_bytes());
+    let received = ssh::parse_init(&init).unwrap();
+    assert_eq!(received.as_bytes(), client.as_bytes());
+
+    let (server, server_k) = ssh::encaps(&mut rng, &received).unwrap();
+    let reply = ssh::reply_message(b"ssh-ed25519 blob", &server, b"signature");
+    assert_eq!(reply[0], MSG_KEX_HYBRID_REPLY);
+    let parsed = ssh::parse_reply(&reply).unwrap();
+    assert_eq!(parsed.host_key, b"ssh-ed25519 blob");
+    assert_eq!(parsed.signature, b"signature");
+    assert_eq!(parsed.server.as_bytes(), server.as_bytes());
+
+    let client_k = ssh::decaps(sk, &parsed.server).unwrap();
+    assert_eq!(client_k.as_bytes(), server_k.as_bytes());
+}
+
+#[test]
+fn rejects_bad_messages() {
+    let mut rng = ChaCha20Rng::seed_from_u64(51);
+    let (client, _) = ssh::keypair(&mut rng);
+    let init = ssh::init_message(&client);
+
+    // Message number, trailing data, string lengths
+    let mut wrong = init.clone();
+    wrong[0] = MSG_KEX_HYBRID_REPLY;
+    assert_eq!(ssh::parse_init(&wrong).err(), Some(Error::InvalidEncoding));
+    assert_eq!(
+        ssh::parse_init(&[init.as_slice(), &[0]].concat()).err(),
+        Some(Error::InvalidEncoding)
+    );
+    assert_eq!(
+        ssh::parse_init(&init[..init.len() - 1]).err(),
+        Some(Error::InvalidEncoding)
+    );
+    assert_eq!(ssh::parse_init(&init[..3]).err(), Some(Error::InvalidEncoding));
+    assert_eq!(ssh::parse_init(&[]).err(), Some(Error::InvalidEncoding));
+    let short = [&[MSG_KEX_HYBRID_INIT, 0, 0, 4, 0][..], &client.as_bytes()[..1024]].concat();
+    assert_eq!(ssh::parse_init(&short).err(), Some(Error::InvalidLength));
+
+    let (server, _) = ssh::encaps(&mut rng, &client).unwrap();
+    let reply = ssh::reply_message(b"key", &server, b"sig");
+    assert!(ssh::parse_reply(&init).is_err());
+    assert_eq!(
+        ssh::parse_reply(&reply[..reply.len() - 1]).err().unwrap(),
+        Error::InvalidEncoding
+    );
+
+    // The encapsulation key modulus check and low-order X25519 points
+    let mut bytes: [u8; ssh::CLIENT_SHARE_BYTES] = client.as_bytes().try_into().unwrap();
+    bytes[0] = 0xff;
+    bytes[1] |= 0x0f;
+    assert_eq!(
+        ssh::encaps(&mut rng, &ClientShare::from(bytes)).err(),
+        Some(Error::InvalidPublicKey)
+    );
+    let mut bytes: [u8; ssh::CLIENT_SHARE_BYTES] = client.as_bytes().try_into().unwrap();
+    bytes[1184..].fill(0);
+    assert_eq!(
+        ssh::encaps(&mut rng, &ClientShare::from(bytes)).err(),
+        Some(Error::InvalidPublicKey)
+    );
+
+    let (_, sk) = ssh::keypair(&mut rng);
+    let mut bytes: [u8; ssh::SERVER_SHARE_BYTES] = server.as_bytes().try_into().unwrap();
+    bytes[1088..].fill(0);
+    assert_eq!(
+        ssh::decaps(sk, &ServerShare::from(bytes)).err(),
+        Some(Error::InvalidPublicKey)
+    );
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "stream")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use rusty_kyber::error::Error;
+use rusty_kyber::stream::{self, CHUNK_BYTES, TAG_BYTES};
+use std::io::{self, Read, Write};
+
+const SEALED_CHUNK: usize = CHUNK_BYTES + TAG_BYTES;
+
+fn payload(len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    ChaCha20Rng::from_seed([80; 32]).fill_bytes(&mut data);
+    data
+}
+
+// The crate error inside an io::Error from the stream
+fn stream_error(err: io::Error) -> Error {
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    *err.into_inner().unwrap().downcast::<Error>().unwrap()
+}
+
+// Reads in small, uneven pieces to cross chunk boundaries mid-read
+fn read_all<R: Read>(mut reader: R) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::new();
+    let mut buf = [0u8; 1000];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => return Ok(out),
+            Ok(n) => out.extend_from_slice(&buf[..n]),
+            Err(e) => return Err(stream_error(e)),
+        }
+    }
+}
+
+macro_rules! generate_stream_tests {
+    ($level:ident, $feature:literal) => {
+        #[cfg(feature = $feature)]
+        mod $level {
+            use super::*;
+            use rusty_kyber::stream::$level::{decrypt, encrypt, HEADER_BYTES, KEM_ID};
+            use rusty_kyber::$level as level;
+
+            fn encrypt_all(seed: u8, data: &[u8]) -> (Vec<u8>, level::SecretKey) {
+                let mut rng = ChaCha20Rng::from_seed([seed; 32]);
+                let (pk, sk) = level::keypair(&mut rng);
+                let mut writer = encrypt(&mut rng, &pk, Vec::new()).unwrap();
+                for piece in data.chunks(7777) {
+                    writer.write_all(piece).unwrap();
+                }
+                (writer.finish().unwrap(), sk)
+            }
+
+            #[test]
+            fn round_trip() {
+                for len in [0, 1, CHUNK_BYTES - 1, CHUNK_BYTES, CHUNK_BYTES + 1, 3 * CHUNK_BYTES + 5] {
+                    let data = payload(len);
+                    let (stream, sk) = encrypt_all(81, &data);
+
+                    let chunks = len.div_ceil(CHUNK_BYTES).max(1);
+                    assert_eq!(stream.len(), HEADER_BYTES + len + chunks * TAG_BYTES, "len {len}");
+                    assert_eq!(stream::kem_id(&stream), Ok(KEM_ID));
+
+                    let reader = decrypt(&sk, &stream[..]).unwrap();
+                    assert_eq!(read_all(reader).unwrap(), data, "len {len}");
+                }
+            }
+
+            #[test]
+            fn rejects_truncation() {
+                let data = payload(2 * CHUNK_BYTES + 10);
+                let (stream, sk) = encrypt_all(82, &data);
+
+                // Cut at a chunk boundary, inside a chunk, and inside the header
+                let boundary = HEADER_BYTES + 2 * SEALED_CHUNK;
+                let err = read_all(decrypt(&sk, &stream[..boundary]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::Truncated);
+                let err = read_all(decrypt(&sk, &stream[..HEADER_BYTES + SEALED_CHUNK]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::Truncated);
+                let err = read_all(decrypt(&sk, &stream[..HEADER_BYTES]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::Truncated);
+                let err = read_all(decrypt(&sk, &stream[..stream.len() - 1]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::AuthenticationFailed);
+                let err = decrypt(&sk, &stream[..HEADER_BYTES - 1]).err().unwrap();
+                assert_eq!(stream_error(err), Error::Truncated);
+
+                // Trailing data after the last chunk
+                let mut longer = stream.clone();
+                longer.push(0);
+                let err = read_all(decrypt(&sk, &longer[..]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::AuthenticationFailed);
+            }
+
+            #[test]
+            fn rejects_reordering() {
+                let data = payload(3 * CHUNK_BYTES + 10);
+                let (stream, sk) = encrypt_all(83, &data);
+
+                let mut swapped = stream.clone();
+                let first = HEADER_BYTES..HEADER_BYTES + SEALED_CHUNK;
+                let second = HEADER_BYTES + SEALED_CHUNK..HEADER_BYTES + 2 * SEALED_CHUNK;
+                swapped[first.clone()].copy_from_slice(&stream[second.clone()]);
+                swapped[second].copy_from_slice(&stream[first]);
+                let err = read_all(decrypt(&sk, &swapped[..]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::AuthenticationFailed);
+
+                // Dropping a middle chunk
+                let mut dropped = stream[..HEADER_BYTES + SEALED_CHUNK].to_vec();
+                dropped.extend_from_slice(&stream[HEADER_BYTES + 2 * SEALED_CHUNK..]);
+                let err = read_all(decrypt(&sk, &dropped[..]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::AuthenticationFailed);
+            }
+
+            #[test]
+            fn rejects_header_tampering() {
+                let (stream, sk) = encrypt_all(84, b"backup");
+
+                for i in [0, 2] {
+                    let mut bad = stream.clone();
+                    bad[i] ^= 1;
+                    let err = decrypt(&sk, &bad[..]).err().unwrap();
+                    assert_eq!(stream_error(err), Error::InvalidHeader, "byte {i}");
+                }
+
+                for i in [3, HEADER_BYTES - 1] {
+                    let mut bad = stream.clone();
+                    bad[i] ^= 1;
+                    let err = read_all(decrypt(&sk, &bad[..]).unwrap()).unwrap_err();
+                    assert_eq!(err, Error::AuthenticationFailed, "byte {i}");
+                }
+            }
+
+            #[test]
+            fn rejects_other_key() {
+                let (stream, _) = encrypt_all(85, b"backup");
+                let (_, other) = level::keypair(&mut ChaCha20Rng::from_seed([86; 32]));
+                let err = read_all(decrypt(&other, &stream[..]).unwrap()).unwrap_err();
+                assert_eq!(err, Error::AuthenticationFailed);
+            }
+        }
+    };
+}
+
+generate_stream_tests!(kyber512, "kyber512");
+generate_stream_tests!(kyber768, "kyber768");
+generate_stream_tests!(kyber1024, "kyber1024");
+
+#[cfg(all(feature = "kyber512", feature = "kyber1024"))]
+#[test]
+fn rejects_stream_for_another_level() {
+    let mut rng = ChaCha20Rng::from_seed([87; 32]);
+    let (pk, _) = rusty_kyber::kyber512::keypair(&mut rng);
+    let (_, sk) = rusty_kyber::kyber1024::keypair(&mut rng);
+    let stream = stream::kyber512::encrypt(&mut rng, &pk, Vec::new()).unwrap().finish().unwrap();
+    let err = stream::kyber1024::decrypt(&sk, &stream[..]).err().unwrap();
+    assert_eq!(stream_error(err), Error::InvalidHeader);
+}
+
+// Reads the prefix once and picks the key by KEM ID, without re-chaining the
+// reader
+#[cfg(all(feature = "kyber512", feature = "kyber1024"))]
+#[test]
+fn decrypts_after_reading_the_prefix_once() {
+    let mut rng = ChaCha20Rng::from_seed([89; 32]);
+    let (pk512, sk512) = rusty_kyber::kyber512::keypair(&mut rng);
+    let (pk1024, sk1024) = rusty_kyber::kyber1024::keypair(&mut rng);
+    let data = payload(CHUNK_BYTES + 5);
+
+    let mut writer = stream::kyber512::encrypt(&mut rng, &pk512, Vec::new()).unwrap();
+    writer.write_all(&data).unwrap();
+    let first = writer.finish().unwrap();
+    let mut writer = stream::kyber1024::encrypt(&mut rng, &pk1024, Vec::new()).unwrap();
+    writer.write_all(&data).unwrap();
+    let second = writer.finish().unwrap();
+
+    for stream in [first, second] {
+        let mut inner = &stream[..];
+        let prefix = stream::read_prefix(&mut inner).unwrap();
+        let reader = match prefix.kem_id() {
+            stream::kyber512::KEM_ID => stream::kyber512::decrypt_with_prefix(&sk512, &prefix, inner),
+            stream::kyber1024::KEM_ID => stream::kyber1024::decrypt_with_prefix(&sk1024, &prefix, inner),
+            _ => unreachable!(),
+        };
+        assert_eq!(read_all(reader.unwrap()).unwrap(), data);
+    }
+
+    // A prefix for another level, another version, or cut short
+    let stream = stream::kyber512::encrypt(&mut rng, &pk512, Vec::new()).unwrap().finish().unwrap();
+    let mut inner = &stream[..];
+    let prefix = stream::read_prefix(&mut inner).unwrap();
+    let err = stream::kyber1024::decrypt_with_prefix(&sk1024, &prefix, inner).err().unwrap();
+    assert_eq!(stream_error(err), Error::InvalidHeader);
+
+    let mut other_version = stream.clone();
+    other_version[0] ^= 1;
+    let err = stream::read_prefix(&mut &other_version[..]).unwrap_err();
+    assert_eq!(stream_error(err), Error::InvalidHeader);
+    let err = stream::read_prefix(&mut &stream[..2]).unwrap_err();
+    assert_eq!(stream_error(err), Error::Truncated);
+}
+
+#[test]
+fn top_level_follows_highest_level() {
+    let mut rng = ChaCha20Rng::from_seed([88; 32]);
+    let (pk, sk) = rusty_kyber::keypair(&mut rng);
+    let mut writer = stream::encrypt(&mut rng, &pk, Vec::new()).unwrap();
+    writer.write_all(b"artifact").unwrap();
+    let out = writer.finish().unwrap();
+    assert_eq!(stream::kem_id(&out), Ok(stream::KEM_ID));
+    assert_eq!(read_all(stream::decrypt(&sk, &out[..]).unwrap()).unwrap(), b"artifact");
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "xwing")]
+
+use rand_chacha::ChaCha20Rng;
+use rand_core::SeedableRng;
+use rusty_kyber::error::Error;
+use rusty_kyber::xwing::{self, Ciphertext, PublicKey, SecretKey};
+use serde_json::Value;
+
+mod common;
+use common::ReplayRng;
+
+// spec/test-vectors.json from draft-connolly-cfrg-xwing-kem
+#[test]
+fn xwing_spec_vectors() {
+    let vectors: Vec<Value> =
+        serde_json::from_str(include_str!("kat_vectors/xwing.json")).unwrap();
+    assert_eq!(vectors.len(), 3);
+
+    for v in vectors {
+        let field = |name: &str| hex::decode(v[name].as_str().unwrap()).unwrap();
+
+        let (pk, sk) = xwing::keypair(&mut ReplayRng::new(field("seed")));
+        assert_eq!(sk.as_bytes(), &field("sk")[..]);
+        assert_eq!(pk.as_bytes(), &field("pk")[..]);
+
+        let (ct, ss) = xwing::encaps(&mut ReplayRng::new(field("eseed")), &pk).unwrap();
+        assert_eq!(ct.as_bytes(), &field("ct")[..]);
+        assert_eq!(ss.as_bytes(), &field("ss")[..]);
+
+        let ss2 = xwing::decaps(&sk, &ct);
+        assert_eq!(ss2.as_bytes(), &field("ss")[..]);
+    }
+}
+
+#[test]
+fn public_key_from_seed() {
+    let mut rng = ChaCha20Rng::from_seed([41; 32]);
+    let (pk, sk) = xwing::keypair(&mut rng);
+    let sk = SecretKey::from(<[u8; xwing::SECRET_KEY_BYTES]>::try_from(sk.as_bytes()).unwrap());
+    assert_eq!(sk.public_key().as_bytes(), pk.as_bytes());
+}
+
+#[test]
+fn implicit_rejection() {
+    let mut rng = ChaCha20Rng::from_seed([42; 32]);
+    let (pk, sk) = xwing::keypair(&mut rng);
+    let (ct, ss) = xwing::encaps(&mut rng, &pk).unwrap();
+
+    // Flipping a bit in either half changes the shared secret
+    for i in [0, xwing::CIPHERTEXT_BYTES - 1] {
+        let mut bytes: [u8; xwing::CIPHERTEXT_BYTES] = ct.into();
+        bytes[i] ^= 1;
+        let ss2 = xwing::decaps(&sk, &Ciphertext::from(bytes));
+        assert_ne!(ss.as_bytes(), ss2.as_bytes());
+    }
+}
+
+#[test]
+fn rejects_unreduced_encapsulation_key() {
+    let mut rng = ChaCha20Rng::from_seed([43; 32]);
+    let (pk, _) = xwing::keypair(&mut rng);
+    let mut bytes: [u8; xwing::PUBLIC_KEY_BYTES] = pk.into();
+    // First coefficient = 0xfff >= q
+    bytes[0] = 0xff;
+    bytes[1] |= 0x0f;
+    let res = xwing::encaps(&mut rng, &PublicKey::from(bytes));
+    assert_eq!(res.err(), Some(Error::InvalidPublicKey));
+}